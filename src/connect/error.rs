@@ -3,6 +3,8 @@ use std::io;
 use derive_more::{Display, From};
 use trust_dns_resolver::error::ResolveError;
 
+use super::socks5::Socks5Error;
+
 #[derive(Debug, From, Display)]
 pub enum ConnectError {
     /// Failed to resolve the hostname
@@ -20,6 +22,10 @@ pub enum ConnectError {
     #[display(fmt = "Connector received `Connect` method with unresolved host")]
     Unresolverd,
 
+    /// SOCKS5 proxy error
+    #[display(fmt = "SOCKS5 proxy error: {}", _0)]
+    Socks5(Socks5Error),
+
     /// Connection io error
     #[display(fmt = "{}", _0)]
     Io(io::Error),
@@ -1,5 +1,7 @@
 //! Tcp connector service
 //!
+//! Connections can go through a SOCKS5 proxy with `Socks5Connector`.
+//!
 //! ## Package feature
 //!
 //! * `rustls` - enables ssl support via `rustls` crate
@@ -10,6 +12,7 @@ mod connector;
 mod error;
 mod resolve;
 mod service;
+mod socks5;
 pub mod ssl;
 
 mod uri;
@@ -31,6 +34,7 @@ pub use self::connector::{TcpConnector, TcpConnectorFactory};
 pub use self::error::ConnectError;
 pub use self::resolve::{Resolver, ResolverFactory};
 pub use self::service::{ConnectService, ConnectServiceFactory, TcpConnectService};
+pub use self::socks5::{Socks5Connector, Socks5Error};
 
 pub fn start_resolver(cfg: ResolverConfig, opts: ResolverOpts) -> AsyncResolver {
     let (resolver, bg) = AsyncResolver::new(cfg, opts);
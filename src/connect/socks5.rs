@@ -0,0 +1,255 @@
+use std::fmt;
+use std::marker::PhantomData;
+use std::net::{IpAddr, SocketAddr};
+use std::task::{Context, Poll};
+
+use futures_util::future::{FutureExt, LocalBoxFuture};
+
+use crate::krse::io::{AsyncReadExt, AsyncWriteExt};
+use crate::krse::net::TcpStream;
+use crate::service::Service;
+
+use super::connect::{Address, Connect, Connection};
+use super::error::ConnectError;
+
+const VERSION: u8 = 5;
+const NO_AUTH: u8 = 0;
+const USERNAME_PASSWORD: u8 = 2;
+const CMD_CONNECT: u8 = 1;
+const ATYP_IPV4: u8 = 1;
+const ATYP_DOMAIN: u8 = 3;
+const ATYP_IPV6: u8 = 4;
+
+/// Errors reported by a SOCKS5 proxy
+#[derive(Debug)]
+pub enum Socks5Error {
+    /// Proxy does not speak SOCKS5
+    InvalidVersion(u8),
+    /// Proxy accepts none of the offered authentication methods
+    NoAcceptableMethods,
+    /// Proxy rejected the username or password
+    AuthenticationFailed,
+    /// Host name is longer than 255 bytes
+    HostTooLong,
+    /// Proxy could not connect to the target, with the reply code
+    Reply(u8),
+}
+
+impl fmt::Display for Socks5Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Socks5Error::InvalidVersion(v) => write!(f, "Invalid SOCKS version {}", v),
+            Socks5Error::NoAcceptableMethods => f.write_str("No acceptable authentication methods"),
+            Socks5Error::AuthenticationFailed => f.write_str("Authentication failed"),
+            Socks5Error::HostTooLong => f.write_str("Host name is too long"),
+            Socks5Error::Reply(code) => {
+                let reason = match code {
+                    1 => "general failure",
+                    2 => "connection not allowed by ruleset",
+                    3 => "network unreachable",
+                    4 => "host unreachable",
+                    5 => "connection refused",
+                    6 => "TTL expired",
+                    7 => "command not supported",
+                    8 => "address type not supported",
+                    _ => "unknown error",
+                };
+                write!(f, "Proxy replied with {} ({})", reason, code)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Socks5Error {}
+
+/// SOCKS5 connector service
+///
+/// Connects to the requested host through a SOCKS5 proxy. Host names are
+/// passed to the proxy as is, so name resolution happens on the proxy side
+/// and the connector does not need a resolver in front of it. Pre-resolved
+/// addresses of the `Connect` request are ignored.
+///
+/// ```rust
+/// use kayrx::connect::Socks5Connector;
+/// use kayrx::http::client::Connector;
+///
+/// let connector = Connector::new()
+///     .connector(Socks5Connector::new("127.0.0.1:1080".parse().unwrap()))
+///     .finish();
+/// ```
+pub struct Socks5Connector<T> {
+    proxy: SocketAddr,
+    credentials: Option<(String, String)>,
+    _t: PhantomData<T>,
+}
+
+impl<T> Socks5Connector<T> {
+    /// Create connector for the SOCKS5 proxy at `proxy`.
+    pub fn new(proxy: SocketAddr) -> Self {
+        Socks5Connector {
+            proxy,
+            credentials: None,
+            _t: PhantomData,
+        }
+    }
+
+    /// Authenticate to the proxy with username and password.
+    pub fn credentials<U, P>(mut self, username: U, password: P) -> Self
+    where
+        U: Into<String>,
+        P: Into<String>,
+    {
+        self.credentials = Some((username.into(), password.into()));
+        self
+    }
+}
+
+impl<T> Clone for Socks5Connector<T> {
+    fn clone(&self) -> Self {
+        Socks5Connector {
+            proxy: self.proxy,
+            credentials: self.credentials.clone(),
+            _t: PhantomData,
+        }
+    }
+}
+
+impl<T> fmt::Debug for Socks5Connector<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Socks5Connector")
+            .field("proxy", &self.proxy)
+            .field("credentials", &self.credentials.is_some())
+            .finish()
+    }
+}
+
+impl<T: Address + 'static> Service for Socks5Connector<T> {
+    type Request = Connect<T>;
+    type Response = Connection<T, TcpStream>;
+    type Error = ConnectError;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: Connect<T>) -> Self::Future {
+        let proxy = self.proxy;
+        let credentials = self.credentials.clone();
+
+        async move {
+            let port = req.port();
+            trace!(
+                "SOCKS5 connector - connecting to {:?} port:{} through {}",
+                req.host(),
+                port,
+                proxy
+            );
+
+            let mut stream = TcpStream::connect(proxy).await?;
+            handshake(&mut stream, credentials.as_ref()).await?;
+            request(&mut stream, req.host(), port).await?;
+
+            Ok(Connection::new(stream, req.req))
+        }
+        .boxed_local()
+    }
+}
+
+/// Negotiate the authentication method and authenticate.
+async fn handshake(
+    stream: &mut TcpStream,
+    credentials: Option<&(String, String)>,
+) -> Result<(), ConnectError> {
+    if credentials.is_some() {
+        stream
+            .write_all(&[VERSION, 2, NO_AUTH, USERNAME_PASSWORD])
+            .await?;
+    } else {
+        stream.write_all(&[VERSION, 1, NO_AUTH]).await?;
+    }
+
+    let mut buf = [0; 2];
+    stream.read_exact(&mut buf).await?;
+    if buf[0] != VERSION {
+        return Err(Socks5Error::InvalidVersion(buf[0]).into());
+    }
+
+    match (buf[1], credentials) {
+        (NO_AUTH, _) => Ok(()),
+        (USERNAME_PASSWORD, Some((username, password))) => {
+            if username.len() > 255 || password.len() > 255 {
+                return Err(Socks5Error::AuthenticationFailed.into());
+            }
+            let mut msg = Vec::with_capacity(3 + username.len() + password.len());
+            msg.push(1);
+            msg.push(username.len() as u8);
+            msg.extend_from_slice(username.as_bytes());
+            msg.push(password.len() as u8);
+            msg.extend_from_slice(password.as_bytes());
+            stream.write_all(&msg).await?;
+
+            stream.read_exact(&mut buf).await?;
+            if buf[1] != 0 {
+                return Err(Socks5Error::AuthenticationFailed.into());
+            }
+            Ok(())
+        }
+        _ => Err(Socks5Error::NoAcceptableMethods.into()),
+    }
+}
+
+/// Ask the proxy to connect to the target.
+async fn request(stream: &mut TcpStream, host: &str, port: u16) -> Result<(), ConnectError> {
+    let mut msg = vec![VERSION, CMD_CONNECT, 0];
+
+    let ip = host
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse::<IpAddr>();
+    match ip {
+        Ok(IpAddr::V4(ip)) => {
+            msg.push(ATYP_IPV4);
+            msg.extend_from_slice(&ip.octets());
+        }
+        Ok(IpAddr::V6(ip)) => {
+            msg.push(ATYP_IPV6);
+            msg.extend_from_slice(&ip.octets());
+        }
+        Err(_) => {
+            if host.len() > 255 {
+                return Err(Socks5Error::HostTooLong.into());
+            }
+            msg.push(ATYP_DOMAIN);
+            msg.push(host.len() as u8);
+            msg.extend_from_slice(host.as_bytes());
+        }
+    }
+    msg.extend_from_slice(&port.to_be_bytes());
+    stream.write_all(&msg).await?;
+
+    let mut buf = [0; 4];
+    stream.read_exact(&mut buf).await?;
+    if buf[0] != VERSION {
+        return Err(Socks5Error::InvalidVersion(buf[0]).into());
+    }
+    if buf[1] != 0 {
+        return Err(Socks5Error::Reply(buf[1]).into());
+    }
+
+    // skip the bound address
+    let len = match buf[3] {
+        ATYP_IPV4 => 4,
+        ATYP_IPV6 => 16,
+        ATYP_DOMAIN => {
+            let mut len = [0; 1];
+            stream.read_exact(&mut len).await?;
+            len[0] as usize
+        }
+        _ => return Err(Socks5Error::Reply(8).into()),
+    };
+    let mut addr = vec![0; len + 2];
+    stream.read_exact(&mut addr).await?;
+
+    Ok(())
+}
@@ -3,6 +3,7 @@ use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
 use std::pin::Pin;
 use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Weak};
 use std::task::{Context, Poll};
 use std::{fmt, thread};
 
@@ -280,6 +281,18 @@ impl Arbiter {
         })
     }
 
+    /// Returns a handle that can be upgraded as long as the current arbiter runs, that is
+    /// until its thread exits or a new system is started on the thread.
+    ///
+    /// Futures spawned on an arbiter that stopped are never polled again, background
+    /// tasks use this to find out that they have to be spawned again.
+    pub(crate) fn liveness() -> Weak<()> {
+        if !Arbiter::contains_item::<Liveness>() {
+            Arbiter::set_item(Liveness(Arc::new(())));
+        }
+        Arbiter::get_item(|item: &Liveness| Arc::downgrade(&item.0))
+    }
+
     fn with_sender(sender: UnboundedSender<ArbiterCommand>) -> Self {
         Self {
             sender,
@@ -297,6 +310,9 @@ impl Arbiter {
     }
 }
 
+/// Kept in the arbiter storage, see `Arbiter::liveness`.
+struct Liveness(Arc<()>);
+
 struct ArbiterController {
     stop: Option<Sender<i32>>,
     rx: UnboundedReceiver<ArbiterCommand>,
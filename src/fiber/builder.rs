@@ -15,6 +15,7 @@ use crate::fiber::runtime::{Runtime, Callback, Kind, RuntimeInner};
 use crate::fiber::system::System;
 use crate::fiber::local::LocalSet;
 use crate::fiber::BasicScheduler;
+use crate::fiber::thread_pool::{self, ThreadPool};
 use crate::fiber::{io as io_in, timer};
 
 /// Builder struct for a kayrx runtime.
@@ -28,6 +29,12 @@ pub struct Builder {
 
     /// Whether the Arbiter will stop the whole System on uncaught panic. Defaults to false.
     stop_on_panic: bool,
+
+    /// Whether `fiber::take` runs fibers on a work-stealing thread pool. Defaults to false.
+    threaded_scheduler: bool,
+
+    /// Number of thread pool workers. Defaults to the number of physical CPU cores.
+    core_threads: Option<usize>,
 }
 
 impl Builder {
@@ -35,6 +42,8 @@ impl Builder {
         Builder {
             name: Cow::Borrowed("fiber"),
             stop_on_panic: false,
+            threaded_scheduler: false,
+            core_threads: None,
         }
     }
 
@@ -53,6 +62,31 @@ impl Builder {
         self
     }
 
+    /// Runs `Send` fibers spawned with `fiber::take` on a multi-threaded,
+    /// work-stealing scheduler instead of the arbiter's thread.
+    ///
+    /// Every worker has its own run queue and I/O and timer drivers; idle
+    /// workers steal fibers from busy ones. Futures spawned with
+    /// `fiber::spawn` keep running on the arbiter that spawned them.
+    pub fn threaded_scheduler(mut self) -> Self {
+        self.threaded_scheduler = true;
+        self
+    }
+
+    /// Sets the number of worker threads of the threaded scheduler.
+    ///
+    /// Defaults to the number of physical CPU cores. Has no effect unless
+    /// `threaded_scheduler` is enabled.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `val` is zero.
+    pub fn core_threads(mut self, val: usize) -> Self {
+        assert_ne!(val, 0, "Core threads cannot be zero");
+        self.core_threads = Some(val);
+        self
+    }
+
     /// Create new System.
     ///
     /// This method panics if it can not create kayrx runtime
@@ -77,11 +111,33 @@ impl Builder {
         self.create_runtime(f).run()
     }
 
+    fn create_thread_pool(&self) -> Option<ThreadPool> {
+        if !self.threaded_scheduler {
+            return None;
+        }
+
+        let mut builder = BuilderInner::new();
+        builder
+            .enable_all()
+            .thread_name(format!("{}-worker", self.name));
+        if let Some(val) = self.core_threads {
+            builder.core_threads(val);
+        }
+
+        Some(builder.build_thread_pool().unwrap())
+    }
+
     fn create_async_runtime(self, local: &LocalSet) -> AsyncSystemRunner {
         let (stop_tx, stop) = channel();
         let (sys_sender, sys_receiver) = unbounded();
 
-        let system = System::construct(sys_sender, Arbiter::new_system(), self.stop_on_panic);
+        let pool = self.create_thread_pool();
+        let system = System::construct(
+            sys_sender,
+            Arbiter::new_system(),
+            self.stop_on_panic,
+            pool.as_ref().map(|pool| pool.spawner().clone()),
+        );
 
         // system arbiter
         let arb = SystemArbiter::new(stop_tx, sys_receiver);
@@ -89,7 +145,7 @@ impl Builder {
         // start the system arbiter
         let _ = local.spawn_local(arb);
 
-        AsyncSystemRunner { stop, system }
+        AsyncSystemRunner { stop, system, pool }
     }
 
     fn create_runtime<F>(self, f: F) -> SystemRunner
@@ -99,7 +155,13 @@ impl Builder {
         let (stop_tx, stop) = channel();
         let (sys_sender, sys_receiver) = unbounded();
 
-        let system = System::construct(sys_sender, Arbiter::new_system(), self.stop_on_panic);
+        let pool = self.create_thread_pool();
+        let system = System::construct(
+            sys_sender,
+            Arbiter::new_system(),
+            self.stop_on_panic,
+            pool.as_ref().map(|pool| pool.spawner().clone()),
+        );
 
         // system arbiter
         let arb = SystemArbiter::new(stop_tx, sys_receiver);
@@ -110,7 +172,12 @@ impl Builder {
         // init system arbiter and run configuration method
         rt.block_on(lazy(move |_| f()));
 
-        SystemRunner { rt, stop, system }
+        SystemRunner {
+            rt,
+            stop,
+            system,
+            pool,
+        }
     }
 }
 
@@ -118,13 +185,14 @@ impl Builder {
 pub(crate) struct AsyncSystemRunner {
     stop: Receiver<i32>,
     system: System,
+    pool: Option<ThreadPool>,
 }
 
 impl AsyncSystemRunner {
     /// This function will start event loop and returns a future that
     /// resolves once the `System::stop()` function is called.
     pub(crate) fn run_nonblocking(self) -> impl Future<Output = Result<(), io::Error>> + Send {
-        let AsyncSystemRunner { stop, .. } = self;
+        let AsyncSystemRunner { stop, pool, .. } = self;
 
         // run loop
         lazy(|_| {
@@ -144,6 +212,7 @@ impl AsyncSystemRunner {
                     Err(e) => Err(io::Error::new(io::ErrorKind::Other, e)),
                 };
                 Arbiter::stop_system();
+                drop(pool);
                 return res;
             }
         })
@@ -158,6 +227,7 @@ pub struct SystemRunner {
     rt: Runtime,
     stop: Receiver<i32>,
     system: System,
+    pool: Option<ThreadPool>,
 }
 
 impl SystemRunner {
@@ -316,6 +386,21 @@ impl BuilderInner {
         self.build_basic_runtime()
     }
 
+    pub(crate) fn build_thread_pool(&mut self) -> io::Result<ThreadPool> {
+        let clock = timer::create_clock();
+
+        // Every worker gets its own I/O and timer driver, so that fibers
+        // polled on a worker register their resources with it.
+        let mut drivers = Vec::with_capacity(self.core_threads);
+        for _ in 0..self.core_threads {
+            let (io_driver, io_handle) = io_in::create_driver(self.enable_io)?;
+            let (driver, timer_handle) = timer::create_driver(self.enable_timer, io_driver, clock.clone());
+            drivers.push((driver, io_handle, timer_handle));
+        }
+
+        thread_pool::create(self, drivers, clock, self.max_threads)
+    }
+
     fn build_basic_runtime(&mut self) -> io::Result<RuntimeInner> {
 
         let clock = timer::create_clock();
@@ -1,4 +1,4 @@
-use crate::fiber::scheduler;
+use crate::fiber::{scheduler, thread_pool};
 use crate::fiber::inner::JoinHandle;
 
 use std::cell::Cell;
@@ -11,6 +11,9 @@ enum State {
 
     // Basic scheduler (runs on the current-thread)
     Basic(*const scheduler::SchedulerPriv),
+
+    // Work-stealing thread pool
+    ThreadPool(*const thread_pool::Spawner),
 }
 
 thread_local! {
@@ -34,6 +37,10 @@ where
             // thread).
             unsafe { basic_scheduler.spawn(future) }
         }
+        State::ThreadPool(spawner_ptr) => {
+            let spawner = unsafe { &*spawner_ptr };
+            spawner.spawn(future)
+        }
         State::Empty => {
             // Explicit drop of `future` silences the warning that `future` is
             // not used when neither rt-* feature flags are enabled.
@@ -56,6 +63,13 @@ where
     )
 }
 
+pub(super) fn with_thread_pool<F, R>(spawner: &thread_pool::Spawner, f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    with_state(State::ThreadPool(spawner as *const thread_pool::Spawner), f)
+}
+
 fn with_state<F, R>(state: State, f: F) -> R
 where
     F: FnOnce() -> R,
@@ -56,6 +56,7 @@ unsafe impl<S: ScheduleSend + 'static> Send for Fiber<S> {}
 /// It is critical for `Header` to be the first field as the task structure will
 /// be referenced by both *mut Cell and *mut Header.
 
+#[repr(C)]
 pub(super) struct Cell<T: Future> {
     /// Hot task state data
     pub(super) header: Header,
@@ -14,8 +14,8 @@ pub(crate) use self::join::{JoinHandle, JoinError};
 pub(crate) use self::yield_now::yield_now;
 
 
-use self::list::OwnedList;
-use self::stack::TransferStack;
+pub(crate) use self::list::OwnedList;
+pub(crate) use self::stack::TransferStack;
 use self::fiber::Cell;
 use self::harness::Harness;
 use self::raw::RawFiber;
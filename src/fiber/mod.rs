@@ -12,6 +12,7 @@ mod runtime;
 mod scheduler;
 mod spawner;
 mod system;
+mod thread_pool;
 mod io;
 mod timer;
 
@@ -50,11 +51,20 @@ where
 }
 
 /// Take fiber to  global  runtime executor.
+///
+/// If the system was built with `Builder::threaded_scheduler`, the fiber runs
+/// on the system's work-stealing thread pool, otherwise on the current runtime.
 pub fn take<T>(fiber: T) -> JoinHandle<T::Output>
 where
     T: Future + Send + 'static,
     T::Output: Send + 'static,
 {
+    if System::is_set() {
+        if let Some(spawner) = System::with_current(|sys| sys.thread_pool().cloned()) {
+            return spawner.spawn(fiber);
+        }
+    }
+
     context::spawn(fiber)
 }
 
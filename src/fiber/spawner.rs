@@ -1,4 +1,4 @@
-    use crate::fiber::scheduler;
+    use crate::fiber::{scheduler, thread_pool};
     use crate::fiber::JoinHandle;
 
     use std::future::Future;
@@ -7,6 +7,7 @@
 #[derive(Debug, Clone)]
 pub(crate) enum Spawner {
     Basic(scheduler::Spawner),
+    ThreadPool(thread_pool::Spawner),
 }
 
 impl Spawner {
@@ -17,6 +18,7 @@ impl Spawner {
     {
         match self {
             Spawner::Basic(spawner) => spawner.enter(f),
+            Spawner::ThreadPool(spawner) => spawner.enter(f),
         }
     }
 }
@@ -29,6 +31,7 @@ impl Spawner {
         {
             match self {
                 Spawner::Basic(spawner) => spawner.spawn(future),
+                Spawner::ThreadPool(spawner) => spawner.spawn(future),
             }
         }
     }
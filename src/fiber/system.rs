@@ -8,6 +8,7 @@ use futures_channel::mpsc::UnboundedSender;
 use crate::fiber::local::LocalSet;
 use crate::fiber::arbiter::{Arbiter, SystemCommand};
 use crate::fiber::builder::{Builder, SystemRunner};
+use crate::fiber::thread_pool;
 
 static SYSTEM_COUNT: AtomicUsize = AtomicUsize::new(0);
 
@@ -18,6 +19,7 @@ pub struct System {
     sys: UnboundedSender<SystemCommand>,
     arbiter: Arbiter,
     stop_on_panic: bool,
+    thread_pool: Option<thread_pool::Spawner>,
 }
 
 thread_local!(
@@ -30,11 +32,13 @@ impl System {
         sys: UnboundedSender<SystemCommand>,
         arbiter: Arbiter,
         stop_on_panic: bool,
+        thread_pool: Option<thread_pool::Spawner>,
     ) -> Self {
         let sys = System {
             sys,
             arbiter,
             stop_on_panic,
+            thread_pool,
             id: SYSTEM_COUNT.fetch_add(1, Ordering::SeqCst),
         };
         System::set_current(sys.clone());
@@ -128,6 +132,11 @@ impl System {
         self.stop_on_panic
     }
 
+    /// Spawner of the work-stealing thread pool, if the system has one.
+    pub(crate) fn thread_pool(&self) -> Option<&thread_pool::Spawner> {
+        self.thread_pool.as_ref()
+    }
+
     /// System arbiter
     pub fn arbiter(&self) -> &Arbiter {
         &self.arbiter
@@ -0,0 +1,243 @@
+//! Multi-threaded, work-stealing fiber scheduler.
+//!
+//! Each worker thread runs its own I/O and timer driver and owns a local run
+//! queue. Fibers spawned or woken on a worker are pushed onto that worker's
+//! local queue; fibers spawned or woken from any other thread go through a
+//! global injection queue. A worker that runs out of work steals half of the
+//! local queue of one of its siblings before parking.
+
+mod queue;
+mod worker;
+
+use crate::fiber::inner::{self as fiber, Fiber};
+use crate::fiber::spawner::Spawner as Fspawner;
+use crate::fiber::{
+    block_pool, context, io, timer, BlockingPool, BuilderInner, Handle, JoinHandle,
+};
+use crate::krse::thread::Park;
+
+use std::fmt;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+/// A pool of worker threads running `Send` fibers.
+pub(crate) struct ThreadPool {
+    spawner: Spawner,
+
+    /// Worker threads, joined on drop
+    threads: Vec<thread::JoinHandle<()>>,
+
+    /// Blocking pool shared by all workers
+    blocking_pool: Option<BlockingPool>,
+}
+
+/// Submits fibers to a `ThreadPool`.
+#[derive(Clone)]
+pub(crate) struct Spawner {
+    set: Arc<Set>,
+}
+
+/// State shared by all workers of a pool.
+pub(crate) struct Set {
+    /// Per-worker state
+    workers: Box<[worker::Shared]>,
+
+    /// Fibers scheduled from outside of the pool
+    inject: queue::Inject<worker::Shared>,
+
+    /// Set when the pool is dropped
+    shutdown: AtomicBool,
+}
+
+/// Builds a thread pool with one worker for each of the given drivers.
+pub(crate) fn create(
+    builder: &BuilderInner,
+    drivers: Vec<(timer::Driver, io::Handle, timer::Handle)>,
+    clock: timer::Clock,
+    thread_cap: usize,
+) -> std::io::Result<ThreadPool> {
+    let unparks: Vec<_> = drivers
+        .iter()
+        .map(|(driver, _, _)| Box::new(driver.unpark()) as Box<_>)
+        .collect();
+
+    let set = Arc::new_cyclic(|set| Set {
+        workers: unparks
+            .into_iter()
+            .enumerate()
+            .map(|(index, unpark)| worker::Shared::new(set.as_ptr(), index, unpark))
+            .collect(),
+        inject: queue::Inject::new(),
+        shutdown: AtomicBool::new(false),
+    });
+    let spawner = Spawner { set };
+
+    // All workers share one blocking pool, which enters the context of the
+    // first worker's drivers.
+    let (_, io_handle, timer_handle) = &drivers[0];
+    let blocking_pool = block_pool::create_blocking_pool(
+        builder,
+        &Fspawner::ThreadPool(spawner.clone()),
+        io_handle,
+        timer_handle,
+        &clock,
+        thread_cap,
+    );
+    let blocking_spawner = blocking_pool.spawner().clone();
+
+    let mut threads = Vec::with_capacity(drivers.len());
+
+    for (index, (driver, io_handle, timer_handle)) in drivers.into_iter().enumerate() {
+        let handle = Handle {
+            spawner: Fspawner::ThreadPool(spawner.clone()),
+            io_handle,
+            timer_handle,
+            clock: clock.clone(),
+            blocking_spawner: blocking_spawner.clone(),
+        };
+        let set = spawner.set.clone();
+        let after_start = builder.after_start.clone();
+        let before_stop = builder.before_stop.clone();
+
+        let mut b = thread::Builder::new().name(format!("{}-{}", builder.thread_name, index));
+        if let Some(stack_size) = builder.thread_stack_size {
+            b = b.stack_size(stack_size);
+        }
+
+        let thread = b.spawn(move || {
+            if let Some(f) = after_start {
+                f()
+            }
+
+            handle.enter(|| worker::Worker::new(set, index, driver).run());
+
+            if let Some(f) = before_stop {
+                f()
+            }
+        });
+
+        match thread {
+            Ok(thread) => threads.push(thread),
+            Err(e) => {
+                // Shut down the workers that did start before bailing out.
+                drop(ThreadPool {
+                    spawner,
+                    threads,
+                    blocking_pool: Some(blocking_pool),
+                });
+                return Err(e);
+            }
+        }
+    }
+
+    Ok(ThreadPool {
+        spawner,
+        threads,
+        blocking_pool: Some(blocking_pool),
+    })
+}
+
+impl ThreadPool {
+    pub(crate) fn spawner(&self) -> &Spawner {
+        &self.spawner
+    }
+}
+
+impl Drop for ThreadPool {
+    fn drop(&mut self) {
+        let set = &self.spawner.set;
+
+        set.shutdown.store(true, Ordering::SeqCst);
+        set.inject.close();
+
+        for worker in set.workers.iter() {
+            worker.unpark();
+        }
+
+        for thread in self.threads.drain(..) {
+            // Panics in fibers are caught by the harness, so a worker
+            // thread only panics on a driver failure.
+            let _ = thread.join();
+        }
+
+        // Fibers that were never picked up by a worker.
+        while let Some(task) = set.inject.pop() {
+            task.shutdown();
+        }
+
+        drop(self.blocking_pool.take());
+    }
+}
+
+impl fmt::Debug for ThreadPool {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("ThreadPool")
+            .field("workers", &self.spawner.set.workers.len())
+            .finish()
+    }
+}
+
+impl Spawner {
+    /// Spawns a future onto the pool. When called from one of the pool's
+    /// workers, the fiber is queued locally; otherwise it is injected.
+    pub(crate) fn spawn<F>(&self, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let (task, handle) = fiber::joinable(future);
+        self.set.schedule(task);
+        handle
+    }
+
+    /// Enter the executor context
+    pub(crate) fn enter<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce() -> R,
+    {
+        context::with_thread_pool(self, f)
+    }
+}
+
+impl fmt::Debug for Spawner {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Spawner")
+            .field("workers", &self.set.workers.len())
+            .finish()
+    }
+}
+
+impl Set {
+    fn schedule(&self, task: Fiber<worker::Shared>) {
+        if let Some(worker) = worker::current(self) {
+            worker.local.push(task);
+            self.notify_one();
+            return;
+        }
+
+        match self.inject.push(task) {
+            Ok(()) => self.notify_one(),
+            // The pool is shutting down.
+            Err(task) => task.shutdown(),
+        }
+    }
+
+    /// Wakes one idle worker, if any.
+    fn notify_one(&self) {
+        for worker in self.workers.iter() {
+            if worker.notify_if_idle() {
+                return;
+            }
+        }
+    }
+
+    fn has_work(&self) -> bool {
+        !self.inject.is_empty() || self.workers.iter().any(|w| !w.local.is_empty())
+    }
+
+    fn is_shutdown(&self) -> bool {
+        self.shutdown.load(Ordering::SeqCst)
+    }
+}
@@ -0,0 +1,149 @@
+use crate::fiber::inner::Fiber;
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// Run queue owned by a single worker.
+///
+/// The owning worker pushes to the back and pops from the front, so fibers
+/// run in FIFO order; other workers steal the newer half from the back.
+/// The length is mirrored in an atomic so that idle workers can look for
+/// work without taking the lock.
+pub(super) struct Local<T: 'static> {
+    queue: Mutex<VecDeque<Fiber<T>>>,
+    len: AtomicUsize,
+}
+
+/// Global run queue, used for fibers spawned or notified from outside of the
+/// pool.
+pub(super) struct Inject<T: 'static> {
+    queue: Mutex<InjectQueue<T>>,
+    len: AtomicUsize,
+}
+
+struct InjectQueue<T: 'static> {
+    tasks: VecDeque<Fiber<T>>,
+    closed: bool,
+}
+
+impl<T: 'static> Local<T> {
+    pub(super) fn new() -> Local<T> {
+        Local {
+            queue: Mutex::new(VecDeque::new()),
+            len: AtomicUsize::new(0),
+        }
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.len.load(Ordering::SeqCst) == 0
+    }
+
+    pub(super) fn push(&self, task: Fiber<T>) {
+        let mut queue = self.queue.lock().unwrap();
+        queue.push_back(task);
+        self.len.store(queue.len(), Ordering::SeqCst);
+    }
+
+    pub(super) fn pop(&self) -> Option<Fiber<T>> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let mut queue = self.queue.lock().unwrap();
+        let task = queue.pop_front();
+        self.len.store(queue.len(), Ordering::SeqCst);
+        task
+    }
+
+    /// Moves half of the fibers in `self` into `dst`, returning one of them
+    /// to be run immediately.
+    pub(super) fn steal_into(&self, dst: &Local<T>) -> Option<Fiber<T>> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let mut stolen = {
+            let mut queue = self.queue.lock().unwrap();
+            let n = queue.len() - queue.len() / 2;
+            let at = queue.len() - n;
+            let stolen = queue.split_off(at);
+            self.len.store(queue.len(), Ordering::SeqCst);
+            stolen
+        };
+
+        let task = stolen.pop_front();
+
+        if !stolen.is_empty() {
+            let mut queue = dst.queue.lock().unwrap();
+            queue.extend(stolen);
+            dst.len.store(queue.len(), Ordering::SeqCst);
+        }
+
+        task
+    }
+}
+
+impl<T: 'static> fmt::Debug for Local<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Local")
+            .field("len", &self.len.load(Ordering::Relaxed))
+            .finish()
+    }
+}
+
+impl<T: 'static> Inject<T> {
+    pub(super) fn new() -> Inject<T> {
+        Inject {
+            queue: Mutex::new(InjectQueue {
+                tasks: VecDeque::new(),
+                closed: false,
+            }),
+            len: AtomicUsize::new(0),
+        }
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.len.load(Ordering::SeqCst) == 0
+    }
+
+    /// Pushes a fiber onto the queue, returning it back if the queue has been
+    /// closed.
+    pub(super) fn push(&self, task: Fiber<T>) -> Result<(), Fiber<T>> {
+        let mut queue = self.queue.lock().unwrap();
+
+        if queue.closed {
+            return Err(task);
+        }
+
+        queue.tasks.push_back(task);
+        self.len.store(queue.tasks.len(), Ordering::SeqCst);
+        Ok(())
+    }
+
+    pub(super) fn pop(&self) -> Option<Fiber<T>> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let mut queue = self.queue.lock().unwrap();
+        let task = queue.tasks.pop_front();
+        self.len.store(queue.tasks.len(), Ordering::SeqCst);
+        task
+    }
+
+    /// Stops accepting new fibers. Fibers already in the queue remain there
+    /// until they are popped.
+    pub(super) fn close(&self) {
+        self.queue.lock().unwrap().closed = true;
+    }
+}
+
+impl<T: 'static> fmt::Debug for Inject<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Inject")
+            .field("len", &self.len.load(Ordering::Relaxed))
+            .finish()
+    }
+}
@@ -0,0 +1,313 @@
+use crate::fiber::inner::{Fiber, OwnedList, Schedule, ScheduleSend, TransferStack};
+use crate::fiber::thread_pool::{queue, Set};
+use crate::fiber::timer;
+use crate::krse::thread::{Park, Unpark};
+
+use std::cell::{Cell, UnsafeCell};
+use std::fmt;
+use std::ptr::{self, NonNull};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Per-worker state that other threads may access.
+pub(crate) struct Shared {
+    /// The set the worker belongs to
+    set: *const Set,
+
+    /// Index of the worker in the set
+    index: usize,
+
+    /// Fibers ready to run on this worker
+    pub(super) local: queue::Local<Shared>,
+
+    /// All fibers bound to this worker. Only accessed from the worker thread.
+    owned: UnsafeCell<OwnedList<Shared>>,
+
+    /// Fibers bound to this worker, released from other threads
+    pending_drop: TransferStack<Shared>,
+
+    /// Set while the worker is parked waiting for work
+    idle: AtomicBool,
+
+    /// Unparks the worker thread
+    unpark: Box<dyn Unpark>,
+}
+
+unsafe impl Send for Shared {}
+unsafe impl Sync for Shared {}
+
+/// The worker thread's run loop state.
+pub(super) struct Worker {
+    set: Arc<Set>,
+    index: usize,
+    park: timer::Driver,
+    tick: u32,
+}
+
+/// Poll the injection queue first once every this many ticks, so fibers
+/// scheduled from outside of the pool are not starved by a busy local queue.
+const GLOBAL_POLL_INTERVAL: u32 = 61;
+
+/// Poll the drivers once every this many ticks, even if there is work.
+const MAINTENANCE_INTERVAL: u32 = 61;
+
+thread_local! {
+    /// The set and index of the worker running on the current thread
+    static CURRENT: Cell<(*const Set, usize)> = Cell::new((ptr::null(), 0))
+}
+
+/// Returns the worker running on the current thread, if it belongs to `set`.
+pub(super) fn current(set: &Set) -> Option<&Shared> {
+    CURRENT.with(|cell| {
+        let (ptr, index) = cell.get();
+
+        if ptr == set as *const Set {
+            Some(&set.workers[index])
+        } else {
+            None
+        }
+    })
+}
+
+impl Shared {
+    pub(super) fn new(set: *const Set, index: usize, unpark: Box<dyn Unpark>) -> Shared {
+        Shared {
+            set,
+            index,
+            local: queue::Local::new(),
+            owned: UnsafeCell::new(OwnedList::new()),
+            pending_drop: TransferStack::new(),
+            idle: AtomicBool::new(false),
+            unpark,
+        }
+    }
+
+    /// Wakes the worker if it is idle. Returns `true` if it was.
+    pub(super) fn notify_if_idle(&self) -> bool {
+        if self.idle.load(Ordering::SeqCst) && self.idle.swap(false, Ordering::SeqCst) {
+            self.unpark.unpark();
+            true
+        } else {
+            false
+        }
+    }
+
+    pub(super) fn unpark(&self) {
+        self.unpark.unpark();
+    }
+
+    /// # Safety
+    ///
+    /// Must be called from the worker thread.
+    unsafe fn drain_pending_drop(&self) {
+        for task in self.pending_drop.drain() {
+            (*self.owned.get()).remove(&task);
+            drop(task);
+        }
+    }
+
+    /// # Safety
+    ///
+    /// Must be called from the worker thread.
+    unsafe fn has_tasks_remaining(&self) -> bool {
+        !(*self.owned.get()).is_empty()
+    }
+
+    fn set(&self) -> &Set {
+        unsafe { &*self.set }
+    }
+}
+
+impl Schedule for Shared {
+    fn bind(&self, task: &Fiber<Self>) {
+        unsafe {
+            // safety: `bind` is called when a fiber is first polled, which
+            // happens on the worker thread that `self` represents.
+            (*self.owned.get()).insert(task);
+        }
+    }
+
+    fn release(&self, task: Fiber<Self>) {
+        self.pending_drop.push(task);
+        self.unpark.unpark();
+    }
+
+    fn release_local(&self, task: &Fiber<Self>) {
+        unsafe {
+            // safety: `release_local` is only called from the thread the
+            // fiber is bound to.
+            (*self.owned.get()).remove(task);
+        }
+    }
+
+    fn schedule(&self, task: Fiber<Self>) {
+        self.set().schedule(task);
+    }
+}
+
+impl ScheduleSend for Shared {}
+
+impl fmt::Debug for Shared {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Worker")
+            .field("index", &self.index)
+            .field("local", &self.local)
+            .field("idle", &self.idle.load(Ordering::Relaxed))
+            .finish()
+    }
+}
+
+impl Worker {
+    pub(super) fn new(set: Arc<Set>, index: usize, park: timer::Driver) -> Worker {
+        Worker {
+            set,
+            index,
+            park,
+            tick: 0,
+        }
+    }
+
+    /// Runs fibers until the set is shut down, then cancels every fiber bound
+    /// to this worker.
+    pub(super) fn run(mut self) {
+        struct Reset((*const Set, usize));
+
+        impl Drop for Reset {
+            fn drop(&mut self) {
+                CURRENT.with(|cell| cell.set(self.0));
+            }
+        }
+
+        let _reset =
+            Reset(CURRENT.with(|cell| cell.replace((&*self.set as *const Set, self.index))));
+
+        while !self.set.is_shutdown() {
+            self.tick = self.tick.wrapping_add(1);
+
+            match self.next_task() {
+                Some(task) => {
+                    self.run_task(task);
+
+                    if self.tick % MAINTENANCE_INTERVAL == 0 {
+                        self.park
+                            .park_timeout(Duration::from_millis(0))
+                            .ok()
+                            .expect("failed to park");
+
+                        unsafe { self.shared().drain_pending_drop() };
+                    }
+                }
+                None => self.park_idle(),
+            }
+        }
+
+        self.shutdown();
+    }
+
+    fn shared(&self) -> &Shared {
+        &self.set.workers[self.index]
+    }
+
+    fn next_task(&self) -> Option<Fiber<Shared>> {
+        let shared = self.shared();
+
+        let task = if self.tick % GLOBAL_POLL_INTERVAL == 0 {
+            self.set.inject.pop().or_else(|| shared.local.pop())
+        } else {
+            shared.local.pop().or_else(|| self.set.inject.pop())
+        };
+
+        task.or_else(|| self.steal())
+    }
+
+    /// Steals half of another worker's local queue, starting the search at a
+    /// different worker on every tick.
+    fn steal(&self) -> Option<Fiber<Shared>> {
+        let workers = &self.set.workers;
+        let start = self.tick as usize % workers.len();
+
+        for i in 0..workers.len() {
+            let index = (start + i) % workers.len();
+
+            if index == self.index {
+                continue;
+            }
+
+            if let Some(task) = workers[index].local.steal_into(&self.shared().local) {
+                return Some(task);
+            }
+        }
+
+        None
+    }
+
+    fn run_task(&self, task: Fiber<Shared>) {
+        let shared = self.shared();
+
+        if let Some(task) = task.run(&mut || Some(shared.into())) {
+            shared.local.push(task);
+        }
+
+        // Stolen fibers may leave more work in the local queue than this
+        // worker can get through, so let an idle worker take a share.
+        if !shared.local.is_empty() {
+            self.set.notify_one();
+        }
+    }
+
+    fn park_idle(&mut self) {
+        let shared = &self.set.workers[self.index];
+
+        shared.idle.store(true, Ordering::SeqCst);
+
+        // A fiber may have been scheduled before the idle flag was visible.
+        if self.set.has_work() || self.set.is_shutdown() {
+            shared.idle.store(false, Ordering::SeqCst);
+            return;
+        }
+
+        unsafe { shared.drain_pending_drop() };
+
+        self.park.park().ok().expect("failed to park");
+
+        shared.idle.store(false, Ordering::SeqCst);
+
+        unsafe { shared.drain_pending_drop() };
+    }
+
+    fn shutdown(&mut self) {
+        let shared = &self.set.workers[self.index];
+
+        unsafe {
+            // safety: we are on the worker thread.
+            (*shared.owned.get()).shutdown();
+        }
+
+        // Wait until all fibers bound to this worker have been released. Some
+        // of them may sit in the queues of other workers, which drain them
+        // concurrently.
+        loop {
+            while let Some(task) = shared.local.pop() {
+                task.shutdown();
+            }
+
+            while let Some(task) = self.set.inject.pop() {
+                task.shutdown();
+            }
+
+            unsafe {
+                shared.drain_pending_drop();
+
+                if !shared.has_tasks_remaining() {
+                    break;
+                }
+            }
+
+            self.park
+                .park_timeout(Duration::from_millis(1))
+                .ok()
+                .expect("failed to park");
+        }
+    }
+}
@@ -0,0 +1,288 @@
+use std::fmt;
+
+use bytes::Bytes;
+use futures_core::Stream;
+use futures_util::future::{poll_fn, ready};
+use futures_util::stream::{self, StreamExt};
+use http::{HeaderValue, Method, Request as H2Request, Uri, Version};
+
+use crate::http::h2::client::{handshake, SendRequest};
+use crate::http::h2::{self, Reason, SendStream};
+use crate::http::header::{HeaderMap, CONTENT_TYPE, TE};
+use crate::http::{Payload, StatusCode, Trailers};
+use crate::krse::io::{AsyncRead, AsyncWrite};
+use crate::timer::{timeout_at, Instant};
+
+use super::codec::{JsonCodec, MessageCodec};
+use super::request::{Request, Response};
+use super::server::{encode_stream, single, MessageStream};
+use super::status::{Code, Status};
+use super::streaming::Streaming;
+use super::timeout;
+
+/// gRPC client
+///
+/// Calls methods over an established HTTP/2 connection. The client is cheap
+/// to clone, clones share the connection.
+///
+/// ```rust,no_run
+/// use kayrx::grpc::{self, Request};
+/// use kayrx::krse::net::TcpStream;
+///
+/// # async fn hello() -> Result<(), grpc::Status> {
+/// let io = TcpStream::connect("127.0.0.1:50051").await?;
+/// let client = grpc::Client::handshake(io, "http://127.0.0.1:50051".parse().unwrap()).await?;
+///
+/// let res = client
+///     .unary::<String, String>("/greeter.Greeter/Hello", Request::new("world".to_owned()))
+///     .await?;
+/// println!("{}", res.into_inner());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Client<C = JsonCodec> {
+    io: SendRequest<Bytes>,
+    uri: Uri,
+    codec: C,
+    max_message_size: usize,
+}
+
+impl Client<JsonCodec> {
+    /// Create client with the JSON message codec
+    ///
+    /// `uri` is the base uri of the server, the scheme and authority of the
+    /// calls are taken from it.
+    pub fn new(io: SendRequest<Bytes>, uri: Uri) -> Self {
+        Client::with_codec(io, uri, JsonCodec)
+    }
+
+    /// Perform HTTP/2 handshake over `io` and create client with the JSON
+    /// message codec.
+    ///
+    /// The connection is driven by a spawned task.
+    pub async fn handshake<T>(io: T, uri: Uri) -> Result<Self, Status>
+    where
+        T: AsyncRead + AsyncWrite + Unpin + 'static,
+    {
+        let (io, connection) = handshake(io).await?;
+        crate::fiber::spawn(async move {
+            if let Err(e) = connection.await {
+                trace!("grpc client connection error: {}", e);
+            }
+        });
+        Ok(Client::new(io, uri))
+    }
+}
+
+impl<C: Clone + 'static> Client<C> {
+    /// Create client with the message codec
+    pub fn with_codec(io: SendRequest<Bytes>, uri: Uri, codec: C) -> Self {
+        Client {
+            io,
+            uri,
+            codec,
+            max_message_size: 4 * 1024 * 1024,
+        }
+    }
+
+    /// Set max size of the response messages.
+    ///
+    /// By default max size is set to 4Mb.
+    pub fn max_message_size(mut self, size: usize) -> Self {
+        self.max_message_size = size;
+        self
+    }
+
+    /// Call unary method
+    pub async fn unary<Req, Res>(
+        &self,
+        path: &str,
+        req: Request<Req>,
+    ) -> Result<Response<Res>, Status>
+    where
+        C: MessageCodec<Req> + MessageCodec<Res>,
+        Req: 'static,
+        Res: 'static,
+    {
+        let req = req.map(|msg| stream::once(ready(msg)));
+        let mut res = self.send(path, req).await?;
+        let msg = single(res.get_mut()).await?;
+        Ok(res.map(|_| msg))
+    }
+
+    /// Call server streaming method
+    pub async fn server_streaming<Req, Res>(
+        &self,
+        path: &str,
+        req: Request<Req>,
+    ) -> Result<Response<Streaming<Res>>, Status>
+    where
+        C: MessageCodec<Req> + MessageCodec<Res>,
+        Req: 'static,
+        Res: 'static,
+    {
+        let req = req.map(|msg| stream::once(ready(msg)));
+        self.send(path, req).await
+    }
+
+    /// Call client streaming method
+    pub async fn client_streaming<S, Req, Res>(
+        &self,
+        path: &str,
+        req: Request<S>,
+    ) -> Result<Response<Res>, Status>
+    where
+        S: Stream<Item = Req> + 'static,
+        C: MessageCodec<Req> + MessageCodec<Res>,
+        Req: 'static,
+        Res: 'static,
+    {
+        let mut res = self.streaming(path, req).await?;
+        let msg = single(res.get_mut()).await?;
+        Ok(res.map(|_| msg))
+    }
+
+    /// Call bidirectional streaming method
+    ///
+    /// Request messages are sent by a spawned task, response messages could
+    /// be received while the request stream is still in progress.
+    pub async fn streaming<S, Req, Res>(
+        &self,
+        path: &str,
+        req: Request<S>,
+    ) -> Result<Response<Streaming<Res>>, Status>
+    where
+        S: Stream<Item = Req> + 'static,
+        C: MessageCodec<Req> + MessageCodec<Res>,
+        Req: 'static,
+        Res: 'static,
+    {
+        self.send(path, req).await
+    }
+
+    async fn send<S, Req, Res>(
+        &self,
+        path: &str,
+        req: Request<S>,
+    ) -> Result<Response<Streaming<Res>>, Status>
+    where
+        S: Stream<Item = Req> + 'static,
+        C: MessageCodec<Req> + MessageCodec<Res>,
+        Req: 'static,
+        Res: 'static,
+    {
+        let content_type = MessageCodec::<Req>::content_type(&self.codec);
+        let req = req.map(|s| encode_stream(self.codec.clone(), s.map(Ok)));
+        let (metadata, payload, trailers, deadline) = self.call(path, content_type, req).await?;
+
+        let stream =
+            Streaming::response(payload, self.codec.clone(), self.max_message_size, trailers)
+                .deadline(deadline);
+        Ok(Response::from_parts(metadata, stream))
+    }
+
+    async fn call(
+        &self,
+        path: &str,
+        content_type: &'static str,
+        req: Request<MessageStream>,
+    ) -> Result<(HeaderMap, Payload, Trailers, Option<Instant>), Status> {
+        let (metadata, messages, timeout) = req.into_parts();
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+
+        let mut parts = self.uri.clone().into_parts();
+        parts.path_and_query = Some(
+            path.parse()
+                .map_err(|_| Status::new(Code::Internal, "Invalid method path"))?,
+        );
+        let uri = Uri::from_parts(parts)
+            .map_err(|_| Status::new(Code::Internal, "Invalid method path"))?;
+
+        let mut req = H2Request::new(());
+        *req.uri_mut() = uri;
+        *req.method_mut() = Method::POST;
+        *req.version_mut() = Version::HTTP_2;
+        let headers = req.headers_mut();
+        for (name, value) in metadata.iter() {
+            headers.append(name.clone(), value.clone());
+        }
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+        headers.insert(TE, HeaderValue::from_static("trailers"));
+        if let Some(timeout) = timeout {
+            headers.insert("grpc-timeout", timeout::encode(timeout));
+        }
+
+        let mut io = self.io.clone();
+        poll_fn(|cx| io.poll_ready(cx)).await?;
+        let (fut, send) = io.send_request(req, false)?;
+        crate::fiber::spawn(send_messages(messages, send));
+
+        let res = match deadline {
+            Some(deadline) => match timeout_at(deadline, fut).await {
+                Ok(res) => res?,
+                Err(_) => return Err(Status::new(Code::DeadlineExceeded, "Deadline exceeded")),
+            },
+            None => fut.await?,
+        };
+
+        let (parts, body) = res.into_parts();
+        if parts.status != StatusCode::OK {
+            return Err(Status::new(
+                Code::from_http(parts.status),
+                format!("Unexpected http status: {}", parts.status),
+            ));
+        }
+        let headers: HeaderMap = parts.headers.into();
+
+        // trailers-only response, the status is sent with the headers
+        let trailers = Trailers::default();
+        if let Some(status) = Status::from_headers(&headers) {
+            if status.code() != Code::Ok {
+                return Err(status);
+            }
+            trailers.set(Some(headers.clone()));
+        }
+
+        let payload = h2::Payload::with_trailers(body, trailers.clone()).into();
+        Ok((headers, payload, trailers, deadline))
+    }
+}
+
+impl<C> fmt::Debug for Client<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("grpc::Client")
+            .field("uri", &self.uri)
+            .field("max_message_size", &self.max_message_size)
+            .finish()
+    }
+}
+
+/// Send request messages, the stream is reset if a message can not be
+/// encoded.
+async fn send_messages(mut messages: MessageStream, mut send: SendStream<Bytes>) {
+    while let Some(msg) = messages.next().await {
+        let mut msg = match msg {
+            Ok(msg) => msg,
+            Err(status) => {
+                trace!("Can not encode grpc message: {}", status);
+                send.send_reset(Reason::CANCEL);
+                return;
+            }
+        };
+
+        while !msg.is_empty() {
+            send.reserve_capacity(msg.len());
+            match poll_fn(|cx| send.poll_capacity(cx)).await {
+                Some(Ok(cap)) => {
+                    let chunk = msg.split_to(std::cmp::min(cap, msg.len()));
+                    if send.send_data(chunk, false).is_err() {
+                        return;
+                    }
+                }
+                _ => return,
+            }
+        }
+    }
+    let _ = send.send_data(Bytes::new(), true);
+}
@@ -0,0 +1,200 @@
+use bytes::buf::BufMutExt;
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::codec::{Decoder, Encoder};
+
+use super::status::{Code, Status};
+
+/// Size of the message prefix: compressed flag and message length
+const PREFIX_SIZE: usize = 5;
+
+/// Default limit of the decoded message size
+const MAX_MESSAGE_SIZE: usize = 4 * 1024 * 1024;
+
+/// Message codec
+///
+/// Serializes messages carried by the gRPC frames. Implement it to plug in
+/// protobuf (e.g. `prost`) or any other serialization format.
+pub trait MessageCodec<T> {
+    /// Content type of the requests and responses
+    fn content_type(&self) -> &'static str {
+        "application/grpc"
+    }
+
+    /// Serialize message to `dst`
+    fn encode(&self, msg: &T, dst: &mut BytesMut) -> Result<(), Status>;
+
+    /// Deserialize message
+    fn decode(&self, src: Bytes) -> Result<T, Status>;
+}
+
+/// JSON message codec, for serde types
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonCodec;
+
+impl<T: Serialize + DeserializeOwned> MessageCodec<T> for JsonCodec {
+    fn content_type(&self) -> &'static str {
+        "application/grpc+json"
+    }
+
+    fn encode(&self, msg: &T, dst: &mut BytesMut) -> Result<(), Status> {
+        serde_json::to_writer(dst.writer(), msg)
+            .map_err(|e| Status::new(Code::Internal, e.to_string()))
+    }
+
+    fn decode(&self, src: Bytes) -> Result<T, Status> {
+        serde_json::from_slice(&src).map_err(|e| Status::new(Code::InvalidArgument, e.to_string()))
+    }
+}
+
+/// gRPC length-prefixed message framing
+///
+/// Every message is prefixed with a compressed flag and its length. Compressed
+/// messages are not supported.
+#[derive(Debug, Clone)]
+pub struct Codec {
+    max_size: usize,
+}
+
+impl Codec {
+    /// Create new gRPC frame codec
+    pub fn new() -> Codec {
+        Codec {
+            max_size: MAX_MESSAGE_SIZE,
+        }
+    }
+
+    /// Set max size of the decoded message.
+    ///
+    /// By default max size is set to 4Mb.
+    pub fn max_size(mut self, size: usize) -> Self {
+        self.max_size = size;
+        self
+    }
+}
+
+impl Default for Codec {
+    fn default() -> Self {
+        Codec::new()
+    }
+}
+
+impl Encoder for Codec {
+    type Item = Bytes;
+    type Error = Status;
+
+    fn encode(&mut self, item: Bytes, dst: &mut BytesMut) -> Result<(), Status> {
+        dst.reserve(PREFIX_SIZE + item.len());
+        dst.put_u8(0);
+        dst.put_u32(item.len() as u32);
+        dst.extend_from_slice(&item);
+        Ok(())
+    }
+}
+
+impl Decoder for Codec {
+    type Item = Bytes;
+    type Error = Status;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Bytes>, Status> {
+        if src.len() < PREFIX_SIZE {
+            return Ok(None);
+        }
+
+        match src[0] {
+            0 => (),
+            1 => {
+                return Err(Status::new(
+                    Code::Unimplemented,
+                    "Compressed messages are not supported",
+                ))
+            }
+            flag => {
+                return Err(Status::new(
+                    Code::Internal,
+                    format!("Invalid message flag: {}", flag),
+                ))
+            }
+        }
+
+        let len = u32::from_be_bytes([src[1], src[2], src[3], src[4]]) as usize;
+        if len > self.max_size {
+            return Err(Status::new(
+                Code::ResourceExhausted,
+                format!("Message is larger than {} bytes", self.max_size),
+            ));
+        }
+        if src.len() < PREFIX_SIZE + len {
+            src.reserve(PREFIX_SIZE + len - src.len());
+            return Ok(None);
+        }
+
+        src.advance(PREFIX_SIZE);
+        Ok(Some(src.split_to(len).freeze()))
+    }
+}
+
+/// Serialize message and prefix it for sending
+pub(crate) fn encode_message<T, C>(codec: &C, msg: &T) -> Result<Bytes, Status>
+where
+    C: MessageCodec<T>,
+{
+    let mut buf = BytesMut::with_capacity(64);
+    buf.put_slice(&[0; PREFIX_SIZE]);
+    codec.encode(msg, &mut buf)?;
+
+    let len = (buf.len() - PREFIX_SIZE) as u32;
+    buf[1..PREFIX_SIZE].copy_from_slice(&len.to_be_bytes());
+    Ok(buf.freeze())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codec() {
+        let mut codec = Codec::new();
+        let mut buf = BytesMut::new();
+        codec
+            .encode(Bytes::from_static(b"hello"), &mut buf)
+            .unwrap();
+        codec.encode(Bytes::new(), &mut buf).unwrap();
+        assert_eq!(&buf[..5], &[0, 0, 0, 0, 5]);
+
+        let mut partial = buf.split_to(7);
+        assert!(codec.decode(&mut partial).unwrap().is_none());
+        partial.unsplit(buf);
+        let mut buf = partial;
+
+        assert_eq!(codec.decode(&mut buf).unwrap().unwrap(), "hello");
+        assert_eq!(codec.decode(&mut buf).unwrap().unwrap(), "");
+        assert!(codec.decode(&mut buf).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_codec_errors() {
+        let mut codec = Codec::new().max_size(4);
+
+        let mut buf = BytesMut::from(&[0, 0, 0, 0, 5][..]);
+        let err = codec.decode(&mut buf).err().unwrap();
+        assert_eq!(err.code(), Code::ResourceExhausted);
+
+        let mut buf = BytesMut::from(&[1, 0, 0, 0, 1, 0][..]);
+        let err = codec.decode(&mut buf).err().unwrap();
+        assert_eq!(err.code(), Code::Unimplemented);
+    }
+
+    #[test]
+    fn test_encode_message() {
+        let msg = encode_message(&JsonCodec, &vec![1, 2]).unwrap();
+        assert_eq!(&msg[..], b"\x00\x00\x00\x00\x05[1,2]");
+
+        let mut buf = BytesMut::from(&msg[..]);
+        let frame = Codec::new().decode(&mut buf).unwrap().unwrap();
+        let decoded: Vec<u32> = JsonCodec.decode(frame).unwrap();
+        assert_eq!(decoded, vec![1, 2]);
+    }
+}
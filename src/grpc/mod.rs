@@ -0,0 +1,24 @@
+//! gRPC support over HTTP/2.
+//!
+//! `Server` routes calls to method services and is used as a regular http
+//! service factory, `Client` calls methods over an established HTTP/2
+//! connection. Unary, server streaming, client streaming and bidirectional
+//! streaming calls are supported, along with metadata, deadlines and status
+//! trailers.
+//!
+//! Messages are serialized with a `MessageCodec`, `JsonCodec` is provided
+//! for serde types. Implement `MessageCodec` to use protobuf.
+mod client;
+mod codec;
+mod request;
+mod server;
+mod status;
+mod streaming;
+mod timeout;
+
+pub use self::client::Client;
+pub use self::codec::{Codec, JsonCodec, MessageCodec};
+pub use self::request::{Request, Response};
+pub use self::server::{Server, ServerService};
+pub use self::status::{Code, Status};
+pub use self::streaming::Streaming;
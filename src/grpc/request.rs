@@ -0,0 +1,156 @@
+use std::time::Duration;
+
+use crate::http::header::HeaderMap;
+use crate::timer::Instant;
+
+/// gRPC request
+///
+/// Carries the message, or the `Streaming` of messages, along with the
+/// request metadata and deadline.
+#[derive(Debug)]
+pub struct Request<T> {
+    metadata: HeaderMap,
+    message: T,
+    timeout: Option<Duration>,
+    deadline: Option<Instant>,
+}
+
+impl<T> Request<T> {
+    /// Create new request
+    pub fn new(message: T) -> Self {
+        Request {
+            metadata: HeaderMap::new(),
+            message,
+            timeout: None,
+            deadline: None,
+        }
+    }
+
+    /// Request metadata
+    pub fn metadata(&self) -> &HeaderMap {
+        &self.metadata
+    }
+
+    /// Mutable request metadata
+    pub fn metadata_mut(&mut self) -> &mut HeaderMap {
+        &mut self.metadata
+    }
+
+    /// Reference to the message
+    pub fn get_ref(&self) -> &T {
+        &self.message
+    }
+
+    /// Mutable reference to the message
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.message
+    }
+
+    /// Consume request and return the message
+    pub fn into_inner(self) -> T {
+        self.message
+    }
+
+    /// Set call timeout, sent to the server in the `grpc-timeout` header.
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = Some(timeout);
+    }
+
+    /// Call timeout set with `set_timeout()`
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Deadline of the call on the server, taken from the `grpc-timeout`
+    /// header.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// Map the message, keeping metadata and deadline
+    pub fn map<U, F>(self, f: F) -> Request<U>
+    where
+        F: FnOnce(T) -> U,
+    {
+        Request {
+            metadata: self.metadata,
+            message: f(self.message),
+            timeout: self.timeout,
+            deadline: self.deadline,
+        }
+    }
+
+    pub(crate) fn from_parts(metadata: HeaderMap, message: T, deadline: Option<Instant>) -> Self {
+        Request {
+            metadata,
+            message,
+            timeout: None,
+            deadline,
+        }
+    }
+
+    pub(crate) fn into_parts(self) -> (HeaderMap, T, Option<Duration>) {
+        (self.metadata, self.message, self.timeout)
+    }
+}
+
+/// gRPC response
+#[derive(Debug)]
+pub struct Response<T> {
+    metadata: HeaderMap,
+    message: T,
+}
+
+impl<T> Response<T> {
+    /// Create new response
+    pub fn new(message: T) -> Self {
+        Response {
+            metadata: HeaderMap::new(),
+            message,
+        }
+    }
+
+    /// Response metadata
+    pub fn metadata(&self) -> &HeaderMap {
+        &self.metadata
+    }
+
+    /// Mutable response metadata
+    pub fn metadata_mut(&mut self) -> &mut HeaderMap {
+        &mut self.metadata
+    }
+
+    /// Reference to the message
+    pub fn get_ref(&self) -> &T {
+        &self.message
+    }
+
+    /// Mutable reference to the message
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.message
+    }
+
+    /// Consume response and return the message
+    pub fn into_inner(self) -> T {
+        self.message
+    }
+
+    /// Map the message, keeping metadata
+    pub fn map<U, F>(self, f: F) -> Response<U>
+    where
+        F: FnOnce(T) -> U,
+    {
+        Response {
+            metadata: self.metadata,
+            message: f(self.message),
+        }
+    }
+
+    pub(crate) fn from_parts(metadata: HeaderMap, message: T) -> Self {
+        Response { metadata, message }
+    }
+
+    pub(crate) fn into_parts(self) -> (HeaderMap, T) {
+        (self.metadata, self.message)
+    }
+}
@@ -0,0 +1,461 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+use bytes::Bytes;
+use futures_core::Stream;
+use futures_util::future::{join_all, poll_fn, FutureExt, LocalBoxFuture};
+use futures_util::stream::{self, LocalBoxStream, StreamExt};
+
+use crate::http::body::Body;
+use crate::http::error::Error;
+use crate::http::header::{HeaderName, CONTENT_TYPE};
+use crate::http::{HttpMessage, Method, Payload, Request as HttpRequest, Response as HttpResponse};
+use crate::service::{IntoServiceFactory, Service, ServiceFactory};
+use crate::timer::{timeout_at, Instant};
+
+use super::codec::{encode_message, JsonCodec, MessageCodec};
+use super::request::{Request, Response};
+use super::status::{Code, Status};
+use super::streaming::{EncodeBody, Streaming};
+use super::timeout;
+
+/// Prefixed messages of a method response
+pub(crate) type MessageStream = LocalBoxStream<'static, Result<Bytes, Status>>;
+
+type Handler = Box<
+    dyn Fn(Request<Payload>) -> LocalBoxFuture<'static, Result<Response<MessageStream>, Status>>,
+>;
+
+type HandlerFactory = Box<dyn Fn() -> LocalBoxFuture<'static, Result<Handler, ()>>>;
+
+struct MethodEntry {
+    content_type: &'static str,
+    factory: HandlerFactory,
+}
+
+/// gRPC server
+///
+/// Routes requests to the method services by path, `/package.Service/Method`.
+/// Method services are regular `Service`s of gRPC `Request`s and `Response`s
+/// with `Status` errors, registered according to the kind of the call.
+///
+/// The server is a `ServiceFactory` of http requests to use with
+/// `HttpService` or `H2Service`.
+///
+/// ```rust
+/// use kayrx::grpc::{self, Request, Response, Status};
+/// use kayrx::http::HttpService;
+/// use kayrx::service::fn_service;
+///
+/// let server = grpc::Server::new().unary(
+///     "/greeter.Greeter/Hello",
+///     fn_service(|req: Request<String>| async move {
+///         Ok::<_, Status>(Response::new(format!("Hello {}", req.into_inner())))
+///     }),
+/// );
+/// let service = HttpService::build().h2(server).tcp();
+/// ```
+pub struct Server<C = JsonCodec> {
+    codec: C,
+    max_message_size: usize,
+    methods: HashMap<String, MethodEntry>,
+}
+
+impl Server<JsonCodec> {
+    /// Create server with the JSON message codec
+    pub fn new() -> Self {
+        Server::with_codec(JsonCodec)
+    }
+}
+
+impl Default for Server<JsonCodec> {
+    fn default() -> Self {
+        Server::new()
+    }
+}
+
+impl<C: Clone + 'static> Server<C> {
+    /// Create server with the message codec
+    pub fn with_codec(codec: C) -> Self {
+        Server {
+            codec,
+            max_message_size: 4 * 1024 * 1024,
+            methods: HashMap::new(),
+        }
+    }
+
+    /// Set max size of the request messages.
+    ///
+    /// By default max size is set to 4Mb.
+    pub fn max_message_size(mut self, size: usize) -> Self {
+        self.max_message_size = size;
+        self
+    }
+
+    /// Register unary method, a single request and a single response
+    /// message.
+    pub fn unary<F, T, Req, Res>(self, path: &str, factory: F) -> Self
+    where
+        F: IntoServiceFactory<T>,
+        T: ServiceFactory<
+                Config = (),
+                Request = Request<Req>,
+                Response = Response<Res>,
+                Error = Status,
+            > + 'static,
+        T::Service: 'static,
+        <T::Service as Service>::Future: 'static,
+        C: MessageCodec<Req> + MessageCodec<Res>,
+        Req: 'static,
+        Res: 'static,
+    {
+        let codec = self.codec.clone();
+        let max_size = self.max_message_size;
+        let content_type = MessageCodec::<Res>::content_type(&self.codec);
+
+        self.method(
+            path,
+            content_type,
+            factory.into_factory(),
+            move |srv, req| {
+                let codec = codec.clone();
+                async move {
+                    let mut req = messages::<Req, _>(req, codec.clone(), max_size);
+                    let msg = single(req.get_mut()).await?;
+                    let res = call(&srv, req.map(|_| msg)).await?;
+                    Ok(res.map(|msg| encode_once(&codec, &msg)))
+                }
+            },
+        )
+    }
+
+    /// Register server streaming method, a single request message and a
+    /// stream of response messages.
+    pub fn server_streaming<F, T, Req, Res, S>(self, path: &str, factory: F) -> Self
+    where
+        F: IntoServiceFactory<T>,
+        T: ServiceFactory<
+                Config = (),
+                Request = Request<Req>,
+                Response = Response<S>,
+                Error = Status,
+            > + 'static,
+        T::Service: 'static,
+        <T::Service as Service>::Future: 'static,
+        S: Stream<Item = Result<Res, Status>> + 'static,
+        C: MessageCodec<Req> + MessageCodec<Res>,
+        Req: 'static,
+        Res: 'static,
+    {
+        let codec = self.codec.clone();
+        let max_size = self.max_message_size;
+        let content_type = MessageCodec::<Res>::content_type(&self.codec);
+
+        self.method(
+            path,
+            content_type,
+            factory.into_factory(),
+            move |srv, req| {
+                let codec = codec.clone();
+                async move {
+                    let mut req = messages::<Req, _>(req, codec.clone(), max_size);
+                    let msg = single(req.get_mut()).await?;
+                    let res = call(&srv, req.map(|_| msg)).await?;
+                    Ok(res.map(|stream| encode_stream(codec, stream)))
+                }
+            },
+        )
+    }
+
+    /// Register client streaming method, a stream of request messages and a
+    /// single response message.
+    pub fn client_streaming<F, T, Req, Res>(self, path: &str, factory: F) -> Self
+    where
+        F: IntoServiceFactory<T>,
+        T: ServiceFactory<
+                Config = (),
+                Request = Request<Streaming<Req>>,
+                Response = Response<Res>,
+                Error = Status,
+            > + 'static,
+        T::Service: 'static,
+        <T::Service as Service>::Future: 'static,
+        C: MessageCodec<Req> + MessageCodec<Res>,
+        Req: 'static,
+        Res: 'static,
+    {
+        let codec = self.codec.clone();
+        let max_size = self.max_message_size;
+        let content_type = MessageCodec::<Res>::content_type(&self.codec);
+
+        self.method(
+            path,
+            content_type,
+            factory.into_factory(),
+            move |srv, req| {
+                let codec = codec.clone();
+                async move {
+                    let req = messages::<Req, _>(req, codec.clone(), max_size);
+                    let res = call(&srv, req).await?;
+                    Ok(res.map(|msg| encode_once(&codec, &msg)))
+                }
+            },
+        )
+    }
+
+    /// Register bidirectional streaming method, streams of request and
+    /// response messages.
+    pub fn streaming<F, T, Req, Res, S>(self, path: &str, factory: F) -> Self
+    where
+        F: IntoServiceFactory<T>,
+        T: ServiceFactory<
+                Config = (),
+                Request = Request<Streaming<Req>>,
+                Response = Response<S>,
+                Error = Status,
+            > + 'static,
+        T::Service: 'static,
+        <T::Service as Service>::Future: 'static,
+        S: Stream<Item = Result<Res, Status>> + 'static,
+        C: MessageCodec<Req> + MessageCodec<Res>,
+        Req: 'static,
+        Res: 'static,
+    {
+        let codec = self.codec.clone();
+        let max_size = self.max_message_size;
+        let content_type = MessageCodec::<Res>::content_type(&self.codec);
+
+        self.method(
+            path,
+            content_type,
+            factory.into_factory(),
+            move |srv, req| {
+                let codec = codec.clone();
+                async move {
+                    let req = messages::<Req, _>(req, codec.clone(), max_size);
+                    let res = call(&srv, req).await?;
+                    Ok(res.map(|stream| encode_stream(codec, stream)))
+                }
+            },
+        )
+    }
+
+    fn method<T, H, Fut>(
+        mut self,
+        path: &str,
+        content_type: &'static str,
+        factory: T,
+        handler: H,
+    ) -> Self
+    where
+        T: ServiceFactory<Config = ()> + 'static,
+        T::Service: 'static,
+        H: Fn(Rc<RefCell<T::Service>>, Request<Payload>) -> Fut + Clone + 'static,
+        Fut: Future<Output = Result<Response<MessageStream>, Status>> + 'static,
+    {
+        let path = path.to_owned();
+        let name = path.clone();
+        let factory: HandlerFactory = Box::new(move || {
+            let fut = factory.new_service(());
+            let handler = handler.clone();
+            let name = name.clone();
+            async move {
+                let srv = match fut.await {
+                    Ok(srv) => Rc::new(RefCell::new(srv)),
+                    Err(_) => {
+                        error!("Can not construct grpc method service: {}", name);
+                        return Err(());
+                    }
+                };
+                let handler: Handler = Box::new(move |req| handler(srv.clone(), req).boxed_local());
+                Ok(handler)
+            }
+            .boxed_local()
+        });
+
+        self.methods.insert(
+            path,
+            MethodEntry {
+                content_type,
+                factory,
+            },
+        );
+        self
+    }
+}
+
+impl<C> fmt::Debug for Server<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("grpc::Server")
+            .field("methods", &self.methods.keys().collect::<Vec<_>>())
+            .field("max_message_size", &self.max_message_size)
+            .finish()
+    }
+}
+
+impl<C> ServiceFactory for Server<C> {
+    type Config = ();
+    type Request = HttpRequest;
+    type Response = HttpResponse<Body>;
+    type Error = Error;
+    type InitError = ();
+    type Service = ServerService;
+    type Future = LocalBoxFuture<'static, Result<ServerService, ()>>;
+
+    fn new_service(&self, _: ()) -> Self::Future {
+        let methods = join_all(self.methods.iter().map(|(path, method)| {
+            let path = path.clone();
+            let content_type = method.content_type;
+            (method.factory)().map(move |res| res.map(|h| (path, (content_type, h))))
+        }));
+
+        async move {
+            let methods = methods.await.into_iter().collect::<Result<_, ()>>()?;
+            Ok(ServerService {
+                methods: Rc::new(methods),
+            })
+        }
+        .boxed_local()
+    }
+}
+
+/// gRPC server service
+pub struct ServerService {
+    methods: Rc<HashMap<String, (&'static str, Handler)>>,
+}
+
+impl fmt::Debug for ServerService {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("grpc::ServerService")
+            .field("methods", &self.methods.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl Service for ServerService {
+    type Request = HttpRequest;
+    type Response = HttpResponse<Body>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<HttpResponse<Body>, Error>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, mut req: HttpRequest) -> Self::Future {
+        let methods = self.methods.clone();
+
+        async move {
+            if req.method() != Method::POST {
+                return Ok(HttpResponse::MethodNotAllowed().finish());
+            }
+            let is_grpc = req
+                .headers()
+                .get(CONTENT_TYPE)
+                .and_then(|ct| ct.to_str().ok())
+                .map(|ct| ct.starts_with("application/grpc"))
+                .unwrap_or(false);
+            if !is_grpc {
+                return Ok(HttpResponse::UnsupportedMediaType().finish());
+            }
+
+            let (content_type, handler) = match methods.get(req.path()) {
+                Some(method) => method,
+                None => {
+                    let status = Status::new(
+                        Code::Unimplemented,
+                        format!("Method not found: {}", req.path()),
+                    );
+                    return Ok(status_response("application/grpc", &status));
+                }
+            };
+
+            let deadline = req
+                .headers()
+                .get("grpc-timeout")
+                .and_then(timeout::parse)
+                .map(|timeout| Instant::now() + timeout);
+            let payload = req.take_payload();
+            let call = handler(Request::from_parts(
+                req.headers().clone(),
+                payload,
+                deadline,
+            ));
+
+            let res = match deadline {
+                Some(deadline) => match timeout_at(deadline, call).await {
+                    Ok(res) => res,
+                    Err(_) => Err(Status::new(Code::DeadlineExceeded, "Deadline exceeded")),
+                },
+                None => call.await,
+            };
+
+            Ok(match res {
+                Ok(res) => {
+                    let (metadata, stream) = res.into_parts();
+                    let mut res = HttpResponse::Ok()
+                        .content_type(*content_type)
+                        .body(Body::from_message(EncodeBody::new(stream, deadline)));
+                    for (name, value) in metadata.iter() {
+                        res.headers_mut().append(name.clone(), value.clone());
+                    }
+                    res
+                }
+                Err(status) => status_response(content_type, &status),
+            })
+        }
+        .boxed_local()
+    }
+}
+
+/// Trailers-only response, the status is sent with the response headers
+fn status_response(content_type: &'static str, status: &Status) -> HttpResponse<Body> {
+    let mut res = HttpResponse::Ok().content_type(content_type).finish();
+    status.to_headers(res.headers_mut());
+    res
+}
+
+/// Decode request messages
+fn messages<Req, C>(req: Request<Payload>, codec: C, max_size: usize) -> Request<Streaming<Req>>
+where
+    C: MessageCodec<Req> + 'static,
+{
+    let deadline = req.deadline();
+    req.map(|payload| Streaming::request(payload, codec, max_size).deadline(deadline))
+}
+
+/// Read the only message of a unary call
+pub(crate) async fn single<T>(stream: &mut Streaming<T>) -> Result<T, Status> {
+    let msg = match stream.message().await? {
+        Some(msg) => msg,
+        None => return Err(Status::new(Code::Internal, "Missing message")),
+    };
+    if stream.message().await?.is_some() {
+        return Err(Status::new(Code::Internal, "Expected a single message"));
+    }
+    Ok(msg)
+}
+
+async fn call<S: Service>(srv: &RefCell<S>, req: S::Request) -> Result<S::Response, S::Error> {
+    poll_fn(|cx| srv.borrow_mut().poll_ready(cx)).await?;
+    let fut = srv.borrow_mut().call(req);
+    fut.await
+}
+
+fn encode_once<T, C: MessageCodec<T>>(codec: &C, msg: &T) -> MessageStream {
+    stream::once(futures_util::future::ready(encode_message(codec, msg))).boxed_local()
+}
+
+pub(crate) fn encode_stream<T, C, S>(codec: C, stream: S) -> MessageStream
+where
+    C: MessageCodec<T> + 'static,
+    S: Stream<Item = Result<T, Status>> + 'static,
+{
+    stream
+        .map(move |msg| msg.and_then(|msg| encode_message(&codec, &msg)))
+        .boxed_local()
+}
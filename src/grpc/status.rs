@@ -0,0 +1,229 @@
+use std::{fmt, io};
+
+use percent_encoding::{percent_decode, percent_encode, AsciiSet, CONTROLS};
+
+use crate::http::error::PayloadError;
+use crate::http::header::{HeaderMap, HeaderName, HeaderValue};
+use crate::http::StatusCode;
+
+// grpc-message is percent-encoded, everything outside of printable ascii
+// and the percent sign itself is escaped
+const MESSAGE: &AsciiSet = &CONTROLS.add(b'%');
+
+/// gRPC status codes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Code {
+    /// The call completed successfully
+    Ok = 0,
+    /// The call was cancelled
+    Cancelled = 1,
+    /// Unknown error
+    Unknown = 2,
+    /// Client specified an invalid argument
+    InvalidArgument = 3,
+    /// Deadline expired before the call could complete
+    DeadlineExceeded = 4,
+    /// Some requested entity was not found
+    NotFound = 5,
+    /// Some entity that the client tried to create already exists
+    AlreadyExists = 6,
+    /// The caller does not have permission to execute the call
+    PermissionDenied = 7,
+    /// Some resource has been exhausted
+    ResourceExhausted = 8,
+    /// The system is not in a state required for the call
+    FailedPrecondition = 9,
+    /// The call was aborted
+    Aborted = 10,
+    /// The call was attempted past the valid range
+    OutOfRange = 11,
+    /// The call is not implemented or supported
+    Unimplemented = 12,
+    /// Internal error
+    Internal = 13,
+    /// The service is currently unavailable
+    Unavailable = 14,
+    /// Unrecoverable data loss or corruption
+    DataLoss = 15,
+    /// The request does not have valid authentication credentials
+    Unauthenticated = 16,
+}
+
+impl Code {
+    /// Convert numeric status code, unknown codes map to `Code::Unknown`.
+    pub fn from_i32(code: i32) -> Code {
+        match code {
+            0 => Code::Ok,
+            1 => Code::Cancelled,
+            2 => Code::Unknown,
+            3 => Code::InvalidArgument,
+            4 => Code::DeadlineExceeded,
+            5 => Code::NotFound,
+            6 => Code::AlreadyExists,
+            7 => Code::PermissionDenied,
+            8 => Code::ResourceExhausted,
+            9 => Code::FailedPrecondition,
+            10 => Code::Aborted,
+            11 => Code::OutOfRange,
+            12 => Code::Unimplemented,
+            13 => Code::Internal,
+            14 => Code::Unavailable,
+            15 => Code::DataLoss,
+            16 => Code::Unauthenticated,
+            _ => Code::Unknown,
+        }
+    }
+
+    /// Numeric value of the status code
+    pub fn as_i32(self) -> i32 {
+        self as i32
+    }
+
+    /// Status code for a non-200 http response, as described by the
+    /// gRPC over HTTP/2 spec
+    pub(crate) fn from_http(status: StatusCode) -> Code {
+        match status {
+            StatusCode::BAD_REQUEST => Code::Internal,
+            StatusCode::UNAUTHORIZED => Code::Unauthenticated,
+            StatusCode::FORBIDDEN => Code::PermissionDenied,
+            StatusCode::NOT_FOUND => Code::Unimplemented,
+            StatusCode::TOO_MANY_REQUESTS
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT => Code::Unavailable,
+            _ => Code::Unknown,
+        }
+    }
+}
+
+/// gRPC call status
+///
+/// Sent to the client in the `grpc-status` and `grpc-message` trailers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Status {
+    code: Code,
+    message: String,
+}
+
+impl Status {
+    /// Create new status
+    pub fn new<T: Into<String>>(code: Code, message: T) -> Self {
+        Status {
+            code,
+            message: message.into(),
+        }
+    }
+
+    /// Successful call status
+    pub fn ok() -> Self {
+        Status::new(Code::Ok, "")
+    }
+
+    /// Status code
+    pub fn code(&self) -> Code {
+        self.code
+    }
+
+    /// Status message
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Read status from `grpc-status` and `grpc-message` headers
+    pub(crate) fn from_headers(headers: &HeaderMap) -> Option<Status> {
+        let code = headers
+            .get("grpc-status")?
+            .to_str()
+            .ok()
+            .and_then(|code| code.trim().parse::<i32>().ok())
+            .map(Code::from_i32)
+            .unwrap_or(Code::Unknown);
+        let message = headers
+            .get("grpc-message")
+            .map(|msg| {
+                percent_decode(msg.as_bytes())
+                    .decode_utf8_lossy()
+                    .into_owned()
+            })
+            .unwrap_or_default();
+        Some(Status::new(code, message))
+    }
+
+    /// Write status to `grpc-status` and `grpc-message` headers
+    pub(crate) fn to_headers(&self, headers: &mut HeaderMap) {
+        headers.insert(
+            HeaderName::from_static("grpc-status"),
+            HeaderValue::from(self.code.as_i32()),
+        );
+        if !self.message.is_empty() {
+            let message = percent_encode(self.message.as_bytes(), MESSAGE).to_string();
+            if let Ok(value) = HeaderValue::from_str(&message) {
+                headers.insert(HeaderName::from_static("grpc-message"), value);
+            }
+        }
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "grpc status {:?}", self.code)?;
+        if !self.message.is_empty() {
+            write!(f, ": {}", self.message)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Status {}
+
+impl From<io::Error> for Status {
+    fn from(err: io::Error) -> Self {
+        Status::new(Code::Unavailable, err.to_string())
+    }
+}
+
+impl From<PayloadError> for Status {
+    fn from(err: PayloadError) -> Self {
+        Status::new(Code::Internal, err.to_string())
+    }
+}
+
+impl From<crate::http::h2::Error> for Status {
+    fn from(err: crate::http::h2::Error) -> Self {
+        if err.is_io() {
+            Status::new(Code::Unavailable, err.to_string())
+        } else {
+            Status::new(Code::Internal, err.to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_headers() {
+        let status = Status::new(Code::NotFound, "no such user: 100%");
+        let mut headers = HeaderMap::new();
+        status.to_headers(&mut headers);
+        assert_eq!(headers.get("grpc-status").unwrap(), "5");
+        assert_eq!(headers.get("grpc-message").unwrap(), "no such user: 100%25");
+        assert_eq!(Status::from_headers(&headers).unwrap(), status);
+
+        let mut headers = HeaderMap::new();
+        Status::ok().to_headers(&mut headers);
+        assert_eq!(headers.get("grpc-status").unwrap(), "0");
+        assert!(!headers.contains_key("grpc-message"));
+
+        headers.insert(
+            HeaderName::from_static("grpc-status"),
+            HeaderValue::from_static("99"),
+        );
+        assert_eq!(
+            Status::from_headers(&headers).unwrap().code(),
+            Code::Unknown
+        );
+        assert!(Status::from_headers(&HeaderMap::new()).is_none());
+    }
+}
@@ -0,0 +1,221 @@
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use bytes::{Bytes, BytesMut};
+use futures_core::Stream;
+use futures_util::future::poll_fn;
+use futures_util::stream::LocalBoxStream;
+
+use crate::codec::Decoder;
+use crate::http::body::{BodySize, MessageBody};
+use crate::http::error::Error;
+use crate::http::header::HeaderMap;
+use crate::http::{Payload, Trailers};
+use crate::timer::{delay_until, Delay, Instant};
+
+use super::codec::{Codec, MessageCodec};
+use super::status::{Code, Status};
+
+/// Stream of messages of a request or response
+///
+/// Yields an error if the message can not be decoded, the deadline of the
+/// call expires or the peer ends the call with a non-ok status.
+pub struct Streaming<T> {
+    payload: Payload,
+    decode: Box<dyn Fn(Bytes) -> Result<T, Status>>,
+    codec: Codec,
+    buf: BytesMut,
+    trailers: Option<Trailers>,
+    deadline: Option<Delay>,
+    eof: bool,
+}
+
+impl<T> Streaming<T> {
+    /// Messages of a request, the request status is not checked.
+    pub(crate) fn request<C>(payload: Payload, codec: C, max_size: usize) -> Self
+    where
+        C: MessageCodec<T> + 'static,
+    {
+        Streaming::new(payload, codec, max_size, None)
+    }
+
+    /// Messages of a response, the status is read from `trailers` once
+    /// the payload ends.
+    pub(crate) fn response<C>(
+        payload: Payload,
+        codec: C,
+        max_size: usize,
+        trailers: Trailers,
+    ) -> Self
+    where
+        C: MessageCodec<T> + 'static,
+    {
+        Streaming::new(payload, codec, max_size, Some(trailers))
+    }
+
+    fn new<C>(payload: Payload, codec: C, max_size: usize, trailers: Option<Trailers>) -> Self
+    where
+        C: MessageCodec<T> + 'static,
+    {
+        Streaming {
+            payload,
+            decode: Box::new(move |msg| codec.decode(msg)),
+            codec: Codec::new().max_size(max_size),
+            buf: BytesMut::new(),
+            trailers,
+            deadline: None,
+            eof: false,
+        }
+    }
+
+    /// Fail the stream with `DEADLINE_EXCEEDED` once `deadline` expires
+    pub(crate) fn deadline(mut self, deadline: Option<Instant>) -> Self {
+        self.deadline = deadline.map(delay_until);
+        self
+    }
+
+    /// Receive next message.
+    pub async fn message(&mut self) -> Result<Option<T>, Status> {
+        match poll_fn(|cx| Pin::new(&mut *self).poll_next(cx)).await {
+            Some(Ok(msg)) => Ok(Some(msg)),
+            Some(Err(status)) => Err(status),
+            None => Ok(None),
+        }
+    }
+
+    /// Status of the finished call
+    fn status(&self) -> Option<Status> {
+        let trailers = self.trailers.as_ref()?.get();
+        match trailers.as_ref().and_then(Status::from_headers) {
+            Some(ref status) if status.code() == Code::Ok => None,
+            Some(status) => Some(status),
+            None => Some(Status::new(Code::Internal, "Missing grpc-status")),
+        }
+    }
+}
+
+impl<T> Stream for Streaming<T> {
+    type Item = Result<T, Status>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            if this.eof {
+                return Poll::Ready(None);
+            }
+
+            if let Some(ref mut delay) = this.deadline {
+                if Pin::new(delay).poll(cx).is_ready() {
+                    this.eof = true;
+                    return Poll::Ready(Some(Err(Status::new(
+                        Code::DeadlineExceeded,
+                        "Deadline exceeded",
+                    ))));
+                }
+            }
+
+            match this.codec.decode(&mut this.buf) {
+                Ok(Some(msg)) => return Poll::Ready(Some((this.decode)(msg))),
+                Ok(None) => (),
+                Err(status) => {
+                    this.eof = true;
+                    return Poll::Ready(Some(Err(status)));
+                }
+            }
+
+            match Pin::new(&mut this.payload).poll_next(cx) {
+                Poll::Ready(Some(Ok(chunk))) => this.buf.extend_from_slice(&chunk),
+                Poll::Ready(Some(Err(err))) => {
+                    this.eof = true;
+                    return Poll::Ready(Some(Err(err.into())));
+                }
+                Poll::Ready(None) => {
+                    this.eof = true;
+                    if !this.buf.is_empty() {
+                        return Poll::Ready(Some(Err(Status::new(
+                            Code::Internal,
+                            "Message is truncated",
+                        ))));
+                    }
+                    return Poll::Ready(this.status().map(Err));
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+impl<T> fmt::Debug for Streaming<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Streaming")
+            .field("buffered", &self.buf.len())
+            .field("eof", &self.eof)
+            .finish()
+    }
+}
+
+/// Response body, prefixed messages followed by the status trailers
+pub(crate) struct EncodeBody {
+    stream: LocalBoxStream<'static, Result<Bytes, Status>>,
+    deadline: Option<Delay>,
+    status: Option<Status>,
+    eof: bool,
+}
+
+impl EncodeBody {
+    pub(crate) fn new(
+        stream: LocalBoxStream<'static, Result<Bytes, Status>>,
+        deadline: Option<Instant>,
+    ) -> Self {
+        EncodeBody {
+            stream,
+            deadline: deadline.map(delay_until),
+            status: None,
+            eof: false,
+        }
+    }
+}
+
+impl MessageBody for EncodeBody {
+    fn size(&self) -> BodySize {
+        BodySize::Stream
+    }
+
+    fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<Bytes, Error>>> {
+        if self.eof {
+            return Poll::Ready(None);
+        }
+
+        if let Some(ref mut delay) = self.deadline {
+            if Pin::new(delay).poll(cx).is_ready() {
+                self.eof = true;
+                self.status = Some(Status::new(Code::DeadlineExceeded, "Deadline exceeded"));
+                return Poll::Ready(None);
+            }
+        }
+
+        match self.stream.as_mut().poll_next(cx) {
+            Poll::Ready(Some(Ok(msg))) => Poll::Ready(Some(Ok(msg))),
+            Poll::Ready(Some(Err(status))) => {
+                self.eof = true;
+                self.status = Some(status);
+                Poll::Ready(None)
+            }
+            Poll::Ready(None) => {
+                self.eof = true;
+                self.status = Some(Status::ok());
+                Poll::Ready(None)
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+
+    fn take_trailers(&mut self) -> Option<HeaderMap> {
+        let mut trailers = HeaderMap::new();
+        self.status.take()?.to_headers(&mut trailers);
+        Some(trailers)
+    }
+}
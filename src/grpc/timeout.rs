@@ -0,0 +1,86 @@
+//! `grpc-timeout` header
+use std::time::Duration;
+
+use crate::http::header::HeaderValue;
+
+// timeout values have at most 8 digits
+const MAX_VALUE: u128 = 99_999_999;
+
+const UNITS: [(char, u128); 6] = [
+    ('n', 1),
+    ('u', 1_000),
+    ('m', 1_000_000),
+    ('S', 1_000_000_000),
+    ('M', 60 * 1_000_000_000),
+    ('H', 60 * 60 * 1_000_000_000),
+];
+
+/// Parse `grpc-timeout` header value
+pub(crate) fn parse(value: &HeaderValue) -> Option<Duration> {
+    let value = value.to_str().ok()?;
+    if value.len() < 2 || value.len() > 9 {
+        return None;
+    }
+    let (digits, unit) = value.split_at(value.len() - 1);
+    if !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let digits: u64 = digits.parse().ok()?;
+
+    match unit {
+        "H" => Some(Duration::from_secs(digits * 60 * 60)),
+        "M" => Some(Duration::from_secs(digits * 60)),
+        "S" => Some(Duration::from_secs(digits)),
+        "m" => Some(Duration::from_millis(digits)),
+        "u" => Some(Duration::from_micros(digits)),
+        "n" => Some(Duration::from_nanos(digits)),
+        _ => None,
+    }
+}
+
+/// Encode `grpc-timeout` header value, using the finest unit that fits.
+pub(crate) fn encode(timeout: Duration) -> HeaderValue {
+    let nanos = timeout.as_nanos();
+    for &(unit, per) in UNITS.iter() {
+        // round up, so that the deadline is never shortened
+        let value = (nanos + per - 1) / per;
+        if value <= MAX_VALUE {
+            return HeaderValue::from_str(&format!("{}{}", value, unit)).unwrap();
+        }
+    }
+    HeaderValue::from_static("99999999H")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let parse = |s: &'static str| parse(&HeaderValue::from_static(s));
+
+        assert_eq!(parse("1H"), Some(Duration::from_secs(3600)));
+        assert_eq!(parse("2M"), Some(Duration::from_secs(120)));
+        assert_eq!(parse("30S"), Some(Duration::from_secs(30)));
+        assert_eq!(parse("250m"), Some(Duration::from_millis(250)));
+        assert_eq!(parse("100u"), Some(Duration::from_micros(100)));
+        assert_eq!(parse("99999999n"), Some(Duration::from_nanos(99_999_999)));
+        assert_eq!(parse("100000000n"), None);
+        assert_eq!(parse("10"), None);
+        assert_eq!(parse("m"), None);
+        assert_eq!(parse("-1S"), None);
+        assert_eq!(parse("1s"), None);
+    }
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(encode(Duration::from_nanos(500)), "500n");
+        assert_eq!(encode(Duration::from_millis(250)), "250000u");
+        assert_eq!(encode(Duration::from_secs(1)), "1000000u");
+        assert_eq!(encode(Duration::from_secs(3600)), "3600000m");
+        assert_eq!(encode(Duration::from_secs(10 * 24 * 3600)), "864000S");
+
+        let timeout = Duration::from_millis(1234);
+        assert!(parse(&encode(timeout)).unwrap() >= timeout);
+    }
+}
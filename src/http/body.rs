@@ -8,6 +8,7 @@ use futures_core::Stream;
 use pin_project::{pin_project, project};
 
 use crate::http::error::Error;
+use crate::http::header::HeaderMap;
 
 #[derive(Debug, PartialEq, Copy, Clone)]
 /// Body size hint
@@ -36,6 +37,15 @@ pub trait MessageBody {
     fn size(&self) -> BodySize;
 
     fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<Bytes, Error>>>;
+
+    /// Trailing headers, sent after the last chunk of the body.
+    ///
+    /// Called once `poll_next()` returned `None`. Trailers are sent over
+    /// HTTP/2 and with chunked HTTP/1.1 bodies, other transfer encodings
+    /// drop them.
+    fn take_trailers(&mut self) -> Option<HeaderMap> {
+        None
+    }
 }
 
 impl MessageBody for () {
@@ -56,6 +66,10 @@ impl<T: MessageBody> MessageBody for Box<T> {
     fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<Bytes, Error>>> {
         self.as_mut().poll_next(cx)
     }
+
+    fn take_trailers(&mut self) -> Option<HeaderMap> {
+        self.as_mut().take_trailers()
+    }
 }
 
 #[pin_project]
@@ -103,6 +117,13 @@ impl<B: MessageBody> MessageBody for ResponseBody<B> {
             ResponseBody::Other(ref mut body) => body.poll_next(cx),
         }
     }
+
+    fn take_trailers(&mut self) -> Option<HeaderMap> {
+        match self {
+            ResponseBody::Body(ref mut body) => body.take_trailers(),
+            ResponseBody::Other(ref mut body) => body.take_trailers(),
+        }
+    }
 }
 
 impl<B: MessageBody> Stream for ResponseBody<B> {
@@ -188,6 +209,13 @@ impl MessageBody for Body {
             Body::Message(ref mut body) => body.poll_next(cx),
         }
     }
+
+    fn take_trailers(&mut self) -> Option<HeaderMap> {
+        match self {
+            Body::Message(ref mut body) => body.take_trailers(),
+            _ => None,
+        }
+    }
 }
 
 impl PartialEq for Body {
@@ -416,6 +444,51 @@ where
     }
 }
 
+/// Message body followed by trailing headers.
+///
+/// The body is always streamed, so HTTP/1.1 responses use chunked transfer
+/// encoding and can carry the trailers.
+///
+/// ```rust
+/// use kayrx::http::body::{Body, WithTrailers};
+/// use kayrx::http::header::{HeaderMap, HeaderName, HeaderValue};
+/// use kayrx::http::Response;
+///
+/// let mut trailers = HeaderMap::new();
+/// trailers.insert(
+///     HeaderName::from_static("grpc-status"),
+///     HeaderValue::from_static("0"),
+/// );
+/// let res = Response::Ok().body(Body::from_message(WithTrailers::new("data", trailers)));
+/// ```
+pub struct WithTrailers<B> {
+    body: B,
+    trailers: Option<HeaderMap>,
+}
+
+impl<B: MessageBody> WithTrailers<B> {
+    pub fn new(body: B, trailers: HeaderMap) -> Self {
+        WithTrailers {
+            body,
+            trailers: Some(trailers),
+        }
+    }
+}
+
+impl<B: MessageBody> MessageBody for WithTrailers<B> {
+    fn size(&self) -> BodySize {
+        BodySize::Stream
+    }
+
+    fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<Bytes, Error>>> {
+        self.body.poll_next(cx)
+    }
+
+    fn take_trailers(&mut self) -> Option<HeaderMap> {
+        self.trailers.take()
+    }
+}
+
 /// Type represent streaming body. This body implementation should be used
 /// if total size of stream is known. Data get sent as is without using transfer encoding.
 #[pin_project]
@@ -16,6 +16,7 @@ use crate::http::payload::Payload;
 
 use super::error::SendRequestError;
 use super::pool::{Acquired, Protocol};
+use super::proxy::Proxy;
 use super::{h1proto, h2proto};
 
 pub(crate) enum ConnectionType<Io> {
@@ -58,6 +59,8 @@ pub struct IoConnection<T> {
     io: Option<ConnectionType<T>>,
     created: time::Instant,
     pool: Option<Acquired<T>>,
+    /// Forward proxy the connection is open to
+    proxy: Option<Proxy>,
 }
 
 impl<T> fmt::Debug for IoConnection<T>
@@ -83,9 +86,15 @@ impl<T: AsyncRead + AsyncWrite + Unpin> IoConnection<T> {
             pool,
             created,
             io: Some(io),
+            proxy: None,
         }
     }
 
+    /// Send requests in absolute-form, to a forward proxy.
+    pub(crate) fn set_proxy(&mut self, proxy: Proxy) {
+        self.proxy = Some(proxy);
+    }
+
     pub(crate) fn into_inner(self) -> (ConnectionType<T>, time::Instant) {
         (self.io.unwrap(), self.created)
     }
@@ -114,8 +123,15 @@ where
     ) -> Self::Future {
         match self.io.take().unwrap() {
             ConnectionType::H1(io) => {
-                h1proto::send_request(io, head.into(), body, self.created, self.pool)
-                    .boxed_local()
+                h1proto::send_request(
+                    io,
+                    head.into(),
+                    body,
+                    self.created,
+                    self.pool,
+                    self.proxy,
+                )
+                .boxed_local()
             }
             ConnectionType::H2(io) => {
                 h2proto::send_request(io, head.into(), body, self.created, self.pool)
@@ -1,4 +1,5 @@
 use std::fmt;
+use std::future::Future;
 use std::marker::PhantomData;
 use std::time::Duration;
 use http::Uri;
@@ -14,6 +15,7 @@ use crate::util::timeout::{TimeoutError, TimeoutService};
 use super::connection::Connection;
 use super::error::ConnectError;
 use super::pool::{ConnectionPool, Protocol};
+use super::proxy::{Proxy, TunnelStream};
 use super::Connect;
 use crate::connect::ssl::rustls::ClientConfig;
 
@@ -42,6 +44,7 @@ pub struct Connector<T, U> {
     conn_keep_alive: Duration,
     disconnect_timeout: Duration,
     limit: usize,
+    proxy: Option<Proxy>,
     #[allow(dead_code)]
     ssl: SslConnector,
     _t: PhantomData<U>,
@@ -81,6 +84,7 @@ impl Connector<(), ()> {
             conn_keep_alive: Duration::from_secs(15),
             disconnect_timeout: Duration::from_millis(3000),
             limit: 100,
+            proxy: None,
             _t: PhantomData,
         }
     }
@@ -104,6 +108,7 @@ impl<T, U> Connector<T, U> {
             conn_keep_alive: self.conn_keep_alive,
             disconnect_timeout: self.disconnect_timeout,
             limit: self.limit,
+            proxy: self.proxy,
             ssl: self.ssl,
             _t: PhantomData,
         }
@@ -141,6 +146,15 @@ where
         self
     }
 
+    /// Send requests through a HTTP proxy.
+    ///
+    /// The proxy is used for all requests that do not specify a proxy in
+    /// `Connect`, except for the hosts excluded with `Proxy::no_proxy`.
+    pub fn proxy(mut self, proxy: Proxy) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
     /// Set keep-alive period for opened connection.
     ///
     /// Keep-alive period is the period between connection usage. If
@@ -189,12 +203,13 @@ where
 
             let ssl_service = TimeoutService::new(
                 self.timeout,
-                pipeline(
-                    apply_fn(self.connector.clone(), |msg: Connect, srv| {
-                        srv.call(TcpConnect::new(msg.uri).set_addr(msg.addr))
-                    })
-                    .map_err(ConnectError::from),
-                )
+                pipeline(apply_fn(
+                    self.connector.clone().map_err(ConnectError::from),
+                    |msg: Connect, srv| {
+                        let fut = srv.call(tcp_connect(&msg));
+                        tunnel(fut, msg)
+                    },
+                ))
                 .and_then(match self.ssl {
                     SslConnector::Rustls(ssl) => service(
                         RustlsConnector::service(ssl)
@@ -223,10 +238,13 @@ where
 
             let tcp_service = TimeoutService::new(
                 self.timeout,
-                apply_fn(self.connector, |msg: Connect, srv| {
-                    srv.call(TcpConnect::new(msg.uri).set_addr(msg.addr))
-                })
-                .map_err(ConnectError::from)
+                apply_fn(
+                    self.connector.map_err(ConnectError::from),
+                    |msg: Connect, srv| {
+                        let fut = srv.call(tcp_connect(&msg));
+                        tunnel(fut, msg)
+                    },
+                )
                 .map(|stream| (stream.into_parts().0, Protocol::Http1)),
             )
             .map_err(|e| match e {
@@ -249,11 +267,39 @@ where
                     Some(self.disconnect_timeout),
                     self.limit,
                 ),
+                proxy: self.proxy,
             }
         }
     }
 }
 
+/// Tcp connect request for the target host, or for the proxy in front of it.
+fn tcp_connect(req: &Connect) -> TcpConnect<Uri> {
+    match req.proxy {
+        Some(ref proxy) => TcpConnect::new(proxy.uri().clone()),
+        None => TcpConnect::new(req.uri.clone()).set_addr(req.addr),
+    }
+}
+
+/// Open a tunnel to the target host if the connection goes through a proxy.
+async fn tunnel<F, Io>(
+    fut: F,
+    req: Connect,
+) -> Result<TcpConnection<Uri, TunnelStream<Io>>, ConnectError>
+where
+    F: Future<Output = Result<TcpConnection<Uri, Io>, ConnectError>>,
+    Io: AsyncRead + AsyncWrite + Unpin,
+{
+    let (io, uri) = fut.await?.into_parts();
+    match req.proxy {
+        Some(ref proxy) if Proxy::tunnels(&req.uri) => {
+            let io = proxy.tunnel(io, &req.uri).await?;
+            Ok(TcpConnection::new(io, req.uri))
+        }
+        _ => Ok(TcpConnection::new(TunnelStream::direct(io), uri)),
+    }
+}
+
 
 mod connect_impl {
     use std::future::Future;
@@ -276,6 +322,7 @@ mod connect_impl {
     {
         pub(crate) tcp_pool: ConnectionPool<T1, Io1>,
         pub(crate) ssl_pool: ConnectionPool<T2, Io2>,
+        pub(crate) proxy: Option<Proxy>,
     }
 
     impl<T1, T2, Io1, Io2> Clone for InnerConnector<T1, T2, Io1, Io2>
@@ -291,6 +338,7 @@ mod connect_impl {
             InnerConnector {
                 tcp_pool: self.tcp_pool.clone(),
                 ssl_pool: self.ssl_pool.clone(),
+                proxy: self.proxy.clone(),
             }
         }
     }
@@ -316,13 +364,22 @@ mod connect_impl {
             self.tcp_pool.poll_ready(cx)
         }
 
-        fn call(&mut self, req: Connect) -> Self::Future {
+        fn call(&mut self, mut req: Connect) -> Self::Future {
+            if req.proxy.is_none() {
+                req.proxy = self
+                    .proxy
+                    .as_ref()
+                    .filter(|proxy| proxy.intercepts(&req.uri))
+                    .cloned();
+            }
+
             match req.uri.scheme_str() {
                 Some("https") | Some("wss") => Either::Right(InnerConnectorResponseB {
                     fut: self.ssl_pool.call(req),
                     _t: PhantomData,
                 }),
                 _ => Either::Left(InnerConnectorResponseA {
+                    proxy: req.proxy.clone().filter(|_| !Proxy::tunnels(&req.uri)),
                     fut: self.tcp_pool.call(req),
                     _t: PhantomData,
                 }),
@@ -339,6 +396,8 @@ mod connect_impl {
     {
         #[pin]
         fut: <ConnectionPool<T, Io1> as Service>::Future,
+        /// Forward proxy for plain http requests
+        proxy: Option<Proxy>,
         _t: PhantomData<Io2>,
     }
 
@@ -352,10 +411,13 @@ mod connect_impl {
         type Output = Result<EitherConnection<Io1, Io2>, ConnectError>;
 
         fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-            Poll::Ready(
-                ready!(Pin::new(&mut self.get_mut().fut).poll(cx))
-                    .map(EitherConnection::A),
-            )
+            let this = self.get_mut();
+            Poll::Ready(ready!(Pin::new(&mut this.fut).poll(cx)).map(|mut conn| {
+                if let Some(proxy) = this.proxy.take() {
+                    conn.set_proxy(proxy);
+                }
+                EitherConnection::A(conn)
+            }))
         }
     }
 
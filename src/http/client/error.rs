@@ -37,6 +37,15 @@ pub enum ConnectError {
     #[display(fmt = "Connector received `Connect` method with unresolved host")]
     Unresolverd,
 
+    /// SOCKS5 proxy error
+    #[display(fmt = "SOCKS5 proxy error: {}", _0)]
+    Socks5(crate::connect::Socks5Error),
+
+    /// Proxy refused to open a tunnel
+    #[display(fmt = "Proxy responded with status {}", _0)]
+    #[from(ignore)]
+    Proxy(StatusCode),
+
     /// Connection io error
     #[display(fmt = "{}", _0)]
     Io(io::Error),
@@ -49,6 +58,7 @@ impl From<crate::connect::ConnectError> for ConnectError {
             crate::connect::ConnectError::NoRecords => ConnectError::NoRecords,
             crate::connect::ConnectError::InvalidInput => panic!(),
             crate::connect::ConnectError::Unresolverd => ConnectError::Unresolverd,
+            crate::connect::ConnectError::Socks5(e) => ConnectError::Socks5(e),
             crate::connect::ConnectError::Io(e) => ConnectError::Io(e),
         }
     }
@@ -14,13 +14,14 @@ use futures_util::{SinkExt, StreamExt};
 use crate::http::error::PayloadError;
 use crate::http::h1;
 use crate::http::header::HeaderMap;
-use crate::http::header::{IntoHeaderValue, HOST};
+use crate::http::header::{IntoHeaderValue, HOST, PROXY_AUTHORIZATION};
 use crate::http::message::{RequestHeadType, ResponseHead};
-use crate::http::payload::{Payload, PayloadStream};
+use crate::http::payload::{Payload, PayloadStream, Trailers};
 
 use super::connection::{ConnectionLifetime, ConnectionType, IoConnection};
 use super::error::{ConnectError, SendRequestError};
 use super::pool::Acquired;
+use super::proxy::Proxy;
 use crate::http::body::{BodySize, MessageBody};
 
 pub(crate) async fn send_request<T, B>(
@@ -29,6 +30,7 @@ pub(crate) async fn send_request<T, B>(
     body: B,
     created: time::Instant,
     pool: Option<Acquired<T>>,
+    proxy: Option<Proxy>,
 ) -> Result<(ResponseHead, Payload), SendRequestError>
 where
     T: AsyncRead + AsyncWrite + Unpin + 'static,
@@ -61,6 +63,16 @@ where
         }
     }
 
+    // requests to a forward proxy carry the absolute uri
+    if let Some(proxy) = proxy {
+        let mut owned = head.into_owned();
+        owned.set_absolute_form();
+        if let Some(auth) = proxy.authorization() {
+            owned.headers.insert(PROXY_AUTHORIZATION, auth.clone());
+        }
+        head = RequestHeadType::Owned(owned);
+    }
+
     let io = H1Connection {
         created,
         pool,
@@ -93,7 +105,9 @@ where
             Ok((head, Payload::None))
         }
         _ => {
-            let pl: PayloadStream = PlStream::new(framed).boxed_local();
+            let trailers = Trailers::default();
+            head.extensions_mut().insert(trailers.clone());
+            let pl: PayloadStream = PlStream::new(framed, trailers).boxed_local();
             Ok((head, pl.into()))
         }
     }
@@ -137,6 +151,9 @@ where
                 }
                 None => {
                     eof = true;
+                    if let Some(trailers) = body.take_trailers() {
+                        framed.get_codec_mut().set_trailers(trailers);
+                    }
                     framed.write(h1::Message::Chunk(None))?;
                 }
             }
@@ -244,12 +261,14 @@ impl<T: AsyncRead + AsyncWrite + Unpin + 'static> AsyncWrite for H1Connection<T>
 
 pub(crate) struct PlStream<Io> {
     framed: Option<Framed<Io, h1::ClientPayloadCodec>>,
+    trailers: Trailers,
 }
 
 impl<Io: ConnectionLifetime> PlStream<Io> {
-    fn new(framed: Framed<Io, h1::ClientCodec>) -> Self {
+    fn new(framed: Framed<Io, h1::ClientCodec>, trailers: Trailers) -> Self {
         PlStream {
             framed: Some(framed.map_codec(|codec| codec.into_payload_codec())),
+            trailers,
         }
     }
 }
@@ -269,7 +288,8 @@ impl<Io: ConnectionLifetime> Stream for PlStream<Io> {
                 if let Some(chunk) = chunk {
                     Poll::Ready(Some(Ok(chunk)))
                 } else {
-                    let framed = this.framed.take().unwrap();
+                    let mut framed = this.framed.take().unwrap();
+                    this.trailers.set(framed.get_codec_mut().take_trailers());
                     let force_close = !framed.get_codec().keepalive();
                     release_connection(framed, force_close);
                     Poll::Ready(None)
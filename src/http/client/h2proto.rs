@@ -11,7 +11,8 @@ use http::{request::Request, Method, Version};
 use crate::http::body::{BodySize, MessageBody};
 use crate::http::header::HeaderMap;
 use crate::http::message::{RequestHeadType, ResponseHead};
-use crate::http::payload::Payload;
+use crate::http::h2;
+use crate::http::payload::{Payload, Trailers};
 
 use super::connection::{ConnectionType, IoConnection};
 use super::error::SendRequestError;
@@ -114,11 +115,18 @@ where
     };
 
     let (parts, body) = resp.into_parts();
-    let payload = if head_req { Payload::None } else { body.into() };
 
     let mut head = ResponseHead::new(parts.status);
     head.version = parts.version;
     head.headers = parts.headers.into();
+
+    let payload = if head_req {
+        Payload::None
+    } else {
+        let trailers = Trailers::default();
+        head.extensions_mut().insert(trailers.clone());
+        h2::Payload::with_trailers(body, trailers).into()
+    };
     Ok((head, payload))
 }
 
@@ -136,7 +144,11 @@ async fn send_body<B: MessageBody>(
                 }
                 Some(Err(e)) => return Err(e.into()),
                 None => {
-                    if let Err(e) = send.send_data(Bytes::new(), true) {
+                    let res = match body.take_trailers() {
+                        Some(trailers) => send.send_trailers(trailers.into()),
+                        None => send.send_data(Bytes::new(), true),
+                    };
+                    if let Err(e) = res {
                         return Err(e.into());
                     }
                     send.reserve_capacity(0);
@@ -7,14 +7,18 @@ mod error;
 mod h1proto;
 mod h2proto;
 mod pool;
+mod proxy;
 
 pub use self::connection::Connection;
 pub use self::connector::Connector;
 pub use self::error::{ConnectError, FreezeRequestError, InvalidUrl, SendRequestError};
 pub use self::pool::Protocol;
+pub use self::proxy::Proxy;
 
 #[derive(Clone)]
 pub struct Connect {
     pub uri: Uri,
     pub addr: Option<std::net::SocketAddr>,
+    /// Proxy to connect through
+    pub proxy: Option<Proxy>,
 }
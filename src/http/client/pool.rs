@@ -9,6 +9,7 @@ use bytes::Bytes;
 use futures_util::future::{poll_fn, FutureExt, LocalBoxFuture};
 use fxhash::FxHashMap;
 use http::uri::Authority;
+use http::HeaderValue;
 use indexmap::IndexSet;
 use slab::Slab;
 
@@ -32,11 +33,25 @@ pub enum Protocol {
 #[derive(Hash, Eq, PartialEq, Clone, Debug)]
 pub(crate) struct Key {
     authority: Authority,
+    /// Authority of the proxy the connection goes through
+    proxy: Option<Authority>,
+    /// Credentials the proxy authorized the connection with
+    proxy_auth: Option<HeaderValue>,
 }
 
-impl From<Authority> for Key {
-    fn from(authority: Authority) -> Key {
-        Key { authority }
+impl Key {
+    fn new(req: &Connect) -> Option<Key> {
+        Some(Key {
+            authority: req.uri.authority()?.clone(),
+            proxy: req
+                .proxy
+                .as_ref()
+                .and_then(|proxy| proxy.uri().authority().cloned()),
+            proxy_auth: req
+                .proxy
+                .as_ref()
+                .and_then(|proxy| proxy.authorization().cloned()),
+        })
     }
 }
 
@@ -108,8 +123,8 @@ where
         let inner = self.1.clone();
 
         let fut = async move {
-            let key = if let Some(authority) = req.uri.authority() {
-                authority.clone().into()
+            let key = if let Some(key) = Key::new(&req) {
+                key
             } else {
                 return Err(ConnectError::Unresolverd);
             };
@@ -299,7 +314,7 @@ where
     ) {
         let (tx, rx) = oneshot::channel();
 
-        let key: Key = connect.uri.authority().unwrap().clone().into();
+        let key = Key::new(&connect).unwrap();
         let entry = self.waiters.vacant_entry();
         let token = entry.key();
         entry.insert(Some((connect, tx)));
@@ -628,3 +643,31 @@ impl<T> Drop for Acquired<T> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::client::Proxy;
+
+    #[test]
+    fn test_key_proxy_auth() {
+        let connect = |proxy: Option<Proxy>| Connect {
+            uri: "https://example.com/".parse().unwrap(),
+            addr: None,
+            proxy,
+        };
+        let proxy = Proxy::new("http://proxy.local:3128").unwrap();
+
+        let direct = Key::new(&connect(None)).unwrap();
+        let anonymous = Key::new(&connect(Some(proxy.clone()))).unwrap();
+        let alice = Key::new(&connect(Some(proxy.clone().basic_auth("alice", None)))).unwrap();
+        let bob = Key::new(&connect(Some(proxy.clone().basic_auth("bob", None)))).unwrap();
+        assert_ne!(direct, anonymous);
+        assert_ne!(anonymous, alice);
+        assert_ne!(alice, bob);
+        assert_eq!(
+            alice,
+            Key::new(&connect(Some(proxy.basic_auth("alice", None)))).unwrap()
+        );
+    }
+}
@@ -0,0 +1,411 @@
+use std::convert::TryFrom;
+use std::io::Write;
+use std::mem::MaybeUninit;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::{cmp, fmt, io};
+
+use bytes::buf::BufMutExt;
+use bytes::{Buf, BufMut, BytesMut};
+use http::Uri;
+
+use crate::connect::Address;
+use crate::http::error::HttpError;
+use crate::http::header::HeaderValue;
+use crate::http::StatusCode;
+use crate::krse::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use super::error::{ConnectError, InvalidUrl};
+
+/// Max size of the response to a `CONNECT` request
+const MAX_RESPONSE_SIZE: usize = 8192;
+const MAX_HEADERS: usize = 32;
+
+/// HTTP proxy configuration.
+///
+/// Requests to `http://` urls are sent to the proxy in absolute-form. For
+/// `https://`, `ws://` and `wss://` urls a tunnel is opened with the `CONNECT`
+/// method, and the connection to the target host is established through it.
+///
+/// ```rust
+/// use kayrx::http::client::Proxy;
+///
+/// let proxy = Proxy::new("http://proxy.local:3128")
+///     .unwrap()
+///     .basic_auth("user", Some("password"))
+///     .no_proxy("localhost")
+///     .no_proxy(".internal.example.com");
+/// ```
+#[derive(Clone)]
+pub struct Proxy {
+    uri: Uri,
+    auth: Option<HeaderValue>,
+    no_proxy: Vec<String>,
+}
+
+impl Proxy {
+    /// Create proxy configuration for the proxy at `uri`.
+    ///
+    /// Only `http://` proxies are supported, the scheme may be omitted.
+    pub fn new<U>(uri: U) -> Result<Proxy, InvalidUrl>
+    where
+        Uri: TryFrom<U>,
+        <Uri as TryFrom<U>>::Error: Into<HttpError>,
+    {
+        let uri = Uri::try_from(uri).map_err(|e| InvalidUrl::HttpError(e.into()))?;
+        match uri.scheme_str() {
+            None | Some("http") => (),
+            Some(_) => return Err(InvalidUrl::UnknownScheme),
+        }
+        let authority = match uri.authority() {
+            Some(authority) if !authority.host().is_empty() => authority.clone(),
+            _ => return Err(InvalidUrl::MissingHost),
+        };
+
+        let uri = Uri::builder()
+            .scheme("http")
+            .authority(authority)
+            .path_and_query("/")
+            .build()
+            .map_err(InvalidUrl::HttpError)?;
+
+        Ok(Proxy {
+            uri,
+            auth: None,
+            no_proxy: Vec::new(),
+        })
+    }
+
+    /// Authenticate to the proxy with HTTP basic authentication.
+    pub fn basic_auth<U>(mut self, username: U, password: Option<&str>) -> Self
+    where
+        U: fmt::Display,
+    {
+        let auth = match password {
+            Some(password) => format!("{}:{}", username, password),
+            None => format!("{}:", username),
+        };
+        self.auth = HeaderValue::from_str(&format!("Basic {}", base64::encode(&auth)))
+            .ok()
+            .map(|mut value| {
+                value.set_sensitive(true);
+                value
+            });
+        self
+    }
+
+    /// Connect to `host` directly instead of through the proxy.
+    ///
+    /// `example.com` matches the domain and all of its subdomains,
+    /// `.example.com` only matches the subdomains. `*` disables the proxy
+    /// for all hosts.
+    pub fn no_proxy(mut self, host: &str) -> Self {
+        self.no_proxy.push(host.trim().to_ascii_lowercase());
+        self
+    }
+
+    /// Returns the uri of the proxy.
+    pub fn uri(&self) -> &Uri {
+        &self.uri
+    }
+
+    /// Check if a request to `uri` has to be sent through the proxy.
+    pub fn intercepts(&self, uri: &Uri) -> bool {
+        let host = match uri.host() {
+            Some(host) => host.to_ascii_lowercase(),
+            None => return false,
+        };
+
+        !self.no_proxy.iter().any(|entry| {
+            if entry == "*" {
+                true
+            } else if entry.starts_with('.') {
+                host.ends_with(entry.as_str())
+            } else {
+                host == *entry
+                    || (host.ends_with(entry.as_str())
+                        && host.as_bytes()[host.len() - entry.len() - 1] == b'.')
+            }
+        })
+    }
+
+    /// Value of the `Proxy-Authorization` header.
+    pub(crate) fn authorization(&self) -> Option<&HeaderValue> {
+        self.auth.as_ref()
+    }
+
+    /// Requests to the target are tunnelled through the proxy, rather than
+    /// sent to it in absolute-form.
+    pub(crate) fn tunnels(target: &Uri) -> bool {
+        target.scheme_str() != Some("http")
+    }
+
+    /// Open a `CONNECT` tunnel to `target` over `io`.
+    pub(crate) async fn tunnel<Io>(
+        &self,
+        mut io: Io,
+        target: &Uri,
+    ) -> Result<TunnelStream<Io>, ConnectError>
+    where
+        Io: AsyncRead + AsyncWrite + Unpin,
+    {
+        let host = Address::host(target);
+        let port = Address::port(target).unwrap_or(80);
+
+        let mut buf = BytesMut::with_capacity(256).writer();
+        let _ = write!(
+            buf,
+            "CONNECT {host}:{port} HTTP/1.1\r\nhost: {host}:{port}\r\n",
+            host = host,
+            port = port
+        );
+        if let Some(auth) = self.authorization() {
+            let _ = buf.write_all(b"proxy-authorization: ");
+            let _ = buf.write_all(auth.as_bytes());
+            let _ = buf.write_all(b"\r\n");
+        }
+        let _ = buf.write_all(b"\r\n");
+        io.write_all(&buf.into_inner()).await?;
+        io.flush().await?;
+
+        let mut buf = BytesMut::with_capacity(1024);
+        loop {
+            if buf.len() >= MAX_RESPONSE_SIZE {
+                return Err(ConnectError::Proxy(StatusCode::BAD_GATEWAY));
+            }
+            if io.read_buf(&mut buf).await? == 0 {
+                return Err(ConnectError::Disconnected);
+            }
+
+            let mut headers = [httparse::EMPTY_HEADER; MAX_HEADERS];
+            let mut res = httparse::Response::new(&mut headers);
+            match res.parse(&buf) {
+                Ok(httparse::Status::Complete(len)) => {
+                    let status = res
+                        .code
+                        .and_then(|code| StatusCode::from_u16(code).ok())
+                        .unwrap_or(StatusCode::BAD_GATEWAY);
+                    return if status.is_success() {
+                        // bytes past the response come from the target
+                        buf.advance(len);
+                        Ok(TunnelStream { io, buf })
+                    } else {
+                        Err(ConnectError::Proxy(status))
+                    };
+                }
+                Ok(httparse::Status::Partial) => continue,
+                Err(_) => return Err(ConnectError::Proxy(StatusCode::BAD_GATEWAY)),
+            }
+        }
+    }
+}
+
+/// Connection to the target host, possibly through a proxy tunnel.
+///
+/// Reads return the data the proxy sent after its response to `CONNECT`
+/// first.
+#[derive(Debug)]
+pub(crate) struct TunnelStream<Io> {
+    io: Io,
+    buf: BytesMut,
+}
+
+impl<Io> TunnelStream<Io> {
+    /// Connection that is not tunnelled
+    pub(crate) fn direct(io: Io) -> Self {
+        TunnelStream {
+            io,
+            buf: BytesMut::new(),
+        }
+    }
+}
+
+impl<Io: AsyncRead + Unpin> AsyncRead for TunnelStream<Io> {
+    unsafe fn prepare_uninitialized_buffer(&self, buf: &mut [MaybeUninit<u8>]) -> bool {
+        self.io.prepare_uninitialized_buffer(buf)
+    }
+
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if !this.buf.is_empty() {
+            let n = cmp::min(buf.len(), this.buf.len());
+            buf[..n].copy_from_slice(&this.buf[..n]);
+            this.buf.advance(n);
+            return Poll::Ready(Ok(n));
+        }
+        Pin::new(&mut this.io).poll_read(cx, buf)
+    }
+
+    fn poll_read_buf<B: BufMut>(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut B,
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if !this.buf.is_empty() {
+            let n = cmp::min(buf.remaining_mut(), this.buf.len());
+            buf.put_slice(&this.buf[..n]);
+            this.buf.advance(n);
+            return Poll::Ready(Ok(n));
+        }
+        Pin::new(&mut this.io).poll_read_buf(cx, buf)
+    }
+}
+
+impl<Io: AsyncWrite + Unpin> AsyncWrite for TunnelStream<Io> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().io).poll_write(cx, buf)
+    }
+
+    fn poll_write_buf<B: Buf>(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut B,
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().io).poll_write_buf(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().io).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().io).poll_shutdown(cx)
+    }
+}
+
+impl fmt::Debug for Proxy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Proxy")
+            .field("uri", &self.uri)
+            .field("auth", &self.auth.is_some())
+            .field("no_proxy", &self.no_proxy)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::future::FutureExt;
+
+    use super::*;
+
+    /// Stream that reads the response of the proxy
+    struct Mock {
+        read: &'static [u8],
+        written: Vec<u8>,
+    }
+
+    impl AsyncRead for Mock {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            Pin::new(&mut self.read).poll_read(cx, buf)
+        }
+    }
+
+    impl AsyncWrite for Mock {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            Pin::new(&mut self.written).poll_write(cx, buf)
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_shutdown(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[test]
+    fn test_new() {
+        let proxy = Proxy::new("proxy.local:3128").unwrap();
+        assert_eq!(proxy.uri(), "http://proxy.local:3128/");
+
+        assert!(Proxy::new("https://proxy.local").is_err());
+        assert!(Proxy::new("/path").is_err());
+    }
+
+    #[test]
+    fn test_no_proxy() {
+        let proxy = Proxy::new("http://proxy.local:3128")
+            .unwrap()
+            .no_proxy("localhost")
+            .no_proxy("example.com")
+            .no_proxy(".internal.org");
+
+        let intercepts = |uri: &str| proxy.intercepts(&uri.parse().unwrap());
+        assert!(!intercepts("http://localhost:8080/"));
+        assert!(!intercepts("http://example.com/"));
+        assert!(!intercepts("https://www.Example.com/"));
+        assert!(intercepts("http://badexample.com/"));
+        assert!(!intercepts("http://host.internal.org/"));
+        assert!(intercepts("http://internal.org/"));
+        assert!(intercepts("http://www.rust-lang.org/"));
+
+        let proxy = proxy.no_proxy("*");
+        assert!(!proxy.intercepts(&"http://www.rust-lang.org/".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_basic_auth() {
+        let proxy = Proxy::new("http://proxy.local")
+            .unwrap()
+            .basic_auth("username", Some("password"));
+        assert_eq!(
+            proxy.authorization().unwrap(),
+            "Basic dXNlcm5hbWU6cGFzc3dvcmQ="
+        );
+    }
+
+    #[test]
+    fn test_tunnel() {
+        let proxy = Proxy::new("http://proxy.local").unwrap();
+        let target = "wss://example.com/".parse().unwrap();
+
+        // the target may send data before the proxy response was read
+        let mock = Mock {
+            read: b"HTTP/1.1 200 Connection established\r\n\r\nfrom target",
+            written: Vec::new(),
+        };
+        let (written, rest) = async {
+            let mut io = proxy.tunnel(mock, &target).await.unwrap();
+            let mut rest = Vec::new();
+            io.read_to_end(&mut rest).await.unwrap();
+            (io.io.written, rest)
+        }
+        .now_or_never()
+        .unwrap();
+        assert_eq!(
+            written,
+            b"CONNECT example.com:443 HTTP/1.1\r\nhost: example.com:443\r\n\r\n".to_vec()
+        );
+        assert_eq!(rest, b"from target");
+
+        let mock = Mock {
+            read: b"HTTP/1.1 407 Proxy Authentication Required\r\n\r\n",
+            written: Vec::new(),
+        };
+        match proxy.tunnel(mock, &target).now_or_never().unwrap() {
+            Err(ConnectError::Proxy(status)) => {
+                assert_eq!(status, StatusCode::PROXY_AUTHENTICATION_REQUIRED)
+            }
+            _ => panic!(),
+        }
+    }
+}
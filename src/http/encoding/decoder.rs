@@ -6,6 +6,7 @@ use brotli2::write::BrotliDecoder;
 use bytes::Bytes;
 use flate2::write::{GzDecoder, ZlibDecoder};
 use futures_core::{ready, Stream};
+use zstd::stream::write::Decoder as ZstdDecoder;
 
 use super::Writer;
 use crate::http::error::PayloadError;
@@ -38,6 +39,13 @@ where
             ContentEncoding::Gzip => Some(ContentDecoder::Gzip(Box::new(
                 GzDecoder::new(Writer::new()),
             ))),
+            ContentEncoding::Zstd => match ZstdDecoder::new(Writer::new()) {
+                Ok(decoder) => Some(ContentDecoder::Zstd(Box::new(decoder))),
+                Err(err) => {
+                    error!("Can not create zstd decoder: {}", err);
+                    None
+                }
+            },
             _ => None,
         };
         Decoder {
@@ -137,6 +145,7 @@ enum ContentDecoder {
     Deflate(Box<ZlibDecoder<Writer>>),
     Gzip(Box<GzDecoder<Writer>>),
     Br(Box<BrotliDecoder<Writer>>),
+    Zstd(Box<ZstdDecoder<Writer>>),
 }
 
 impl ContentDecoder {
@@ -175,6 +184,17 @@ impl ContentDecoder {
                 }
                 Err(e) => Err(e),
             },
+            ContentDecoder::Zstd(ref mut decoder) => match decoder.flush() {
+                Ok(()) => {
+                    let b = decoder.get_mut().take();
+                    if !b.is_empty() {
+                        Ok(Some(b))
+                    } else {
+                        Ok(None)
+                    }
+                }
+                Err(e) => Err(e),
+            },
         }
     }
 
@@ -216,6 +236,18 @@ impl ContentDecoder {
                 }
                 Err(e) => Err(e),
             },
+            ContentDecoder::Zstd(ref mut decoder) => match decoder.write_all(&data) {
+                Ok(_) => {
+                    decoder.flush()?;
+                    let b = decoder.get_mut().take();
+                    if !b.is_empty() {
+                        Ok(Some(b))
+                    } else {
+                        Ok(None)
+                    }
+                }
+                Err(e) => Err(e),
+            },
         }
     }
 }
@@ -9,9 +9,10 @@ use brotli2::write::BrotliEncoder;
 use bytes::Bytes;
 use flate2::write::{GzEncoder, ZlibEncoder};
 use futures_core::ready;
+use zstd::stream::write::Encoder as ZstdEncoder;
 
 use crate::http::body::{Body, BodySize, MessageBody, ResponseBody};
-use crate::http::header::{ContentEncoding, CONTENT_ENCODING};
+use crate::http::header::{ContentEncoding, HeaderMap, CONTENT_ENCODING};
 use crate::http::{HeaderValue, StatusCode};
 use crate::http::{error::Error, ResponseHead};
 
@@ -19,6 +20,58 @@ use super::Writer;
 
 const INPLACE: usize = 1024;
 
+/// Compression level of each content encoding.
+///
+/// Levels range from 0 to 11 for brotli, from 0 to 9 for gzip and deflate,
+/// and from 1 to 21 for zstd. Higher levels compress better but slower. The
+/// defaults favour speed: 3 for brotli and zstd, 1 for gzip and deflate.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CompressionLevels {
+    br: u32,
+    deflate: u32,
+    gzip: u32,
+    zstd: u32,
+}
+
+impl Default for CompressionLevels {
+    fn default() -> Self {
+        CompressionLevels {
+            br: 3,
+            deflate: 1,
+            gzip: 1,
+            zstd: 3,
+        }
+    }
+}
+
+impl CompressionLevels {
+    /// Set compression level of `encoding`.
+    ///
+    /// The level is clamped to the range supported by the encoding.
+    /// `Identity` and `Auto` are ignored.
+    pub fn set(mut self, encoding: ContentEncoding, level: u32) -> Self {
+        match encoding {
+            ContentEncoding::Br => self.br = level.min(11),
+            ContentEncoding::Deflate => self.deflate = level.min(9),
+            ContentEncoding::Gzip => self.gzip = level.min(9),
+            ContentEncoding::Zstd => self.zstd = level.max(1).min(21),
+            ContentEncoding::Identity | ContentEncoding::Auto => (),
+        }
+        self
+    }
+
+    /// Compression level of `encoding`, 0 for `Identity` and `Auto`.
+    pub fn get(&self, encoding: ContentEncoding) -> u32 {
+        match encoding {
+            ContentEncoding::Br => self.br,
+            ContentEncoding::Deflate => self.deflate,
+            ContentEncoding::Gzip => self.gzip,
+            ContentEncoding::Zstd => self.zstd,
+            ContentEncoding::Identity | ContentEncoding::Auto => 0,
+        }
+    }
+}
+
 pub struct Encoder<B> {
     eof: bool,
     body: EncoderBody<B>,
@@ -31,6 +84,16 @@ impl<B: MessageBody> Encoder<B> {
         encoding: ContentEncoding,
         head: &mut ResponseHead,
         body: ResponseBody<B>,
+    ) -> ResponseBody<Encoder<B>> {
+        Encoder::response_with_levels(encoding, CompressionLevels::default(), head, body)
+    }
+
+    /// Same as `Encoder::response`, compresses with the given levels.
+    pub fn response_with_levels(
+        encoding: ContentEncoding,
+        levels: CompressionLevels,
+        head: &mut ResponseHead,
+        body: ResponseBody<B>,
     ) -> ResponseBody<Encoder<B>> {
         let can_encode = !(head.headers().contains_key(&CONTENT_ENCODING)
             || head.status == StatusCode::SWITCHING_PROTOCOLS
@@ -56,7 +119,7 @@ impl<B: MessageBody> Encoder<B> {
 
         if can_encode {
             // Modify response body only if encoder is not None
-            if let Some(enc) = ContentEncoder::encoder(encoding) {
+            if let Some(enc) = ContentEncoder::encoder(encoding, levels.get(encoding)) {
                 update_head(encoding, head);
                 head.no_chunking(false);
                 return ResponseBody::Body(Encoder {
@@ -162,6 +225,14 @@ impl<B: MessageBody> MessageBody for Encoder<B> {
             }
         }
     }
+
+    fn take_trailers(&mut self) -> Option<HeaderMap> {
+        match self.body {
+            EncoderBody::Bytes(_) => None,
+            EncoderBody::Stream(ref mut b) => b.take_trailers(),
+            EncoderBody::BoxedStream(ref mut b) => b.take_trailers(),
+        }
+    }
 }
 
 fn update_head(encoding: ContentEncoding, head: &mut ResponseHead) {
@@ -175,22 +246,30 @@ enum ContentEncoder {
     Deflate(ZlibEncoder<Writer>),
     Gzip(GzEncoder<Writer>),
     Br(BrotliEncoder<Writer>),
+    Zstd(ZstdEncoder<Writer>),
 }
 
 impl ContentEncoder {
-    fn encoder(encoding: ContentEncoding) -> Option<Self> {
+    fn encoder(encoding: ContentEncoding, level: u32) -> Option<Self> {
         match encoding {
             ContentEncoding::Deflate => Some(ContentEncoder::Deflate(ZlibEncoder::new(
                 Writer::new(),
-                flate2::Compression::fast(),
+                flate2::Compression::new(level),
             ))),
             ContentEncoding::Gzip => Some(ContentEncoder::Gzip(GzEncoder::new(
                 Writer::new(),
-                flate2::Compression::fast(),
+                flate2::Compression::new(level),
             ))),
             ContentEncoding::Br => {
-                Some(ContentEncoder::Br(BrotliEncoder::new(Writer::new(), 3)))
+                Some(ContentEncoder::Br(BrotliEncoder::new(Writer::new(), level)))
             }
+            ContentEncoding::Zstd => match ZstdEncoder::new(Writer::new(), level as i32) {
+                Ok(encoder) => Some(ContentEncoder::Zstd(encoder)),
+                Err(err) => {
+                    error!("Can not create zstd encoder: {}", err);
+                    None
+                }
+            },
             _ => None,
         }
     }
@@ -201,6 +280,7 @@ impl ContentEncoder {
             ContentEncoder::Br(ref mut encoder) => encoder.get_mut().take(),
             ContentEncoder::Deflate(ref mut encoder) => encoder.get_mut().take(),
             ContentEncoder::Gzip(ref mut encoder) => encoder.get_mut().take(),
+            ContentEncoder::Zstd(ref mut encoder) => encoder.get_mut().take(),
         }
     }
 
@@ -218,6 +298,10 @@ impl ContentEncoder {
                 Ok(writer) => Ok(writer.buf.freeze()),
                 Err(err) => Err(err),
             },
+            ContentEncoder::Zstd(encoder) => match encoder.finish() {
+                Ok(writer) => Ok(writer.buf.freeze()),
+                Err(err) => Err(err),
+            },
         }
     }
 
@@ -244,6 +328,13 @@ impl ContentEncoder {
                     Err(err)
                 }
             },
+            ContentEncoder::Zstd(ref mut encoder) => match encoder.write_all(data) {
+                Ok(_) => Ok(()),
+                Err(err) => {
+                    trace!("Error decoding zstd encoding: {}", err);
+                    Err(err)
+                }
+            },
         }
     }
 }
@@ -7,7 +7,7 @@ mod decoder;
 mod encoder;
 
 pub use self::decoder::Decoder;
-pub use self::encoder::Encoder;
+pub use self::encoder::{CompressionLevels, Encoder};
 
 pub(self) struct Writer {
     buf: BytesMut,
@@ -11,6 +11,7 @@ use super::{Message, MessageType};
 use crate::http::body::BodySize;
 use crate::http::config::ServiceConfig;
 use crate::http::error::{ParseError, PayloadError};
+use crate::http::header::HeaderMap;
 use crate::http::message::{ConnectionType, RequestHeadType, ResponseHead};
 
 bitflags! {
@@ -37,6 +38,7 @@ struct ClientCodecInner {
     payload: Option<PayloadDecoder>,
     version: Version,
     ctype: ConnectionType,
+    trailers: Option<HeaderMap>,
 
     // encoder part
     flags: Flags,
@@ -66,6 +68,7 @@ impl ClientCodec {
                 payload: None,
                 version: Version::HTTP_11,
                 ctype: ConnectionType::Close,
+                trailers: None,
 
                 flags,
                 encoder: encoder::MessageEncoder::default(),
@@ -94,6 +97,11 @@ impl ClientCodec {
         }
     }
 
+    /// Set trailers to send with the end of the chunked request payload
+    pub fn set_trailers(&mut self, trailers: HeaderMap) {
+        self.inner.encoder.set_trailers(trailers);
+    }
+
     /// Convert message codec to a payload codec
     pub fn into_payload_codec(self) -> ClientPayloadCodec {
         ClientPayloadCodec { inner: self.inner }
@@ -106,6 +114,12 @@ impl ClientPayloadCodec {
         self.inner.ctype == ConnectionType::KeepAlive
     }
 
+    /// Take trailers of the chunked response payload, available once the
+    /// payload is read to the end
+    pub fn take_trailers(&mut self) -> Option<HeaderMap> {
+        self.inner.trailers.take()
+    }
+
     /// Transform payload codec to a message codec
     pub fn into_message_codec(self) -> ClientCodec {
         ClientCodec { inner: self.inner }
@@ -120,6 +134,7 @@ impl Decoder for ClientCodec {
         debug_assert!(!self.inner.payload.is_some(), "Payload decoder is set");
 
         if let Some((req, payload)) = self.inner.decoder.decode(src)? {
+            self.inner.trailers = None;
             if let Some(ctype) = req.ctype() {
                 // do not use peer's keep-alive
                 self.inner.ctype = if ctype == ConnectionType::KeepAlive {
@@ -165,7 +180,9 @@ impl Decoder for ClientPayloadCodec {
                 Some(Some(chunk))
             }
             Some(PayloadItem::Eof) => {
-                self.inner.payload.take();
+                if let Some(mut payload) = self.inner.payload.take() {
+                    self.inner.trailers = payload.take_trailers();
+                }
                 Some(None)
             }
             None => None,
@@ -11,6 +11,7 @@ use super::{Message, MessageType};
 use crate::http::body::BodySize;
 use crate::http::config::ServiceConfig;
 use crate::http::error::ParseError;
+use crate::http::header::HeaderMap;
 use crate::http::message::ConnectionType;
 use crate::http::request::Request;
 use crate::http::response::Response;
@@ -103,6 +104,11 @@ impl Codec {
     pub fn config(&self) -> &ServiceConfig {
         &self.config
     }
+
+    /// Set trailers to send with the end of the chunked response payload
+    pub fn set_trailers(&mut self, trailers: HeaderMap) {
+        self.encoder.set_trailers(trailers);
+    }
 }
 
 impl Decoder for Codec {
@@ -357,23 +357,41 @@ pub enum PayloadItem {
 #[derive(Debug, Clone, PartialEq)]
 pub struct PayloadDecoder {
     kind: Kind,
+    trailers: Vec<(HeaderName, HeaderValue)>,
 }
 
 impl PayloadDecoder {
     pub fn length(x: u64) -> PayloadDecoder {
         PayloadDecoder {
             kind: Kind::Length(x),
+            trailers: Vec::new(),
         }
     }
 
     pub fn chunked() -> PayloadDecoder {
         PayloadDecoder {
             kind: Kind::Chunked(ChunkedState::Size, 0),
+            trailers: Vec::new(),
         }
     }
 
     pub fn eof() -> PayloadDecoder {
-        PayloadDecoder { kind: Kind::Eof }
+        PayloadDecoder {
+            kind: Kind::Eof,
+            trailers: Vec::new(),
+        }
+    }
+
+    /// Take trailers of the chunked payload, available after `PayloadItem::Eof`
+    pub fn take_trailers(&mut self) -> Option<HeaderMap> {
+        if self.trailers.is_empty() {
+            return None;
+        }
+        let mut trailers = HeaderMap::with_capacity(self.trailers.len());
+        for (name, value) in self.trailers.drain(..) {
+            trailers.append(name, value);
+        }
+        Some(trailers)
     }
 }
 
@@ -410,8 +428,7 @@ enum ChunkedState {
     Body,
     BodyCr,
     BodyLf,
-    EndCr,
-    EndLf,
+    Trailers,
     End,
 }
 
@@ -443,6 +460,16 @@ impl Decoder for PayloadDecoder {
             }
             Kind::Chunked(ref mut state, ref mut size) => {
                 loop {
+                    if *state == ChunkedState::Trailers {
+                        match read_trailers(src)? {
+                            Some(trailers) => {
+                                self.trailers = trailers;
+                                *state = ChunkedState::End;
+                            }
+                            None => return Ok(None),
+                        }
+                    }
+
                     let mut buf = None;
                     // advances the chunked state
                     *state = match state.step(src, size, &mut buf) {
@@ -501,8 +528,7 @@ impl ChunkedState {
             Body => ChunkedState::read_body(body, size, buf),
             BodyCr => ChunkedState::read_body_cr(body),
             BodyLf => ChunkedState::read_body_lf(body),
-            EndCr => ChunkedState::read_end_cr(body),
-            EndLf => ChunkedState::read_end_lf(body),
+            Trailers => Poll::Ready(Ok(ChunkedState::Trailers)),
             End => Poll::Ready(Ok(ChunkedState::End)),
         }
     }
@@ -563,7 +589,7 @@ impl ChunkedState {
     ) -> Poll<Result<ChunkedState, io::Error>> {
         match byte!(rdr) {
             b'\n' if *size > 0 => Poll::Ready(Ok(ChunkedState::Body)),
-            b'\n' if *size == 0 => Poll::Ready(Ok(ChunkedState::EndCr)),
+            b'\n' if *size == 0 => Poll::Ready(Ok(ChunkedState::Trailers)),
             _ => Poll::Ready(Err(io::Error::new(
                 io::ErrorKind::InvalidInput,
                 "Invalid chunk size LF",
@@ -617,23 +643,49 @@ impl ChunkedState {
             ))),
         }
     }
-    fn read_end_cr(rdr: &mut BytesMut) -> Poll<Result<ChunkedState, io::Error>> {
-        match byte!(rdr) {
-            b'\r' => Poll::Ready(Ok(ChunkedState::EndLf)),
-            _ => Poll::Ready(Err(io::Error::new(
-                io::ErrorKind::InvalidInput,
-                "Invalid chunk end CR",
-            ))),
+}
+
+/// Parse the trailer section of a chunked payload, up to and including
+/// the final empty line.
+fn read_trailers(
+    src: &mut BytesMut,
+) -> Result<Option<Vec<(HeaderName, HeaderValue)>>, io::Error> {
+    let mut parsed = [httparse::EMPTY_HEADER; MAX_HEADERS];
+
+    match httparse::parse_headers(src, &mut parsed) {
+        Ok(httparse::Status::Complete((len, headers))) => {
+            let mut trailers = Vec::with_capacity(headers.len());
+            for header in headers {
+                let name = HeaderName::from_bytes(header.name.as_bytes());
+                let value = HeaderValue::from_bytes(header.value);
+                match (name, value) {
+                    (Ok(name), Ok(value)) => trailers.push((name, value)),
+                    _ => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            "Invalid chunked trailer",
+                        ))
+                    }
+                }
+            }
+            src.advance(len);
+            Ok(Some(trailers))
         }
-    }
-    fn read_end_lf(rdr: &mut BytesMut) -> Poll<Result<ChunkedState, io::Error>> {
-        match byte!(rdr) {
-            b'\n' => Poll::Ready(Ok(ChunkedState::End)),
-            _ => Poll::Ready(Err(io::Error::new(
-                io::ErrorKind::InvalidInput,
-                "Invalid chunk end LF",
-            ))),
+        Ok(httparse::Status::Partial) => {
+            if src.len() >= MAX_BUFFER_SIZE {
+                error!("MAX_BUFFER_SIZE unprocessed data reached, closing");
+                Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Chunked trailers are too large",
+                ))
+            } else {
+                Ok(None)
+            }
         }
+        Err(_) => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Invalid chunked trailers",
+        )),
     }
 }
 
@@ -1114,6 +1166,47 @@ mod tests {
         assert!(pl.decode(&mut buf).unwrap().unwrap().eof());
     }
 
+    #[test]
+    fn test_http_chunked_payload_trailers() {
+        let mut buf = BytesMut::from(
+            "HTTP/1.1 200 OK\r\n\
+             transfer-encoding: chunked\r\n\r\n",
+        );
+        let mut reader = MessageDecoder::<ResponseHead>::default();
+        let (_, pl) = reader.decode(&mut buf).unwrap().unwrap();
+        let mut pl = pl.unwrap();
+
+        buf.extend(b"4\r\ndata\r\n0\r\ngrpc-status: 0\r\n");
+        assert_eq!(
+            pl.decode(&mut buf).unwrap().unwrap().chunk().as_ref(),
+            b"data"
+        );
+        assert!(pl.decode(&mut buf).unwrap().is_none());
+
+        buf.extend(b"grpc-message: ok\r\n\r\nHTTP/1.1");
+        assert!(pl.decode(&mut buf).unwrap().unwrap().eof());
+        assert_eq!(&buf[..], b"HTTP/1.1");
+
+        let trailers = pl.take_trailers().unwrap();
+        assert_eq!(trailers.get("grpc-status").unwrap(), "0");
+        assert_eq!(trailers.get("grpc-message").unwrap(), "ok");
+        assert!(pl.take_trailers().is_none());
+    }
+
+    #[test]
+    fn test_http_chunked_payload_invalid_trailers() {
+        let mut buf = BytesMut::from(
+            "HTTP/1.1 200 OK\r\n\
+             transfer-encoding: chunked\r\n\r\n",
+        );
+        let mut reader = MessageDecoder::<ResponseHead>::default();
+        let (_, pl) = reader.decode(&mut buf).unwrap().unwrap();
+        let mut pl = pl.unwrap();
+
+        buf.extend(b"0\r\ngrpc status\r\n\r\n");
+        assert!(pl.decode(&mut buf).is_err());
+    }
+
     #[test]
     fn test_http_request_chunked_payload_and_next_message() {
         let mut buf = BytesMut::from(
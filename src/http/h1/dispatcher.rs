@@ -415,6 +415,9 @@ where
                                     continue;
                                 }
                                 Poll::Ready(None) => {
+                                    if let Some(trailers) = stream.take_trailers() {
+                                        self.codec.set_trailers(trailers);
+                                    }
                                     self.codec.encode(
                                         Message::Chunk(None),
                                         &mut self.write_buf,
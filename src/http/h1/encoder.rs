@@ -2,7 +2,7 @@ use std::io::Write;
 use std::marker::PhantomData;
 use std::ptr::copy_nonoverlapping;
 use std::slice::from_raw_parts_mut;
-use std::{cmp, io};
+use std::{cmp, fmt, io};
 
 use bytes::{buf::BufMutExt, BufMut, BytesMut};
 
@@ -11,8 +11,8 @@ use crate::http::config::ServiceConfig;
 use crate::http::header::map;
 use crate::http::helpers;
 use crate::http::header::{CONNECTION, CONTENT_LENGTH, DATE, TRANSFER_ENCODING};
-use crate::http::{HeaderMap, StatusCode, Version};
-use crate::http::message::{ConnectionType, RequestHeadType};
+use crate::http::{HeaderMap, Method, StatusCode, Version};
+use crate::http::message::{ConnectionType, RequestHead, RequestHeadType};
 use crate::http::response::Response;
 
 const AVERAGE_HEADER_SIZE: usize = 30;
@@ -21,6 +21,7 @@ const AVERAGE_HEADER_SIZE: usize = 30;
 pub(crate) struct MessageEncoder<T: MessageType> {
     pub length: BodySize,
     pub te: TransferEncoding,
+    trailers: Option<HeaderMap>,
     _t: PhantomData<T>,
 }
 
@@ -29,6 +30,7 @@ impl<T: MessageType> Default for MessageEncoder<T> {
         MessageEncoder {
             length: BodySize::None,
             te: TransferEncoding::empty(),
+            trailers: None,
             _t: PhantomData,
         }
     }
@@ -294,7 +296,7 @@ impl MessageType for RequestHeadType {
             Writer(dst),
             "{} {} {}",
             head.method,
-            head.uri.path_and_query().map(|u| u.as_str()).unwrap_or("/"),
+            RequestTarget(head),
             match head.version {
                 Version::HTTP_09 => "HTTP/0.9",
                 Version::HTTP_10 => "HTTP/1.0",
@@ -312,15 +314,42 @@ impl MessageType for RequestHeadType {
     }
 }
 
+/// Request target of the request line.
+struct RequestTarget<'a>(&'a RequestHead);
+
+impl<'a> fmt::Display for RequestTarget<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let head = self.0;
+        if head.absolute_form() {
+            write!(f, "{}", head.uri)
+        } else if head.method == Method::CONNECT {
+            match head.uri.authority() {
+                Some(authority) => f.write_str(authority.as_str()),
+                None => f.write_str("/"),
+            }
+        } else {
+            f.write_str(head.uri.path_and_query().map(|u| u.as_str()).unwrap_or("/"))
+        }
+    }
+}
+
 impl<T: MessageType> MessageEncoder<T> {
     /// Encode message
     pub fn encode_chunk(&mut self, msg: &[u8], buf: &mut BytesMut) -> io::Result<bool> {
         self.te.encode(msg, buf)
     }
 
-    /// Encode eof
+    /// Encode eof, followed by the trailers if any were set
     pub fn encode_eof(&mut self, buf: &mut BytesMut) -> io::Result<()> {
-        self.te.encode_eof(buf)
+        match self.trailers.take() {
+            Some(trailers) => self.te.encode_trailers(&trailers, buf),
+            None => self.te.encode_eof(buf),
+        }
+    }
+
+    /// Set trailers to send with the end of the payload
+    pub fn set_trailers(&mut self, trailers: HeaderMap) {
+        self.trailers = Some(trailers);
     }
 
     pub fn encode(
@@ -334,6 +363,8 @@ impl<T: MessageType> MessageEncoder<T> {
         ctype: ConnectionType,
         config: &ServiceConfig,
     ) -> io::Result<()> {
+        self.trailers = None;
+
         // transfer encoding
         if !head {
             self.te = match length {
@@ -473,6 +504,35 @@ impl TransferEncoding {
             }
         }
     }
+
+    /// Encode eof with trailers. Only chunked encoding can carry trailers,
+    /// other encodings drop them.
+    pub fn encode_trailers(
+        &mut self,
+        trailers: &HeaderMap,
+        buf: &mut BytesMut,
+    ) -> io::Result<()> {
+        match self.kind {
+            TransferEncodingKind::Chunked(ref mut eof) => {
+                if !*eof {
+                    *eof = true;
+                    buf.extend_from_slice(b"0\r\n");
+                    for (key, value) in trailers.iter() {
+                        let k = key.as_str().as_bytes();
+                        let v = value.as_ref();
+                        buf.reserve(k.len() + v.len() + 4);
+                        buf.extend_from_slice(k);
+                        buf.extend_from_slice(b": ");
+                        buf.extend_from_slice(v);
+                        buf.extend_from_slice(b"\r\n");
+                    }
+                    buf.extend_from_slice(b"\r\n");
+                }
+                Ok(())
+            }
+            _ => self.encode_eof(buf),
+        }
+    }
 }
 
 struct Writer<'a>(pub &'a mut BytesMut);
@@ -527,7 +587,7 @@ mod tests {
     use http::header::AUTHORIZATION;
 
     use super::*;
-    use crate::http::header::{HeaderValue, CONTENT_TYPE};
+    use crate::http::header::{HeaderName, HeaderValue, CONTENT_TYPE};
     use crate::http::RequestHead;
 
     #[test]
@@ -544,6 +604,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_chunked_te_trailers() {
+        let mut trailers = HeaderMap::new();
+        trailers.insert(
+            HeaderName::from_static("grpc-status"),
+            HeaderValue::from_static("0"),
+        );
+
+        let mut bytes = BytesMut::new();
+        let mut enc = TransferEncoding::chunked();
+        assert!(!enc.encode(b"test", &mut bytes).unwrap());
+        enc.encode_trailers(&trailers, &mut bytes).unwrap();
+        assert!(enc.encode(b"", &mut bytes).unwrap());
+        assert_eq!(
+            bytes.split().freeze(),
+            Bytes::from_static(b"4\r\ntest\r\n0\r\ngrpc-status: 0\r\n\r\n")
+        );
+
+        // trailers are dropped without chunked encoding
+        let mut enc = TransferEncoding::length(4);
+        assert!(enc.encode(b"test", &mut bytes).unwrap());
+        enc.encode_trailers(&trailers, &mut bytes).unwrap();
+        assert_eq!(bytes.split().freeze(), Bytes::from_static(b"test"));
+    }
+
     #[test]
     fn test_camel_case() {
         let mut bytes = BytesMut::with_capacity(2048);
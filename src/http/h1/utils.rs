@@ -54,7 +54,10 @@ where
                         Poll::Ready(item) => {
                             // body is done
                             if item.is_none() {
-                                let _ = this.body.take();
+                                let mut body = this.body.take().unwrap();
+                                if let Some(trailers) = body.take_trailers() {
+                                    framed.get_codec_mut().set_trailers(trailers);
+                                }
                             }
                             framed.write(Message::Chunk(item))?;
                         }
@@ -9,9 +9,15 @@ use crate::krse::io::{AsyncRead, AsyncWrite};
 use crate::timer::{Delay, Instant};
 use crate::service::Service;
 use bytes::{Bytes, BytesMut};
+use futures_channel::mpsc;
+use futures_core::Stream;
 use crate::http::h2::server::{Connection, SendResponse};
 use crate::http::h2::SendStream;
-use http::header::{HeaderValue, CONNECTION, CONTENT_LENGTH, DATE, TRANSFER_ENCODING};
+use http::header::{
+    HeaderMap, HeaderValue, ACCEPT, ACCEPT_ENCODING, ACCEPT_LANGUAGE, CONNECTION,
+    CONTENT_LENGTH, COOKIE, DATE, HOST, TRANSFER_ENCODING, USER_AGENT,
+};
+use http::{Method, Uri};
 use log::{error, trace};
 
 use crate::http::body::{BodySize, MessageBody, ResponseBody};
@@ -25,8 +31,14 @@ use crate::http::payload::Payload;
 use crate::http::request::Request;
 use crate::http::response::Response;
 
+use super::push;
+
 const CHUNK_SIZE: usize = 16_384;
 
+/// Request headers copied to promised requests
+const PUSH_HEADERS: [http::header::HeaderName; 5] =
+    [ACCEPT, ACCEPT_ENCODING, ACCEPT_LANGUAGE, COOKIE, USER_AGENT];
+
 /// Dispatcher for HTTP/2 protocol
 #[pin_project::pin_project]
 pub struct Dispatcher<T, S: Service<Request = Request>, B: MessageBody>
@@ -40,6 +52,8 @@ where
     peer_addr: Option<net::SocketAddr>,
     ka_expire: Instant,
     ka_timer: Option<Delay>,
+    push_tx: mpsc::UnboundedSender<(Request, SendResponse<Bytes>)>,
+    push_rx: mpsc::UnboundedReceiver<(Request, SendResponse<Bytes>)>,
     _t: PhantomData<B>,
 }
 
@@ -75,6 +89,7 @@ where
         } else {
             (config.now(), None)
         };
+        let (push_tx, push_rx) = mpsc::unbounded();
 
         Dispatcher {
             service,
@@ -84,6 +99,8 @@ where
             on_connect,
             ka_expire,
             ka_timer,
+            push_tx,
+            push_rx,
             _t: PhantomData,
         }
     }
@@ -105,6 +122,32 @@ where
         let this = self.get_mut();
 
         loop {
+            // serve requests promised by responses
+            while let Poll::Ready(Some((mut req, res))) =
+                Pin::new(&mut this.push_rx).poll_next(cx)
+            {
+                req.head_mut().peer_addr = this.peer_addr;
+                if let Some(ref on_connect) = this.on_connect {
+                    on_connect.set(&mut req.extensions_mut());
+                }
+
+                crate::fiber::spawn(ServiceResponse::<
+                    S::Future,
+                    S::Response,
+                    S::Error,
+                    B,
+                > {
+                    state: ServiceResponseState::ServiceCall(
+                        this.service.call(req),
+                        Some(res),
+                    ),
+                    config: this.config.clone(),
+                    buffer: None,
+                    pusher: None,
+                    _t: PhantomData,
+                });
+            }
+
             match Pin::new(&mut this.connection).poll_accept(cx) {
                 Poll::Ready(None) => return Poll::Ready(Ok(())),
                 Poll::Ready(Some(Err(err))) => return Poll::Ready(Err(err.into())),
@@ -123,6 +166,9 @@ where
                         crate::http::h2::Payload::new(body)
                     ));
 
+                    let pusher =
+                        Pusher::new(this.push_tx.clone(), &parts.uri, &parts.headers);
+
                     let head = &mut req.head_mut();
                     head.uri = parts.uri;
                     head.method = parts.method;
@@ -147,6 +193,7 @@ where
                         ),
                         config: this.config.clone(),
                         buffer: None,
+                        pusher,
                         _t: PhantomData,
                     });
                 }
@@ -156,11 +203,95 @@ where
     }
 }
 
+/// Sends push promises for the resources associated with a response.
+///
+/// Promised requests are handed to the dispatcher, which serves them with the
+/// service.
+struct Pusher {
+    tx: mpsc::UnboundedSender<(Request, SendResponse<Bytes>)>,
+    uri: Uri,
+    headers: HeaderMap,
+}
+
+impl Pusher {
+    fn new(
+        tx: mpsc::UnboundedSender<(Request, SendResponse<Bytes>)>,
+        uri: &Uri,
+        headers: &HeaderMap,
+    ) -> Option<Self> {
+        // promised requests need the scheme and authority of the request
+        let authority = match uri.authority() {
+            Some(authority) => authority.as_str(),
+            None => headers.get(HOST)?.to_str().ok()?,
+        };
+        let uri = Uri::builder()
+            .scheme(uri.scheme_str().unwrap_or("http"))
+            .authority(authority)
+            .path_and_query("/")
+            .build()
+            .ok()?;
+
+        let mut push_headers = HeaderMap::new();
+        for name in PUSH_HEADERS.iter() {
+            for value in headers.get_all(name) {
+                push_headers.append(name.clone(), value.clone());
+            }
+        }
+
+        Some(Pusher {
+            tx,
+            uri,
+            headers: push_headers,
+        })
+    }
+
+    /// Push the resources associated with the response head.
+    fn push(self, send: &mut SendResponse<Bytes>, head: &ResponseHead) {
+        if !send.is_push_enabled() {
+            return;
+        }
+
+        for path in push::paths(head) {
+            let mut parts = self.uri.clone().into_parts();
+            parts.path_and_query = Some(path);
+            let uri = match Uri::from_parts(parts) {
+                Ok(uri) => uri,
+                Err(_) => continue,
+            };
+
+            let mut promise = http::Request::new(());
+            *promise.uri_mut() = uri.clone();
+            *promise.headers_mut() = self.headers.clone();
+
+            let pushed = match send.push_request(promise) {
+                Ok(pushed) => pushed.into_inner(),
+                Err(e) => {
+                    trace!("Error sending h2 push promise for {}: {:?}", uri, e);
+                    break;
+                }
+            };
+
+            let mut req = Request::new();
+            let head = req.head_mut();
+            head.uri = uri;
+            head.method = Method::GET;
+            head.version = http::Version::HTTP_2;
+            head.headers = self.headers.clone().into();
+
+            if self.tx.unbounded_send((req, pushed)).is_err() {
+                break;
+            }
+        }
+    }
+}
+
 #[pin_project::pin_project]
 struct ServiceResponse<F, I, E, B> {
     state: ServiceResponseState<F, B>,
     config: ServiceConfig,
     buffer: Option<Bytes>,
+    /// Pushes resources of the response, not set for pushed responses
+    pusher: Option<Pusher>,
     _t: PhantomData<(I, E)>,
 }
 
@@ -257,6 +388,10 @@ where
                         let (res, body) = res.into().replace_body(());
 
                         let mut send = send.take().unwrap();
+                        if let Some(pusher) = this.pusher.take() {
+                            pusher.push(&mut send, res.head());
+                        }
+
                         let mut size = body.size();
                         let h2_res =
                             self.as_mut().prepare_response(res.head(), &mut size);
@@ -338,7 +473,11 @@ where
                         match body.poll_next(cx) {
                             Poll::Pending => return Poll::Pending,
                             Poll::Ready(None) => {
-                                if let Err(e) = stream.send_data(Bytes::new(), true) {
+                                let res = match body.take_trailers() {
+                                    Some(trailers) => stream.send_trailers(trailers.into()),
+                                    None => stream.send_data(Bytes::new(), true),
+                                };
+                                if let Err(e) = res {
                                     warn!("{:?}", e);
                                 }
                                 return Poll::Ready(());
@@ -99,6 +99,10 @@ impl Settings {
         self.max_header_list_size = size;
     }
 
+    pub fn enable_push(&self) -> Option<u32> {
+        self.enable_push
+    }
+
     pub fn is_push_enabled(&self) -> bool {
         self.enable_push.unwrap_or(1) != 0
     }
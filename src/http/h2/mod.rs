@@ -145,9 +145,11 @@ impl<T, E> PollExt<T, E> for Poll<Option<Result<T, E>>> {
 
 
 mod dispatcher;
+mod push;
 mod service;
 
 pub use self::dispatcher::Dispatcher;
+pub(crate) use self::push::PushPromises;
 pub use self::service::H2Service;
 
 use std::pin::Pin;
@@ -156,15 +158,25 @@ use futures_core::Stream;
 // use h2::RecvStream;
 
 use crate::http::error::PayloadError;
+use crate::http::payload::Trailers;
 
 /// H2 receive stream
 pub struct Payload {
     pl: RecvStream,
+    trailers: Option<Trailers>,
 }
 
 impl Payload {
     pub(crate) fn new(pl: RecvStream) -> Self {
-        Self { pl }
+        Self { pl, trailers: None }
+    }
+
+    /// Receive stream that stores its trailers in `trailers`
+    pub(crate) fn with_trailers(pl: RecvStream, trailers: Trailers) -> Self {
+        Self {
+            pl,
+            trailers: Some(trailers),
+        }
     }
 }
 
@@ -188,7 +200,20 @@ impl Stream for Payload {
             }
             Poll::Ready(Some(Err(err))) => Poll::Ready(Some(Err(err.into()))),
             Poll::Pending => Poll::Pending,
-            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Ready(None) => {
+                if let Some(trailers) = this.trailers.take() {
+                    match this.pl.poll_trailers(cx) {
+                        Poll::Ready(Ok(Some(map))) => trailers.set(Some(map.into())),
+                        Poll::Ready(Ok(None)) => (),
+                        Poll::Ready(Err(err)) => return Poll::Ready(Some(Err(err.into()))),
+                        Poll::Pending => {
+                            this.trailers = Some(trailers);
+                            return Poll::Pending;
+                        }
+                    }
+                }
+                Poll::Ready(None)
+            }
         }
     }
 }
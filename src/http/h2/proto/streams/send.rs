@@ -23,6 +23,9 @@ pub(super) struct Send {
 
     /// Prioritization layer
     prioritize: Prioritize,
+
+    /// If the peer allows server push
+    is_push_enabled: bool,
 }
 
 /// A value to detect which public API has called `poll_reset`.
@@ -39,6 +42,7 @@ impl Send {
             init_window_sz: config.remote_init_window_sz,
             next_stream_id: Ok(config.local_next_stream_id),
             prioritize: Prioritize::new(config),
+            is_push_enabled: true,
         }
     }
 
@@ -47,6 +51,11 @@ impl Send {
         self.init_window_sz
     }
 
+    /// Returns true if the peer allows server push
+    pub fn is_push_enabled(&self) -> bool {
+        self.is_push_enabled
+    }
+
     pub fn open(&mut self) -> Result<StreamId, UserError> {
         let stream_id = self.ensure_next_stream_id()?;
         self.next_stream_id = stream_id.next_id();
@@ -54,6 +63,10 @@ impl Send {
     }
 
     pub fn reserve_local(&mut self) -> Result<StreamId, UserError> {
+        if !self.is_push_enabled {
+            return Err(UserError::PeerDisabledServerPush);
+        }
+
         let stream_id = self.ensure_next_stream_id()?;
         self.next_stream_id = stream_id.next_id();
         Ok(stream_id)
@@ -389,6 +402,10 @@ impl Send {
         counts: &mut Counts,
         task: &mut Option<Waker>,
     ) -> Result<(), RecvError> {
+        if let Some(val) = settings.enable_push() {
+            self.is_push_enabled = val != 0;
+        }
+
         // Applies an update to the remote endpoint's initial window size.
         //
         // Per RFC 7540 §6.9.2:
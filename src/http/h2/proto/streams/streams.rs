@@ -1106,6 +1106,11 @@ impl<B> StreamRef<B> {
     pub fn stream_id(&self) -> StreamId {
         self.opaque.stream_id()
     }
+
+    pub fn is_push_enabled(&self) -> bool {
+        let me = self.opaque.inner.lock().unwrap();
+        me.actions.send.is_push_enabled()
+    }
 }
 
 impl<B> Clone for StreamRef<B> {
@@ -0,0 +1,126 @@
+//! HTTP/2 server push
+use std::convert::TryFrom;
+
+use http::uri::PathAndQuery;
+
+use crate::http::header::LINK;
+use crate::http::message::ResponseHead;
+
+/// Paths registered with `ResponseBuilder::push()`, kept in the response
+/// extensions.
+#[derive(Debug, Default)]
+pub(crate) struct PushPromises(pub(crate) Vec<PathAndQuery>);
+
+/// Returns the paths to push along with the response.
+///
+/// These are the paths registered with `ResponseBuilder::push()` followed by
+/// same-origin targets of `Link: <path>; rel=preload` headers, unless the
+/// link has the `nopush` parameter.
+pub(crate) fn paths(head: &ResponseHead) -> Vec<PathAndQuery> {
+    let mut paths = head
+        .extensions
+        .borrow()
+        .get::<PushPromises>()
+        .map(|push| push.0.clone())
+        .unwrap_or_default();
+
+    for value in head.headers.get_all(LINK) {
+        if let Ok(value) = value.to_str() {
+            for path in preload_links(value) {
+                if !paths.contains(&path) {
+                    paths.push(path);
+                }
+            }
+        }
+    }
+    paths
+}
+
+/// Parse preload targets of a `Link` header value.
+fn preload_links(value: &str) -> Vec<PathAndQuery> {
+    let mut links = Vec::new();
+    let mut rest = value;
+
+    while let Some(start) = rest.find('<') {
+        let end = match rest[start..].find('>') {
+            Some(end) => start + end,
+            None => break,
+        };
+        let target = rest[start + 1..end].trim();
+        rest = &rest[end + 1..];
+
+        // parameters run up to the next link
+        let params = &rest[..rest.find('<').unwrap_or_else(|| rest.len())];
+        let mut preload = false;
+        let mut nopush = false;
+        for param in params.split(|c| c == ';' || c == ',') {
+            let mut param = param.splitn(2, '=');
+            let name = param.next().unwrap_or("").trim();
+            let value = param.next().unwrap_or("").trim().trim_matches('"');
+            if name.eq_ignore_ascii_case("rel") {
+                preload = value
+                    .split_whitespace()
+                    .any(|rel| rel.eq_ignore_ascii_case("preload"));
+            } else if name.eq_ignore_ascii_case("nopush") {
+                nopush = true;
+            }
+        }
+
+        // only same-origin paths can be pushed
+        if preload && !nopush && target.starts_with('/') && !target.starts_with("//") {
+            if let Ok(path) = PathAndQuery::try_from(target) {
+                links.push(path);
+            }
+        }
+    }
+    links
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::header::HeaderValue;
+    use crate::http::StatusCode;
+
+    fn links(value: &str) -> Vec<String> {
+        preload_links(value)
+            .iter()
+            .map(|path| path.as_str().to_owned())
+            .collect()
+    }
+
+    #[test]
+    fn test_preload_links() {
+        assert_eq!(
+            links("</style.css>; rel=preload; as=style"),
+            vec!["/style.css"]
+        );
+        assert_eq!(
+            links("</app.js>; rel=\"preload\", </font.woff2>; rel=\"preload prefetch\""),
+            vec!["/app.js", "/font.woff2"]
+        );
+        assert_eq!(
+            links("</a.js>; rel=preload; nopush, </b.js>; rel=preload"),
+            vec!["/b.js"]
+        );
+        assert!(links("</next.html>; rel=prefetch").is_empty());
+        assert!(links("<https://cdn.example.com/a.js>; rel=preload").is_empty());
+        assert!(links("<//cdn.example.com/a.js>; rel=preload").is_empty());
+        assert!(links("</broken; rel=preload").is_empty());
+    }
+
+    #[test]
+    fn test_paths() {
+        let mut head = ResponseHead::new(StatusCode::OK);
+        head.extensions
+            .borrow_mut()
+            .insert(PushPromises(vec![PathAndQuery::from_static("/app.js")]));
+        head.headers.append(
+            LINK,
+            HeaderValue::from_static("</app.js>; rel=preload, </style.css>; rel=preload"),
+        );
+
+        let paths: Vec<_> = paths(&head).iter().map(|p| p.to_string()).collect();
+        assert_eq!(paths, vec!["/app.js", "/style.css"]);
+    }
+}
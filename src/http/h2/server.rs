@@ -978,6 +978,16 @@ impl<B: Buf> SendResponse<B> {
             .map_err(Into::into)
     }
 
+    /// Returns true if the client allows server push.
+    ///
+    /// [`push_request`] fails if the client disabled push with
+    /// `SETTINGS_ENABLE_PUSH`.
+    ///
+    /// [`push_request`]: #method.push_request
+    pub fn is_push_enabled(&self) -> bool {
+        self.inner.is_push_enabled()
+    }
+
     /// Send a stream reset to the peer.
     ///
     /// This essentially cancels the stream, including any inbound or outbound
@@ -1026,6 +1036,11 @@ impl<B: Buf> SendResponse<B> {
 // ===== impl SendPushedResponse =====
 
 impl<B: Buf> SendPushedResponse<B> {
+    /// Returns the underlying `SendResponse` of the promised stream.
+    pub(crate) fn into_inner(self) -> SendResponse<B> {
+        self.inner
+    }
+
     /// Send a response to a promised request.
     ///
     /// On success, a [`SendStream`] instance is returned. This instance can be
@@ -1219,8 +1234,9 @@ where
                 codec,
                 Config {
                     next_stream_id: 2.into(),
-                    // Server does not need to locally initiate any streams
-                    initial_max_send_streams: 0,
+                    // Pushed streams are only limited once the client
+                    // sends SETTINGS_MAX_CONCURRENT_STREAMS
+                    initial_max_send_streams: usize::MAX,
                     reset_stream_duration: self.builder.reset_stream_duration,
                     reset_stream_max: self.builder.reset_stream_max,
                     settings: self.builder.settings.clone(),
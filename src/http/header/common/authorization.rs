@@ -0,0 +1,263 @@
+use std::borrow::Cow;
+use std::fmt::{self, Display, Write};
+use std::str::FromStr;
+
+use crate::http::error::ParseError;
+use crate::http::header::{
+    self, from_one_raw_str, Header, HeaderName, HeaderValue, IntoHeaderValue, InvalidHeaderValue,
+    Writer,
+};
+use crate::http::httpmessage::HttpMessage;
+
+/// `Authorization` header, defined in [RFC7235](https://tools.ietf.org/html/rfc7235#section-4.2)
+///
+/// The "Authorization" header field allows a user agent to authenticate
+/// itself with an origin server -- usually, but not necessarily, after
+/// receiving a 401 (Unauthorized) response. Its value consists of
+/// credentials containing the authentication information of the user
+/// agent for the realm of the resource being requested.
+///
+/// The header is generic over the authentication scheme, `Basic` and
+/// `Bearer` schemes are provided.
+///
+/// # ABNF
+///
+/// ```text
+/// Authorization = credentials
+/// credentials = auth-scheme [ 1*SP ( token68 / #auth-param ) ]
+/// ```
+///
+/// # Example values
+///
+/// * `Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ==`
+/// * `Bearer mF_9.B5f-4.1JqM`
+///
+/// # Examples
+///
+/// ```rust
+/// use kayrx::http::Response;
+/// use kayrx::http::header::{Authorization, Bearer};
+///
+/// let mut builder = Response::Ok();
+/// builder.set(Authorization(Bearer::new("mF_9.B5f-4.1JqM")));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Authorization<S: Scheme>(pub S);
+
+/// Authentication scheme of the `Authorization` header
+pub trait Scheme: FromStr + Display + fmt::Debug + Clone + Send + Sync + 'static {}
+
+impl<S: Scheme> Authorization<S> {
+    /// Consumes the header, returning the credentials
+    pub fn into_scheme(self) -> S {
+        self.0
+    }
+}
+
+impl<S: Scheme> Header for Authorization<S> {
+    fn name() -> HeaderName {
+        header::AUTHORIZATION
+    }
+
+    #[inline]
+    fn parse<T>(msg: &T) -> Result<Self, ParseError>
+    where
+        T: HttpMessage,
+    {
+        from_one_raw_str(msg.headers().get(&header::AUTHORIZATION)).map(Authorization)
+    }
+}
+
+impl<S: Scheme> Display for Authorization<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+impl<S: Scheme> IntoHeaderValue for Authorization<S> {
+    type Error = InvalidHeaderValue;
+
+    fn try_into(self) -> Result<HeaderValue, Self::Error> {
+        let mut writer = Writer::new();
+        let _ = write!(&mut writer, "{}", self);
+        HeaderValue::from_maybe_shared(writer.take())
+    }
+}
+
+/// Splits credentials into the case-insensitive scheme name and the rest.
+fn strip_scheme<'a>(s: &'a str, scheme: &str) -> Option<&'a str> {
+    let s = s.trim();
+    if s.len() > scheme.len()
+        && s.is_char_boundary(scheme.len())
+        && s[..scheme.len()].eq_ignore_ascii_case(scheme)
+        && s[scheme.len()..].starts_with(' ')
+    {
+        Some(s[scheme.len()..].trim_start())
+    } else {
+        None
+    }
+}
+
+/// Credentials of the `Basic` scheme, defined in
+/// [RFC7617](https://tools.ietf.org/html/rfc7617)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Basic {
+    user_id: Cow<'static, str>,
+    password: Option<Cow<'static, str>>,
+}
+
+impl Basic {
+    /// Create credentials of the user with optional password
+    pub fn new<U, P>(user_id: U, password: Option<P>) -> Basic
+    where
+        U: Into<Cow<'static, str>>,
+        P: Into<Cow<'static, str>>,
+    {
+        Basic {
+            user_id: user_id.into(),
+            password: password.map(Into::into),
+        }
+    }
+
+    /// User id
+    pub fn user_id(&self) -> &Cow<'static, str> {
+        &self.user_id
+    }
+
+    /// Password, `None` if the credentials do not contain the `:` separator
+    pub fn password(&self) -> Option<&Cow<'static, str>> {
+        self.password.as_ref()
+    }
+}
+
+impl Scheme for Basic {}
+
+impl FromStr for Basic {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Basic, ParseError> {
+        let credentials = strip_scheme(s, "Basic").ok_or(ParseError::Header)?;
+        let decoded = base64::decode(credentials).map_err(|_| ParseError::Header)?;
+        let decoded = String::from_utf8(decoded).map_err(|_| ParseError::Header)?;
+
+        let mut parts = decoded.splitn(2, ':');
+        let user_id = parts.next().ok_or(ParseError::Header)?.to_owned();
+        let password = parts.next().map(|password| password.to_owned());
+        Ok(Basic::new(user_id, password))
+    }
+}
+
+impl Display for Basic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let credentials = match self.password {
+            Some(ref password) => format!("{}:{}", self.user_id, password),
+            None => self.user_id.to_string(),
+        };
+        write!(f, "Basic {}", base64::encode(&credentials))
+    }
+}
+
+/// Credentials of the `Bearer` scheme, defined in
+/// [RFC6750](https://tools.ietf.org/html/rfc6750#section-2.1)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Bearer {
+    token: Cow<'static, str>,
+}
+
+impl Bearer {
+    /// Create credentials with the token
+    pub fn new<T: Into<Cow<'static, str>>>(token: T) -> Bearer {
+        Bearer {
+            token: token.into(),
+        }
+    }
+
+    /// Bearer token
+    pub fn token(&self) -> &Cow<'static, str> {
+        &self.token
+    }
+}
+
+impl Scheme for Bearer {}
+
+impl FromStr for Bearer {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Bearer, ParseError> {
+        let token = strip_scheme(s, "Bearer").ok_or(ParseError::Header)?;
+
+        // b64token = 1*( ALPHA / DIGIT / "-" / "." / "_" / "~" / "+" / "/" ) *"="
+        let chars = token.trim_end_matches('=');
+        let valid = !chars.is_empty()
+            && chars
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b"-._~+/".contains(&b));
+        if valid {
+            Ok(Bearer::new(token.to_owned()))
+        } else {
+            Err(ParseError::Header)
+        }
+    }
+}
+
+impl Display for Bearer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Bearer {}", self.token)
+    }
+}
+
+#[cfg(test)]
+mod test_basic {
+    use super::{Authorization, Basic};
+    use crate::http::header::*;
+    use std::str;
+
+    type HeaderField = Authorization<Basic>;
+
+    test_header!(
+        test1,
+        vec![b"Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ=="],
+        Some(Authorization(Basic::new("Aladdin", Some("open sesame"))))
+    );
+    test_header!(
+        test2,
+        vec![b"Basic QWxhZGRpbg=="],
+        Some(Authorization(Basic::new("Aladdin", None::<&str>)))
+    );
+    test_header!(
+        test3,
+        vec![b"Basic QWxhZGRpbjo="],
+        Some(Authorization(Basic::new("Aladdin", Some(""))))
+    );
+    test_header!(test4, vec![b"Basic !!!"], None::<HeaderField>);
+    test_header!(test5, vec![b"Bearer QWxhZGRpbg=="], None::<HeaderField>);
+
+    #[test]
+    fn test_case_insensitive_scheme() {
+        let basic: Basic = "basic  QWxhZGRpbg==".parse().unwrap();
+        assert_eq!(basic.user_id(), "Aladdin");
+    }
+}
+
+#[cfg(test)]
+mod test_bearer {
+    use super::{Authorization, Bearer};
+    use crate::http::header::*;
+    use std::str;
+
+    type HeaderField = Authorization<Bearer>;
+
+    test_header!(
+        test1,
+        vec![b"Bearer mF_9.B5f-4.1JqM"],
+        Some(Authorization(Bearer::new("mF_9.B5f-4.1JqM")))
+    );
+    test_header!(
+        test2,
+        vec![b"Bearer abc/def+=="],
+        Some(Authorization(Bearer::new("abc/def+==")))
+    );
+    test_header!(test3, vec![b"Bearer"], None::<HeaderField>);
+    test_header!(test4, vec![b"Bearer a b"], None::<HeaderField>);
+    test_header!(test5, vec![b"Basic QWxhZGRpbg=="], None::<HeaderField>);
+}
@@ -12,6 +12,7 @@ pub use self::accept_charset::AcceptCharset;
 pub use self::accept_language::AcceptLanguage;
 pub use self::accept::Accept;
 pub use self::allow::Allow;
+pub use self::authorization::{Authorization, Basic, Bearer, Scheme};
 pub use self::cache_control::{CacheControl, CacheDirective};
 pub use self::content_disposition::{ContentDisposition, DispositionType, DispositionParam};
 pub use self::content_language::ContentLanguage;
@@ -26,6 +27,7 @@ pub use self::if_none_match::IfNoneMatch;
 pub use self::if_range::IfRange;
 pub use self::if_unmodified_since::IfUnmodifiedSince;
 pub use self::last_modified::LastModified;
+pub use self::www_authenticate::{BasicChallenge, BearerChallenge, BearerError, Challenge};
 // pub use self::range::{Range, ByteRangeSpec};
 
 #[doc(hidden)]
@@ -336,6 +338,7 @@ mod accept_charset;
 mod accept_language;
 mod accept;
 mod allow;
+mod authorization;
 mod cache_control;
 mod content_disposition;
 mod content_language;
@@ -350,4 +353,5 @@ mod if_none_match;
 mod if_range;
 mod if_unmodified_since;
 mod last_modified;
+mod www_authenticate;
 // mod range;
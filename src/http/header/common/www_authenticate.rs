@@ -0,0 +1,255 @@
+use std::borrow::Cow;
+use std::fmt::{self, Display, Write};
+
+use crate::http::header::{HeaderValue, IntoHeaderValue, InvalidHeaderValue, Writer};
+use crate::http::StatusCode;
+
+/// Challenge of the `WWW-Authenticate` header, defined in
+/// [RFC7235](https://tools.ietf.org/html/rfc7235#section-4.1)
+///
+/// A server generating a 401 (Unauthorized) response sends a
+/// `WWW-Authenticate` header field containing at least one challenge
+/// applicable to the target resource.
+///
+/// # ABNF
+///
+/// ```text
+/// WWW-Authenticate = 1#challenge
+/// challenge = auth-scheme [ 1*SP ( token68 / #auth-param ) ]
+/// ```
+///
+/// # Examples
+///
+/// ```rust
+/// use kayrx::http::Response;
+/// use kayrx::http::header::{BasicChallenge, WWW_AUTHENTICATE};
+///
+/// let mut builder = Response::Unauthorized();
+/// builder.header(WWW_AUTHENTICATE, BasicChallenge::new().realm("Restricted area"));
+/// ```
+pub trait Challenge:
+    IntoHeaderValue + Display + fmt::Debug + Clone + Send + Sync + 'static
+{
+}
+
+/// Writes `name="value"` auth-param, escaping the quoted string.
+fn write_param(
+    f: &mut fmt::Formatter<'_>,
+    first: &mut bool,
+    name: &str,
+    value: &str,
+) -> fmt::Result {
+    f.write_str(if *first { " " } else { ", " })?;
+    *first = false;
+
+    write!(f, "{}=\"", name)?;
+    for c in value.chars() {
+        if c == '"' || c == '\\' {
+            f.write_char('\\')?;
+        }
+        f.write_char(c)?;
+    }
+    f.write_char('"')
+}
+
+fn to_header_value<C: Display>(challenge: &C) -> Result<HeaderValue, InvalidHeaderValue> {
+    let mut writer = Writer::new();
+    let _ = write!(&mut writer, "{}", challenge);
+    HeaderValue::from_maybe_shared(writer.take())
+}
+
+/// Challenge of the `Basic` scheme, defined in
+/// [RFC7617](https://tools.ietf.org/html/rfc7617#section-2)
+///
+/// Displays as `Basic realm="Restricted area"`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BasicChallenge {
+    realm: Option<Cow<'static, str>>,
+}
+
+impl BasicChallenge {
+    /// Create challenge without realm
+    pub fn new() -> BasicChallenge {
+        BasicChallenge::default()
+    }
+
+    /// Set protection space of the challenge
+    pub fn realm<T: Into<Cow<'static, str>>>(mut self, value: T) -> Self {
+        self.realm = Some(value.into());
+        self
+    }
+}
+
+impl Challenge for BasicChallenge {}
+
+impl Display for BasicChallenge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Basic")?;
+        if let Some(ref realm) = self.realm {
+            write_param(f, &mut true, "realm", realm)?;
+        }
+        Ok(())
+    }
+}
+
+impl IntoHeaderValue for BasicChallenge {
+    type Error = InvalidHeaderValue;
+
+    fn try_into(self) -> Result<HeaderValue, Self::Error> {
+        to_header_value(&self)
+    }
+}
+
+/// Error code of the `Bearer` challenge, defined in
+/// [RFC6750](https://tools.ietf.org/html/rfc6750#section-3.1)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BearerError {
+    /// The request is missing a parameter or is otherwise malformed
+    InvalidRequest,
+    /// The access token is expired, revoked, malformed or invalid
+    InvalidToken,
+    /// The request requires higher privileges than provided by the token
+    InsufficientScope,
+}
+
+impl BearerError {
+    /// Status code of the response with the error
+    pub fn status_code(self) -> StatusCode {
+        match self {
+            BearerError::InvalidRequest => StatusCode::BAD_REQUEST,
+            BearerError::InvalidToken => StatusCode::UNAUTHORIZED,
+            BearerError::InsufficientScope => StatusCode::FORBIDDEN,
+        }
+    }
+}
+
+impl Display for BearerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            BearerError::InvalidRequest => "invalid_request",
+            BearerError::InvalidToken => "invalid_token",
+            BearerError::InsufficientScope => "insufficient_scope",
+        })
+    }
+}
+
+/// Challenge of the `Bearer` scheme, defined in
+/// [RFC6750](https://tools.ietf.org/html/rfc6750#section-3)
+///
+/// Displays as `Bearer realm="example", error="invalid_token"`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BearerChallenge {
+    realm: Option<Cow<'static, str>>,
+    scope: Option<Cow<'static, str>>,
+    error: Option<BearerError>,
+    error_description: Option<Cow<'static, str>>,
+    error_uri: Option<Cow<'static, str>>,
+}
+
+impl BearerChallenge {
+    /// Create challenge without parameters
+    pub fn new() -> BearerChallenge {
+        BearerChallenge::default()
+    }
+
+    /// Set protection space of the challenge
+    pub fn realm<T: Into<Cow<'static, str>>>(mut self, value: T) -> Self {
+        self.realm = Some(value.into());
+        self
+    }
+
+    /// Set space-delimited list of scopes required to access the resource
+    pub fn scope<T: Into<Cow<'static, str>>>(mut self, value: T) -> Self {
+        self.scope = Some(value.into());
+        self
+    }
+
+    /// Set error code
+    pub fn error(mut self, value: BearerError) -> Self {
+        self.error = Some(value);
+        self
+    }
+
+    /// Set human-readable error description
+    pub fn error_description<T: Into<Cow<'static, str>>>(mut self, value: T) -> Self {
+        self.error_description = Some(value.into());
+        self
+    }
+
+    /// Set URI of the page describing the error
+    pub fn error_uri<T: Into<Cow<'static, str>>>(mut self, value: T) -> Self {
+        self.error_uri = Some(value.into());
+        self
+    }
+
+    /// Error code of the challenge
+    pub fn get_error(&self) -> Option<BearerError> {
+        self.error
+    }
+}
+
+impl Challenge for BearerChallenge {}
+
+impl Display for BearerChallenge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Bearer")?;
+        let mut first = true;
+        if let Some(ref realm) = self.realm {
+            write_param(f, &mut first, "realm", realm)?;
+        }
+        if let Some(ref scope) = self.scope {
+            write_param(f, &mut first, "scope", scope)?;
+        }
+        if let Some(ref error) = self.error {
+            write_param(f, &mut first, "error", &error.to_string())?;
+        }
+        if let Some(ref description) = self.error_description {
+            write_param(f, &mut first, "error_description", description)?;
+        }
+        if let Some(ref uri) = self.error_uri {
+            write_param(f, &mut first, "error_uri", uri)?;
+        }
+        Ok(())
+    }
+}
+
+impl IntoHeaderValue for BearerChallenge {
+    type Error = InvalidHeaderValue;
+
+    fn try_into(self) -> Result<HeaderValue, Self::Error> {
+        to_header_value(&self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_basic() {
+        assert_eq!(BasicChallenge::new().to_string(), "Basic");
+        assert_eq!(
+            BasicChallenge::new().realm("Say \"hi\"").to_string(),
+            "Basic realm=\"Say \\\"hi\\\"\""
+        );
+    }
+
+    #[test]
+    fn test_bearer() {
+        assert_eq!(BearerChallenge::new().to_string(), "Bearer");
+        let challenge = BearerChallenge::new()
+            .realm("example")
+            .scope("read write")
+            .error(BearerError::InvalidToken)
+            .error_description("The access token expired");
+        assert_eq!(
+            challenge.to_string(),
+            "Bearer realm=\"example\", scope=\"read write\", error=\"invalid_token\", \
+             error_description=\"The access token expired\""
+        );
+        assert_eq!(
+            BearerError::InsufficientScope.status_code(),
+            StatusCode::FORBIDDEN
+        );
+    }
+}
@@ -28,6 +28,7 @@ bitflags! {
         const EXPECT      = 0b0000_1000;
         const NO_CHUNKING = 0b0001_0000;
         const CAMEL_CASE  = 0b0010_0000;
+        const ABSOLUTE    = 0b0100_0000;
     }
 }
 
@@ -179,6 +180,18 @@ impl RequestHead {
     pub(crate) fn set_expect(&mut self) {
         self.flags.insert(Flags::EXPECT);
     }
+
+    /// Is the request line written with the absolute uri, as required for
+    /// requests to a forward proxy
+    #[inline]
+    pub(crate) fn absolute_form(&self) -> bool {
+        self.flags.contains(Flags::ABSOLUTE)
+    }
+
+    #[inline]
+    pub(crate) fn set_absolute_form(&mut self) {
+        self.flags.insert(Flags::ABSOLUTE);
+    }
 }
 
 #[derive(Debug)]
@@ -194,6 +207,34 @@ impl RequestHeadType {
             RequestHeadType::Rc(_, headers) => headers.as_ref(),
         }
     }
+
+    /// Convert into an owned head, extra headers replace the headers of the
+    /// shared head. Extensions of a shared head are not copied.
+    pub(crate) fn into_owned(self) -> RequestHead {
+        match self {
+            RequestHeadType::Owned(head) => head,
+            RequestHeadType::Rc(head, extra_headers) => {
+                let mut head = Rc::try_unwrap(head).unwrap_or_else(|head| RequestHead {
+                    uri: head.uri.clone(),
+                    method: head.method.clone(),
+                    version: head.version,
+                    headers: head.headers.clone(),
+                    extensions: RefCell::new(Extensions::new()),
+                    peer_addr: head.peer_addr,
+                    flags: head.flags,
+                });
+                if let Some(extra_headers) = extra_headers {
+                    for name in extra_headers.keys() {
+                        head.headers.remove(name);
+                    }
+                    for (name, value) in extra_headers.iter() {
+                        head.headers.append(name.clone(), value.clone());
+                    }
+                }
+                head
+            }
+        }
+    }
 }
 
 impl AsRef<RequestHead> for RequestHeadType {
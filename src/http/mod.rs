@@ -4,7 +4,7 @@ mod builder;
 mod cloneable;
 mod config;
 mod extensions;
-mod helpers;
+pub(crate) mod helpers;
 mod httpcodes;
 mod payload;
 mod request;
@@ -27,6 +27,7 @@ pub use self::config::{KeepAlive, ServiceConfig};
 pub use self::extensions::Extensions;
 pub use self::message::{Message, RequestHead, RequestHeadType, ResponseHead};
 pub use self::payload::{Payload, PayloadStream};
+pub(crate) use self::payload::Trailers;
 pub use self::request::Request;
 pub use self::response::{Response, ResponseBuilder};
 pub use self::service::HttpService;
@@ -1,4 +1,6 @@
+use std::cell::RefCell;
 use std::pin::Pin;
+use std::rc::Rc;
 use std::task::{Context, Poll};
 
 use bytes::Bytes;
@@ -6,10 +8,28 @@ use futures_core::Stream;
 use crate::http::h2::RecvStream;
 
 use crate::http::error::PayloadError;
+use crate::http::header::HeaderMap;
 
 /// Type represent boxed payload
 pub type PayloadStream = Pin<Box<dyn Stream<Item = Result<Bytes, PayloadError>>>>;
 
+/// Trailers of a received payload
+///
+/// Shared between the payload stream and the message extensions, filled in
+/// once the payload is read to the end.
+#[derive(Clone, Default)]
+pub(crate) struct Trailers(Rc<RefCell<Option<HeaderMap>>>);
+
+impl Trailers {
+    pub(crate) fn set(&self, trailers: Option<HeaderMap>) {
+        *self.0.borrow_mut() = trailers;
+    }
+
+    pub(crate) fn get(&self) -> Option<HeaderMap> {
+        self.0.borrow().clone()
+    }
+}
+
 /// Type represent streaming payload
 pub enum Payload<S = PayloadStream> {
     None,
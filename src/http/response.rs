@@ -16,7 +16,9 @@ use coo_kie::{Cookie, CookieJar};
 
 use crate::http::body::{Body, BodyStream, MessageBody, ResponseBody};
 use crate::http::extensions::Extensions;
+use crate::http::h2::PushPromises;
 use crate::http::header::{self, Header,  HeaderName, HeaderValue, IntoHeaderValue};
+use crate::http::uri::PathAndQuery;
 use crate::http::{HeaderMap, StatusCode};
 use crate::http::error::{Error, HttpError};
 use crate::http::message::{BoxedResponseHead, ConnectionType, ResponseHead};
@@ -479,6 +481,45 @@ impl ResponseBuilder {
         self
     }
 
+    /// Push a resource to the client along with the response.
+    ///
+    /// Over HTTP/2 a push promise for `path` is sent before the response, and
+    /// the promised request is served by the same service. Nothing is pushed
+    /// over HTTP/1 or if the client disabled server push.
+    /// `Link: <path>; rel=preload` headers are pushed the same way, unless
+    /// the link has the `nopush` parameter.
+    ///
+    /// ```rust
+    /// use kayrx::http::{Request, Response};
+    ///
+    /// fn index(req: Request) -> Response {
+    ///     Response::Ok()
+    ///         .push("/static/style.css")
+    ///         .content_type("text/html")
+    ///         .body("<link rel=\"stylesheet\" href=\"/static/style.css\">")
+    /// }
+    /// ```
+    pub fn push<P>(&mut self, path: P) -> &mut Self
+    where
+        PathAndQuery: TryFrom<P>,
+        <PathAndQuery as TryFrom<P>>::Error: Into<HttpError>,
+    {
+        if let Some(parts) = parts(&mut self.head, &self.err) {
+            match PathAndQuery::try_from(path) {
+                Ok(path) => {
+                    let mut extensions = parts.extensions.borrow_mut();
+                    if let Some(push) = extensions.get_mut::<PushPromises>() {
+                        push.0.push(path);
+                    } else {
+                        extensions.insert(PushPromises(vec![path]));
+                    }
+                }
+                Err(e) => self.err = Some(e.into()),
+            }
+        }
+        self
+    }
+
     /// Disable chunked transfer encoding for HTTP/1.1 streaming responses.
     #[inline]
     pub fn no_chunking(&mut self) -> &mut Self {
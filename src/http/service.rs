@@ -20,7 +20,8 @@ use crate::http::error::{DispatchError, Error};
 use crate::http::helpers::DataFactory;
 use crate::http::request::Request;
 use crate::http::response::Response;
-use crate::http::{h1, h2::Dispatcher, Protocol};
+use crate::http::{h1, h2::Dispatcher, Extensions, Protocol};
+use crate::server::proxy::{ProxyHeader, ProxyStream};
 
 /// `ServiceFactory` HTTP1.1/HTTP2 transport implementation
 pub struct HttpService<T, S, B, X = h1::ExpectHandler, U = h1::UpgradeHandler<T>> {
@@ -191,6 +192,67 @@ where
     }
 }
 
+impl<S, B, X, U> HttpService<ProxyStream<TcpStream>, S, B, X, U>
+where
+    S: ServiceFactory<Config = (), Request = Request>,
+    S::Error: Into<Error> + 'static,
+    S::InitError: fmt::Debug,
+    S::Response: Into<Response<B>> + 'static,
+    <S::Service as Service>::Future: 'static,
+    B: MessageBody + 'static,
+    X: ServiceFactory<Config = (), Request = Request, Response = Request>,
+    X::Error: Into<Error>,
+    X::InitError: fmt::Debug,
+    <X::Service as Service>::Future: 'static,
+    U: ServiceFactory<
+        Config = (),
+        Request = (Request, Framed<ProxyStream<TcpStream>, h1::Codec>),
+        Response = (),
+    >,
+    U::Error: fmt::Display + Into<Error>,
+    U::InitError: fmt::Debug,
+    <U::Service as Service>::Future: 'static,
+{
+    /// Create tcp stream service for connections with the PROXY protocol
+    /// header.
+    ///
+    /// Peer address of the requests is the source address of the header,
+    /// `ProxyHeader` is stored to the request's extensions.
+    pub fn proxy(
+        mut self,
+    ) -> impl ServiceFactory<
+        Config = (),
+        Request = ProxyStream<TcpStream>,
+        Response = (),
+        Error = DispatchError,
+        InitError = (),
+    > {
+        let on_connect = self.on_connect.take();
+        self.on_connect = Some(rc::Rc::new(move |io: &ProxyStream<TcpStream>| {
+            let data = on_connect.as_ref().map(|f| f(io));
+            Box::new(ProxyData(*io.header(), data)) as Box<dyn DataFactory>
+        }));
+
+        pipeline_factory(|io: ProxyStream<TcpStream>| {
+            let peer_addr = io.peer_addr().ok();
+            ok((io, Protocol::Http1, peer_addr))
+        })
+        .and_then(self)
+    }
+}
+
+/// PROXY protocol header along with the on-connect data
+struct ProxyData(ProxyHeader, Option<Box<dyn DataFactory>>);
+
+impl DataFactory for ProxyData {
+    fn set(&self, ext: &mut Extensions) {
+        ext.insert(self.0);
+        if let Some(ref data) = self.1 {
+            data.set(ext);
+        }
+    }
+}
+
 mod rustls {
     use super::*;
     use crate::secure::tls::{Acceptor, ServerConfig, Session, TlsStream};
@@ -253,6 +315,76 @@ mod rustls {
             .and_then(self.map_err(SslError::Service))
         }
     }
+
+    impl<S, B, X, U> HttpService<TlsStream<ProxyStream<TcpStream>>, S, B, X, U>
+    where
+        S: ServiceFactory<Config = (), Request = Request>,
+        S::Error: Into<Error> + 'static,
+        S::InitError: fmt::Debug,
+        S::Response: Into<Response<B>> + 'static,
+        <S::Service as Service>::Future: 'static,
+        B: MessageBody + 'static,
+        X: ServiceFactory<Config = (), Request = Request, Response = Request>,
+        X::Error: Into<Error>,
+        X::InitError: fmt::Debug,
+        <X::Service as Service>::Future: 'static,
+        U: ServiceFactory<
+            Config = (),
+            Request = (Request, Framed<TlsStream<ProxyStream<TcpStream>>, h1::Codec>),
+            Response = (),
+        >,
+        U::Error: fmt::Display + Into<Error>,
+        U::InitError: fmt::Debug,
+        <U::Service as Service>::Future: 'static,
+    {
+        /// Create rustls based service for connections with the PROXY
+        /// protocol header in front of the tls handshake.
+        ///
+        /// Peer address of the requests is the source address of the header,
+        /// `ProxyHeader` is stored to the request's extensions.
+        pub fn rustls_proxy(
+            mut self,
+            mut config: ServerConfig,
+        ) -> impl ServiceFactory<
+            Config = (),
+            Request = ProxyStream<TcpStream>,
+            Response = (),
+            Error = SslError<io::Error, DispatchError>,
+            InitError = (),
+        > {
+            let protos = vec!["h2".to_string().into(), "http/1.1".to_string().into()];
+            config.set_protocols(&protos);
+
+            let on_connect = self.on_connect.take();
+            self.on_connect = Some(rc::Rc::new(
+                move |io: &TlsStream<ProxyStream<TcpStream>>| {
+                    let data = on_connect.as_ref().map(|f| f(io));
+                    Box::new(ProxyData(*io.get_ref().0.header(), data))
+                        as Box<dyn DataFactory>
+                },
+            ));
+
+            pipeline_factory(
+                Acceptor::new(config)
+                    .map_err(SslError::Ssl)
+                    .map_init_err(|_| panic!()),
+            )
+            .and_then(|io: TlsStream<ProxyStream<TcpStream>>| {
+                let proto = if let Some(protos) = io.get_ref().1.get_alpn_protocol() {
+                    if protos.windows(2).any(|window| window == b"h2") {
+                        Protocol::Http2
+                    } else {
+                        Protocol::Http1
+                    }
+                } else {
+                    Protocol::Http1
+                };
+                let peer_addr = io.get_ref().0.peer_addr().ok();
+                ok((io, proto, peer_addr))
+            })
+            .and_then(self.map_err(SslError::Service))
+        }
+    }
 }
 
 impl<T, S, B, X, U> ServiceFactory for HttpService<T, S, B, X, U>
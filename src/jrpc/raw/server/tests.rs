@@ -26,6 +26,7 @@
 
 #![cfg(test)]
 
+use crate::fiber::{self, System};
 use crate::jrpc::common;
 use crate::jrpc::raw::{RawServer, RawServerEvent};
 use crate::jrpc::transport::{local_transport, TransportClient};
@@ -37,7 +38,7 @@ fn notifications_work() {
         (c, RawServer::new(s))
     };
 
-    kayrx::fiber::spawn(async move {
+    let client = async move {
         let n = common::Notification {
             jsonrpc: common::Version::V2,
             method: "foo".to_string(),
@@ -46,9 +47,11 @@ fn notifications_work() {
 
         let request = common::Request::Single(common::Call::Notification(n));
         client.send_request(request).await.unwrap();
-    });
+    };
+
+    System::new("test").block_on(async move {
+        fiber::spawn(client);
 
-    kayrx::fiber::run(async move {
         match server.next_event().await {
             RawServerEvent::Notification(n) => {
                 assert_eq!(n.method(), "foo");
@@ -79,7 +82,7 @@ fn subscriptions_work() {
         (c, RawServer::new(s))
     };
 
-    kayrx::fiber::spawn(async move {
+    let client = async move {
         let request = common::Request::Single(common::Call::MethodCall(common::MethodCall {
             jsonrpc: common::Version::V2,
             method: "foo".to_string(),
@@ -110,9 +113,11 @@ fn subscriptions_work() {
 
         // We destroy the client here, which triggers the `SubscriptionsClosed` event on the
         // server side.
-    });
+    };
+
+    System::new("test").block_on(async move {
+        fiber::spawn(client);
 
-    kayrx::fiber::run(async move {
         let sub_id = match server.next_event().await {
             RawServerEvent::Request(rq) => {
                 assert_eq!(rq.method(), "foo");
@@ -307,7 +307,7 @@ impl RegisteredMethod {
 impl RegisteredSubscription {
     /// Sends out a value to all the registered clients.
     pub async fn send(&mut self, value: JsonValue) {
-        let _ = self.to_back.send(FrontToBack::SendOutNotif {
+        let _ = self.to_back.unbounded_send(FrontToBack::SendOutNotif {
             unique_id: self.unique_id,
             notification: value,
         });
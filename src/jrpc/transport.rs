@@ -64,15 +64,9 @@ pub use client::TransportClient;
 pub use local::local_transport;
 pub use server::{TransportServer, TransportServerEvent};
 
+pub mod http;
 pub mod local;
-
-// #[cfg(feature = "http")]
-// #[cfg_attr(docsrs, doc(cfg(feature = "http")))]
-// pub mod http;
-
-// #[cfg(feature = "ws")]
-// #[cfg_attr(docsrs, doc(cfg(feature = "ws")))]
-// pub mod ws;
+pub mod ws;
 
 mod client;
 mod server;
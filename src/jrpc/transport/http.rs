@@ -24,13 +24,16 @@
 // IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
-//! Implementation of [`TransportClient`](crate::transport::TransportClient) and
-//! [`TransportServer`](crate::transport::TransportServer) for HTTP.
+//! Implementation of [`TransportClient`](crate::jrpc::transport::TransportClient) and
+//! [`TransportServer`](crate::jrpc::transport::TransportServer) for HTTP.
+//!
+//! The server side plugs into a kayrx [`App`](crate::web::App) through
+//! [`HttpTransportHandle::resource`], or can be started on its own with
+//! [`HttpTransportServer::bind`]. Each JSON-RPC request (single or batch) is sent as the body of
+//! a `POST` request, and the response is sent back as the body of the HTTP response.
 
-pub use crate::transport::http::client::{HttpTransportClient, RequestError};
-pub use crate::transport::http::server::HttpTransportServer;
-
-pub use crate::server_utils::access_control;
+pub use crate::jrpc::transport::http::client::{HttpTransportClient, RequestError};
+pub use crate::jrpc::transport::http::server::{HttpTransportHandle, HttpTransportServer};
 
 mod client;
 mod server;
@@ -0,0 +1,190 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use crate::fiber::{Arbiter, System};
+use crate::jrpc::common;
+use crate::jrpc::transport::TransportClient;
+use crate::web::client::Client;
+
+use core::{fmt, pin::Pin};
+use futures::{channel::mpsc, prelude::*};
+use std::thread;
+
+/// Maximum size of the body of a response, in bytes.
+const MAX_BODY_SIZE: usize = 16 * 1024 * 1024;
+
+/// Implementation of [`TransportClient`] that sends JSON-RPC requests over HTTP `POST`.
+///
+/// The kayrx HTTP client isn't thread-safe, so requests are performed by a background thread
+/// that runs its own [`System`]. The thread is stopped when this object is destroyed.
+pub struct HttpTransportClient {
+    /// Channel to the background thread.
+    to_back: mpsc::Sender<common::Request>,
+    /// Responses sent back by the background thread.
+    from_back: mpsc::Receiver<Result<common::Response, RequestError>>,
+}
+
+/// Error that can happen during a request.
+#[derive(Debug, thiserror::Error)]
+pub enum RequestError {
+    /// Error while sending the request or receiving the response.
+    #[error("Error while performing the HTTP request: {0}")]
+    Http(String),
+    /// Server returned a non-success status code.
+    #[error("Server returned an error status code: {status_code}")]
+    RequestFailure {
+        /// Status code returned by the server.
+        status_code: u16,
+    },
+    /// Failed to parse the JSON returned by the server into a JSON-RPC response.
+    #[error("Error while parsing the response body")]
+    ParseError(#[source] serde_json::Error),
+    /// The background thread has stopped.
+    #[error("HTTP client background thread has stopped")]
+    Closed,
+}
+
+impl HttpTransportClient {
+    /// Initializes a new HTTP client that sends its requests to the given URL.
+    pub fn new(target: impl Into<String>) -> Self {
+        Self::with_client(target, Client::new)
+    }
+
+    /// Initializes a new HTTP client that sends its requests to the given URL, using the
+    /// [`Client`] returned by `make_client`.
+    ///
+    /// The closure is called on the background thread, which makes it possible to use a client
+    /// with a custom connector, for example one that goes through a SOCKS5 proxy:
+    ///
+    /// ```rust
+    /// use kayrx::connect::Socks5Connector;
+    /// use kayrx::http::client::Connector;
+    /// use kayrx::jrpc::transport::http::HttpTransportClient;
+    /// use kayrx::web::client::Client;
+    ///
+    /// let transport = HttpTransportClient::with_client("http://localhost:8000", || {
+    ///     let connector = Socks5Connector::new("127.0.0.1:1080".parse().unwrap());
+    ///     Client::build()
+    ///         .connector(Connector::new().connector(connector).finish())
+    ///         .finish()
+    /// });
+    /// ```
+    pub fn with_client<F>(target: impl Into<String>, make_client: F) -> Self
+    where
+        F: FnOnce() -> Client + Send + 'static,
+    {
+        let target = target.into();
+        let (to_back, from_front) = mpsc::channel(16);
+        let (to_front, from_back) = mpsc::channel(16);
+
+        let result = thread::Builder::new()
+            .name("jrpc-http-client".into())
+            .spawn(move || {
+                let mut sys = System::new("jrpc-http-client");
+                sys.block_on(background_task(make_client(), target, from_front, to_front));
+            });
+
+        // If the thread can't be spawned, `from_back` terminates and every request fails with
+        // `RequestError::Closed`.
+        if let Err(err) = result {
+            log::error!("Failed to spawn the jrpc HTTP client thread: {}", err);
+        }
+
+        HttpTransportClient { to_back, from_back }
+    }
+}
+
+impl TransportClient for HttpTransportClient {
+    type Error = RequestError;
+
+    fn send_request<'a>(
+        &'a mut self,
+        request: common::Request,
+    ) -> Pin<Box<dyn Future<Output = Result<(), Self::Error>> + Send + 'a>> {
+        Box::pin(async move {
+            self.to_back
+                .send(request)
+                .await
+                .map_err(|_| RequestError::Closed)
+        })
+    }
+
+    fn next_response<'a>(
+        &'a mut self,
+    ) -> Pin<Box<dyn Future<Output = Result<common::Response, Self::Error>> + Send + 'a>> {
+        Box::pin(async move {
+            match self.from_back.next().await {
+                Some(response) => response,
+                None => Err(RequestError::Closed),
+            }
+        })
+    }
+}
+
+impl fmt::Debug for HttpTransportClient {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("HttpTransportClient").finish()
+    }
+}
+
+/// Runs in the background thread. Sends every request received from the front as a separate
+/// HTTP request, and sends back the responses as they arrive.
+async fn background_task(
+    client: Client,
+    target: String,
+    mut from_front: mpsc::Receiver<common::Request>,
+    to_front: mpsc::Sender<Result<common::Response, RequestError>>,
+) {
+    while let Some(request) = from_front.next().await {
+        let request = client.post(&target).send_json(&request);
+        let mut to_front = to_front.clone();
+
+        Arbiter::spawn(async move {
+            let response = match request.await {
+                Ok(mut response) => {
+                    let status = response.status();
+                    match response.body().limit(MAX_BODY_SIZE).await {
+                        Ok(_) if status == crate::http::StatusCode::NO_CONTENT => return,
+                        Ok(ref body) if status.is_success() && body.is_empty() => return,
+                        Ok(body) if status.is_success() => {
+                            serde_json::from_slice(&body).map_err(RequestError::ParseError)
+                        }
+                        Ok(_) => Err(RequestError::RequestFailure {
+                            status_code: status.as_u16(),
+                        }),
+                        Err(err) => Err(RequestError::Http(err.to_string())),
+                    }
+                }
+                Err(err) => Err(RequestError::Http(err.to_string())),
+            };
+
+            let _ = to_front.send(response).await;
+        });
+    }
+
+    // The front has been destroyed.
+    System::current().stop();
+}
@@ -0,0 +1,311 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use crate::fiber::System;
+use crate::jrpc::common;
+use crate::jrpc::transport::{TransportServer, TransportServerEvent};
+use crate::web::types::PayloadConfig;
+use crate::web::{self, App, Data, HttpResponse, HttpServer, Resource};
+
+use bytes::Bytes;
+use core::{fmt, pin::Pin, task::Poll};
+use fnv::FnvHashMap;
+use futures::{
+    channel::{mpsc, oneshot},
+    prelude::*,
+};
+use std::{io, net::SocketAddr, thread};
+
+/// Default maximum size of the body of a request, in bytes.
+const DEFAULT_MAX_BODY_SIZE: usize = 16 * 1024 * 1024;
+
+/// Implementation of [`TransportServer`] that receives JSON-RPC requests over HTTP.
+///
+/// The HTTP side of the transport is served by kayrx web workers. Requests are forwarded to this
+/// object through a channel, and [`HttpTransportHandle`] is the sending half of that channel.
+pub struct HttpTransportServer {
+    /// Receives requests from the HTTP handlers.
+    from_handlers: mpsc::Receiver<Incoming>,
+    /// Handle to give to the HTTP handlers. Kept alive so that `from_handlers` never terminates.
+    handle: HttpTransportHandle,
+    /// Address we are listening on, if the server has been started with
+    /// [`HttpTransportServer::bind`].
+    local_addr: Option<SocketAddr>,
+    /// HTTP server started by [`HttpTransportServer::bind`], stopped when we are dropped.
+    server: Option<crate::server::Server>,
+    /// Id of the next request to insert in the `requests` hashmap.
+    next_request_id: u64,
+    /// List of requests waiting for an answer, and the channel where to send the answer.
+    requests: FnvHashMap<u64, oneshot::Sender<Option<common::Response>>>,
+}
+
+/// Cloneable handle that plugs an [`HttpTransportServer`] into a kayrx [`App`].
+///
+/// ```rust,no_run
+/// use kayrx::jrpc::{raw::RawServer, transport::http::HttpTransportServer};
+/// use kayrx::web::{App, HttpServer};
+///
+/// #[kayrx::main]
+/// async fn main() -> std::io::Result<()> {
+///     let transport = HttpTransportServer::new();
+///     let handle = transport.handle();
+///     let _server = kayrx::jrpc::Server::from(RawServer::new(transport));
+///
+///     HttpServer::new(move || App::new().service(handle.resource("/rpc")))
+///         .bind("127.0.0.1:8000")?
+///         .run()
+///         .await
+/// }
+/// ```
+#[derive(Clone)]
+pub struct HttpTransportHandle {
+    /// Channel to the [`HttpTransportServer`].
+    to_server: mpsc::Sender<Incoming>,
+    /// Maximum size of the body of a request, in bytes.
+    max_body_size: usize,
+}
+
+/// Request received by an HTTP handler and forwarded to the [`HttpTransportServer`].
+struct Incoming {
+    /// Body of the request.
+    request: common::Request,
+    /// Where to send back the response. `None` means that the request doesn't expect any answer.
+    send_back: oneshot::Sender<Option<common::Response>>,
+}
+
+impl HttpTransportServer {
+    /// Creates a new server that isn't attached to any HTTP listener yet.
+    ///
+    /// Use [`handle`](HttpTransportServer::handle) in order to register it within an [`App`].
+    pub fn new() -> Self {
+        let (to_server, from_handlers) = mpsc::channel(32);
+        HttpTransportServer {
+            from_handlers,
+            handle: HttpTransportHandle {
+                to_server,
+                max_body_size: DEFAULT_MAX_BODY_SIZE,
+            },
+            local_addr: None,
+            server: None,
+            next_request_id: 0,
+            requests: Default::default(),
+        }
+    }
+
+    /// Starts an HTTP server listening on the given address, in a background thread, and returns
+    /// a transport that receives the requests sent to `/`.
+    ///
+    /// The HTTP server is stopped when the returned object is destroyed.
+    pub async fn bind(addr: &SocketAddr) -> io::Result<HttpTransportServer> {
+        let mut transport = HttpTransportServer::new();
+        let handle = transport.handle();
+        let addr = *addr;
+        let (tx, rx) = oneshot::channel();
+
+        thread::Builder::new()
+            .name("jrpc-http-server".into())
+            .spawn(move || {
+                let sys = System::new("jrpc-http-server");
+                let srv = HttpServer::new(move || App::new().service(handle.resource("/")))
+                    .disable_signals()
+                    .system_exit()
+                    .bind(addr);
+
+                match srv {
+                    Ok(srv) => {
+                        let local_addr = srv.addrs()[0];
+                        let _ = tx.send(Ok((local_addr, srv.run())));
+                        let _ = sys.run();
+                    }
+                    Err(err) => {
+                        let _ = tx.send(Err(err));
+                    }
+                }
+            })?;
+
+        let (local_addr, server) = rx
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "HTTP server thread has stopped"))??;
+        transport.local_addr = Some(local_addr);
+        transport.server = Some(server);
+        Ok(transport)
+    }
+
+    /// Returns the address the HTTP server is listening on, if it has been started with
+    /// [`bind`](HttpTransportServer::bind).
+    pub fn local_addr(&self) -> Option<&SocketAddr> {
+        self.local_addr.as_ref()
+    }
+
+    /// Returns a handle that can be used to register this transport within an [`App`].
+    pub fn handle(&self) -> HttpTransportHandle {
+        self.handle.clone()
+    }
+
+    /// Sets the maximum size of the body of a request, in bytes. Larger requests are answered
+    /// with `413 Payload Too Large`.
+    ///
+    /// Only applies to the handles obtained after this call. Defaults to 16MiB.
+    pub fn max_body_size(mut self, size: usize) -> Self {
+        self.handle.max_body_size = size;
+        self
+    }
+}
+
+impl Default for HttpTransportServer {
+    fn default() -> Self {
+        HttpTransportServer::new()
+    }
+}
+
+impl Drop for HttpTransportServer {
+    fn drop(&mut self) {
+        if let Some(server) = self.server.take() {
+            let _ = server.stop(true);
+        }
+    }
+}
+
+impl fmt::Debug for HttpTransportServer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("HttpTransportServer")
+            .field("local_addr", &self.local_addr)
+            .finish()
+    }
+}
+
+impl TransportServer for HttpTransportServer {
+    type RequestId = u64;
+
+    fn next_request<'a>(
+        &'a mut self,
+    ) -> Pin<Box<dyn Future<Output = TransportServerEvent<Self::RequestId>> + Send + 'a>> {
+        Box::pin(future::poll_fn(move |cx| {
+            // Requests whose HTTP connection has been closed are reported first.
+            let closed = self
+                .requests
+                .iter_mut()
+                .find_map(|(id, send_back)| match send_back.poll_canceled(cx) {
+                    Poll::Ready(()) => Some(*id),
+                    Poll::Pending => None,
+                });
+            if let Some(id) = closed {
+                self.requests.remove(&id);
+                return Poll::Ready(TransportServerEvent::Closed(id));
+            }
+
+            // `from_handlers` never terminates, as we hold a sender in `self.handle`.
+            let Incoming { request, send_back } =
+                match Stream::poll_next(Pin::new(&mut self.from_handlers), cx) {
+                    Poll::Ready(Some(incoming)) => incoming,
+                    Poll::Ready(None) | Poll::Pending => return Poll::Pending,
+                };
+
+            loop {
+                let id = self.next_request_id;
+                self.next_request_id = self.next_request_id.wrapping_add(1);
+                if self.requests.contains_key(&id) {
+                    continue;
+                }
+                self.requests.insert(id, send_back);
+                return Poll::Ready(TransportServerEvent::Request { id, request });
+            }
+        }))
+    }
+
+    fn finish<'a>(
+        &'a mut self,
+        request_id: &'a Self::RequestId,
+        response: Option<&'a common::Response>,
+    ) -> Pin<Box<dyn Future<Output = Result<(), ()>> + Send + 'a>> {
+        let result = match self.requests.remove(request_id) {
+            Some(send_back) => send_back.send(response.cloned()).map_err(|_| ()),
+            None => Err(()),
+        };
+        Box::pin(future::ready(result))
+    }
+
+    fn supports_resuming(&self, request_id: &Self::RequestId) -> Result<bool, ()> {
+        if self.requests.contains_key(request_id) {
+            Ok(false)
+        } else {
+            Err(())
+        }
+    }
+
+    fn send<'a>(
+        &'a mut self,
+        _: &'a Self::RequestId,
+        _: &'a common::Response,
+    ) -> Pin<Box<dyn Future<Output = Result<(), ()>> + Send + 'a>> {
+        Box::pin(future::ready(Err(())))
+    }
+}
+
+impl HttpTransportHandle {
+    /// Builds a [`Resource`] that answers JSON-RPC `POST` requests on the given path.
+    pub fn resource(&self, path: &str) -> Resource {
+        web::resource(path)
+            .app_data(PayloadConfig::new(self.max_body_size))
+            .data(self.clone())
+            .route(web::post().to(handle_request))
+    }
+}
+
+impl fmt::Debug for HttpTransportHandle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("HttpTransportHandle")
+            .field("max_body_size", &self.max_body_size)
+            .finish()
+    }
+}
+
+/// Handler for the requests registered by [`HttpTransportHandle::resource`].
+async fn handle_request(handle: Data<HttpTransportHandle>, body: Bytes) -> HttpResponse {
+    let request = match serde_json::from_slice::<common::Request>(&body) {
+        Ok(request) => request,
+        Err(err) => {
+            let error = if err.is_syntax() || err.is_eof() {
+                common::Error::parse_error()
+            } else {
+                common::Error::invalid_request()
+            };
+            return HttpResponse::Ok().json(common::Response::from(error, common::Version::V2));
+        }
+    };
+
+    let (send_back, response) = oneshot::channel();
+    let incoming = Incoming { request, send_back };
+    if handle.to_server.clone().send(incoming).await.is_err() {
+        return HttpResponse::ServiceUnavailable().finish();
+    }
+
+    match response.await {
+        Ok(Some(response)) => HttpResponse::Ok().json(response),
+        Ok(None) => HttpResponse::NoContent().finish(),
+        Err(_) => HttpResponse::ServiceUnavailable().finish(),
+    }
+}
@@ -24,14 +24,19 @@
 // IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
-//! Implementation of [`TransportClient`](crate::transport::TransportClient) and
-//! [`TransportServer`](crate::transport::TransportServer) for the WebSocket protocol.
+//! Implementation of [`TransportClient`](crate::jrpc::transport::TransportClient) and
+//! [`TransportServer`](crate::jrpc::transport::TransportServer) for WebSockets.
+//!
+//! Contrary to HTTP, a WebSocket connection stays open after a request has been answered, which
+//! lets the server push notifications to the client. This is the transport to use in order to
+//! serve subscriptions.
+//!
+//! The server side plugs into a kayrx [`App`](crate::web::App) through
+//! [`WsTransportHandle::resource`], or can be started on its own with
+//! [`WsTransportServer::bind`]. Each JSON-RPC request or response is sent as a text message.
 
-pub use crate::transport::ws::client::{
-    Mode, WsConnecError, WsNewDnsError, WsNewError, WsTransportClient,
-};
-
-// TODO: server
+pub use crate::jrpc::transport::ws::client::{WsConnectError, WsNewError, WsTransportClient};
+pub use crate::jrpc::transport::ws::server::{WsTransportHandle, WsTransportServer};
 
 mod client;
-mod stream;
+mod server;
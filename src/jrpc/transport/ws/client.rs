@@ -0,0 +1,224 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use crate::fiber::System;
+use crate::jrpc::common;
+use crate::jrpc::transport::TransportClient;
+use crate::web::client::{ws, Client};
+
+use core::{fmt, pin::Pin};
+use futures::{
+    channel::{mpsc, oneshot},
+    future::Either,
+    prelude::*,
+};
+use std::thread;
+
+/// Maximum size of a message, in bytes.
+const MAX_FRAME_SIZE: usize = 16 * 1024 * 1024;
+
+/// Implementation of [`TransportClient`] that sends JSON-RPC requests over a WebSocket.
+///
+/// The kayrx WebSocket client isn't thread-safe, so the connection is driven by a background
+/// thread that runs its own [`System`]. The connection is closed and the thread is stopped when
+/// this object is destroyed.
+pub struct WsTransportClient {
+    /// Channel to the background thread.
+    to_back: mpsc::Sender<common::Request>,
+    /// Responses and notifications sent back by the background thread.
+    from_back: mpsc::Receiver<Result<common::Response, WsConnectError>>,
+}
+
+/// Error that can happen when opening the connection.
+#[derive(Debug, thiserror::Error)]
+pub enum WsNewError {
+    /// Error while performing the WebSocket handshake.
+    #[error("Error while connecting to the server: {0}")]
+    Connect(String),
+    /// The background thread couldn't be started.
+    #[error("Failed to start the WebSocket client thread")]
+    Thread(#[source] std::io::Error),
+}
+
+/// Error that can happen on an opened connection.
+#[derive(Debug, thiserror::Error)]
+pub enum WsConnectError {
+    /// Error on the WebSocket protocol layer.
+    #[error("WebSocket protocol error: {0}")]
+    Protocol(String),
+    /// Failed to parse the JSON sent by the server into a JSON-RPC response.
+    #[error("Error while parsing a message from the server")]
+    ParseError(#[source] serde_json::Error),
+    /// The connection has been closed.
+    #[error("WebSocket connection has been closed")]
+    Closed,
+}
+
+impl WsTransportClient {
+    /// Opens a WebSocket connection to the given URL.
+    ///
+    /// The returned `Future` resolves once the handshake has been performed.
+    pub async fn new(target: impl Into<String>) -> Result<Self, WsNewError> {
+        Self::with_client(target, Client::new).await
+    }
+
+    /// Opens a WebSocket connection to the given URL, using the [`Client`] returned by
+    /// `make_client`.
+    ///
+    /// The closure is called on the background thread, which makes it possible to use a client
+    /// with a custom connector, for example one that goes through a SOCKS5 proxy. See
+    /// [`HttpTransportClient::with_client`](crate::jrpc::transport::http::HttpTransportClient::with_client).
+    pub async fn with_client<F>(target: impl Into<String>, make_client: F) -> Result<Self, WsNewError>
+    where
+        F: FnOnce() -> Client + Send + 'static,
+    {
+        let target = target.into();
+        let (to_back, from_front) = mpsc::channel(16);
+        let (to_front, from_back) = mpsc::channel(16);
+        let (connected_tx, connected_rx) = oneshot::channel();
+
+        thread::Builder::new()
+            .name("jrpc-ws-client".into())
+            .spawn(move || {
+                let mut sys = System::new("jrpc-ws-client");
+                sys.block_on(async move {
+                    let framed = match make_client()
+                        .ws(target)
+                        .max_frame_size(MAX_FRAME_SIZE)
+                        .connect()
+                        .await
+                    {
+                        Ok((_, framed)) => framed,
+                        Err(err) => {
+                            let _ = connected_tx.send(Err(WsNewError::Connect(err.to_string())));
+                            return;
+                        }
+                    };
+                    let _ = connected_tx.send(Ok(()));
+                    background_task(framed, from_front, to_front).await;
+                });
+            })
+            .map_err(WsNewError::Thread)?;
+
+        match connected_rx.await {
+            Ok(Ok(())) => Ok(WsTransportClient { to_back, from_back }),
+            Ok(Err(err)) => Err(err),
+            Err(_) => Err(WsNewError::Connect(
+                "WebSocket client thread has stopped".into(),
+            )),
+        }
+    }
+}
+
+impl TransportClient for WsTransportClient {
+    type Error = WsConnectError;
+
+    fn send_request<'a>(
+        &'a mut self,
+        request: common::Request,
+    ) -> Pin<Box<dyn Future<Output = Result<(), Self::Error>> + Send + 'a>> {
+        Box::pin(async move {
+            self.to_back
+                .send(request)
+                .await
+                .map_err(|_| WsConnectError::Closed)
+        })
+    }
+
+    fn next_response<'a>(
+        &'a mut self,
+    ) -> Pin<Box<dyn Future<Output = Result<common::Response, Self::Error>> + Send + 'a>> {
+        Box::pin(async move {
+            match self.from_back.next().await {
+                Some(response) => response,
+                None => Err(WsConnectError::Closed),
+            }
+        })
+    }
+}
+
+impl fmt::Debug for WsTransportClient {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("WsTransportClient").finish()
+    }
+}
+
+/// Runs in the background thread. Sends the requests received from the front as text messages,
+/// and sends back the messages received from the server.
+async fn background_task<T>(
+    mut framed: T,
+    mut from_front: mpsc::Receiver<common::Request>,
+    mut to_front: mpsc::Sender<Result<common::Response, WsConnectError>>,
+) where
+    T: Stream<Item = Result<ws::Frame, ws::websocket::ProtocolError>>
+        + Sink<ws::Message, Error = ws::websocket::ProtocolError>
+        + Unpin,
+{
+    loop {
+        let event = match future::select(from_front.next(), framed.next()).await {
+            Either::Left((event, _)) => Either::Left(event),
+            Either::Right((event, _)) => Either::Right(event),
+        };
+
+        match event {
+            // The front has been destroyed.
+            Either::Left(None) => {
+                let _ = framed.send(ws::Message::Close(None)).await;
+                break;
+            }
+            Either::Left(Some(request)) => {
+                let text = serde_json::to_string(&request)
+                    .expect("JSON-RPC requests can always be serialized; qed");
+                if let Err(err) = framed.send(ws::Message::Text(text)).await {
+                    let _ = to_front
+                        .send(Err(WsConnectError::Protocol(err.to_string())))
+                        .await;
+                    break;
+                }
+            }
+            Either::Right(Some(Ok(ws::Frame::Text(data))))
+            | Either::Right(Some(Ok(ws::Frame::Binary(data)))) => {
+                let response = serde_json::from_slice(&data).map_err(WsConnectError::ParseError);
+                if to_front.send(response).await.is_err() {
+                    break;
+                }
+            }
+            Either::Right(Some(Ok(ws::Frame::Ping(data)))) => {
+                let _ = framed.send(ws::Message::Pong(data)).await;
+            }
+            Either::Right(Some(Ok(ws::Frame::Close(_)))) | Either::Right(None) => break,
+            Either::Right(Some(Ok(_))) => {}
+            Either::Right(Some(Err(err))) => {
+                let _ = to_front
+                    .send(Err(WsConnectError::Protocol(err.to_string())))
+                    .await;
+                break;
+            }
+        }
+    }
+
+    System::current().stop();
+}
@@ -0,0 +1,472 @@
+// Copyright 2019 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use crate::codec::{Decoder, Encoder};
+use crate::fiber::System;
+use crate::jrpc::common;
+use crate::jrpc::transport::{TransportServer, TransportServerEvent};
+use crate::web::{self, App, Data, HttpRequest, HttpResponse, HttpServer, Resource};
+use crate::websocket::{self, CloseCode, Codec, Frame, Item, Message};
+
+use bytes::BytesMut;
+use core::{fmt, pin::Pin, task::Poll};
+use fnv::FnvHashMap;
+use futures::{
+    channel::{mpsc, oneshot},
+    prelude::*,
+};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::{io, net::SocketAddr, thread};
+
+/// Default maximum size of a message, in bytes.
+const DEFAULT_MAX_FRAME_SIZE: usize = 16 * 1024 * 1024;
+
+/// Implementation of [`TransportServer`] that receives JSON-RPC requests over WebSockets.
+///
+/// The WebSocket connections are served by kayrx web workers. Requests are forwarded to this
+/// object through a channel, and [`WsTransportHandle`] is the sending half of that channel.
+///
+/// Requests can be answered multiple times, which is what subscriptions rely on. When a
+/// connection is closed, all the requests that were received on it are reported as
+/// [`TransportServerEvent::Closed`].
+pub struct WsTransportServer {
+    /// Receives requests and events from the WebSocket connections.
+    from_connections: mpsc::Receiver<FromConnection>,
+    /// Handle to give to the HTTP handlers. Kept alive so that `from_connections` never
+    /// terminates.
+    handle: WsTransportHandle,
+    /// Address we are listening on, if the server has been started with
+    /// [`WsTransportServer::bind`].
+    local_addr: Option<SocketAddr>,
+    /// HTTP server started by [`WsTransportServer::bind`], stopped when we are dropped.
+    server: Option<crate::server::Server>,
+    /// Id of the next request to insert in the `requests` hashmap.
+    next_request_id: u64,
+    /// List of requests that are alive, with the connection they belong to and the channel to
+    /// that connection.
+    requests: FnvHashMap<u64, (u64, mpsc::UnboundedSender<Message>)>,
+    /// Requests whose connection has been closed, and that have yet to be reported.
+    closed: VecDeque<u64>,
+}
+
+/// Cloneable handle that plugs a [`WsTransportServer`] into a kayrx [`App`].
+///
+/// ```rust,no_run
+/// use kayrx::jrpc::{raw::RawServer, transport::ws::WsTransportServer};
+/// use kayrx::web::{App, HttpServer};
+///
+/// #[kayrx::main]
+/// async fn main() -> std::io::Result<()> {
+///     let transport = WsTransportServer::new();
+///     let handle = transport.handle();
+///     let _server = kayrx::jrpc::Server::from(RawServer::new(transport));
+///
+///     HttpServer::new(move || App::new().service(handle.resource("/ws")))
+///         .bind("127.0.0.1:8000")?
+///         .run()
+///         .await
+/// }
+/// ```
+#[derive(Clone)]
+pub struct WsTransportHandle {
+    /// Channel to the [`WsTransportServer`].
+    to_server: mpsc::Sender<FromConnection>,
+    /// Id to assign to the next connection.
+    next_connection_id: Arc<AtomicU64>,
+    /// Maximum size of a message, in bytes.
+    max_frame_size: usize,
+}
+
+/// Message sent by a connection to the [`WsTransportServer`].
+enum FromConnection {
+    /// A request has been received.
+    Request {
+        /// Connection the request has been received on.
+        connection: u64,
+        /// Body of the request.
+        request: common::Request,
+        /// Channel to the connection.
+        send_back: mpsc::UnboundedSender<Message>,
+    },
+    /// The connection has been closed.
+    Closed {
+        /// Connection that has been closed.
+        connection: u64,
+    },
+}
+
+impl WsTransportServer {
+    /// Creates a new server that isn't attached to any HTTP listener yet.
+    ///
+    /// Use [`handle`](WsTransportServer::handle) in order to register it within an [`App`].
+    pub fn new() -> Self {
+        let (to_server, from_connections) = mpsc::channel(32);
+        WsTransportServer {
+            from_connections,
+            handle: WsTransportHandle {
+                to_server,
+                next_connection_id: Arc::new(AtomicU64::new(0)),
+                max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+            },
+            local_addr: None,
+            server: None,
+            next_request_id: 0,
+            requests: Default::default(),
+            closed: VecDeque::new(),
+        }
+    }
+
+    /// Starts an HTTP server listening on the given address, in a background thread, and returns
+    /// a transport that accepts WebSocket connections on `/`.
+    ///
+    /// The HTTP server is stopped when the returned object is destroyed.
+    pub async fn bind(addr: &SocketAddr) -> io::Result<WsTransportServer> {
+        let mut transport = WsTransportServer::new();
+        let handle = transport.handle();
+        let addr = *addr;
+        let (tx, rx) = oneshot::channel();
+
+        thread::Builder::new()
+            .name("jrpc-ws-server".into())
+            .spawn(move || {
+                let sys = System::new("jrpc-ws-server");
+                let srv = HttpServer::new(move || App::new().service(handle.resource("/")))
+                    .disable_signals()
+                    .system_exit()
+                    .bind(addr);
+
+                match srv {
+                    Ok(srv) => {
+                        let local_addr = srv.addrs()[0];
+                        let _ = tx.send(Ok((local_addr, srv.run())));
+                        let _ = sys.run();
+                    }
+                    Err(err) => {
+                        let _ = tx.send(Err(err));
+                    }
+                }
+            })?;
+
+        let (local_addr, server) = rx.await.map_err(|_| {
+            io::Error::new(io::ErrorKind::Other, "HTTP server thread has stopped")
+        })??;
+        transport.local_addr = Some(local_addr);
+        transport.server = Some(server);
+        Ok(transport)
+    }
+
+    /// Returns the address the HTTP server is listening on, if it has been started with
+    /// [`bind`](WsTransportServer::bind).
+    pub fn local_addr(&self) -> Option<&SocketAddr> {
+        self.local_addr.as_ref()
+    }
+
+    /// Returns a handle that can be used to register this transport within an [`App`].
+    pub fn handle(&self) -> WsTransportHandle {
+        self.handle.clone()
+    }
+
+    /// Sets the maximum size of a message, in bytes. Connections that send larger messages are
+    /// closed.
+    ///
+    /// Only applies to the handles obtained after this call. Defaults to 16MiB.
+    pub fn max_frame_size(mut self, size: usize) -> Self {
+        self.handle.max_frame_size = size;
+        self
+    }
+}
+
+impl Default for WsTransportServer {
+    fn default() -> Self {
+        WsTransportServer::new()
+    }
+}
+
+impl Drop for WsTransportServer {
+    fn drop(&mut self) {
+        if let Some(server) = self.server.take() {
+            let _ = server.stop(true);
+        }
+    }
+}
+
+impl fmt::Debug for WsTransportServer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("WsTransportServer")
+            .field("local_addr", &self.local_addr)
+            .finish()
+    }
+}
+
+impl TransportServer for WsTransportServer {
+    type RequestId = u64;
+
+    fn next_request<'a>(
+        &'a mut self,
+    ) -> Pin<Box<dyn Future<Output = TransportServerEvent<Self::RequestId>> + Send + 'a>> {
+        Box::pin(future::poll_fn(move |cx| loop {
+            if let Some(id) = self.closed.pop_front() {
+                return Poll::Ready(TransportServerEvent::Closed(id));
+            }
+
+            // `from_connections` never terminates, as we hold a sender in `self.handle`.
+            match Stream::poll_next(Pin::new(&mut self.from_connections), cx) {
+                Poll::Ready(Some(FromConnection::Request {
+                    connection,
+                    request,
+                    send_back,
+                })) => loop {
+                    let id = self.next_request_id;
+                    self.next_request_id = self.next_request_id.wrapping_add(1);
+                    if self.requests.contains_key(&id) {
+                        continue;
+                    }
+                    self.requests.insert(id, (connection, send_back));
+                    return Poll::Ready(TransportServerEvent::Request { id, request });
+                },
+                Poll::Ready(Some(FromConnection::Closed { connection })) => {
+                    let closed = &mut self.closed;
+                    self.requests.retain(|id, (conn, _)| {
+                        if *conn == connection {
+                            closed.push_back(*id);
+                            false
+                        } else {
+                            true
+                        }
+                    });
+                }
+                Poll::Ready(None) | Poll::Pending => return Poll::Pending,
+            }
+        }))
+    }
+
+    fn finish<'a>(
+        &'a mut self,
+        request_id: &'a Self::RequestId,
+        response: Option<&'a common::Response>,
+    ) -> Pin<Box<dyn Future<Output = Result<(), ()>> + Send + 'a>> {
+        let result = match (self.requests.remove(request_id), response) {
+            (Some((_, send_back)), Some(response)) => send_response(&send_back, response),
+            (Some(_), None) => Ok(()),
+            (None, _) => Err(()),
+        };
+        Box::pin(future::ready(result))
+    }
+
+    fn supports_resuming(&self, request_id: &Self::RequestId) -> Result<bool, ()> {
+        if self.requests.contains_key(request_id) {
+            Ok(true)
+        } else {
+            Err(())
+        }
+    }
+
+    fn send<'a>(
+        &'a mut self,
+        request_id: &'a Self::RequestId,
+        response: &'a common::Response,
+    ) -> Pin<Box<dyn Future<Output = Result<(), ()>> + Send + 'a>> {
+        let result = match self.requests.get(request_id) {
+            Some((_, send_back)) => send_response(send_back, response),
+            None => Err(()),
+        };
+        Box::pin(future::ready(result))
+    }
+}
+
+impl WsTransportHandle {
+    /// Builds a [`Resource`] that accepts WebSocket connections on the given path.
+    pub fn resource(&self, path: &str) -> Resource {
+        web::resource(path)
+            .data(self.clone())
+            .route(web::get().to(handle_connection))
+    }
+}
+
+impl fmt::Debug for WsTransportHandle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("WsTransportHandle")
+            .field("max_frame_size", &self.max_frame_size)
+            .finish()
+    }
+}
+
+/// Serializes `response` and sends it to a connection.
+fn send_response(
+    send_back: &mpsc::UnboundedSender<Message>,
+    response: &common::Response,
+) -> Result<(), ()> {
+    let text = serde_json::to_string(response).map_err(|_| ())?;
+    send_back
+        .unbounded_send(Message::Text(text))
+        .map_err(|_| ())
+}
+
+/// Handler for the requests registered by [`WsTransportHandle::resource`].
+///
+/// Answers the handshake, then streams the messages sent back by the [`WsTransportServer`] as
+/// the body of the response. Incoming messages are read from the payload by a separate task.
+async fn handle_connection(
+    req: HttpRequest,
+    payload: web::types::Payload,
+    handle: Data<WsTransportHandle>,
+) -> Result<HttpResponse, websocket::HandshakeError> {
+    let mut response = websocket::handshake(req.head())?;
+
+    let connection = handle.next_connection_id.fetch_add(1, Ordering::Relaxed);
+    let (send_back, outgoing) = mpsc::unbounded();
+    crate::fiber::spawn(read_connection(
+        connection,
+        payload,
+        handle.get_ref().clone(),
+        send_back,
+    ));
+
+    let mut codec = Codec::new();
+    Ok(response.streaming(outgoing.map(move |message| {
+        let mut buf = BytesMut::new();
+        codec.encode(message, &mut buf)?;
+        Ok::<_, websocket::ProtocolError>(buf.freeze())
+    })))
+}
+
+/// Reads the messages sent by the client on a connection and forwards the requests to the
+/// [`WsTransportServer`]. Reports the connection as closed when the client goes away.
+async fn read_connection(
+    connection: u64,
+    mut payload: web::types::Payload,
+    mut handle: WsTransportHandle,
+    send_back: mpsc::UnboundedSender<Message>,
+) {
+    let max_frame_size = handle.max_frame_size;
+    let mut codec = Codec::new().max_size(max_frame_size);
+    let mut buf = BytesMut::new();
+    // Message being received as multiple frames, if any.
+    let mut continuation: Option<BytesMut> = None;
+
+    'connection: loop {
+        loop {
+            let frame = match codec.decode(&mut buf) {
+                Ok(Some(frame)) => frame,
+                Ok(None) => break,
+                Err(err) => {
+                    log::debug!("Closing jrpc WebSocket connection: {}", err);
+                    let _ =
+                        send_back.unbounded_send(Message::Close(Some(CloseCode::Protocol.into())));
+                    break 'connection;
+                }
+            };
+
+            let message = match frame {
+                Frame::Text(data) | Frame::Binary(data) => data,
+                Frame::Continuation(Item::FirstText(data))
+                | Frame::Continuation(Item::FirstBinary(data)) => {
+                    continuation = Some(BytesMut::from(&data[..]));
+                    continue;
+                }
+                Frame::Continuation(Item::Continue(data)) => {
+                    if append_continuation(&mut continuation, &data, max_frame_size).is_err() {
+                        let _ =
+                            send_back.unbounded_send(Message::Close(Some(CloseCode::Size.into())));
+                        break 'connection;
+                    }
+                    continue;
+                }
+                Frame::Continuation(Item::Last(data)) => {
+                    if append_continuation(&mut continuation, &data, max_frame_size).is_err() {
+                        let _ =
+                            send_back.unbounded_send(Message::Close(Some(CloseCode::Size.into())));
+                        break 'connection;
+                    }
+                    match continuation.take() {
+                        Some(message) => message.freeze(),
+                        None => continue,
+                    }
+                }
+                Frame::Ping(data) => {
+                    let _ = send_back.unbounded_send(Message::Pong(data));
+                    continue;
+                }
+                Frame::Pong(_) => continue,
+                Frame::Close(reason) => {
+                    let _ = send_back.unbounded_send(Message::Close(reason));
+                    break 'connection;
+                }
+            };
+
+            let request = match serde_json::from_slice::<common::Request>(&message) {
+                Ok(request) => request,
+                Err(err) => {
+                    let error = if err.is_syntax() || err.is_eof() {
+                        common::Error::parse_error()
+                    } else {
+                        common::Error::invalid_request()
+                    };
+                    let response = common::Response::from(error, common::Version::V2);
+                    let _ = send_response(&send_back, &response);
+                    continue;
+                }
+            };
+
+            let request = FromConnection::Request {
+                connection,
+                request,
+                send_back: send_back.clone(),
+            };
+            if handle.to_server.send(request).await.is_err() {
+                break 'connection;
+            }
+        }
+
+        match payload.next().await {
+            Some(Ok(chunk)) => buf.extend_from_slice(&chunk),
+            Some(Err(_)) | None => break,
+        }
+    }
+
+    let _ = handle
+        .to_server
+        .send(FromConnection::Closed { connection })
+        .await;
+}
+
+/// Appends a frame to the message being received as multiple frames, if any. Returns an error if
+/// the message becomes larger than `max_size`.
+fn append_continuation(
+    continuation: &mut Option<BytesMut>,
+    data: &[u8],
+    max_size: usize,
+) -> Result<(), ()> {
+    if let Some(message) = continuation {
+        if message.len() + data.len() > max_size {
+            return Err(());
+        }
+        message.extend_from_slice(data);
+    }
+    Ok(())
+}
@@ -18,6 +18,7 @@ pub mod codec;
 pub mod connect;
 pub mod fiber;
 pub mod framed;
+pub mod grpc;
 pub mod http;
 pub mod jrpc;
 pub mod krse;
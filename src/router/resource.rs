@@ -19,6 +19,14 @@ pub struct ResourceDef {
     name: String,
     pattern: String,
     elements: Vec<PatternElement>,
+    segments: Option<Vec<Segment>>,
+}
+
+/// Path segment of a pattern that can be matched without a regex.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Segment {
+    Static(String),
+    Param,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -68,6 +76,7 @@ impl ResourceDef {
                 id: 0,
                 tp: PatternType::DynamicSet(RegexSet::new(re_set).unwrap(), data),
                 elements: Vec::new(),
+                segments: None,
                 name: String::new(),
                 pattern: "".to_owned(),
             }
@@ -108,6 +117,11 @@ impl ResourceDef {
     fn with_prefix(path: &str, for_prefix: bool) -> Self {
         let path = path.to_owned();
         let (pattern, elements, is_dynamic, len) = ResourceDef::parse(&path, for_prefix);
+        let segments = if for_prefix {
+            None
+        } else {
+            ResourceDef::parse_segments(&path, is_dynamic)
+        };
 
         let tp = if is_dynamic {
             let re = match Regex::new(&pattern) {
@@ -129,6 +143,7 @@ impl ResourceDef {
         ResourceDef {
             tp,
             elements,
+            segments,
             id: 0,
             name: String::new(),
             pattern: path,
@@ -459,6 +474,35 @@ impl ResourceDef {
         }
     }
 
+    /// `/`-separated segments of the pattern, if it matches the whole path and
+    /// consists of static segments and `{name}` segments only.
+    pub(crate) fn segments(&self) -> Option<&[Segment]> {
+        self.segments.as_ref().map(|s| s.as_slice())
+    }
+
+    /// Complete a match of a pattern with `segments`, given the positions of
+    /// its parameters in the path.
+    pub(crate) fn match_segments<T: ResourcePath>(
+        &self,
+        path: &mut Path<T>,
+        params: &[(u16, u16)],
+    ) {
+        match self.tp {
+            PatternType::Static(_) => {
+                path.skip(path.len() as u16);
+            }
+            PatternType::Dynamic(_, ref names, len) => {
+                let mut pos = 0;
+                for (name, &(start, end)) in names.iter().zip(params) {
+                    path.add(name.clone(), PathItem::Segment(start, end));
+                    pos = end as usize;
+                }
+                path.skip((pos + len) as u16);
+            }
+            _ => unreachable!("pattern has no segments"),
+        }
+    }
+
     /// Build resource path from elements. Returns `true` on success.
     pub fn resource_path<U, I>(&self, path: &mut String, elements: &mut U) -> bool
     where
@@ -536,6 +580,31 @@ impl ResourceDef {
         )
     }
 
+    fn parse_segments(pattern: &str, is_dynamic: bool) -> Option<Vec<Segment>> {
+        // `/path*` is a prefix match
+        if is_dynamic && pattern.find('{').is_none() {
+            return None;
+        }
+
+        pattern
+            .split('/')
+            .map(|segment| {
+                if !segment.contains(|c| c == '{' || c == '}') {
+                    Some(Segment::Static(segment.to_owned()))
+                } else if segment.len() > 2
+                    && segment.starts_with('{')
+                    && segment.ends_with('}')
+                    && !segment[1..segment.len() - 1]
+                        .contains(|c| c == '{' || c == '}' || c == ':')
+                {
+                    Some(Segment::Param)
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
     fn parse(
         mut pattern: &str,
         mut for_prefix: bool,
@@ -754,6 +823,30 @@ mod tests {
         assert!(!re.is_match("/user/2345/sdg"));
     }
 
+    #[test]
+    fn test_segments() {
+        let re = ResourceDef::new("/user/{id}/");
+        assert_eq!(
+            re.segments().unwrap(),
+            &[
+                Segment::Static("".to_owned()),
+                Segment::Static("user".to_owned()),
+                Segment::Param,
+                Segment::Static("".to_owned()),
+            ][..]
+        );
+        assert!(ResourceDef::new("/name").segments().is_some());
+
+        assert!(ResourceDef::new("/v{version}/resource").segments().is_none());
+        assert!(ResourceDef::new("/file/{file}.{ext}").segments().is_none());
+        assert!(ResourceDef::new("/{id:[[:digit:]]{6}}").segments().is_none());
+        assert!(ResourceDef::new("/user/{tail}*").segments().is_none());
+        assert!(ResourceDef::new("/user/*").segments().is_none());
+        assert!(ResourceDef::new(vec!["/a", "/b"]).segments().is_none());
+        assert!(ResourceDef::prefix("/name").segments().is_none());
+        assert!(ResourceDef::prefix("/{name}/").segments().is_none());
+    }
+
     #[test]
     fn test_parse_tail() {
         let re = ResourceDef::new("/user/-{id}*");
@@ -1,3 +1,7 @@
+use std::collections::HashMap;
+use std::str::Split;
+
+use crate::router::resource::Segment;
 use crate::router::{IntoPattern, Resource, ResourceDef, ResourcePath};
 
 #[derive(Debug, Copy, Clone, PartialEq)]
@@ -10,7 +14,25 @@ pub struct ResourceInfo {
 }
 
 /// Resource router.
-pub struct Router<T, U = ()>(Vec<(ResourceDef, T, Option<U>)>);
+///
+/// Patterns made of static segments and `{name}` segments are looked up in a
+/// segment tree, other patterns are matched one by one. Either way the first
+/// registered resource that matches wins.
+pub struct Router<T, U = ()> {
+    resources: Vec<(ResourceDef, T, Option<U>)>,
+    tree: Node,
+    /// Indexes of resources that are not in the tree
+    rest: Vec<usize>,
+}
+
+/// Segment tree node.
+#[derive(Default)]
+struct Node {
+    statics: HashMap<String, Node>,
+    param: Option<Box<Node>>,
+    /// Indexes of resources whose pattern ends at this node
+    resources: Vec<usize>,
+}
 
 impl<T, U> Router<T, U> {
     pub fn build() -> RouterBuilder<T, U> {
@@ -24,12 +46,9 @@ impl<T, U> Router<T, U> {
         R: Resource<P>,
         P: ResourcePath,
     {
-        for item in self.0.iter() {
-            if item.0.match_path(resource.resource_path()) {
-                return Some((&item.1, ResourceId(item.0.id())));
-            }
-        }
-        None
+        let idx = self.find(resource, &|_, _| true)?;
+        let item = &self.resources[idx];
+        Some((&item.1, ResourceId(item.0.id())))
     }
 
     pub fn recognize_mut<R, P>(&mut self, resource: &mut R) -> Option<(&mut T, ResourceId)>
@@ -37,12 +56,9 @@ impl<T, U> Router<T, U> {
         R: Resource<P>,
         P: ResourcePath,
     {
-        for item in self.0.iter_mut() {
-            if item.0.match_path(resource.resource_path()) {
-                return Some((&mut item.1, ResourceId(item.0.id())));
-            }
-        }
-        None
+        let idx = self.find(resource, &|_, _| true)?;
+        let item = &mut self.resources[idx];
+        Some((&mut item.1, ResourceId(item.0.id())))
     }
 
     pub fn recognize_mut_checked<R, P, F>(
@@ -55,12 +71,99 @@ impl<T, U> Router<T, U> {
         R: Resource<P>,
         P: ResourcePath,
     {
-        for item in self.0.iter_mut() {
-            if item.0.match_path_checked(resource, &check, &item.2) {
-                return Some((&mut item.1, ResourceId(item.0.id())));
+        let idx = self.find(resource, &check)?;
+        let item = &mut self.resources[idx];
+        Some((&mut item.1, ResourceId(item.0.id())))
+    }
+
+    /// Index of the first resource that matches and passes `check`.
+    fn find<R, P, F>(&self, resource: &mut R, check: &F) -> Option<usize>
+    where
+        F: Fn(&R, &Option<U>) -> bool,
+        R: Resource<P>,
+        P: ResourcePath,
+    {
+        let mut candidates = Vec::new();
+        self.tree
+            .find(resource.resource_path().path().split('/'), &mut candidates);
+        candidates.sort_unstable();
+
+        // merge tree candidates with the remaining resources, in registration order
+        let mut candidates = candidates.into_iter().peekable();
+        let mut rest = self.rest.iter().copied().peekable();
+        let mut params = Vec::new();
+
+        loop {
+            let idx = match (candidates.peek(), rest.peek()) {
+                (Some(&a), Some(&b)) if a < b => candidates.next(),
+                (Some(_), None) => candidates.next(),
+                (_, Some(_)) => rest.next(),
+                (None, None) => return None,
+            }
+            .unwrap();
+            let (ref rdef, _, ref data) = self.resources[idx];
+
+            match rdef.segments() {
+                Some(segments) => {
+                    if check(resource, data) {
+                        params.clear();
+                        collect_params(segments, resource.resource_path().path(), &mut params);
+                        rdef.match_segments(resource.resource_path(), &params);
+                        return Some(idx);
+                    }
+                }
+                None => {
+                    if rdef.match_path_checked(resource, check, data) {
+                        return Some(idx);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Positions of the `{name}` segments in a path that is known to match.
+fn collect_params(segments: &[Segment], path: &str, params: &mut Vec<(u16, u16)>) {
+    let mut pos = 0;
+    for (segment, part) in segments.iter().zip(path.split('/')) {
+        if let Segment::Param = segment {
+            params.push((pos as u16, (pos + part.len()) as u16));
+        }
+        pos += part.len() + 1;
+    }
+}
+
+impl Node {
+    fn insert(&mut self, segments: &[Segment], idx: usize) {
+        match segments.split_first() {
+            None => self.resources.push(idx),
+            Some((Segment::Static(s), rest)) => self
+                .statics
+                .entry(s.clone())
+                .or_insert_with(Node::default)
+                .insert(rest, idx),
+            Some((Segment::Param, rest)) => self
+                .param
+                .get_or_insert_with(Box::default)
+                .insert(rest, idx),
+        }
+    }
+
+    /// Collect indexes of all resources matching the remaining path segments.
+    fn find(&self, mut path: Split<'_, char>, found: &mut Vec<usize>) {
+        match path.next() {
+            None => found.extend_from_slice(&self.resources),
+            Some(segment) => {
+                if let Some(node) = self.statics.get(segment) {
+                    node.find(path.clone(), found);
+                }
+                if !segment.is_empty() {
+                    if let Some(ref node) = self.param {
+                        node.find(path, found);
+                    }
+                }
             }
         }
-        None
     }
 }
 
@@ -95,7 +198,21 @@ impl<T, U> RouterBuilder<T, U> {
 
     /// Finish configuration and create router instance.
     pub fn finish(self) -> Router<T, U> {
-        Router(self.resources)
+        let mut tree = Node::default();
+        let mut rest = Vec::new();
+
+        for (idx, (rdef, _, _)) in self.resources.iter().enumerate() {
+            match rdef.segments() {
+                Some(segments) => tree.insert(segments, idx),
+                None => rest.push(idx),
+            }
+        }
+
+        Router {
+            resources: self.resources,
+            tree,
+            rest,
+        }
     }
 }
 
@@ -237,4 +354,60 @@ mod tests {
         assert_eq!(*h, 11);
         assert_eq!(&path["val"], "ttt");
     }
+
+    #[test]
+    fn test_recognizer_registration_order() {
+        let mut router = Router::<usize>::build();
+        router.path("/user/{id:[[:digit:]]+}", 10);
+        router.path("/user/{id}", 11);
+        router.path("/user/me", 12);
+        router.path("/user/{id}/posts", 13);
+        router.path("/{kind}/{id}/posts", 14);
+        let mut router = router.finish();
+
+        // custom regex registered first wins over the tree
+        let mut path = Path::new("/user/42");
+        let (h, _) = router.recognize_mut(&mut path).unwrap();
+        assert_eq!(*h, 10);
+        assert_eq!(&path["id"], "42");
+
+        // `{id}` registered before the static segment
+        let mut path = Path::new("/user/me");
+        let (h, _) = router.recognize_mut(&mut path).unwrap();
+        assert_eq!(*h, 11);
+        assert_eq!(&path["id"], "me");
+
+        let mut path = Path::new("/post/1/posts");
+        let (h, _) = router.recognize_mut(&mut path).unwrap();
+        assert_eq!(*h, 14);
+        assert_eq!(&path["kind"], "post");
+        assert_eq!(&path["id"], "1");
+
+        let mut path = Path::new("/user//posts");
+        assert!(router.recognize_mut(&mut path).is_none());
+    }
+
+    #[test]
+    fn test_recognizer_checked() {
+        let mut router = Router::<usize, bool>::build();
+        router.path("/name/{val}", 10).2 = Some(false);
+        router.path("/name/{val:.*}", 11).2 = Some(false);
+        router.path("/name/{val}", 12).2 = Some(true);
+        router.prefix("/name", 13);
+        let mut router = router.finish();
+
+        let check = |_: &Path<&str>, allowed: &Option<bool>| allowed.unwrap_or(true);
+
+        let mut path = Path::new("/name/value");
+        let (h, _) = router.recognize_mut_checked(&mut path, check).unwrap();
+        assert_eq!(*h, 12);
+        assert_eq!(path.len(), 1);
+        assert_eq!(&path["val"], "value");
+        assert_eq!(path.unprocessed(), "");
+
+        let mut path = Path::new("/name/value/more");
+        let (h, _) = router.recognize_mut_checked(&mut path, check).unwrap();
+        assert_eq!(*h, 13);
+        assert!(path.is_empty());
+    }
 }
@@ -1,6 +1,7 @@
 use std::pin::Pin;
 use std::task::{Context, Poll};
 use std::time::Duration;
+use std::os::unix::io::{AsRawFd, RawFd};
 use std::{io, mem, net};
 use futures_core::{Future, Stream};
 use futures_channel::mpsc::{unbounded, UnboundedReceiver};
@@ -13,10 +14,13 @@ use net2::TcpBuilder;
 use num_cpus;
 
 use crate::krse::net::TcpStream;
+use crate::service::ServiceFactory as KayrxServiceFactory;
 use crate::timer::{delay_until, Instant};
 use crate::fiber::{spawn, System};
 use crate::server::accept::{AcceptLoop, AcceptNotify, Command};
 use crate::server::config::{ConfiguredService, ServiceConfig};
+use crate::server::inherit;
+use crate::server::proxy::{ProxyFactory, ProxyStream};
 use crate::server::server::{Server, ServerCommand};
 use crate::server::service::{InternalServiceFactory, ServiceFactory, StreamNewService};
 use crate::server::signal::{Signal, Signals};
@@ -24,6 +28,10 @@ use crate::server::socket::StdListener;
 use crate::server::worker::{self, Worker, WorkerAvailability, WorkerClient};
 use crate::server::Token;
 
+/// Time for the process started on `SIGUSR2` to come up before the
+/// current one stops
+const RESTART_GRACE: Duration = Duration::from_secs(2);
+
 /// Server builder
 pub struct ServerBuilder {
     threads: usize,
@@ -32,6 +40,7 @@ pub struct ServerBuilder {
     workers: Vec<(usize, WorkerClient)>,
     services: Vec<Box<dyn InternalServiceFactory>>,
     sockets: Vec<(Token, StdListener)>,
+    listeners: Vec<(String, RawFd)>,
     accept: AcceptLoop,
     exit: bool,
     shutdown_timeout: Duration,
@@ -50,6 +59,8 @@ impl Default for ServerBuilder {
 impl ServerBuilder {
     /// Create new Server builder instance
     pub fn new() -> ServerBuilder {
+        inherit::init();
+
         let (tx, rx) = unbounded();
         let server = Server::new(tx);
 
@@ -59,6 +70,7 @@ impl ServerBuilder {
             workers: Vec::new(),
             services: Vec::new(),
             sockets: Vec::new(),
+            listeners: Vec::new(),
             accept: AcceptLoop::new(server.clone()),
             backlog: 2048,
             exit: false,
@@ -123,6 +135,8 @@ impl ServerBuilder {
     /// serving requests. Workers still alive after the timeout are force
     /// dropped.
     ///
+    /// The same timeout applies to the old process after `SIGUSR2` restart.
+    ///
     /// By default shutdown timeout sets to 30 seconds.
     pub fn shutdown_timeout(mut self, sec: u64) -> Self {
         self.shutdown_timeout = Duration::from_secs(sec);
@@ -146,7 +160,8 @@ impl ServerBuilder {
             let mut srv = ConfiguredService::new(apply);
             for (name, lst) in cfg.services {
                 let token = self.token.next();
-                srv.stream(token, name, lst.local_addr()?);
+                srv.stream(token, name.clone(), lst.local_addr()?);
+                self.listeners.push((name, lst.as_raw_fd()));
                 self.sockets.push((token, StdListener::Tcp(lst)));
             }
             self.services.push(Box::new(srv));
@@ -157,24 +172,26 @@ impl ServerBuilder {
     }
 
     /// Add new service to the server.
-    pub fn bind<F, U, N: AsRef<str>>(mut self, name: N, addr: U, factory: F) -> io::Result<Self>
+    ///
+    /// If the process was started with listeners inherited under the same
+    /// `name` (`LISTEN_FDS` and `LISTEN_FDNAMES` environment variables, set
+    /// by the `SIGUSR2` restart or by systemd socket activation), these
+    /// listeners are adopted instead of binding to `addr`.
+    pub fn bind<F, U, N: AsRef<str>>(self, name: N, addr: U, factory: F) -> io::Result<Self>
     where
         F: ServiceFactory<TcpStream>,
         U: net::ToSocketAddrs,
     {
-        let sockets = bind_addr(addr, self.backlog)?;
+        let sockets = match inherit::take_tcp(name.as_ref()) {
+            Some(sockets) => sockets,
+            None => bind_addr(addr, self.backlog)?,
+        };
 
+        let mut srv = self;
         for lst in sockets {
-            let token = self.token.next();
-            self.services.push(StreamNewService::create(
-                name.as_ref().to_string(),
-                token,
-                factory.clone(),
-                lst.local_addr()?,
-            ));
-            self.sockets.push((token, StdListener::Tcp(lst)));
+            srv = srv.listen(name.as_ref(), lst, factory.clone())?;
         }
-        Ok(self)
+        Ok(srv)
     }
 
     /// Add new unix domain service to the server.
@@ -186,6 +203,10 @@ impl ServerBuilder {
     {
         use std::os::unix::net::UnixListener;
 
+        if let Some(lst) = inherit::take_uds(name.as_ref()) {
+            return self.listen_uds(name, lst, factory);
+        }
+
         // The path must not exist when we try to bind.
         // Try to remove it to avoid bind error.
         if let Err(e) = std::fs::remove_file(addr.as_ref()) {
@@ -220,6 +241,8 @@ impl ServerBuilder {
             factory.clone(),
             addr,
         ));
+        self.listeners
+            .push((name.as_ref().to_string(), lst.as_raw_fd()));
         self.sockets.push((token, StdListener::Uds(lst)));
         Ok(self)
     }
@@ -241,10 +264,46 @@ impl ServerBuilder {
             factory,
             lst.local_addr()?,
         ));
+        self.listeners
+            .push((name.as_ref().to_string(), lst.as_raw_fd()));
         self.sockets.push((token, StdListener::Tcp(lst)));
         Ok(self)
     }
 
+    /// Add new service to the server, for connections with the PROXY
+    /// protocol header.
+    ///
+    /// The header is read and validated before the stream is passed to the
+    /// service, connections without a valid header are dropped.
+    pub fn bind_proxy<F, T, U, N>(self, name: N, addr: U, factory: F) -> io::Result<Self>
+    where
+        F: Fn() -> T + Send + Clone + 'static,
+        T: KayrxServiceFactory<Config = (), Request = ProxyStream<TcpStream>>,
+        U: net::ToSocketAddrs,
+        N: AsRef<str>,
+    {
+        self.bind(name, addr, ProxyFactory(factory))
+    }
+
+    /// Add new service to the server, for connections with the PROXY
+    /// protocol header.
+    ///
+    /// The header is read and validated before the stream is passed to the
+    /// service, connections without a valid header are dropped.
+    pub fn listen_proxy<F, T, N>(
+        self,
+        name: N,
+        lst: net::TcpListener,
+        factory: F,
+    ) -> io::Result<Self>
+    where
+        F: Fn() -> T + Send + Clone + 'static,
+        T: KayrxServiceFactory<Config = (), Request = ProxyStream<TcpStream>>,
+        N: AsRef<str>,
+    {
+        self.listen(name, lst, ProxyFactory(factory))
+    }
+
     /// Spawn new thread and start listening for incoming connections.
     ///
     /// This method spawns new thread and starts new fiber system. Other than
@@ -312,7 +371,8 @@ impl ServerBuilder {
             }
             ServerCommand::Signal(sig) => {
                 // Signals support
-                // Handle `SIGINT`, `SIGTERM`, `SIGQUIT` signals and stop fiber system
+                // Handle `SIGINT`, `SIGTERM`, `SIGQUIT` signals and stop fiber system,
+                // `SIGUSR2` starts new process with inherited listeners
                 match sig {
                     Signal::Int => {
                         info!("SIGINT received, exiting");
@@ -338,9 +398,40 @@ impl ServerBuilder {
                             completion: None,
                         })
                     }
+                    Signal::Usr2 => {
+                        info!("SIGUSR2 received, restarting");
+                        match inherit::spawn(&self.listeners) {
+                            Ok(mut child) => {
+                                info!("Started new process {}", child.id());
+                                // stop once the new process survived its startup
+                                let server = self.server.clone();
+                                spawn(async move {
+                                    delay_until(Instant::now() + RESTART_GRACE).await;
+                                    match child.try_wait() {
+                                        Ok(None) => server.restarted(),
+                                        Ok(Some(status)) => error!(
+                                            "New process {} exited with {}, keep running",
+                                            child.id(),
+                                            status
+                                        ),
+                                        Err(e) => error!("Can not check new process: {}", e),
+                                    }
+                                });
+                            }
+                            Err(e) => error!("Can not start new process: {}", e),
+                        }
+                    }
                     _ => (),
                 }
             }
+            ServerCommand::Restarted => {
+                info!("New process is running, stopping");
+                self.exit = true;
+                self.handle_cmd(ServerCommand::Stop {
+                    graceful: true,
+                    completion: None,
+                })
+            }
             ServerCommand::Notify(tx) => {
                 self.notify.push(tx);
             }
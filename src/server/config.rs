@@ -8,6 +8,7 @@ use  crate::krse::net::TcpStream;
 use crate::krse::task::counter::CounterGuard;
 use crate::service as kayrx;
 use super::builder::bind_addr;
+use super::inherit;
 use super::service::{
     BoxedServerService, InternalServiceFactory, ServerMessage, StreamService,
 };
@@ -43,7 +44,10 @@ impl ServiceConfig {
     where
         U: net::ToSocketAddrs,
     {
-        let sockets = bind_addr(addr, self.backlog)?;
+        let sockets = match inherit::take_tcp(name.as_ref()) {
+            Some(sockets) => sockets,
+            None => bind_addr(addr, self.backlog)?,
+        };
 
         for lst in sockets {
             self.listen(name.as_ref(), lst);
@@ -0,0 +1,220 @@
+//! Listener inheritance
+//!
+//! Listeners are passed to the new process the systemd way: the sockets are
+//! duplicated to the descriptors starting from 3, `LISTEN_FDS` holds the
+//! number of the sockets and `LISTEN_FDNAMES` the colon separated names.
+//! `ServerBuilder` adopts the inherited sockets by name instead of binding,
+//! so listeners could be inherited from systemd socket activation as well.
+use std::os::unix::io::{FromRawFd, RawFd};
+use std::os::unix::net::UnixListener;
+use std::os::unix::process::CommandExt;
+use std::process::{Child, Command};
+use std::sync::{Mutex, Once};
+use std::{env, io, net};
+
+use lazy_static::lazy_static;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, CONTROLS};
+
+/// First inherited descriptor
+const LISTEN_FDS_START: RawFd = 3;
+
+/// Separator of the names and the escape character are encoded
+const NAME: &AsciiSet = &CONTROLS.add(b':').add(b'%');
+
+lazy_static! {
+    static ref INHERITED: Mutex<Vec<(String, RawFd)>> = Mutex::new(Vec::new());
+}
+
+static INIT: Once = Once::new();
+
+/// Read inherited descriptors from the environment.
+///
+/// Only the first call has an effect. It should happen early on the main
+/// thread, `ServerBuilder::new()` calls it, since the environment is not
+/// safe to modify while other threads read it.
+pub(crate) fn init() {
+    INIT.call_once(|| *INHERITED.lock().unwrap() = from_env());
+}
+
+/// Take inherited tcp listeners registered with the name
+pub(crate) fn take_tcp(name: &str) -> Option<Vec<net::TcpListener>> {
+    let fds = take(name, false);
+    if fds.is_empty() {
+        None
+    } else {
+        Some(
+            fds.into_iter()
+                .map(|fd| unsafe { net::TcpListener::from_raw_fd(fd) })
+                .collect(),
+        )
+    }
+}
+
+/// Take inherited unix listener registered with the name
+pub(crate) fn take_uds(name: &str) -> Option<UnixListener> {
+    take(name, true)
+        .into_iter()
+        .next()
+        .map(|fd| unsafe { UnixListener::from_raw_fd(fd) })
+}
+
+fn take(name: &str, unix: bool) -> Vec<RawFd> {
+    let mut inherited = INHERITED.lock().unwrap();
+    let mut fds = Vec::new();
+    inherited.retain(|(n, fd)| {
+        if n == name && is_unix(*fd) == Some(unix) {
+            info!("Adopting inherited listener {:?}", name);
+            fds.push(*fd);
+            false
+        } else {
+            true
+        }
+    });
+    fds
+}
+
+/// Read inherited descriptors from the environment, the variables are
+/// removed so that they are not passed to the child processes.
+fn from_env() -> Vec<(String, RawFd)> {
+    let fds = parse(
+        std::process::id(),
+        env::var("LISTEN_PID").ok().as_deref(),
+        env::var("LISTEN_FDS").ok().as_deref(),
+        env::var("LISTEN_FDNAMES").ok().as_deref(),
+    );
+    env::remove_var("LISTEN_PID");
+    env::remove_var("LISTEN_FDS");
+    env::remove_var("LISTEN_FDNAMES");
+
+    for (_, fd) in &fds {
+        unsafe {
+            libc::fcntl(*fd, libc::F_SETFD, libc::FD_CLOEXEC);
+        }
+    }
+    fds
+}
+
+fn parse(
+    pid: u32,
+    listen_pid: Option<&str>,
+    listen_fds: Option<&str>,
+    names: Option<&str>,
+) -> Vec<(String, RawFd)> {
+    // descriptors are passed to another process
+    if let Some(listen_pid) = listen_pid {
+        if listen_pid.parse() != Ok(pid) {
+            return Vec::new();
+        }
+    }
+    let count: RawFd = match listen_fds.and_then(|n| n.parse().ok()) {
+        Some(count) => count,
+        None => return Vec::new(),
+    };
+
+    let mut names = names.unwrap_or("").split(':');
+    (0..count)
+        .map(|idx| {
+            let name = match names.next() {
+                Some(name) if !name.is_empty() => {
+                    percent_decode_str(name).decode_utf8_lossy().into_owned()
+                }
+                _ => "unknown".to_owned(),
+            };
+            (name, LISTEN_FDS_START + idx)
+        })
+        .collect()
+}
+
+/// Check if the descriptor is a unix or inet socket
+fn is_unix(fd: RawFd) -> Option<bool> {
+    let mut addr: libc::sockaddr_storage = unsafe { std::mem::zeroed() };
+    let mut len = std::mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+    let res = unsafe { libc::getsockname(fd, &mut addr as *mut _ as *mut _, &mut len) };
+    if res < 0 {
+        return None;
+    }
+    match i32::from(addr.ss_family) {
+        libc::AF_UNIX => Some(true),
+        libc::AF_INET | libc::AF_INET6 => Some(false),
+        _ => None,
+    }
+}
+
+/// Start the current executable with the same arguments, passing
+/// listeners to it.
+pub(crate) fn spawn(listeners: &[(String, RawFd)]) -> io::Result<Child> {
+    let names = listeners
+        .iter()
+        .map(|(name, _)| utf8_percent_encode(name, NAME).to_string())
+        .collect::<Vec<_>>()
+        .join(":");
+    let mut fds = listeners.iter().map(|(_, fd)| *fd).collect::<Vec<_>>();
+    let count = fds.len() as RawFd;
+
+    let mut cmd = Command::new(env::current_exe()?);
+    cmd.args(env::args_os().skip(1))
+        .env_remove("LISTEN_PID")
+        .env("LISTEN_FDS", count.to_string())
+        .env("LISTEN_FDNAMES", names);
+
+    unsafe {
+        cmd.pre_exec(move || {
+            // move descriptors out of the way first, the targets could
+            // overlap with them
+            for fd in fds.iter_mut() {
+                *fd = libc::fcntl(*fd, libc::F_DUPFD_CLOEXEC, LISTEN_FDS_START + count);
+                if *fd < 0 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+            // dup2() clears close-on-exec flag
+            for (idx, fd) in fds.iter().enumerate() {
+                if libc::dup2(*fd, LISTEN_FDS_START + idx as RawFd) < 0 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+            Ok(())
+        });
+    }
+    cmd.spawn()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let fds = parse(
+            10,
+            None,
+            Some("2"),
+            Some("web:kayrx-service-127.0.0.1%3A8080"),
+        );
+        assert_eq!(
+            fds,
+            vec![
+                ("web".to_owned(), 3),
+                ("kayrx-service-127.0.0.1:8080".to_owned(), 4)
+            ]
+        );
+
+        let fds = parse(10, Some("10"), Some("2"), None);
+        assert_eq!(
+            fds,
+            vec![("unknown".to_owned(), 3), ("unknown".to_owned(), 4)]
+        );
+
+        assert!(parse(10, Some("11"), Some("2"), None).is_empty());
+        assert!(parse(10, None, None, Some("web")).is_empty());
+        assert!(parse(10, None, Some("x"), None).is_empty());
+    }
+
+    #[test]
+    fn test_name_encoding() {
+        let name = "kayrx-service-[::1]:8080";
+        let encoded = utf8_percent_encode(name, NAME).to_string();
+        assert!(!encoded.contains(':'));
+        assert_eq!(percent_decode_str(&encoded).decode_utf8_lossy(), name);
+    }
+}
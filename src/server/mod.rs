@@ -3,6 +3,8 @@
 mod accept;
 mod builder;
 mod config;
+pub(crate) mod inherit;
+pub mod proxy;
 mod server;
 mod service;
 mod signal;
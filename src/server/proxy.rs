@@ -0,0 +1,592 @@
+//! PROXY protocol support
+//!
+//! Load balancers such as HAProxy or AWS NLB prepend connections with the
+//! PROXY protocol header, which carries the addresses of the original
+//! connection. Both the v1 text and the v2 binary formats are supported.
+use std::marker::PhantomData;
+use std::mem::MaybeUninit;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::pin::Pin;
+use std::str;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use std::{cmp, fmt, io};
+
+use bytes::{Buf, BufMut, BytesMut};
+use futures_util::future::{ok, FutureExt, LocalBoxFuture, Ready};
+
+use crate::krse::io::{AsyncRead, AsyncReadExt, AsyncWrite};
+use crate::krse::net::TcpStream;
+use crate::server::ServiceFactory as ServerServiceFactory;
+use crate::service::dev::{AndThenServiceFactory, MapErrServiceFactory, MapInitErr};
+use crate::service::{pipeline_factory, PipelineFactory, Service, ServiceFactory};
+
+/// v1 header prefix
+const V1_PREFIX: &[u8] = b"PROXY ";
+
+/// v2 header signature
+const V2_SIGNATURE: &[u8] = b"\r\n\r\n\0\r\nQUIT\n";
+
+/// Max size of the v1 header, including CRLF
+const V1_MAX_SIZE: usize = 107;
+
+/// The shortest v1 header is `PROXY UNKNOWN\r\n`, v2 header is never
+/// shorter.
+const MIN_SIZE: usize = 15;
+
+/// Size of the v2 fixed header
+const V2_HEADER_SIZE: usize = 16;
+
+/// Parsed PROXY protocol header
+///
+/// Source and destination addresses of the original connection are
+/// `None` for `UNKNOWN` (v1) and `LOCAL` or non-inet (v2) connections.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProxyHeader {
+    version: u8,
+    source: Option<SocketAddr>,
+    destination: Option<SocketAddr>,
+}
+
+impl ProxyHeader {
+    /// Protocol version, 1 or 2
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    /// Source address of the original connection, the client address.
+    pub fn source(&self) -> Option<SocketAddr> {
+        self.source
+    }
+
+    /// Destination address of the original connection, the address of the
+    /// proxy listener.
+    pub fn destination(&self) -> Option<SocketAddr> {
+        self.destination
+    }
+}
+
+/// Length of the header at the start of `buf`, `None` if it is not read
+/// completely yet.
+fn header_len(buf: &[u8]) -> io::Result<Option<usize>> {
+    if buf.len() < MIN_SIZE {
+        Ok(None)
+    } else if buf.starts_with(V1_PREFIX) {
+        let line = &buf[..buf.len().min(V1_MAX_SIZE)];
+        match line.windows(2).position(|w| w == b"\r\n") {
+            Some(pos) => Ok(Some(pos + 2)),
+            None if line.len() >= V1_MAX_SIZE => Err(invalid("v1 header is too long")),
+            None => Ok(None),
+        }
+    } else if buf.starts_with(V2_SIGNATURE) {
+        if buf.len() < V2_HEADER_SIZE {
+            return Ok(None);
+        }
+        let len = V2_HEADER_SIZE + (&buf[14..16]).get_u16() as usize;
+        Ok(if buf.len() >= len { Some(len) } else { None })
+    } else {
+        Err(invalid("missing header"))
+    }
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Invalid PROXY protocol header: {}", msg),
+    )
+}
+
+/// Parse v1 header, `PROXY TCP4 192.0.2.1 192.0.2.2 56324 443\r\n`
+fn parse_v1(buf: &[u8]) -> io::Result<ProxyHeader> {
+    let line = buf
+        .strip_suffix(b"\r\n")
+        .and_then(|line| str::from_utf8(line).ok())
+        .ok_or_else(|| invalid("malformed v1 header"))?;
+    let mut parts = line.split(' ').skip(1);
+
+    let header = |source, destination| ProxyHeader {
+        version: 1,
+        source,
+        destination,
+    };
+
+    match parts.next() {
+        Some("UNKNOWN") => return Ok(header(None, None)),
+        Some("TCP4") | Some("TCP6") => (),
+        _ => return Err(invalid("unsupported v1 protocol")),
+    }
+    let ipv4 = line.as_bytes()[6..].starts_with(b"TCP4");
+
+    let addr = |ip: Option<&str>| -> io::Result<IpAddr> {
+        let ip: IpAddr = ip
+            .and_then(|ip| ip.parse().ok())
+            .ok_or_else(|| invalid("malformed v1 address"))?;
+        if ip.is_ipv4() != ipv4 {
+            return Err(invalid("v1 address does not match protocol"));
+        }
+        Ok(ip)
+    };
+    let src = addr(parts.next())?;
+    let dst = addr(parts.next())?;
+
+    let mut port = || -> io::Result<u16> {
+        parts
+            .next()
+            .filter(|port| !port.starts_with('0') || *port == "0")
+            .and_then(|port| port.parse().ok())
+            .ok_or_else(|| invalid("malformed v1 port"))
+    };
+    let src_port = port()?;
+    let dst_port = port()?;
+    if parts.next().is_some() {
+        return Err(invalid("malformed v1 header"));
+    }
+
+    Ok(header(
+        Some(SocketAddr::new(src, src_port)),
+        Some(SocketAddr::new(dst, dst_port)),
+    ))
+}
+
+/// Parse v2 header, signature, version and command, family and protocol,
+/// length and addresses followed by TLVs.
+fn parse_v2(buf: &[u8]) -> io::Result<ProxyHeader> {
+    let ver_cmd = buf[12];
+    if ver_cmd >> 4 != 2 {
+        return Err(invalid("unsupported version"));
+    }
+
+    let header = |source, destination| ProxyHeader {
+        version: 2,
+        source,
+        destination,
+    };
+
+    match ver_cmd & 0x0f {
+        // LOCAL, connection established by the proxy itself
+        0 => return Ok(header(None, None)),
+        // PROXY
+        1 => (),
+        _ => return Err(invalid("unsupported command")),
+    }
+
+    // only STREAM transport is supported, UNSPEC is allowed for unknown
+    // connections
+    if buf[13] & 0x0f != 1 && buf[13] != 0 {
+        return Err(invalid("unsupported transport protocol"));
+    }
+
+    let mut addrs = &buf[V2_HEADER_SIZE..];
+    match buf[13] >> 4 {
+        // AF_INET
+        1 => {
+            if addrs.len() < 12 {
+                return Err(invalid("v2 address block is too short"));
+            }
+            let src = Ipv4Addr::from(addrs.get_u32());
+            let dst = Ipv4Addr::from(addrs.get_u32());
+            let src_port = addrs.get_u16();
+            let dst_port = addrs.get_u16();
+            Ok(header(
+                Some(SocketAddr::new(src.into(), src_port)),
+                Some(SocketAddr::new(dst.into(), dst_port)),
+            ))
+        }
+        // AF_INET6
+        2 => {
+            if addrs.len() < 36 {
+                return Err(invalid("v2 address block is too short"));
+            }
+            let src = Ipv6Addr::from(addrs.get_u128());
+            let dst = Ipv6Addr::from(addrs.get_u128());
+            let src_port = addrs.get_u16();
+            let dst_port = addrs.get_u16();
+            Ok(header(
+                Some(SocketAddr::new(src.into(), src_port)),
+                Some(SocketAddr::new(dst.into(), dst_port)),
+            ))
+        }
+        // AF_UNSPEC, AF_UNIX
+        0 | 3 => Ok(header(None, None)),
+        _ => Err(invalid("unsupported address family")),
+    }
+}
+
+/// Stream with the parsed PROXY protocol header
+pub struct ProxyStream<T> {
+    io: T,
+    header: ProxyHeader,
+    buf: BytesMut,
+}
+
+impl<T: AsyncRead + Unpin> ProxyStream<T> {
+    /// Read PROXY header from the stream.
+    ///
+    /// Bytes read past the header are kept and returned by the first reads
+    /// from the stream.
+    pub async fn read(mut io: T) -> io::Result<Self> {
+        let mut buf = BytesMut::with_capacity(V1_MAX_SIZE);
+        loop {
+            if let Some(len) = header_len(&buf)? {
+                let header = buf.split_to(len);
+                let header = if header.starts_with(V1_PREFIX) {
+                    parse_v1(&header)?
+                } else {
+                    parse_v2(&header)?
+                };
+                return Ok(ProxyStream { io, header, buf });
+            }
+            if io.read_buf(&mut buf).await? == 0 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+        }
+    }
+}
+
+impl<T> ProxyStream<T> {
+    /// Create stream with the header, read from it already.
+    pub fn new(io: T, header: ProxyHeader) -> Self {
+        ProxyStream {
+            io,
+            header,
+            buf: BytesMut::new(),
+        }
+    }
+
+    /// PROXY protocol header of the connection
+    pub fn header(&self) -> &ProxyHeader {
+        &self.header
+    }
+
+    /// Reference to the underlying stream
+    pub fn get_ref(&self) -> &T {
+        &self.io
+    }
+
+    /// Mutable reference to the underlying stream
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.io
+    }
+
+    /// Consume stream and return the underlying stream
+    ///
+    /// Bytes read past the header, but not read from the stream yet,
+    /// are lost.
+    pub fn into_inner(self) -> T {
+        self.io
+    }
+}
+
+impl ProxyStream<TcpStream> {
+    /// Address of the original client.
+    ///
+    /// Falls back to the address of the proxy if the header does not carry
+    /// the source address.
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        match self.header.source {
+            Some(addr) => Ok(addr),
+            None => self.io.peer_addr(),
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ProxyStream<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProxyStream")
+            .field("io", &self.io)
+            .field("header", &self.header)
+            .finish()
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for ProxyStream<T> {
+    unsafe fn prepare_uninitialized_buffer(&self, buf: &mut [MaybeUninit<u8>]) -> bool {
+        self.io.prepare_uninitialized_buffer(buf)
+    }
+
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if !this.buf.is_empty() {
+            let n = cmp::min(buf.len(), this.buf.len());
+            buf[..n].copy_from_slice(&this.buf[..n]);
+            this.buf.advance(n);
+            return Poll::Ready(Ok(n));
+        }
+        Pin::new(&mut this.io).poll_read(cx, buf)
+    }
+
+    fn poll_read_buf<B: BufMut>(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut B,
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if !this.buf.is_empty() {
+            let n = cmp::min(buf.remaining_mut(), this.buf.len());
+            buf.put_slice(&this.buf[..n]);
+            this.buf.advance(n);
+            return Poll::Ready(Ok(n));
+        }
+        Pin::new(&mut this.io).poll_read_buf(cx, buf)
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for ProxyStream<T> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().io).poll_write(cx, buf)
+    }
+
+    fn poll_write_buf<B: Buf>(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut B,
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().io).poll_write_buf(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().io).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().io).poll_shutdown(cx)
+    }
+}
+
+/// PROXY protocol `Acceptor` service factory
+///
+/// Reads and validates PROXY protocol header of the accepted streams.
+/// Connections without a valid header are rejected.
+pub struct Acceptor<T> {
+    timeout: u64,
+    io: PhantomData<T>,
+}
+
+impl<T> Acceptor<T> {
+    /// Create PROXY protocol `Acceptor` service factory
+    pub fn new() -> Self {
+        Acceptor {
+            timeout: 5000,
+            io: PhantomData,
+        }
+    }
+
+    /// Set header read timeout in milliseconds.
+    ///
+    /// To disable timeout set value to 0.
+    ///
+    /// By default timeout is set to 5000 milliseconds.
+    pub fn timeout(mut self, timeout: u64) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+impl<T> Default for Acceptor<T> {
+    fn default() -> Self {
+        Acceptor::new()
+    }
+}
+
+impl<T> Clone for Acceptor<T> {
+    fn clone(&self) -> Self {
+        Acceptor {
+            timeout: self.timeout,
+            io: PhantomData,
+        }
+    }
+}
+
+impl<T: AsyncRead + Unpin + 'static> ServiceFactory for Acceptor<T> {
+    type Request = T;
+    type Response = ProxyStream<T>;
+    type Error = io::Error;
+    type Service = AcceptorService<T>;
+
+    type Config = ();
+    type InitError = ();
+    type Future = Ready<Result<Self::Service, Self::InitError>>;
+
+    fn new_service(&self, _: ()) -> Self::Future {
+        ok(AcceptorService {
+            timeout: self.timeout,
+            io: PhantomData,
+        })
+    }
+}
+
+/// PROXY protocol `Acceptor` service
+pub struct AcceptorService<T> {
+    timeout: u64,
+    io: PhantomData<T>,
+}
+
+impl<T: AsyncRead + Unpin + 'static> Service for AcceptorService<T> {
+    type Request = T;
+    type Response = ProxyStream<T>;
+    type Error = io::Error;
+    type Future = LocalBoxFuture<'static, Result<ProxyStream<T>, io::Error>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, io: T) -> Self::Future {
+        let timeout = self.timeout;
+
+        async move {
+            if timeout == 0 {
+                ProxyStream::read(io).await
+            } else {
+                let read = ProxyStream::read(io);
+                match crate::timer::timeout(Duration::from_millis(timeout), read).await {
+                    Ok(res) => res,
+                    Err(_) => Err(io::Error::new(
+                        io::ErrorKind::TimedOut,
+                        "PROXY protocol header read timed out",
+                    )),
+                }
+            }
+        }
+        .boxed_local()
+    }
+}
+
+/// Server service factory, reads PROXY protocol header before the stream
+/// is passed to the service.
+#[derive(Clone)]
+pub(crate) struct ProxyFactory<F>(pub(crate) F);
+
+impl<F, T> ServerServiceFactory<TcpStream> for ProxyFactory<F>
+where
+    F: Fn() -> T + Send + Clone + 'static,
+    T: ServiceFactory<Config = (), Request = ProxyStream<TcpStream>>,
+{
+    type Factory = PipelineFactory<
+        AndThenServiceFactory<
+            MapErrServiceFactory<Acceptor<TcpStream>, fn(io::Error), ()>,
+            MapInitErr<MapErrServiceFactory<T, fn(T::Error), ()>, fn(T::InitError), ()>,
+        >,
+    >;
+
+    fn create(&self) -> Self::Factory {
+        pipeline_factory(Acceptor::new().map_err(log_error as fn(io::Error))).and_then(
+            (self.0)()
+                .map_err(drop as fn(T::Error))
+                .map_init_err(drop as fn(T::InitError)),
+        )
+    }
+}
+
+fn log_error(e: io::Error) {
+    debug!("Can not read PROXY protocol header: {}", e);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_v1() {
+        let header = parse_v1(b"PROXY TCP4 192.0.2.1 192.0.2.2 56324 443\r\n").unwrap();
+        assert_eq!(header.version(), 1);
+        assert_eq!(header.source(), Some("192.0.2.1:56324".parse().unwrap()));
+        assert_eq!(header.destination(), Some("192.0.2.2:443".parse().unwrap()));
+
+        let header = parse_v1(b"PROXY TCP6 2001:db8::1 2001:db8::2 4711 80\r\n").unwrap();
+        assert_eq!(header.source(), Some("[2001:db8::1]:4711".parse().unwrap()));
+
+        let header = parse_v1(b"PROXY UNKNOWN\r\n").unwrap();
+        assert_eq!(header.source(), None);
+
+        assert!(parse_v1(b"PROXY TCP4 2001:db8::1 192.0.2.2 56324 443\r\n").is_err());
+        assert!(parse_v1(b"PROXY TCP4 192.0.2.1 192.0.2.2 056324 443\r\n").is_err());
+        assert!(parse_v1(b"PROXY TCP4 192.0.2.1 192.0.2.2 56324\r\n").is_err());
+        assert!(parse_v1(b"PROXY TCP4 192.0.2.1 192.0.2.2 56324 443 1\r\n").is_err());
+        assert!(parse_v1(b"PROXY UDP4 192.0.2.1 192.0.2.2 56324 443\r\n").is_err());
+    }
+
+    #[test]
+    fn test_v2() {
+        let header = parse_v2(
+            b"\r\n\r\n\0\r\nQUIT\n\x21\x11\x00\x0c\
+              \xc0\x00\x02\x01\xc0\x00\x02\x02\xdc\x04\x01\xbb",
+        )
+        .unwrap();
+        assert_eq!(header.version(), 2);
+        assert_eq!(header.source(), Some("192.0.2.1:56324".parse().unwrap()));
+        assert_eq!(header.destination(), Some("192.0.2.2:443".parse().unwrap()));
+
+        // TLVs are skipped
+        let header = parse_v2(
+            b"\r\n\r\n\0\r\nQUIT\n\x21\x21\x00\x28\
+              \x20\x01\x0d\xb8\0\0\0\0\0\0\0\0\0\0\0\x01\
+              \x20\x01\x0d\xb8\0\0\0\0\0\0\0\0\0\0\0\x02\
+              \x12\x67\x00\x50\x04\x00\x01\x00",
+        )
+        .unwrap();
+        assert_eq!(header.source(), Some("[2001:db8::1]:4711".parse().unwrap()));
+        assert_eq!(
+            header.destination(),
+            Some("[2001:db8::2]:80".parse().unwrap())
+        );
+
+        // LOCAL
+        let header = parse_v2(b"\r\n\r\n\0\r\nQUIT\n\x20\x00\x00\x00").unwrap();
+        assert_eq!(header.source(), None);
+
+        // short address block
+        assert!(parse_v2(b"\r\n\r\n\0\r\nQUIT\n\x21\x11\x00\x03\x00\x00\x00").is_err());
+        // version 1 in binary header
+        assert!(parse_v2(b"\r\n\r\n\0\r\nQUIT\n\x11\x11\x00\x00").is_err());
+        // DGRAM transport
+        assert!(parse_v2(
+            b"\r\n\r\n\0\r\nQUIT\n\x21\x12\x00\x0c\
+              \xc0\x00\x02\x01\xc0\x00\x02\x02\xdc\x04\x01\xbb",
+        )
+        .is_err());
+    }
+
+    fn read_stream(data: &[u8]) -> io::Result<(ProxyHeader, Vec<u8>)> {
+        async {
+            let mut io = ProxyStream::read(data).await?;
+            let mut rest = Vec::new();
+            io.read_to_end(&mut rest).await?;
+            Ok((*io.header(), rest))
+        }
+        .now_or_never()
+        .unwrap()
+    }
+
+    #[test]
+    fn test_read() {
+        let (header, rest) =
+            read_stream(b"PROXY TCP4 192.0.2.1 192.0.2.2 56324 443\r\nGET / HTTP/1.1\r\n").unwrap();
+        assert_eq!(header.source(), Some("192.0.2.1:56324".parse().unwrap()));
+        assert_eq!(rest, b"GET / HTTP/1.1\r\n");
+
+        let (header, rest) = read_stream(
+            b"\r\n\r\n\0\r\nQUIT\n\x21\x11\x00\x0c\
+              \xc0\x00\x02\x01\xc0\x00\x02\x02\xdc\x04\x01\xbbpayload",
+        )
+        .unwrap();
+        assert_eq!(header.version(), 2);
+        assert_eq!(rest, b"payload");
+
+        let err = read_stream(b"PROXY TCP4 192.0.2.1 192.0.2.2 56324 443").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        let mut long = V1_PREFIX.to_vec();
+        long.resize(V1_MAX_SIZE + 10, b'x');
+        let err = read_stream(&long).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(read_stream(b"GET / HTTP/1.1\r\nHost: localhost\r\n").is_err());
+    }
+}
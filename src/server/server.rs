@@ -16,6 +16,8 @@ pub(crate) enum ServerCommand {
     Pause(oneshot::Sender<()>),
     Resume(oneshot::Sender<()>),
     Signal(Signal),
+    /// Process started on `SIGUSR2` is up
+    Restarted,
     /// Whether to try and shut down gracefully
     Stop {
         graceful: bool,
@@ -45,6 +47,10 @@ impl Server {
         let _ = self.0.unbounded_send(ServerCommand::Signal(sig));
     }
 
+    pub(crate) fn restarted(&self) {
+        let _ = self.0.unbounded_send(ServerCommand::Restarted);
+    }
+
     pub(crate) fn worker_faulted(&self, idx: usize) {
         let _ = self.0.unbounded_send(ServerCommand::WorkerFaulted(idx));
     }
@@ -19,6 +19,8 @@ pub(crate) enum Signal {
     Term,
     /// SIGQUIT
     Quit,
+    /// SIGUSR2
+    Usr2,
 }
 
 pub(crate) struct Signals {
@@ -38,6 +40,7 @@ impl Signals {
                     (unix::SignalKind::hangup(), Signal::Hup),
                     (unix::SignalKind::terminate(), Signal::Term),
                     (unix::SignalKind::quit(), Signal::Quit),
+                    (unix::SignalKind::user_defined2(), Signal::Usr2),
                 ];
 
                 for (kind, sig) in sig_map.iter() {
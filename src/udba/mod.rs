@@ -0,0 +1,9 @@
+//! Database access
+//!
+//! Asynchronous database clients that run on the kayrx runtime.
+//!
+//! * [`pool`](pool/index.html) - Generic connection pool, usable with `web::Data`
+//! * [`postgres`](postgres/index.html) - PostgreSQL frontend/backend protocol client
+
+pub mod pool;
+pub mod postgres;
@@ -0,0 +1,610 @@
+//! Generic asynchronous connection pool
+//!
+//! A [`Pool`](struct.Pool.html) hands out connections created by a
+//! [`Manager`](trait.Manager.html). The number of checked out connections is limited by a
+//! `krse::sync::Semaphore`, idle connections are reaped by a background task driven by a
+//! `timer::DelayQueue`.
+//!
+//! The pool is cheap to clone and can be shared between workers with `web::Data`.
+//! [`PooledConnection`](struct.PooledConnection.html) implements `FromRequest`, so handlers
+//! can take a connection directly:
+//!
+//! ```rust,no_run
+//! use kayrx::udba::pool::{Pool, PooledConnection};
+//! use kayrx::udba::postgres::{Config, PostgresConnectionManager};
+//! use kayrx::web::{self, App, HttpResponse, HttpServer};
+//!
+//! async fn index(mut conn: PooledConnection<PostgresConnectionManager>) -> HttpResponse {
+//!     match conn.query_one("SELECT 'hello'", &[]).await {
+//!         Ok(row) => HttpResponse::Ok().body(row.get::<_, String>(0)),
+//!         Err(_) => HttpResponse::InternalServerError().finish(),
+//!     }
+//! }
+//!
+//! #[kayrx::main]
+//! async fn main() -> std::io::Result<()> {
+//!     let config = "postgres://postgres@localhost/postgres".parse::<Config>().unwrap();
+//!     let pool = Pool::builder()
+//!         .max_size(16)
+//!         .build(PostgresConnectionManager::new(config));
+//!
+//!     HttpServer::new(move || {
+//!         App::new()
+//!             .data(pool.clone())
+//!             .route("/", web::get().to(index))
+//!     })
+//!     .bind("127.0.0.1:8080")?
+//!     .run()
+//!     .await
+//! }
+//! ```
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex, Weak};
+use std::task::{Poll, Waker};
+use std::time::{Duration, Instant};
+use std::{error, fmt};
+
+use derive_more::Display;
+use futures_util::future::{poll_fn, FutureExt, LocalBoxFuture};
+
+use crate::fiber::Arbiter;
+use crate::http::error::{Error, ErrorInternalServerError, ErrorServiceUnavailable};
+use crate::krse::sync::Semaphore;
+use crate::timer::{timeout, DelayQueue};
+use crate::web::dev::Payload;
+use crate::web::{Data, FromRequest, HttpRequest};
+
+/// Creates and checks connections for a [`Pool`](struct.Pool.html).
+pub trait Manager: Sized + 'static {
+    /// Connection type
+    type Connection: 'static;
+
+    /// Error returned when a connection can not be created or is not usable
+    type Error: fmt::Debug + fmt::Display + 'static;
+
+    /// Opens a new connection.
+    fn connect(&self) -> LocalBoxFuture<'_, Result<Self::Connection, Self::Error>>;
+
+    /// Checks that a connection is still usable before it is handed out.
+    fn check<'a>(
+        &'a self,
+        conn: &'a mut Self::Connection,
+    ) -> LocalBoxFuture<'a, Result<(), Self::Error>>;
+
+    /// Quick synchronous check performed when a connection is returned to the pool.
+    ///
+    /// Broken connections are dropped instead of being reused.
+    fn has_broken(&self, _: &mut Self::Connection) -> bool {
+        false
+    }
+}
+
+/// Error returned by [`Pool::get`](struct.Pool.html#method.get).
+#[derive(Debug, Display)]
+pub enum PoolError<E> {
+    /// No connection became available within the connection timeout
+    #[display(fmt = "Timed out waiting for a connection")]
+    Timeout,
+    /// The manager failed to create a connection
+    #[display(fmt = "{}", _0)]
+    Backend(E),
+}
+
+impl<E: error::Error + 'static> error::Error for PoolError<E> {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            PoolError::Timeout => None,
+            PoolError::Backend(err) => Some(err),
+        }
+    }
+}
+
+/// Pool configuration.
+pub struct Builder<M: Manager> {
+    config: Config,
+    _t: PhantomData<M>,
+}
+
+#[derive(Debug, Clone)]
+struct Config {
+    max_size: usize,
+    idle_timeout: Option<Duration>,
+    max_lifetime: Option<Duration>,
+    connection_timeout: Duration,
+    test_on_check_out: bool,
+}
+
+impl<M: Manager> Default for Builder<M> {
+    fn default() -> Self {
+        Builder {
+            config: Config {
+                max_size: 10,
+                idle_timeout: Some(Duration::from_secs(10 * 60)),
+                max_lifetime: Some(Duration::from_secs(30 * 60)),
+                connection_timeout: Duration::from_secs(30),
+                test_on_check_out: true,
+            },
+            _t: PhantomData,
+        }
+    }
+}
+
+impl<M: Manager> fmt::Debug for Builder<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Builder")
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+impl<M: Manager> Builder<M> {
+    /// Creates a builder with the default configuration.
+    pub fn new() -> Builder<M> {
+        Builder::default()
+    }
+
+    /// Sets the maximum number of connections managed by the pool.
+    ///
+    /// By default max size is set to 10.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_size` is 0.
+    pub fn max_size(mut self, max_size: usize) -> Self {
+        assert!(max_size > 0, "max_size must be greater than zero");
+        self.config.max_size = max_size;
+        self
+    }
+
+    /// Sets how long a connection can stay idle before it is closed.
+    ///
+    /// By default idle timeout is set to 10 minutes. `None` disables it.
+    pub fn idle_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.config.idle_timeout = timeout;
+        self
+    }
+
+    /// Sets the maximum lifetime of a connection. Connections are closed once they
+    /// are returned to the pool after this time.
+    ///
+    /// By default max lifetime is set to 30 minutes. `None` disables it.
+    pub fn max_lifetime(mut self, lifetime: Option<Duration>) -> Self {
+        self.config.max_lifetime = lifetime;
+        self
+    }
+
+    /// Sets how long `Pool::get` waits for a connection before failing with
+    /// `PoolError::Timeout`.
+    ///
+    /// By default connection timeout is set to 30 seconds.
+    pub fn connection_timeout(mut self, timeout: Duration) -> Self {
+        self.config.connection_timeout = timeout;
+        self
+    }
+
+    /// Enables or disables `Manager::check` on checkout.
+    ///
+    /// Enabled by default.
+    pub fn test_on_check_out(mut self, test: bool) -> Self {
+        self.config.test_on_check_out = test;
+        self
+    }
+
+    /// Creates a pool. Connections are opened lazily.
+    pub fn build(self, manager: M) -> Pool<M> {
+        Pool(Arc::new(SharedPool {
+            semaphore: Semaphore::new(self.config.max_size),
+            config: self.config,
+            manager,
+            internals: Mutex::new(Internals {
+                idle: VecDeque::new(),
+                scheduled: Vec::new(),
+                connections: 0,
+                next_id: 0,
+                reaper: None,
+                reaper_arbiter: None,
+            }),
+        }))
+    }
+}
+
+/// Snapshot of the pool state.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct State {
+    /// Number of open connections, idle or checked out
+    pub connections: usize,
+    /// Number of idle connections
+    pub idle_connections: usize,
+}
+
+/// Asynchronous connection pool.
+pub struct Pool<M: Manager>(Arc<SharedPool<M>>);
+
+struct SharedPool<M: Manager> {
+    config: Config,
+    manager: M,
+    semaphore: Semaphore,
+    internals: Mutex<Internals<M::Connection>>,
+}
+
+struct Internals<C> {
+    /// Idle connections, the most recently used one is at the back
+    idle: VecDeque<Idle<C>>,
+    /// Idle connections the reaper has not put into its delay queue yet
+    scheduled: Vec<(u64, Instant)>,
+    connections: usize,
+    next_id: u64,
+    reaper: Option<Waker>,
+    /// Liveness of the arbiter the reaper runs on, unset once the reaper exits
+    reaper_arbiter: Option<Weak<()>>,
+}
+
+struct Conn<C> {
+    raw: C,
+    created: Instant,
+}
+
+struct Idle<C> {
+    id: u64,
+    conn: Conn<C>,
+    since: Instant,
+}
+
+impl<M: Manager> Pool<M> {
+    /// Creates a pool with the default configuration.
+    pub fn new(manager: M) -> Pool<M> {
+        Builder::new().build(manager)
+    }
+
+    /// Returns a builder to configure a pool.
+    pub fn builder() -> Builder<M> {
+        Builder::new()
+    }
+
+    /// Returns the manager of the pool.
+    pub fn manager(&self) -> &M {
+        &self.0.manager
+    }
+
+    /// Returns the current state of the pool.
+    pub fn state(&self) -> State {
+        let internals = self.0.internals.lock().unwrap();
+        State {
+            connections: internals.connections,
+            idle_connections: internals.idle.len(),
+        }
+    }
+
+    /// Checks out a connection.
+    ///
+    /// Idle connections are reused, most recently used first. If none is available and the
+    /// pool is not full, a new connection is opened. Otherwise waits until a connection is
+    /// returned, for at most the configured connection timeout.
+    pub async fn get(&self) -> Result<PooledConnection<M>, PoolError<M::Error>> {
+        self.start_reaper();
+
+        match timeout(self.0.config.connection_timeout, self.get_inner()).await {
+            Ok(res) => res,
+            Err(_) => Err(PoolError::Timeout),
+        }
+    }
+
+    async fn get_inner(&self) -> Result<PooledConnection<M>, PoolError<M::Error>> {
+        // the permit is given back when the pooled connection is dropped
+        self.0.semaphore.acquire().await.forget();
+        let mut pooled = PooledConnection {
+            pool: self.clone(),
+            conn: None,
+        };
+
+        loop {
+            let idle = self.0.internals.lock().unwrap().idle.pop_back();
+            let idle = match idle {
+                Some(idle) => idle,
+                None => break,
+            };
+
+            let now = Instant::now();
+            if self.0.is_expired(&idle.conn, now)
+                || self
+                    .0
+                    .config
+                    .idle_timeout
+                    .map_or(false, |t| now - idle.since >= t)
+            {
+                self.0.close(idle.conn);
+                continue;
+            }
+
+            // the connection is closed if the check fails or is cancelled
+            let mut checkout = CheckOut {
+                pool: &self.0,
+                conn: Some(idle.conn),
+            };
+            if self.0.config.test_on_check_out {
+                let raw = &mut checkout.conn.as_mut().unwrap().raw;
+                if let Err(err) = self.0.manager.check(raw).await {
+                    log::debug!("Connection failed health check: {}", err);
+                    continue;
+                }
+            }
+            pooled.conn = checkout.conn.take();
+            return Ok(pooled);
+        }
+
+        let raw = self.0.manager.connect().await.map_err(PoolError::Backend)?;
+        self.0.internals.lock().unwrap().connections += 1;
+        pooled.conn = Some(Conn {
+            raw,
+            created: Instant::now(),
+        });
+        Ok(pooled)
+    }
+
+    fn start_reaper(&self) {
+        let config = &self.0.config;
+        if config.idle_timeout.is_none() && config.max_lifetime.is_none() {
+            return;
+        }
+
+        let mut internals = self.0.internals.lock().unwrap();
+        let running = internals
+            .reaper_arbiter
+            .as_ref()
+            .map_or(false, |arbiter| arbiter.upgrade().is_some());
+        if !running {
+            let arbiter = Arbiter::liveness();
+            internals.reaper_arbiter = Some(arbiter.clone());
+            internals.reaper = None;
+            // timers of a previous reaper are gone along with it
+            let scheduled = internals
+                .idle
+                .iter()
+                .filter_map(|idle| Some((idle.id, self.0.deadline(&idle.conn, idle.since)?)))
+                .collect();
+            internals.scheduled = scheduled;
+            crate::fiber::spawn(reaper(Arc::downgrade(&self.0), arbiter));
+        }
+    }
+}
+
+impl<M: Manager> Clone for Pool<M> {
+    fn clone(&self) -> Self {
+        Pool(self.0.clone())
+    }
+}
+
+impl<M: Manager> fmt::Debug for Pool<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pool")
+            .field("config", &self.0.config)
+            .field("state", &self.state())
+            .finish()
+    }
+}
+
+impl<M: Manager> SharedPool<M> {
+    fn is_expired(&self, conn: &Conn<M::Connection>, now: Instant) -> bool {
+        self.config
+            .max_lifetime
+            .map_or(false, |lifetime| now - conn.created >= lifetime)
+    }
+
+    /// Earliest instant a connection idle since `since` has to be closed at.
+    fn deadline(&self, conn: &Conn<M::Connection>, since: Instant) -> Option<Instant> {
+        match (self.config.idle_timeout, self.config.max_lifetime) {
+            (Some(idle), Some(lifetime)) => Some((since + idle).min(conn.created + lifetime)),
+            (Some(idle), None) => Some(since + idle),
+            (None, Some(lifetime)) => Some(conn.created + lifetime),
+            (None, None) => None,
+        }
+    }
+
+    fn close(&self, conn: Conn<M::Connection>) {
+        self.internals.lock().unwrap().connections -= 1;
+        drop(conn);
+    }
+
+    fn put_back(&self, mut conn: Conn<M::Connection>) {
+        let now = Instant::now();
+        if self.manager.has_broken(&mut conn.raw) || self.is_expired(&conn, now) {
+            self.close(conn);
+            return;
+        }
+
+        let deadline = self.deadline(&conn, now);
+        let mut internals = self.internals.lock().unwrap();
+        let id = internals.next_id;
+        internals.next_id += 1;
+        internals.idle.push_back(Idle {
+            id,
+            conn,
+            since: now,
+        });
+        if let Some(deadline) = deadline {
+            internals.scheduled.push((id, deadline));
+            if let Some(ref waker) = internals.reaper {
+                waker.wake_by_ref();
+            }
+        }
+    }
+}
+
+impl<M: Manager> Drop for SharedPool<M> {
+    fn drop(&mut self) {
+        // let the reaper task exit
+        if let Some(waker) = self.internals.get_mut().unwrap().reaper.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Closes idle connections once their idle timeout or max lifetime elapsed.
+///
+/// Timers are only touched from this task, so connections can be returned to the pool
+/// from any thread.
+async fn reaper<M: Manager>(pool: Weak<SharedPool<M>>, arbiter: Weak<()>) {
+    let _guard = ReaperGuard {
+        pool: pool.clone(),
+        arbiter,
+    };
+    let mut expirations = DelayQueue::new();
+
+    poll_fn(|cx| {
+        let pool = match pool.upgrade() {
+            Some(pool) => pool,
+            None => return Poll::Ready(()),
+        };
+        let mut guard = pool.internals.lock().unwrap();
+        let internals = &mut *guard;
+        internals.reaper = Some(cx.waker().clone());
+
+        let now = Instant::now();
+        for (id, deadline) in internals.scheduled.drain(..) {
+            expirations.insert(id, deadline.saturating_duration_since(now));
+        }
+
+        loop {
+            match expirations.poll_expired(cx) {
+                Poll::Ready(Some(Ok(expired))) => {
+                    // the connection may have been checked out in the meantime
+                    let id = expired.into_inner();
+                    if let Some(idx) = internals.idle.iter().position(|idle| idle.id == id) {
+                        internals.idle.remove(idx);
+                        internals.connections -= 1;
+                    }
+                }
+                Poll::Ready(Some(Err(err))) => {
+                    log::error!("Pool reaper timer error: {}", err);
+                    return Poll::Ready(());
+                }
+                Poll::Ready(None) | Poll::Pending => return Poll::Pending,
+            }
+        }
+    })
+    .await
+}
+
+/// Lets the next `Pool::get` start a new reaper once the task exits.
+struct ReaperGuard<M: Manager> {
+    pool: Weak<SharedPool<M>>,
+    arbiter: Weak<()>,
+}
+
+impl<M: Manager> Drop for ReaperGuard<M> {
+    fn drop(&mut self) {
+        if let Some(pool) = self.pool.upgrade() {
+            let mut internals = pool.internals.lock().unwrap();
+            // another reaper may have replaced this one
+            if let Some(ref arbiter) = internals.reaper_arbiter {
+                if arbiter.ptr_eq(&self.arbiter) {
+                    internals.reaper_arbiter = None;
+                    internals.reaper = None;
+                }
+            }
+        }
+    }
+}
+
+/// Idle connection being checked out, closed if dropped before the checkout completes.
+struct CheckOut<'a, M: Manager> {
+    pool: &'a SharedPool<M>,
+    conn: Option<Conn<M::Connection>>,
+}
+
+impl<'a, M: Manager> Drop for CheckOut<'a, M> {
+    fn drop(&mut self) {
+        if let Some(conn) = self.conn.take() {
+            self.pool.close(conn);
+        }
+    }
+}
+
+/// A connection checked out from a [`Pool`](struct.Pool.html).
+///
+/// Dereferences to the connection, and returns it to the pool when dropped.
+pub struct PooledConnection<M: Manager> {
+    pool: Pool<M>,
+    conn: Option<Conn<M::Connection>>,
+}
+
+impl<M: Manager> PooledConnection<M> {
+    /// Takes the connection out of the pool.
+    ///
+    /// The pool opens a new connection in its place when needed.
+    pub fn detach(mut self) -> M::Connection {
+        let conn = self.conn.take().unwrap();
+        self.pool.0.internals.lock().unwrap().connections -= 1;
+        conn.raw
+    }
+}
+
+impl<M: Manager> Deref for PooledConnection<M> {
+    type Target = M::Connection;
+
+    fn deref(&self) -> &M::Connection {
+        &self.conn.as_ref().unwrap().raw
+    }
+}
+
+impl<M: Manager> DerefMut for PooledConnection<M> {
+    fn deref_mut(&mut self) -> &mut M::Connection {
+        &mut self.conn.as_mut().unwrap().raw
+    }
+}
+
+impl<M: Manager> Drop for PooledConnection<M> {
+    fn drop(&mut self) {
+        if let Some(conn) = self.conn.take() {
+            self.pool.0.put_back(conn);
+        }
+        self.pool.0.semaphore.add_permits(1);
+    }
+}
+
+impl<M> fmt::Debug for PooledConnection<M>
+where
+    M: Manager,
+    M::Connection: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+/// Checks out a connection from the `web::Data<Pool<M>>` registered with `App::data()`.
+///
+/// Responds with `503 Service Unavailable` if no connection became available in time,
+/// and with `500 Internal Server Error` if a connection could not be opened.
+impl<M: Manager> FromRequest for PooledConnection<M> {
+    type Config = ();
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let pool = match req.app_data::<Data<Pool<M>>>() {
+            Some(pool) => pool.clone(),
+            None => {
+                log::debug!(
+                    "Failed to construct PooledConnection extractor. \
+                     Request path: {:?}",
+                    req.path()
+                );
+                let err = ErrorInternalServerError(
+                    "Pool is not configured, to configure use App::data()",
+                );
+                return async move { Err(err) }.boxed_local();
+            }
+        };
+
+        async move {
+            pool.get().await.map_err(|err| match err {
+                PoolError::Timeout => ErrorServiceUnavailable(err),
+                PoolError::Backend(err) => ErrorInternalServerError(err.to_string()),
+            })
+        }
+        .boxed_local()
+    }
+}
@@ -0,0 +1,617 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, Weak};
+
+use bytes::BytesMut;
+use futures::{Sink, SinkExt, StreamExt};
+
+use super::codec::PgCodec;
+use super::connect::{unexpected, Socket};
+use super::error::Error;
+use super::message::{DataRow, FieldDescription, Frontend, Message};
+use super::row::{Column, Row};
+use super::types::{IsNull, ToSql, Type};
+use crate::codec::Framed;
+
+/// A connection to a PostgreSQL server.
+///
+/// Requests are executed one at a time, so methods take `&mut self`. If a request
+/// future is dropped before completion, remaining responses are discarded before the
+/// next request is sent.
+pub struct Client {
+    framed: Framed<Box<dyn Socket>, PgCodec>,
+    parameters: HashMap<String, String>,
+    process_id: i32,
+    secret_key: i32,
+    next_statement: u64,
+    /// Names of dropped prepared statements that have to be closed on the server
+    dropped: Arc<Mutex<Vec<String>>>,
+    /// Number of `ReadyForQuery` messages the server still has to send
+    pending_ready: usize,
+    /// A transaction was dropped without being committed or rolled back
+    pending_rollback: bool,
+    transaction_status: u8,
+    closed: bool,
+}
+
+impl Client {
+    pub(crate) fn new(
+        framed: Framed<Box<dyn Socket>, PgCodec>,
+        parameters: HashMap<String, String>,
+        (process_id, secret_key): (i32, i32),
+    ) -> Client {
+        Client {
+            framed,
+            parameters,
+            process_id,
+            secret_key,
+            next_statement: 0,
+            dropped: Arc::new(Mutex::new(Vec::new())),
+            pending_ready: 0,
+            pending_rollback: false,
+            transaction_status: b'I',
+            closed: false,
+        }
+    }
+
+    /// Returns the value of a server parameter, such as `server_version`.
+    pub fn parameter(&self, name: &str) -> Option<&str> {
+        self.parameters.get(name).map(|s| s.as_str())
+    }
+
+    /// Returns the process id of the backend serving this connection.
+    pub fn process_id(&self) -> i32 {
+        self.process_id
+    }
+
+    /// Returns the secret key of the backend, used to cancel requests.
+    pub fn secret_key(&self) -> i32 {
+        self.secret_key
+    }
+
+    /// Returns true if the connection is closed and can not be used anymore.
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    /// Returns true if the connection is inside of a transaction block.
+    pub fn is_in_transaction(&self) -> bool {
+        self.transaction_status != b'I' || self.pending_rollback
+    }
+
+    /// Executes a sequence of SQL statements using the simple query protocol.
+    ///
+    /// Statements are separated by semicolons. Values are returned as strings.
+    pub async fn simple_query(&mut self, query: &str) -> Result<Vec<SimpleQueryMessage>, Error> {
+        self.begin().await?;
+        self.simple_query_inner(query).await
+    }
+
+    /// Executes a sequence of SQL statements using the simple query protocol,
+    /// discarding the results.
+    pub async fn batch_execute(&mut self, query: &str) -> Result<(), Error> {
+        self.simple_query(query).await.map(|_| ())
+    }
+
+    /// Creates a new prepared statement.
+    ///
+    /// Parameters are written as `$1`, `$2`, etc. The statement is closed on the server
+    /// once it has been dropped.
+    pub async fn prepare(&mut self, query: &str) -> Result<Statement, Error> {
+        self.begin().await?;
+        let name = format!("s{}", self.next_statement);
+        self.next_statement += 1;
+        self.prepare_inner(name, query).await
+    }
+
+    /// Executes a statement, returning the resulting rows.
+    ///
+    /// The statement can be either a `Statement` or a query string, which is
+    /// prepared as an unnamed statement.
+    pub async fn query<T>(
+        &mut self,
+        statement: &T,
+        params: &[&dyn ToSql],
+    ) -> Result<Vec<Row>, Error>
+    where
+        T: ?Sized + ToStatement,
+    {
+        self.execute_inner(statement, params)
+            .await
+            .map(|(rows, _)| rows)
+    }
+
+    /// Executes a statement which returns exactly one row.
+    pub async fn query_one<T>(&mut self, statement: &T, params: &[&dyn ToSql]) -> Result<Row, Error>
+    where
+        T: ?Sized + ToStatement,
+    {
+        let mut rows = self.query(statement, params).await?;
+        if rows.len() != 1 {
+            return Err(Error::RowCount);
+        }
+        Ok(rows.pop().unwrap())
+    }
+
+    /// Executes a statement which returns zero or one rows.
+    pub async fn query_opt<T>(
+        &mut self,
+        statement: &T,
+        params: &[&dyn ToSql],
+    ) -> Result<Option<Row>, Error>
+    where
+        T: ?Sized + ToStatement,
+    {
+        let mut rows = self.query(statement, params).await?;
+        if rows.len() > 1 {
+            return Err(Error::RowCount);
+        }
+        Ok(rows.pop())
+    }
+
+    /// Executes a statement, returning the number of rows modified.
+    ///
+    /// Statements which do not report a row count return 0.
+    pub async fn execute<T>(&mut self, statement: &T, params: &[&dyn ToSql]) -> Result<u64, Error>
+    where
+        T: ?Sized + ToStatement,
+    {
+        self.execute_inner(statement, params).await.map(|(_, n)| n)
+    }
+
+    /// Begins a new transaction.
+    ///
+    /// The transaction is rolled back if it is dropped without calling `commit`.
+    pub async fn transaction(&mut self) -> Result<Transaction<'_>, Error> {
+        self.batch_execute("BEGIN").await?;
+        Ok(Transaction {
+            client: self,
+            done: false,
+        })
+    }
+
+    /// Gracefully closes the connection.
+    pub async fn close(mut self) -> Result<(), Error> {
+        if self.closed {
+            return Ok(());
+        }
+        self.closed = true;
+        self.framed.send(Frontend::Terminate).await?;
+        self.framed.close().await
+    }
+
+    async fn simple_query_inner(&mut self, query: &str) -> Result<Vec<SimpleQueryMessage>, Error> {
+        self.send(vec![Frontend::Query(query.to_owned())]).await?;
+
+        let mut result = Vec::new();
+        let mut columns: Option<Arc<[String]>> = None;
+        loop {
+            match self.next_message().await? {
+                Message::RowDescription(fields) => {
+                    columns = Some(fields.into_iter().map(|f| f.name).collect());
+                }
+                Message::DataRow(data) => {
+                    let columns = columns
+                        .clone()
+                        .ok_or_else(|| Error::Protocol("data row without description".into()))?;
+                    if columns.len() != data.len() {
+                        return Err(Error::Protocol("invalid data row length".into()));
+                    }
+                    result.push(SimpleQueryMessage::Row(SimpleQueryRow { columns, data }));
+                }
+                Message::CommandComplete(tag) => {
+                    columns = None;
+                    result.push(SimpleQueryMessage::CommandComplete(rows_affected(&tag)));
+                }
+                Message::EmptyQueryResponse => (),
+                Message::ErrorResponse(err) => return Err(Error::Db(err)),
+                Message::ReadyForQuery(_) => return Ok(result),
+                msg => return Err(unexpected(&msg)),
+            }
+        }
+    }
+
+    async fn prepare_inner(&mut self, name: String, query: &str) -> Result<Statement, Error> {
+        self.send(vec![
+            Frontend::Parse {
+                name: name.clone(),
+                query: query.to_owned(),
+                param_types: Vec::new(),
+            },
+            Frontend::Describe {
+                kind: b'S',
+                name: name.clone(),
+            },
+            Frontend::Sync,
+        ])
+        .await?;
+
+        let mut params = Vec::new();
+        let mut fields = Vec::new();
+        loop {
+            match self.next_message().await? {
+                Message::ParseComplete | Message::NoData => (),
+                Message::ParameterDescription(types) => params = types,
+                Message::RowDescription(f) => fields = f,
+                Message::ErrorResponse(err) => return Err(Error::Db(err)),
+                Message::ReadyForQuery(_) => break,
+                msg => return Err(unexpected(&msg)),
+            }
+        }
+
+        let dropped = if name.is_empty() {
+            Weak::new()
+        } else {
+            Arc::downgrade(&self.dropped)
+        };
+        Ok(Statement(Arc::new(StatementInner {
+            name,
+            params,
+            columns: fields.into_iter().map(column).collect(),
+            dropped,
+        })))
+    }
+
+    async fn execute_inner<T>(
+        &mut self,
+        statement: &T,
+        params: &[&dyn ToSql],
+    ) -> Result<(Vec<Row>, u64), Error>
+    where
+        T: ?Sized + ToStatement,
+    {
+        self.begin().await?;
+        let unnamed;
+        let statement = match statement.__convert() {
+            ToStatementType::Statement(statement) => statement,
+            ToStatementType::Query(query) => {
+                unnamed = self.prepare_inner(String::new(), query).await?;
+                &unnamed
+            }
+        };
+
+        if statement.params().len() != params.len() {
+            return Err(Error::Parameters {
+                expected: statement.params().len(),
+                actual: params.len(),
+            });
+        }
+        let params = params
+            .iter()
+            .map(|param| {
+                let mut buf = BytesMut::new();
+                match param.to_sql(&mut buf) {
+                    IsNull::Yes => None,
+                    IsNull::No => Some(buf.freeze()),
+                }
+            })
+            .collect();
+
+        self.send(vec![
+            Frontend::Bind {
+                portal: String::new(),
+                statement: statement.name().to_owned(),
+                params,
+            },
+            Frontend::Execute {
+                portal: String::new(),
+                max_rows: 0,
+            },
+            Frontend::Sync,
+        ])
+        .await?;
+
+        let mut rows = Vec::new();
+        let mut affected = 0;
+        loop {
+            match self.next_message().await? {
+                Message::BindComplete | Message::EmptyQueryResponse | Message::PortalSuspended => {
+                    ()
+                }
+                Message::DataRow(data) => rows.push(Row::new(statement.0.columns.clone(), data)?),
+                Message::CommandComplete(tag) => affected = rows_affected(&tag),
+                Message::ErrorResponse(err) => return Err(Error::Db(err)),
+                Message::ReadyForQuery(_) => return Ok((rows, affected)),
+                msg => return Err(unexpected(&msg)),
+            }
+        }
+    }
+
+    /// Brings the connection into a clean state before sending a new request.
+    async fn begin(&mut self) -> Result<(), Error> {
+        if self.closed {
+            return Err(Error::Closed);
+        }
+
+        // responses of cancelled requests
+        while self.pending_ready > 0 {
+            self.next_message().await?;
+        }
+
+        if self.pending_rollback {
+            self.pending_rollback = false;
+            self.simple_query_inner("ROLLBACK").await?;
+        }
+
+        let dropped = std::mem::replace(&mut *self.dropped.lock().unwrap(), Vec::new());
+        if !dropped.is_empty() {
+            let mut messages = dropped
+                .into_iter()
+                .map(|name| Frontend::Close { kind: b'S', name })
+                .collect::<Vec<_>>();
+            messages.push(Frontend::Sync);
+            self.send(messages).await?;
+            while self.pending_ready > 0 {
+                self.next_message().await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Queues all messages of a request at once, so that a cancelled request never
+    /// leaves a partial message sequence behind, then flushes them.
+    async fn send(&mut self, messages: Vec<Frontend>) -> Result<(), Error> {
+        for msg in messages {
+            if let Err(err) = Pin::new(&mut self.framed).start_send(msg) {
+                self.closed = true;
+                return Err(err);
+            }
+        }
+        self.pending_ready += 1;
+        if let Err(err) = self.framed.flush().await {
+            self.closed = true;
+            return Err(err);
+        }
+        Ok(())
+    }
+
+    /// Reads the next message relevant to the current request.
+    async fn next_message(&mut self) -> Result<Message, Error> {
+        loop {
+            let msg = match self.framed.next().await {
+                Some(Ok(msg)) => msg,
+                Some(Err(err)) => {
+                    self.closed = true;
+                    return Err(err);
+                }
+                None => {
+                    self.closed = true;
+                    return Err(Error::Closed);
+                }
+            };
+            match msg {
+                Message::ParameterStatus { name, value } => {
+                    self.parameters.insert(name, value);
+                }
+                Message::NoticeResponse(notice) => {
+                    log::info!("{}", notice);
+                }
+                Message::NotificationResponse { .. } | Message::CloseComplete => (),
+                Message::ReadyForQuery(status) => {
+                    self.pending_ready = self.pending_ready.saturating_sub(1);
+                    self.transaction_status = status;
+                    return Ok(Message::ReadyForQuery(status));
+                }
+                msg => return Ok(msg),
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Client {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Client")
+            .field("process_id", &self.process_id)
+            .field("closed", &self.closed)
+            .finish()
+    }
+}
+
+fn column(field: FieldDescription) -> Column {
+    Column {
+        name: field.name,
+        type_: Type::from_oid(field.type_oid),
+    }
+}
+
+/// Extracts the number of affected rows from a command tag, e.g. `INSERT 0 5`.
+fn rows_affected(tag: &str) -> u64 {
+    tag.rsplit(' ')
+        .next()
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(0)
+}
+
+/// A prepared statement.
+///
+/// Prepared statements can only be used with the connection that created them.
+#[derive(Clone)]
+pub struct Statement(Arc<StatementInner>);
+
+struct StatementInner {
+    name: String,
+    params: Vec<Type>,
+    columns: Arc<[Column]>,
+    dropped: Weak<Mutex<Vec<String>>>,
+}
+
+impl Drop for StatementInner {
+    fn drop(&mut self) {
+        if let Some(dropped) = self.dropped.upgrade() {
+            dropped
+                .lock()
+                .unwrap()
+                .push(std::mem::replace(&mut self.name, String::new()));
+        }
+    }
+}
+
+impl Statement {
+    pub(crate) fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    /// Types of the statement parameters.
+    pub fn params(&self) -> &[Type] {
+        &self.0.params
+    }
+
+    /// Columns returned by the statement.
+    pub fn columns(&self) -> &[Column] {
+        &self.0.columns
+    }
+}
+
+impl fmt::Debug for Statement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Statement")
+            .field("name", &self.0.name)
+            .field("params", &self.0.params)
+            .field("columns", &self.0.columns)
+            .finish()
+    }
+}
+
+mod private {
+    use super::Statement;
+
+    pub trait Sealed {}
+
+    pub enum ToStatementType<'a> {
+        Statement(&'a Statement),
+        Query(&'a str),
+    }
+}
+
+use self::private::ToStatementType;
+
+/// A trait abstracting over prepared and unprepared statements.
+///
+/// Implemented for `Statement` and `str`. This cannot be implemented outside of this crate.
+pub trait ToStatement: private::Sealed {
+    #[doc(hidden)]
+    fn __convert(&self) -> ToStatementType<'_>;
+}
+
+impl private::Sealed for Statement {}
+
+impl ToStatement for Statement {
+    fn __convert(&self) -> ToStatementType<'_> {
+        ToStatementType::Statement(self)
+    }
+}
+
+impl private::Sealed for str {}
+
+impl ToStatement for str {
+    fn __convert(&self) -> ToStatementType<'_> {
+        ToStatementType::Query(self)
+    }
+}
+
+impl private::Sealed for String {}
+
+impl ToStatement for String {
+    fn __convert(&self) -> ToStatementType<'_> {
+        ToStatementType::Query(self)
+    }
+}
+
+/// Result of a simple query.
+#[derive(Debug)]
+pub enum SimpleQueryMessage {
+    /// A row of data.
+    Row(SimpleQueryRow),
+    /// A statement completed, with the number of rows it affected.
+    CommandComplete(u64),
+}
+
+/// A row returned by the simple query protocol, with values in text format.
+#[derive(Debug)]
+pub struct SimpleQueryRow {
+    columns: Arc<[String]>,
+    data: DataRow,
+}
+
+impl SimpleQueryRow {
+    /// Names of the columns.
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    /// Number of values in the row.
+    pub fn len(&self) -> usize {
+        self.columns.len()
+    }
+
+    /// Returns true if the row has no values.
+    pub fn is_empty(&self) -> bool {
+        self.columns.is_empty()
+    }
+
+    /// Returns a value by index, `None` for `NULL`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is out of bounds or the value is not valid utf-8.
+    pub fn get(&self, idx: usize) -> Option<&str> {
+        self.data
+            .get(idx)
+            .map(|raw| std::str::from_utf8(raw).expect("invalid utf-8 value"))
+    }
+}
+
+/// A transaction.
+///
+/// Dereferences to the `Client`, so queries can be executed on it directly.
+/// The transaction is rolled back when dropped without calling `commit`.
+pub struct Transaction<'a> {
+    client: &'a mut Client,
+    done: bool,
+}
+
+impl<'a> Transaction<'a> {
+    /// Commits the transaction.
+    pub async fn commit(mut self) -> Result<(), Error> {
+        self.done = true;
+        self.client.batch_execute("COMMIT").await
+    }
+
+    /// Rolls back the transaction.
+    pub async fn rollback(mut self) -> Result<(), Error> {
+        self.done = true;
+        self.client.batch_execute("ROLLBACK").await
+    }
+}
+
+impl<'a> Deref for Transaction<'a> {
+    type Target = Client;
+
+    fn deref(&self) -> &Client {
+        self.client
+    }
+}
+
+impl<'a> DerefMut for Transaction<'a> {
+    fn deref_mut(&mut self) -> &mut Client {
+        self.client
+    }
+}
+
+impl<'a> Drop for Transaction<'a> {
+    fn drop(&mut self) {
+        if !self.done {
+            self.client.pending_rollback = true;
+        }
+    }
+}
+
+impl<'a> fmt::Debug for Transaction<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Transaction")
+            .field("client", &self.client)
+            .finish()
+    }
+}
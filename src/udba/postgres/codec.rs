@@ -0,0 +1,107 @@
+use bytes::{Buf, BytesMut};
+
+use super::error::Error;
+use super::message::{Frontend, Message};
+use crate::codec::{Decoder, Encoder};
+
+/// Maximum size of a single backend message.
+const MAX_MESSAGE_SIZE: usize = 1 << 30;
+
+/// PostgreSQL frontend/backend protocol codec.
+///
+/// Encodes frontend messages and decodes backend messages.
+#[derive(Debug, Default)]
+pub struct PgCodec;
+
+impl Decoder for PgCodec {
+    type Item = Message;
+    type Error = Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Message>, Error> {
+        if src.len() < 5 {
+            return Ok(None);
+        }
+
+        let tag = src[0];
+        let len = i32::from_be_bytes([src[1], src[2], src[3], src[4]]);
+        if len < 4 || len as usize > MAX_MESSAGE_SIZE {
+            return Err(Error::Protocol(format!("invalid message length: {}", len)));
+        }
+
+        let total = 1 + len as usize;
+        if src.len() < total {
+            src.reserve(total - src.len());
+            return Ok(None);
+        }
+
+        let mut frame = src.split_to(total);
+        frame.advance(5);
+        Message::parse(tag, frame.freeze()).map(Some)
+    }
+}
+
+impl Encoder for PgCodec {
+    type Item = Frontend;
+    type Error = Error;
+
+    fn encode(&mut self, item: Frontend, dst: &mut BytesMut) -> Result<(), Error> {
+        item.encode(dst);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        let mut codec = PgCodec;
+        let mut buf = BytesMut::from(&b"Z\0\0\0\x05IC\0\0\0\x0dSELECT 1\0"[..]);
+
+        match codec.decode(&mut buf).unwrap() {
+            Some(Message::ReadyForQuery(b'I')) => (),
+            msg => panic!("unexpected {:?}", msg),
+        }
+        // partial message
+        let rest = buf.split_off(6);
+        assert!(codec.decode(&mut buf).unwrap().is_none());
+        buf.extend_from_slice(&rest);
+        match codec.decode(&mut buf).unwrap() {
+            Some(Message::CommandComplete(tag)) => assert_eq!(tag, "SELECT 1"),
+            msg => panic!("unexpected {:?}", msg),
+        }
+        assert!(buf.is_empty());
+
+        let mut buf = BytesMut::from(&b"Z\0\0\0\x02"[..]);
+        assert!(codec.decode(&mut buf).is_err());
+    }
+
+    #[test]
+    fn test_data_row() {
+        let mut codec = PgCodec;
+        let mut buf = BytesMut::from(&b"D\0\0\0\x11\0\x02\0\0\0\x03abc\xff\xff\xff\xff"[..]);
+        match codec.decode(&mut buf).unwrap() {
+            Some(Message::DataRow(row)) => {
+                assert_eq!(row.len(), 2);
+                assert_eq!(row.get(0), Some(&b"abc"[..]));
+                assert_eq!(row.get(1), None);
+            }
+            msg => panic!("unexpected {:?}", msg),
+        }
+    }
+
+    #[test]
+    fn test_encode() {
+        let mut codec = PgCodec;
+        let mut buf = BytesMut::new();
+        codec
+            .encode(Frontend::Query("SELECT 1".into()), &mut buf)
+            .unwrap();
+        assert_eq!(&buf[..], &b"Q\0\0\0\x0dSELECT 1\0"[..]);
+
+        buf.clear();
+        codec.encode(Frontend::Sync, &mut buf).unwrap();
+        assert_eq!(&buf[..], &b"S\0\0\0\x04"[..]);
+    }
+}
@@ -0,0 +1,254 @@
+use std::collections::HashMap;
+use std::io;
+use std::sync::Arc;
+
+use bytes::{Bytes, BytesMut};
+use futures::{SinkExt, StreamExt};
+
+use super::client::Client;
+use super::codec::PgCodec;
+use super::config::{Config, SslMode};
+use super::error::Error;
+use super::message::{Frontend, Message};
+use super::scram::{ScramSha256, SCRAM_SHA_256};
+#[cfg(feature = "postgres-tls")]
+use super::verifier;
+use crate::codec::Framed;
+use crate::krse::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use crate::krse::net::TcpStream;
+use crate::secure::tls::rust_tls::ClientConfig;
+use crate::secure::tls::webpki::DNSNameRef;
+use crate::secure::TlsConnector;
+
+/// Transport of a connection, either plain TCP or TLS.
+pub(crate) trait Socket: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Socket for T {}
+
+pub(crate) async fn connect(config: &Config) -> Result<Client, Error> {
+    match config.connect_timeout {
+        Some(timeout) => crate::timer::timeout(timeout, connect_inner(config))
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "connect timed out"))?,
+        None => connect_inner(config).await,
+    }
+}
+
+async fn connect_inner(config: &Config) -> Result<Client, Error> {
+    // checked first, a mode that can not be used fails without connecting
+    let tls_config = tls_config(config)?;
+    let stream = TcpStream::connect((config.host.as_str(), config.port)).await?;
+    stream.set_nodelay(true)?;
+
+    let io = match tls_config {
+        Some(tls_config) => negotiate_tls(stream, config, tls_config).await?,
+        None => Box::new(stream),
+    };
+    startup(io, config).await
+}
+
+async fn negotiate_tls(
+    mut stream: TcpStream,
+    config: &Config,
+    tls_config: Arc<ClientConfig>,
+) -> Result<Box<dyn Socket>, Error> {
+    let mut buf = BytesMut::new();
+    Frontend::SslRequest.encode(&mut buf);
+    stream.write_all(&buf).await?;
+
+    let mut reply = [0u8];
+    stream.read_exact(&mut reply).await?;
+    match reply[0] {
+        b'S' => (),
+        b'N' if config.ssl_mode == SslMode::Prefer => return Ok(Box::new(stream)),
+        b'N' => return Err(Error::Tls("server does not support TLS".into())),
+        _ => return Err(Error::Protocol("invalid response to SSL request".into())),
+    }
+
+    // `tls_config` only allows IP hosts if the name is not verified
+    let domain = DNSNameRef::try_from_ascii_str(&config.host)
+        .unwrap_or_else(|_| DNSNameRef::try_from_ascii_str("localhost").unwrap());
+    let stream = TlsConnector::from(tls_config)
+        .connect(domain, stream)
+        .await
+        .map_err(|e| Error::Tls(e.to_string()))?;
+    Ok(Box::new(stream))
+}
+
+/// The TLS configuration of the connection, `None` for `SslMode::Disable`.
+fn tls_config(config: &Config) -> Result<Option<Arc<ClientConfig>>, Error> {
+    if config.ssl_mode == SslMode::Disable {
+        return Ok(None);
+    }
+
+    // IP addresses are not DNS names. Unless the host name is verified the
+    // name is only used for SNI, which is not sent for them.
+    let is_dns_name = DNSNameRef::try_from_ascii_str(&config.host).is_ok();
+    match config.tls_config {
+        // the verifier of the caller is kept, it may check the host name
+        Some(_) if !is_dns_name => Err(Error::Tls(format!(
+            "a custom TLS configuration requires a DNS name, got {}",
+            config.host
+        ))),
+        Some(ref tls_config) => Ok(Some(tls_config.clone())),
+        None => default_tls_config(config, is_dns_name).map(Some),
+    }
+}
+
+/// The TLS configuration for `config.ssl_mode` if the caller did not set one.
+fn default_tls_config(config: &Config, is_dns_name: bool) -> Result<Arc<ClientConfig>, Error> {
+    let mut tls_config = ClientConfig::new();
+    tls_config
+        .root_store
+        .add_server_trust_anchors(&crate::secure::tls::TLS_SERVER_ROOTS);
+    match config.ssl_mode {
+        SslMode::VerifyFull if !is_dns_name => Err(Error::Tls(format!(
+            "verify-full requires a DNS name, got {}",
+            config.host
+        ))),
+        SslMode::VerifyFull => Ok(Arc::new(tls_config)),
+        #[cfg(feature = "postgres-tls")]
+        mode => {
+            use crate::secure::tls::rust_tls::ServerCertVerifier;
+
+            let verifier: Arc<dyn ServerCertVerifier> = match mode {
+                SslMode::VerifyCa => Arc::new(verifier::VerifyCa),
+                _ => Arc::new(verifier::NoVerification),
+            };
+            tls_config.dangerous().set_certificate_verifier(verifier);
+            tls_config.enable_sni = is_dns_name;
+            Ok(Arc::new(tls_config))
+        }
+        #[cfg(not(feature = "postgres-tls"))]
+        mode => Err(Error::Tls(format!(
+            "{:?} requires the `postgres-tls` feature or a custom TLS configuration",
+            mode
+        ))),
+    }
+}
+
+/// Runs the startup and authentication flow on an established transport.
+pub(crate) async fn startup(io: Box<dyn Socket>, config: &Config) -> Result<Client, Error> {
+    let user = config
+        .user
+        .as_ref()
+        .ok_or_else(|| Error::Config("user is missing".into()))?;
+
+    let mut params = vec![
+        ("user".to_owned(), user.clone()),
+        ("client_encoding".to_owned(), "UTF8".to_owned()),
+    ];
+    if let Some(ref dbname) = config.dbname {
+        params.push(("database".to_owned(), dbname.clone()));
+    }
+    if let Some(ref name) = config.application_name {
+        params.push(("application_name".to_owned(), name.clone()));
+    }
+
+    let mut framed = Framed::new(io, PgCodec);
+    framed.send(Frontend::Startup { params }).await?;
+    authenticate(&mut framed, user, config).await?;
+
+    let mut parameters = HashMap::new();
+    let mut key_data = (0, 0);
+    loop {
+        match next(&mut framed).await? {
+            Message::ReadyForQuery(_) => break,
+            Message::BackendKeyData {
+                process_id,
+                secret_key,
+            } => key_data = (process_id, secret_key),
+            Message::ParameterStatus { name, value } => {
+                parameters.insert(name, value);
+            }
+            Message::NoticeResponse(_) => (),
+            Message::ErrorResponse(err) => return Err(Error::Db(err)),
+            msg => return Err(unexpected(&msg)),
+        }
+    }
+
+    Ok(Client::new(framed, parameters, key_data))
+}
+
+async fn authenticate(
+    framed: &mut Framed<Box<dyn Socket>, PgCodec>,
+    user: &str,
+    config: &Config,
+) -> Result<(), Error> {
+    let password = || {
+        config
+            .password
+            .as_ref()
+            .ok_or_else(|| Error::Config("password is missing".into()))
+    };
+    let mut scram = None;
+
+    loop {
+        match next(framed).await? {
+            Message::AuthenticationOk => return Ok(()),
+            Message::AuthenticationCleartextPassword => {
+                let mut data = password()?.clone();
+                data.push(0);
+                framed.send(Frontend::Password(data.into())).await?;
+            }
+            Message::AuthenticationMd5Password(salt) => {
+                let password = password()?;
+                let mut inner = md5::Context::new();
+                inner.consume(password);
+                inner.consume(user.as_bytes());
+                let mut outer = md5::Context::new();
+                outer.consume(format!("{:x}", inner.compute()));
+                outer.consume(salt);
+                let data = format!("md5{:x}\0", outer.compute());
+                framed.send(Frontend::Password(data.into())).await?;
+            }
+            Message::AuthenticationSasl(mechanisms) => {
+                if !mechanisms.iter().any(|m| m == SCRAM_SHA_256) {
+                    return Err(Error::Authentication(format!(
+                        "unsupported SASL mechanisms: {:?}",
+                        mechanisms
+                    )));
+                }
+                let state = ScramSha256::new(password()?)?;
+                framed
+                    .send(Frontend::SaslInitialResponse {
+                        mechanism: SCRAM_SHA_256.to_owned(),
+                        data: Bytes::copy_from_slice(state.message()),
+                    })
+                    .await?;
+                scram = Some(state);
+            }
+            Message::AuthenticationSaslContinue(data) => {
+                let state = scram
+                    .as_mut()
+                    .ok_or_else(|| Error::Protocol("unexpected SASL message".into()))?;
+                state.update(&data)?;
+                framed
+                    .send(Frontend::SaslResponse(Bytes::copy_from_slice(
+                        state.message(),
+                    )))
+                    .await?;
+            }
+            Message::AuthenticationSaslFinal(data) => {
+                scram
+                    .as_mut()
+                    .ok_or_else(|| Error::Protocol("unexpected SASL message".into()))?
+                    .finish(&data)?;
+            }
+            Message::NoticeResponse(_) => (),
+            Message::ErrorResponse(err) => return Err(Error::Db(err)),
+            msg => return Err(unexpected(&msg)),
+        }
+    }
+}
+
+async fn next(framed: &mut Framed<Box<dyn Socket>, PgCodec>) -> Result<Message, Error> {
+    match framed.next().await {
+        Some(msg) => msg,
+        None => Err(Error::Closed),
+    }
+}
+
+pub(crate) fn unexpected(msg: &Message) -> Error {
+    Error::Protocol(format!("unexpected message: {:?}", msg))
+}
@@ -0,0 +1,143 @@
+use std::{error, fmt, io};
+
+use derive_more::{Display, From};
+
+/// Errors that can happen while talking to a PostgreSQL server.
+#[derive(Debug, Display, From)]
+pub enum Error {
+    /// Connection io error
+    #[display(fmt = "{}", _0)]
+    Io(io::Error),
+
+    /// Error reported by the server
+    #[display(fmt = "{}", _0)]
+    Db(DbError),
+
+    /// Invalid connection configuration
+    #[display(fmt = "Invalid configuration: {}", _0)]
+    #[from(ignore)]
+    Config(String),
+
+    /// Authentication failed on the client side
+    #[display(fmt = "Authentication error: {}", _0)]
+    #[from(ignore)]
+    Authentication(String),
+
+    /// TLS negotiation failed
+    #[display(fmt = "TLS error: {}", _0)]
+    #[from(ignore)]
+    Tls(String),
+
+    /// The server sent an unexpected or malformed message
+    #[display(fmt = "Protocol error: {}", _0)]
+    #[from(ignore)]
+    Protocol(String),
+
+    /// Wrong number of parameters for a statement
+    #[display(fmt = "Expected {} parameters but got {}", expected, actual)]
+    Parameters {
+        /// Number of parameters of the statement
+        expected: usize,
+        /// Number of parameters provided
+        actual: usize,
+    },
+
+    /// Column does not exist in the row
+    #[display(fmt = "Column not found: {}", _0)]
+    #[from(ignore)]
+    ColumnNotFound(String),
+
+    /// Column value can not be converted to the requested Rust type
+    #[display(fmt = "Error converting column {}: {}", column, error)]
+    Conversion {
+        /// Name of the column
+        column: String,
+        /// Conversion error
+        error: Box<dyn error::Error + Send + Sync>,
+    },
+
+    /// Query was expected to return exactly one row
+    #[display(fmt = "Query returned an unexpected number of rows")]
+    RowCount,
+
+    /// Connection is closed
+    #[display(fmt = "Connection closed")]
+    Closed,
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            Error::Db(err) => Some(err),
+            Error::Conversion { error, .. } => Some(&**error),
+            _ => None,
+        }
+    }
+}
+
+impl Error {
+    /// Returns the database error reported by the server, if any.
+    pub fn as_db_error(&self) -> Option<&DbError> {
+        match self {
+            Error::Db(err) => Some(err),
+            _ => None,
+        }
+    }
+
+    /// Returns the SQLSTATE code reported by the server, if any.
+    pub fn code(&self) -> Option<&str> {
+        self.as_db_error().map(|err| err.code())
+    }
+}
+
+/// Error or notice reported by the server.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DbError {
+    pub(crate) severity: String,
+    pub(crate) code: String,
+    pub(crate) message: String,
+    pub(crate) detail: Option<String>,
+    pub(crate) hint: Option<String>,
+    pub(crate) position: Option<u32>,
+}
+
+impl DbError {
+    /// Severity, e.g. `ERROR`, `FATAL` or `NOTICE`.
+    pub fn severity(&self) -> &str {
+        &self.severity
+    }
+
+    /// SQLSTATE code of the error, e.g. `23505` for unique violations.
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    /// Primary human-readable error message.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Optional secondary message with more detail.
+    pub fn detail(&self) -> Option<&str> {
+        self.detail.as_ref().map(|s| s.as_str())
+    }
+
+    /// Optional suggestion on what to do about the problem.
+    pub fn hint(&self) -> Option<&str> {
+        self.hint.as_ref().map(|s| s.as_str())
+    }
+
+    /// Position of the error in the query string, counted in characters from 1.
+    pub fn position(&self) -> Option<u32> {
+        self.position
+    }
+}
+
+impl fmt::Display for DbError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} ({})", self.severity, self.message, self.code)
+    }
+}
+
+impl error::Error for DbError {}
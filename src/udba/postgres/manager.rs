@@ -0,0 +1,43 @@
+use futures_util::future::{FutureExt, LocalBoxFuture};
+
+use super::client::Client;
+use super::config::Config;
+use super::error::Error;
+use crate::udba::pool::Manager;
+
+/// [`Manager`](../pool/trait.Manager.html) for PostgreSQL connections.
+///
+/// Connections are checked with an empty simple query on checkout.
+#[derive(Debug, Clone)]
+pub struct PostgresConnectionManager {
+    config: Config,
+}
+
+impl PostgresConnectionManager {
+    /// Creates a manager opening connections with the given configuration.
+    pub fn new(config: Config) -> Self {
+        PostgresConnectionManager { config }
+    }
+
+    /// Returns the connection configuration.
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+}
+
+impl Manager for PostgresConnectionManager {
+    type Connection = Client;
+    type Error = Error;
+
+    fn connect(&self) -> LocalBoxFuture<'_, Result<Client, Error>> {
+        self.config.connect().boxed_local()
+    }
+
+    fn check<'a>(&'a self, conn: &'a mut Client) -> LocalBoxFuture<'a, Result<(), Error>> {
+        conn.batch_execute("").boxed_local()
+    }
+
+    fn has_broken(&self, conn: &mut Client) -> bool {
+        conn.is_closed()
+    }
+}
@@ -0,0 +1,377 @@
+//! Frontend and backend protocol messages.
+use std::ops::Range;
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+
+use super::error::{DbError, Error};
+use super::types::Type;
+
+/// Protocol version 3.0
+const PROTOCOL_VERSION: i32 = 196_608;
+/// `SSLRequest` code
+const SSL_REQUEST_CODE: i32 = 80_877_103;
+
+/// Message sent by the client.
+#[derive(Debug, Clone)]
+pub enum Frontend {
+    SslRequest,
+    Startup {
+        params: Vec<(String, String)>,
+    },
+    Password(Bytes),
+    SaslInitialResponse {
+        mechanism: String,
+        data: Bytes,
+    },
+    SaslResponse(Bytes),
+    Query(String),
+    Parse {
+        name: String,
+        query: String,
+        param_types: Vec<Type>,
+    },
+    Bind {
+        portal: String,
+        statement: String,
+        params: Vec<Option<Bytes>>,
+    },
+    Describe {
+        kind: u8,
+        name: String,
+    },
+    Execute {
+        portal: String,
+        max_rows: i32,
+    },
+    Close {
+        kind: u8,
+        name: String,
+    },
+    Sync,
+    Flush,
+    Terminate,
+}
+
+impl Frontend {
+    pub(crate) fn encode(&self, dst: &mut BytesMut) {
+        match self {
+            Frontend::SslRequest => untagged(dst, |buf| buf.put_i32(SSL_REQUEST_CODE)),
+            Frontend::Startup { params } => untagged(dst, |buf| {
+                buf.put_i32(PROTOCOL_VERSION);
+                for (key, value) in params {
+                    put_cstr(buf, key);
+                    put_cstr(buf, value);
+                }
+                buf.put_u8(0);
+            }),
+            Frontend::Password(data) | Frontend::SaslResponse(data) => {
+                tagged(dst, b'p', |buf| buf.put_slice(data))
+            }
+            Frontend::SaslInitialResponse { mechanism, data } => tagged(dst, b'p', |buf| {
+                put_cstr(buf, mechanism);
+                buf.put_i32(data.len() as i32);
+                buf.put_slice(data);
+            }),
+            Frontend::Query(query) => tagged(dst, b'Q', |buf| put_cstr(buf, query)),
+            Frontend::Parse {
+                name,
+                query,
+                param_types,
+            } => tagged(dst, b'P', |buf| {
+                put_cstr(buf, name);
+                put_cstr(buf, query);
+                buf.put_i16(param_types.len() as i16);
+                for ty in param_types {
+                    buf.put_u32(ty.oid());
+                }
+            }),
+            Frontend::Bind {
+                portal,
+                statement,
+                params,
+            } => tagged(dst, b'B', |buf| {
+                put_cstr(buf, portal);
+                put_cstr(buf, statement);
+                // all parameters and results use the text format
+                buf.put_i16(0);
+                buf.put_i16(params.len() as i16);
+                for param in params {
+                    match param {
+                        Some(value) => {
+                            buf.put_i32(value.len() as i32);
+                            buf.put_slice(value);
+                        }
+                        None => buf.put_i32(-1),
+                    }
+                }
+                buf.put_i16(0);
+            }),
+            Frontend::Describe { kind, name } => tagged(dst, b'D', |buf| {
+                buf.put_u8(*kind);
+                put_cstr(buf, name);
+            }),
+            Frontend::Execute { portal, max_rows } => tagged(dst, b'E', |buf| {
+                put_cstr(buf, portal);
+                buf.put_i32(*max_rows);
+            }),
+            Frontend::Close { kind, name } => tagged(dst, b'C', |buf| {
+                buf.put_u8(*kind);
+                put_cstr(buf, name);
+            }),
+            Frontend::Sync => tagged(dst, b'S', |_| ()),
+            Frontend::Flush => tagged(dst, b'H', |_| ()),
+            Frontend::Terminate => tagged(dst, b'X', |_| ()),
+        }
+    }
+}
+
+fn put_cstr(buf: &mut BytesMut, s: &str) {
+    buf.put_slice(s.as_bytes());
+    buf.put_u8(0);
+}
+
+fn untagged<F: FnOnce(&mut BytesMut)>(dst: &mut BytesMut, f: F) {
+    let start = dst.len();
+    dst.put_i32(0);
+    f(dst);
+    let len = (dst.len() - start) as i32;
+    dst[start..start + 4].copy_from_slice(&len.to_be_bytes());
+}
+
+fn tagged<F: FnOnce(&mut BytesMut)>(dst: &mut BytesMut, tag: u8, f: F) {
+    dst.put_u8(tag);
+    untagged(dst, f);
+}
+
+/// Description of a result column.
+#[derive(Debug, Clone)]
+pub struct FieldDescription {
+    pub name: String,
+    pub type_oid: u32,
+}
+
+/// Message sent by the server.
+#[derive(Debug)]
+pub enum Message {
+    AuthenticationOk,
+    AuthenticationCleartextPassword,
+    AuthenticationMd5Password([u8; 4]),
+    AuthenticationSasl(Vec<String>),
+    AuthenticationSaslContinue(Bytes),
+    AuthenticationSaslFinal(Bytes),
+    BackendKeyData {
+        process_id: i32,
+        secret_key: i32,
+    },
+    ParameterStatus {
+        name: String,
+        value: String,
+    },
+    ReadyForQuery(u8),
+    RowDescription(Vec<FieldDescription>),
+    DataRow(DataRow),
+    CommandComplete(String),
+    EmptyQueryResponse,
+    ErrorResponse(DbError),
+    NoticeResponse(DbError),
+    ParseComplete,
+    BindComplete,
+    CloseComplete,
+    NoData,
+    ParameterDescription(Vec<Type>),
+    PortalSuspended,
+    NotificationResponse {
+        process_id: i32,
+        channel: String,
+        payload: String,
+    },
+}
+
+/// Raw values of a data row, `None` for `NULL`.
+#[derive(Debug, Clone)]
+pub struct DataRow {
+    buf: Bytes,
+    ranges: Vec<Option<Range<usize>>>,
+}
+
+impl DataRow {
+    pub fn len(&self) -> usize {
+        self.ranges.len()
+    }
+
+    pub fn get(&self, idx: usize) -> Option<&[u8]> {
+        self.ranges[idx].as_ref().map(|r| &self.buf[r.clone()])
+    }
+}
+
+impl Message {
+    /// Parses the body of a message with the given tag.
+    pub(crate) fn parse(tag: u8, mut body: Bytes) -> Result<Message, Error> {
+        let msg = match tag {
+            b'R' => match get_i32(&mut body)? {
+                0 => Message::AuthenticationOk,
+                3 => Message::AuthenticationCleartextPassword,
+                5 => {
+                    let mut salt = [0; 4];
+                    if body.remaining() < 4 {
+                        return Err(eof());
+                    }
+                    body.copy_to_slice(&mut salt);
+                    Message::AuthenticationMd5Password(salt)
+                }
+                10 => {
+                    let mut mechanisms = Vec::new();
+                    loop {
+                        let mechanism = get_cstr(&mut body)?;
+                        if mechanism.is_empty() {
+                            break;
+                        }
+                        mechanisms.push(mechanism);
+                    }
+                    Message::AuthenticationSasl(mechanisms)
+                }
+                11 => Message::AuthenticationSaslContinue(body),
+                12 => Message::AuthenticationSaslFinal(body),
+                code => {
+                    return Err(Error::Authentication(format!(
+                        "unsupported authentication method: {}",
+                        code
+                    )))
+                }
+            },
+            b'K' => Message::BackendKeyData {
+                process_id: get_i32(&mut body)?,
+                secret_key: get_i32(&mut body)?,
+            },
+            b'S' => Message::ParameterStatus {
+                name: get_cstr(&mut body)?,
+                value: get_cstr(&mut body)?,
+            },
+            b'Z' => Message::ReadyForQuery(get_u8(&mut body)?),
+            b'T' => {
+                let count = get_i16(&mut body)?;
+                let mut fields = Vec::with_capacity(count.max(0) as usize);
+                for _ in 0..count {
+                    let name = get_cstr(&mut body)?;
+                    if body.remaining() < 18 {
+                        return Err(eof());
+                    }
+                    // table oid, column number
+                    body.advance(6);
+                    let type_oid = body.get_u32();
+                    // type size, type modifier, format code
+                    body.advance(8);
+                    fields.push(FieldDescription { name, type_oid });
+                }
+                Message::RowDescription(fields)
+            }
+            b'D' => {
+                let count = get_i16(&mut body)?;
+                let buf = body.clone();
+                let mut ranges = Vec::with_capacity(count.max(0) as usize);
+                for _ in 0..count {
+                    let len = get_i32(&mut body)?;
+                    if len < 0 {
+                        ranges.push(None);
+                    } else {
+                        let len = len as usize;
+                        if body.remaining() < len {
+                            return Err(eof());
+                        }
+                        let start = buf.len() - body.remaining();
+                        ranges.push(Some(start..start + len));
+                        body.advance(len);
+                    }
+                }
+                Message::DataRow(DataRow { buf, ranges })
+            }
+            b'C' => Message::CommandComplete(get_cstr(&mut body)?),
+            b'I' => Message::EmptyQueryResponse,
+            b'E' => Message::ErrorResponse(parse_fields(&mut body)?),
+            b'N' => Message::NoticeResponse(parse_fields(&mut body)?),
+            b'1' => Message::ParseComplete,
+            b'2' => Message::BindComplete,
+            b'3' => Message::CloseComplete,
+            b'n' => Message::NoData,
+            b't' => {
+                let count = get_i16(&mut body)?;
+                let mut types = Vec::with_capacity(count.max(0) as usize);
+                for _ in 0..count {
+                    types.push(Type::from_oid(get_i32(&mut body)? as u32));
+                }
+                Message::ParameterDescription(types)
+            }
+            b's' => Message::PortalSuspended,
+            b'A' => Message::NotificationResponse {
+                process_id: get_i32(&mut body)?,
+                channel: get_cstr(&mut body)?,
+                payload: get_cstr(&mut body)?,
+            },
+            tag => {
+                return Err(Error::Protocol(format!(
+                    "unexpected message tag: {:?}",
+                    tag as char
+                )))
+            }
+        };
+        Ok(msg)
+    }
+}
+
+fn parse_fields(body: &mut Bytes) -> Result<DbError, Error> {
+    let mut err = DbError::default();
+    loop {
+        let field = get_u8(body)?;
+        if field == 0 {
+            break;
+        }
+        let value = get_cstr(body)?;
+        match field {
+            b'V' => err.severity = value,
+            b'S' if err.severity.is_empty() => err.severity = value,
+            b'C' => err.code = value,
+            b'M' => err.message = value,
+            b'D' => err.detail = Some(value),
+            b'H' => err.hint = Some(value),
+            b'P' => err.position = value.parse().ok(),
+            _ => (),
+        }
+    }
+    Ok(err)
+}
+
+fn eof() -> Error {
+    Error::Protocol("unexpected end of message".into())
+}
+
+fn get_u8(buf: &mut Bytes) -> Result<u8, Error> {
+    if buf.remaining() < 1 {
+        return Err(eof());
+    }
+    Ok(buf.get_u8())
+}
+
+fn get_i16(buf: &mut Bytes) -> Result<i16, Error> {
+    if buf.remaining() < 2 {
+        return Err(eof());
+    }
+    Ok(buf.get_i16())
+}
+
+fn get_i32(buf: &mut Bytes) -> Result<i32, Error> {
+    if buf.remaining() < 4 {
+        return Err(eof());
+    }
+    Ok(buf.get_i32())
+}
+
+fn get_cstr(buf: &mut Bytes) -> Result<String, Error> {
+    let end = buf
+        .iter()
+        .position(|b| *b == 0)
+        .ok_or_else(|| Error::Protocol("missing string terminator".into()))?;
+    let s = String::from_utf8(buf[..end].to_vec())
+        .map_err(|_| Error::Protocol("invalid utf-8 string".into()))?;
+    buf.advance(end + 1);
+    Ok(s)
+}
@@ -0,0 +1,59 @@
+//! Asynchronous PostgreSQL client
+//!
+//! Implements the PostgreSQL frontend/backend protocol (version 3) on top of
+//! `krse::net::TcpStream` and `codec::Framed`, with optional TLS.
+//!
+//! Queries are sent with the simple query protocol
+//! ([`Client::simple_query`](struct.Client.html#method.simple_query)) or with the extended
+//! query protocol ([`Client::query`](struct.Client.html#method.query)), which supports
+//! parameters and prepared statements. Values are exchanged in text format and converted
+//! with the [`ToSql`](trait.ToSql.html) and [`FromSql`](trait.FromSql.html) traits.
+//!
+//! ```rust,no_run
+//! use kayrx::udba::postgres::{self, Error};
+//!
+//! #[kayrx::main]
+//! async fn main() -> Result<(), Error> {
+//!     let mut client = postgres::connect("postgres://postgres@localhost/postgres").await?;
+//!
+//!     let stmt = client.prepare("SELECT $1::TEXT, $2::INT8").await?;
+//!     let row = client.query_one(&stmt, &[&"hello", &42i64]).await?;
+//!     let (text, number): (String, i64) = (row.get(0), row.get(1));
+//!     assert_eq!((text.as_str(), number), ("hello", 42));
+//!
+//!     let mut tx = client.transaction().await?;
+//!     tx.execute("INSERT INTO log (msg) VALUES ($1)", &[&"hi"]).await?;
+//!     tx.commit().await?;
+//!     Ok(())
+//! }
+//! ```
+
+mod client;
+mod codec;
+mod config;
+mod connect;
+mod error;
+mod manager;
+mod message;
+mod row;
+mod scram;
+mod types;
+#[cfg(feature = "postgres-tls")]
+mod verifier;
+
+pub use self::client::{
+    Client, SimpleQueryMessage, SimpleQueryRow, Statement, ToStatement, Transaction,
+};
+pub use self::codec::PgCodec;
+pub use self::config::{Config, SslMode};
+pub use self::error::{DbError, Error};
+pub use self::manager::PostgresConnectionManager;
+pub use self::row::{Column, Row, RowIndex};
+pub use self::types::{FromSql, IsNull, ToSql, Type};
+
+/// Connects to a server described by a connection URL.
+///
+/// See [`Config`](struct.Config.html) for the accepted format.
+pub async fn connect(url: &str) -> Result<Client, Error> {
+    url.parse::<Config>()?.connect().await
+}
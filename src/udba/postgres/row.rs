@@ -0,0 +1,157 @@
+use std::fmt;
+use std::sync::Arc;
+
+use super::error::Error;
+use super::message::DataRow;
+use super::types::{FromSql, Type};
+
+/// Information about a result column.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Column {
+    pub(crate) name: String,
+    pub(crate) type_: Type,
+}
+
+impl Column {
+    /// Name of the column.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Type of the column.
+    pub fn type_(&self) -> &Type {
+        &self.type_
+    }
+}
+
+/// A row of data returned by the server.
+pub struct Row {
+    columns: Arc<[Column]>,
+    data: DataRow,
+}
+
+impl Row {
+    pub(crate) fn new(columns: Arc<[Column]>, data: DataRow) -> Result<Row, Error> {
+        if columns.len() != data.len() {
+            return Err(Error::Protocol(format!(
+                "expected {} values in data row, got {}",
+                columns.len(),
+                data.len()
+            )));
+        }
+        Ok(Row { columns, data })
+    }
+
+    /// Columns of the row.
+    pub fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    /// Number of values in the row.
+    pub fn len(&self) -> usize {
+        self.columns.len()
+    }
+
+    /// Returns true if the row has no values.
+    pub fn is_empty(&self) -> bool {
+        self.columns.is_empty()
+    }
+
+    /// Deserializes a value from the row.
+    ///
+    /// The value can be specified either by its numeric index or by its column name.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is out of bounds or if the value can not be converted.
+    pub fn get<I, T>(&self, idx: I) -> T
+    where
+        I: RowIndex + fmt::Display,
+        T: FromSql,
+    {
+        match self.try_get(&idx) {
+            Ok(value) => value,
+            Err(err) => panic!("error retrieving column {}: {}", idx, err),
+        }
+    }
+
+    /// Like `Row::get`, but returns an error instead of panicking.
+    pub fn try_get<I, T>(&self, idx: I) -> Result<T, Error>
+    where
+        I: RowIndex + fmt::Display,
+        T: FromSql,
+    {
+        let i = idx
+            .index(&self.columns)
+            .ok_or_else(|| Error::ColumnNotFound(idx.to_string()))?;
+        let column = &self.columns[i];
+        let conversion = |error| Error::Conversion {
+            column: column.name.clone(),
+            error,
+        };
+
+        if !T::accepts(&column.type_) {
+            return Err(conversion(
+                format!(
+                    "cannot convert postgres type with oid {} to {}",
+                    column.type_.oid(),
+                    std::any::type_name::<T>()
+                )
+                .into(),
+            ));
+        }
+        match self.data.get(i) {
+            Some(raw) => T::from_sql(&column.type_, raw),
+            None => T::from_sql_null(&column.type_),
+        }
+        .map_err(conversion)
+    }
+}
+
+impl fmt::Debug for Row {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Row")
+            .field("columns", &self.columns)
+            .finish()
+    }
+}
+
+/// A trait implemented by types that can index into columns of a row.
+///
+/// This cannot be implemented outside of this crate.
+pub trait RowIndex: sealed::Sealed {
+    #[doc(hidden)]
+    fn index(&self, columns: &[Column]) -> Option<usize>;
+}
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+impl sealed::Sealed for usize {}
+
+impl RowIndex for usize {
+    fn index(&self, columns: &[Column]) -> Option<usize> {
+        if *self < columns.len() {
+            Some(*self)
+        } else {
+            None
+        }
+    }
+}
+
+impl sealed::Sealed for str {}
+
+impl RowIndex for str {
+    fn index(&self, columns: &[Column]) -> Option<usize> {
+        columns.iter().position(|c| c.name == self)
+    }
+}
+
+impl<'a, T: sealed::Sealed + ?Sized> sealed::Sealed for &'a T {}
+
+impl<'a, T: RowIndex + ?Sized> RowIndex for &'a T {
+    fn index(&self, columns: &[Column]) -> Option<usize> {
+        (**self).index(columns)
+    }
+}
@@ -0,0 +1,214 @@
+//! SCRAM-SHA-256 authentication (RFC 5802, RFC 7677).
+use std::num::NonZeroU32;
+
+use ring::rand::{SecureRandom, SystemRandom};
+use ring::{digest, hmac, pbkdf2};
+
+use super::error::Error;
+
+pub(crate) const SCRAM_SHA_256: &str = "SCRAM-SHA-256";
+
+const NONCE_LENGTH: usize = 24;
+/// gs2 header without channel binding
+const GS2_HEADER: &str = "n,,";
+
+enum State {
+    Update {
+        nonce: String,
+        password: Vec<u8>,
+    },
+    Finish {
+        salted_password: [u8; 32],
+        auth_message: String,
+    },
+    Done,
+}
+
+/// Client side of a SCRAM-SHA-256 exchange.
+pub(crate) struct ScramSha256 {
+    message: String,
+    state: State,
+}
+
+impl ScramSha256 {
+    /// Starts an exchange. The user name is sent in the startup message, so it is left empty.
+    pub(crate) fn new(password: &[u8]) -> Result<ScramSha256, Error> {
+        let mut raw = [0u8; NONCE_LENGTH];
+        SystemRandom::new()
+            .fill(&mut raw)
+            .map_err(|_| Error::Authentication("failed to generate nonce".into()))?;
+        // printable characters except ','
+        let nonce = raw
+            .iter()
+            .map(|b| (0x21 + b % (0x7e - 0x21)) as char)
+            .map(|c| if c == ',' { '~' } else { c })
+            .collect::<String>();
+        Ok(ScramSha256::with_nonce(password, nonce))
+    }
+
+    fn with_nonce(password: &[u8], nonce: String) -> ScramSha256 {
+        ScramSha256 {
+            message: format!("{}n=,r={}", GS2_HEADER, nonce),
+            state: State::Update {
+                nonce,
+                password: password.to_vec(),
+            },
+        }
+    }
+
+    /// Returns the message to send to the server.
+    pub(crate) fn message(&self) -> &[u8] {
+        self.message.as_bytes()
+    }
+
+    /// Processes the server-first message.
+    pub(crate) fn update(&mut self, message: &[u8]) -> Result<(), Error> {
+        let (client_nonce, password) = match std::mem::replace(&mut self.state, State::Done) {
+            State::Update { nonce, password } => (nonce, password),
+            _ => return Err(invalid("unexpected SASL continue message")),
+        };
+
+        let server_first =
+            std::str::from_utf8(message).map_err(|_| invalid("invalid server-first message"))?;
+        let mut nonce = None;
+        let mut salt = None;
+        let mut iterations = None;
+        for part in server_first.split(',') {
+            if part.starts_with("r=") {
+                nonce = Some(&part[2..]);
+            } else if part.starts_with("s=") {
+                salt = base64::decode(&part[2..]).ok();
+            } else if part.starts_with("i=") {
+                iterations = part[2..].parse::<u32>().ok().and_then(NonZeroU32::new);
+            }
+        }
+        let nonce = nonce.ok_or_else(|| invalid("missing nonce"))?;
+        let salt = salt.ok_or_else(|| invalid("missing or invalid salt"))?;
+        let iterations = iterations.ok_or_else(|| invalid("missing or invalid iteration count"))?;
+        if !nonce.starts_with(&client_nonce) {
+            return Err(invalid("invalid nonce"));
+        }
+
+        let mut salted_password = [0u8; 32];
+        pbkdf2::derive(
+            pbkdf2::PBKDF2_HMAC_SHA256,
+            iterations,
+            &salt,
+            &password,
+            &mut salted_password,
+        );
+
+        let client_key = sign(&salted_password, b"Client Key");
+        let stored_key = digest::digest(&digest::SHA256, &client_key);
+
+        let client_final = format!("c={},r={}", base64::encode(GS2_HEADER), nonce);
+        let auth_message = format!("n=,r={},{},{}", client_nonce, server_first, client_final);
+
+        let signature = sign(stored_key.as_ref(), auth_message.as_bytes());
+        let proof = client_key
+            .iter()
+            .zip(signature.iter())
+            .map(|(a, b)| a ^ b)
+            .collect::<Vec<_>>();
+
+        self.message = format!("{},p={}", client_final, base64::encode(&proof));
+        self.state = State::Finish {
+            salted_password,
+            auth_message,
+        };
+        Ok(())
+    }
+
+    /// Verifies the server-final message.
+    pub(crate) fn finish(&mut self, message: &[u8]) -> Result<(), Error> {
+        let (salted_password, auth_message) = match std::mem::replace(&mut self.state, State::Done)
+        {
+            State::Finish {
+                salted_password,
+                auth_message,
+            } => (salted_password, auth_message),
+            _ => return Err(invalid("unexpected SASL final message")),
+        };
+
+        let server_final =
+            std::str::from_utf8(message).map_err(|_| invalid("invalid server-final message"))?;
+        if server_final.starts_with("e=") {
+            return Err(Error::Authentication(format!(
+                "SCRAM error: {}",
+                &server_final[2..]
+            )));
+        }
+        let verifier = server_final
+            .split(',')
+            .find(|part| part.starts_with("v="))
+            .and_then(|part| base64::decode(&part[2..]).ok())
+            .ok_or_else(|| invalid("missing server signature"))?;
+
+        let server_key = sign(&salted_password, b"Server Key");
+        let key = hmac::Key::new(hmac::HMAC_SHA256, &server_key);
+        hmac::verify(&key, auth_message.as_bytes(), &verifier)
+            .map_err(|_| invalid("invalid server signature"))
+    }
+}
+
+fn sign(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, key);
+    hmac::sign(&key, data).as_ref().to_vec()
+}
+
+fn invalid(msg: &str) -> Error {
+    Error::Authentication(format!("SCRAM: {}", msg))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // example exchange from RFC 7677, with an empty user name as sent by postgres clients
+    #[test]
+    fn test_exchange() {
+        let mut scram = ScramSha256::with_nonce(b"pencil", "rOprNGfwEbeRWgbNEkqO".to_owned());
+        assert_eq!(scram.message(), b"n,,n=,r=rOprNGfwEbeRWgbNEkqO");
+
+        let server_first = "r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0,\
+                            s=W22ZaJ0SNY7soEsUEjb6gQ==,i=4096";
+        scram.update(server_first.as_bytes()).unwrap();
+        let client_final = std::str::from_utf8(scram.message()).unwrap();
+        assert!(client_final
+            .starts_with("c=biws,r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0,p="));
+
+        // compute the server signature the way the server would
+        let mut salted = [0u8; 32];
+        pbkdf2::derive(
+            pbkdf2::PBKDF2_HMAC_SHA256,
+            NonZeroU32::new(4096).unwrap(),
+            &base64::decode("W22ZaJ0SNY7soEsUEjb6gQ==").unwrap(),
+            b"pencil",
+            &mut salted,
+        );
+        let auth_message = format!(
+            "n=,r=rOprNGfwEbeRWgbNEkqO,{},{}",
+            server_first,
+            &client_final[..client_final.find(",p=").unwrap()]
+        );
+        let signature = sign(&sign(&salted, b"Server Key"), auth_message.as_bytes());
+
+        let mut bad = ScramSha256 {
+            message: String::new(),
+            state: State::Finish {
+                salted_password: salted,
+                auth_message: auth_message.clone(),
+            },
+        };
+        assert!(bad.finish(b"v=AAAA").is_err());
+        scram
+            .finish(format!("v={}", base64::encode(&signature)).as_bytes())
+            .unwrap();
+    }
+
+    #[test]
+    fn test_invalid_nonce() {
+        let mut scram = ScramSha256::with_nonce(b"pencil", "abc".to_owned());
+        assert!(scram.update(b"r=xyz,s=QUJD,i=1").is_err());
+    }
+}
@@ -0,0 +1,312 @@
+//! Conversions between Rust and PostgreSQL values.
+//!
+//! Values are exchanged in the text format, so any type can be read as a `String`.
+use std::error::Error as StdError;
+use std::fmt;
+
+use bytes::{BufMut, BytesMut};
+
+type BoxError = Box<dyn StdError + Send + Sync>;
+
+/// Postgres type, identified by its oid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Type(u32);
+
+impl Type {
+    pub const BOOL: Type = Type(16);
+    pub const BYTEA: Type = Type(17);
+    pub const CHAR: Type = Type(18);
+    pub const NAME: Type = Type(19);
+    pub const INT8: Type = Type(20);
+    pub const INT2: Type = Type(21);
+    pub const INT4: Type = Type(23);
+    pub const TEXT: Type = Type(25);
+    pub const OID: Type = Type(26);
+    pub const JSON: Type = Type(114);
+    pub const FLOAT4: Type = Type(700);
+    pub const FLOAT8: Type = Type(701);
+    pub const UNKNOWN: Type = Type(705);
+    pub const BPCHAR: Type = Type(1042);
+    pub const VARCHAR: Type = Type(1043);
+    pub const DATE: Type = Type(1082);
+    pub const TIMESTAMP: Type = Type(1114);
+    pub const TIMESTAMPTZ: Type = Type(1184);
+    pub const NUMERIC: Type = Type(1700);
+    pub const UUID: Type = Type(2950);
+    pub const JSONB: Type = Type(3802);
+
+    /// Creates a type from its oid.
+    pub fn from_oid(oid: u32) -> Type {
+        Type(oid)
+    }
+
+    /// Returns the oid of the type.
+    pub fn oid(&self) -> u32 {
+        self.0
+    }
+}
+
+/// Result of [`ToSql::to_sql`](trait.ToSql.html#tymethod.to_sql).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IsNull {
+    /// The value is `NULL`, nothing has been written.
+    Yes,
+    /// The value has been written.
+    No,
+}
+
+/// A Rust type that can be sent to the server as a query parameter.
+pub trait ToSql: fmt::Debug {
+    /// Writes the text representation of the value to `out`.
+    fn to_sql(&self, out: &mut BytesMut) -> IsNull;
+}
+
+/// A Rust type that can be decoded from a column value.
+pub trait FromSql: Sized {
+    /// Decodes a non-`NULL` value from its text representation.
+    fn from_sql(ty: &Type, raw: &[u8]) -> Result<Self, BoxError>;
+
+    /// Decodes a `NULL` value. Fails by default.
+    fn from_sql_null(_: &Type) -> Result<Self, BoxError> {
+        Err("unexpected NULL value".into())
+    }
+
+    /// Returns true if values of the given postgres type can be decoded into this type.
+    fn accepts(ty: &Type) -> bool;
+}
+
+impl<'a, T: ToSql + ?Sized> ToSql for &'a T {
+    fn to_sql(&self, out: &mut BytesMut) -> IsNull {
+        (**self).to_sql(out)
+    }
+}
+
+impl<T: ToSql> ToSql for Option<T> {
+    fn to_sql(&self, out: &mut BytesMut) -> IsNull {
+        match self {
+            Some(value) => value.to_sql(out),
+            None => IsNull::Yes,
+        }
+    }
+}
+
+impl<T: FromSql> FromSql for Option<T> {
+    fn from_sql(ty: &Type, raw: &[u8]) -> Result<Self, BoxError> {
+        T::from_sql(ty, raw).map(Some)
+    }
+
+    fn from_sql_null(_: &Type) -> Result<Self, BoxError> {
+        Ok(None)
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        T::accepts(ty)
+    }
+}
+
+impl ToSql for bool {
+    fn to_sql(&self, out: &mut BytesMut) -> IsNull {
+        out.put_slice(if *self { b"t" } else { b"f" });
+        IsNull::No
+    }
+}
+
+impl FromSql for bool {
+    fn from_sql(_: &Type, raw: &[u8]) -> Result<Self, BoxError> {
+        match raw {
+            b"t" => Ok(true),
+            b"f" => Ok(false),
+            _ => Err("invalid boolean value".into()),
+        }
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        *ty == Type::BOOL
+    }
+}
+
+macro_rules! number {
+    ($t:ty, $($ty:ident),+) => {
+        impl ToSql for $t {
+            fn to_sql(&self, out: &mut BytesMut) -> IsNull {
+                out.extend_from_slice(self.to_string().as_bytes());
+                IsNull::No
+            }
+        }
+
+        impl FromSql for $t {
+            fn from_sql(_: &Type, raw: &[u8]) -> Result<Self, BoxError> {
+                Ok(std::str::from_utf8(raw)?.parse()?)
+            }
+
+            fn accepts(ty: &Type) -> bool {
+                $(*ty == Type::$ty)||+
+            }
+        }
+    };
+}
+
+number!(i16, INT2);
+number!(i32, INT2, INT4);
+number!(i64, INT2, INT4, INT8);
+number!(u32, OID);
+
+macro_rules! float {
+    ($t:ty, $($ty:ident),+) => {
+        impl ToSql for $t {
+            fn to_sql(&self, out: &mut BytesMut) -> IsNull {
+                if self.is_nan() {
+                    out.put_slice(b"NaN");
+                } else if self.is_infinite() {
+                    out.put_slice(if *self > 0.0 { b"Infinity" } else { b"-Infinity" });
+                } else {
+                    out.extend_from_slice(self.to_string().as_bytes());
+                }
+                IsNull::No
+            }
+        }
+
+        impl FromSql for $t {
+            fn from_sql(_: &Type, raw: &[u8]) -> Result<Self, BoxError> {
+                match raw {
+                    b"NaN" => Ok(<$t>::NAN),
+                    b"Infinity" => Ok(<$t>::INFINITY),
+                    b"-Infinity" => Ok(<$t>::NEG_INFINITY),
+                    _ => Ok(std::str::from_utf8(raw)?.parse()?),
+                }
+            }
+
+            fn accepts(ty: &Type) -> bool {
+                $(*ty == Type::$ty)||+
+            }
+        }
+    };
+}
+
+float!(f32, FLOAT4);
+float!(f64, FLOAT4, FLOAT8);
+
+impl ToSql for str {
+    fn to_sql(&self, out: &mut BytesMut) -> IsNull {
+        out.extend_from_slice(self.as_bytes());
+        IsNull::No
+    }
+}
+
+impl ToSql for String {
+    fn to_sql(&self, out: &mut BytesMut) -> IsNull {
+        self.as_str().to_sql(out)
+    }
+}
+
+/// Any value can be read as a string, as the server sends values in text format.
+impl FromSql for String {
+    fn from_sql(_: &Type, raw: &[u8]) -> Result<Self, BoxError> {
+        Ok(std::str::from_utf8(raw)?.to_owned())
+    }
+
+    fn accepts(_: &Type) -> bool {
+        true
+    }
+}
+
+impl ToSql for [u8] {
+    fn to_sql(&self, out: &mut BytesMut) -> IsNull {
+        const HEX: &[u8; 16] = b"0123456789abcdef";
+
+        out.reserve(2 + self.len() * 2);
+        out.put_slice(b"\\x");
+        for b in self {
+            out.put_u8(HEX[(b >> 4) as usize]);
+            out.put_u8(HEX[(b & 0xf) as usize]);
+        }
+        IsNull::No
+    }
+}
+
+impl ToSql for Vec<u8> {
+    fn to_sql(&self, out: &mut BytesMut) -> IsNull {
+        self.as_slice().to_sql(out)
+    }
+}
+
+impl FromSql for Vec<u8> {
+    fn from_sql(_: &Type, raw: &[u8]) -> Result<Self, BoxError> {
+        fn nibble(c: u8) -> Result<u8, BoxError> {
+            match c {
+                b'0'..=b'9' => Ok(c - b'0'),
+                b'a'..=b'f' => Ok(c - b'a' + 10),
+                b'A'..=b'F' => Ok(c - b'A' + 10),
+                _ => Err("invalid hex digit in bytea value".into()),
+            }
+        }
+
+        if !raw.starts_with(b"\\x") || raw.len() % 2 != 0 {
+            return Err("unsupported bytea output format, expected hex".into());
+        }
+        raw[2..]
+            .chunks(2)
+            .map(|pair| Ok(nibble(pair[0])? << 4 | nibble(pair[1])?))
+            .collect()
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        *ty == Type::BYTEA
+    }
+}
+
+impl ToSql for serde_json::Value {
+    fn to_sql(&self, out: &mut BytesMut) -> IsNull {
+        out.extend_from_slice(self.to_string().as_bytes());
+        IsNull::No
+    }
+}
+
+impl FromSql for serde_json::Value {
+    fn from_sql(_: &Type, raw: &[u8]) -> Result<Self, BoxError> {
+        Ok(serde_json::from_slice(raw)?)
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        *ty == Type::JSON || *ty == Type::JSONB
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode<T: ToSql>(value: T) -> Option<BytesMut> {
+        let mut buf = BytesMut::new();
+        match value.to_sql(&mut buf) {
+            IsNull::Yes => None,
+            IsNull::No => Some(buf),
+        }
+    }
+
+    #[test]
+    fn test_to_sql() {
+        assert_eq!(encode(true).unwrap(), &b"t"[..]);
+        assert_eq!(encode(-12i32).unwrap(), &b"-12"[..]);
+        assert_eq!(encode(std::f64::INFINITY).unwrap(), &b"Infinity"[..]);
+        assert_eq!(encode("abc").unwrap(), &b"abc"[..]);
+        assert_eq!(encode(vec![0u8, 0xab]).unwrap(), &b"\\x00ab"[..]);
+        assert_eq!(encode(None::<i32>), None);
+    }
+
+    #[test]
+    fn test_from_sql() {
+        assert_eq!(bool::from_sql(&Type::BOOL, b"t").unwrap(), true);
+        assert_eq!(i64::from_sql(&Type::INT8, b"-42").unwrap(), -42);
+        assert!(f64::from_sql(&Type::FLOAT8, b"NaN").unwrap().is_nan());
+        assert_eq!(
+            Vec::<u8>::from_sql(&Type::BYTEA, b"\\x00aB").unwrap(),
+            vec![0, 0xab]
+        );
+        assert!(Vec::<u8>::from_sql(&Type::BYTEA, b"\\x0").is_err());
+        assert_eq!(Option::<i32>::from_sql_null(&Type::INT4).unwrap(), None);
+        assert!(i32::from_sql_null(&Type::INT4).is_err());
+        assert!(i32::accepts(&Type::INT2));
+        assert!(!i32::accepts(&Type::INT8));
+    }
+}
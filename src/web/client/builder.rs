@@ -4,7 +4,7 @@ use std::fmt;
 use std::rc::Rc;
 use std::time::Duration;
 
-use crate::http::client::{Connect, ConnectError, Connection, Connector};
+use crate::http::client::{Connect, ConnectError, Connection, Connector, Proxy};
 use crate::http::{header, error::HttpError, HeaderMap, HeaderName};
 use crate::service::Service;
 
@@ -20,6 +20,7 @@ pub struct ClientBuilder {
     default_headers: bool,
     allow_redirects: bool,
     max_redirects: usize,
+    proxy: Option<Proxy>,
 }
 
 impl Default for ClientBuilder {
@@ -34,11 +35,13 @@ impl ClientBuilder {
             default_headers: true,
             allow_redirects: true,
             max_redirects: 10,
+            proxy: None,
             config: ClientConfig {
                 headers: HeaderMap::new(),
                 timeout: Some(Duration::from_secs(5)),
                 connector: RefCell::new(Box::new(ConnectorWrapper(
                     Connector::new().finish(),
+                    None,
                 ))),
             },
         }
@@ -52,7 +55,29 @@ impl ClientBuilder {
         <T::Response as Connection>::Future: 'static,
         T::Future: 'static,
     {
-        self.config.connector = RefCell::new(Box::new(ConnectorWrapper(connector)));
+        self.config.connector = RefCell::new(Box::new(ConnectorWrapper(connector, None)));
+        self
+    }
+
+    /// Send requests through a HTTP proxy.
+    ///
+    /// `http://` requests are sent to the proxy in absolute-form, `https://`
+    /// and websocket connections are tunnelled through it. Hosts excluded
+    /// with `Proxy::no_proxy` are connected to directly.
+    ///
+    /// ```rust
+    /// use kayrx::http::client::Proxy;
+    /// use kayrx::web::client::ClientBuilder;
+    ///
+    /// let proxy = Proxy::new("http://proxy.local:3128")
+    ///     .unwrap()
+    ///     .basic_auth("user", Some("password"))
+    ///     .no_proxy("localhost");
+    ///
+    /// let client = ClientBuilder::new().proxy(proxy).finish();
+    /// ```
+    pub fn proxy(mut self, proxy: Proxy) -> Self {
+        self.proxy = Some(proxy);
         self
     }
 
@@ -139,7 +164,10 @@ impl ClientBuilder {
     }
 
     /// Finish build process and create `Client` instance.
-    pub fn finish(self) -> Client {
+    pub fn finish(mut self) -> Client {
+        if let Some(proxy) = self.proxy {
+            self.config.connector.get_mut().set_proxy(proxy);
+        }
         Client(Rc::new(self.config))
     }
 }
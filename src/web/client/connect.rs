@@ -8,18 +8,31 @@ use crate::krse::io::{AsyncRead, AsyncWrite};
 use crate::codec::Framed2 as Framed;
 use crate::http::body::Body;
 use crate::http::client::{
-    Connect as ClientConnect, ConnectError, Connection, SendRequestError,
+    Connect as ClientConnect, ConnectError, Connection, Proxy, SendRequestError,
 };
 use crate::http::h1::ClientCodec;
-use crate::http::HeaderMap;
+use crate::http::{HeaderMap, Uri};
 use crate::http::{RequestHead, RequestHeadType, ResponseHead};
 use crate::service::Service;
 
 use crate::web::client::response::ClientResponse;
 
-pub(crate) struct ConnectorWrapper<T>(pub T);
+pub(crate) struct ConnectorWrapper<T>(pub T, pub Option<Proxy>);
+
+impl<T> ConnectorWrapper<T> {
+    fn connect(&self, uri: &Uri, addr: Option<net::SocketAddr>) -> ClientConnect {
+        ClientConnect {
+            uri: uri.clone(),
+            addr,
+            proxy: self.1.as_ref().filter(|proxy| proxy.intercepts(uri)).cloned(),
+        }
+    }
+}
 
 pub(crate) trait Connect {
+    /// Send requests through a proxy
+    fn set_proxy(&mut self, proxy: Proxy);
+
     fn send_request(
         &mut self,
         head: RequestHead,
@@ -78,6 +91,10 @@ where
     <T::Response as Connection>::TunnelFuture: 'static,
     T::Future: 'static,
 {
+    fn set_proxy(&mut self, proxy: Proxy) {
+        self.1 = Some(proxy);
+    }
+
     fn send_request(
         &mut self,
         head: RequestHead,
@@ -85,10 +102,7 @@ where
         addr: Option<net::SocketAddr>,
     ) -> Pin<Box<dyn Future<Output = Result<ClientResponse, SendRequestError>>>> {
         // connect to the host
-        let fut = self.0.call(ClientConnect {
-            uri: head.uri.clone(),
-            addr,
-        });
+        let fut = self.0.call(self.connect(&head.uri, addr));
 
         Box::pin(async move {
             let connection = fut.await?;
@@ -109,10 +123,7 @@ where
         addr: Option<net::SocketAddr>,
     ) -> Pin<Box<dyn Future<Output = Result<ClientResponse, SendRequestError>>>> {
         // connect to the host
-        let fut = self.0.call(ClientConnect {
-            uri: head.uri.clone(),
-            addr,
-        });
+        let fut = self.0.call(self.connect(&head.uri, addr));
 
         Box::pin(async move {
             let connection = fut.await?;
@@ -141,10 +152,7 @@ where
         >,
     > {
         // connect to the host
-        let fut = self.0.call(ClientConnect {
-            uri: head.uri.clone(),
-            addr,
-        });
+        let fut = self.0.call(self.connect(&head.uri, addr));
 
         Box::pin(async move {
             let connection = fut.await?;
@@ -174,10 +182,7 @@ where
         >,
     > {
         // connect to the host
-        let fut = self.0.call(ClientConnect {
-            uri: head.uri.clone(),
-            addr,
-        });
+        let fut = self.0.call(self.connect(&head.uri, addr));
 
         Box::pin(async move {
             let connection = fut.await?;
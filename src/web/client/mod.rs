@@ -77,6 +77,7 @@ impl Default for Client {
         Client(Rc::new(ClientConfig {
             connector: RefCell::new(Box::new(ConnectorWrapper(
                 Connector::new().finish(),
+                None,
             ))),
             headers: HeaderMap::new(),
             timeout: Some(Duration::from_secs(5)),
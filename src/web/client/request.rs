@@ -24,7 +24,8 @@ use crate::web::client::frozen::FrozenClientRequest;
 use crate::web::client::sender::{PrepForSendingError, RequestSender, SendClientRequest};
 use crate::web::client::ClientConfig;
 
-const HTTPS_ENCODING: &str = "br, gzip, deflate";
+const HTTPS_ENCODING: &str = "br, zstd, gzip, deflate";
+const HTTP_ENCODING: &str = "zstd, gzip, deflate";
 
 /// An HTTP Client request builder
 ///
@@ -561,10 +562,7 @@ impl ClientRequest {
             if https {
                 slf = slf.set_header_if_none(header::ACCEPT_ENCODING, HTTPS_ENCODING)
             } else {
-                {
-                    slf =
-                        slf.set_header_if_none(header::ACCEPT_ENCODING, "gzip, deflate")
-                }
+                slf = slf.set_header_if_none(header::ACCEPT_ENCODING, HTTP_ENCODING)
             };
         }
 
@@ -12,7 +12,7 @@ use coo_kie::{Cookie, ParseError as CookieParseError};
 use crate::http::error::PayloadError;
 use crate::http::header::{CONTENT_LENGTH, SET_COOKIE};
 use crate::http::{HeaderMap, StatusCode, Version};
-use crate::http::{Extensions, HttpMessage, Payload, PayloadStream, ResponseHead};
+use crate::http::{Extensions, HttpMessage, Payload, PayloadStream, ResponseHead, Trailers};
 use serde::de::DeserializeOwned;
 
 use crate::web::client::error::JsonPayloadError;
@@ -92,6 +92,14 @@ impl<S> ClientResponse<S> {
         &self.head().headers
     }
 
+    /// Returns response's trailers.
+    ///
+    /// Trailers follow the payload, so they are available only after the
+    /// payload has been read to the end.
+    pub fn trailers(&self) -> Option<HeaderMap> {
+        self.extensions().get::<Trailers>().and_then(Trailers::get)
+    }
+
     /// Set a body and return previous body value
     pub fn map_body<F, U>(mut self, f: F) -> ClientResponse<U>
     where
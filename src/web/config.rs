@@ -8,6 +8,7 @@ use crate::service::{boxed, IntoServiceFactory, ServiceFactory};
 use crate::web::data::{Data, DataFactory};
 use crate::web::error::Error;
 use crate::web::guard::Guard;
+use crate::web::info::TrustedProxies;
 use crate::web::resource::Resource;
 use crate::web::rmap::ResourceMap;
 use crate::web::route::Route;
@@ -130,11 +131,26 @@ struct AppConfigInner {
     secure: bool,
     host: String,
     addr: SocketAddr,
+    trusted_proxies: TrustedProxies,
 }
 
 impl AppConfig {
     pub(crate) fn new(secure: bool, addr: SocketAddr, host: String) -> Self {
-        AppConfig(Rc::new(AppConfigInner { secure, addr, host }))
+        AppConfig::with_trusted_proxies(secure, addr, host, TrustedProxies::default())
+    }
+
+    pub(crate) fn with_trusted_proxies(
+        secure: bool,
+        addr: SocketAddr,
+        host: String,
+        trusted_proxies: TrustedProxies,
+    ) -> Self {
+        AppConfig(Rc::new(AppConfigInner {
+            secure,
+            addr,
+            host,
+            trusted_proxies,
+        }))
     }
 
     /// Server host name.
@@ -157,6 +173,14 @@ impl AppConfig {
     pub fn local_addr(&self) -> SocketAddr {
         self.0.addr
     }
+
+    /// Peers allowed to set forwarded headers.
+    ///
+    /// Check [ConnectionInfo](./struct.ConnectionInfo.html)
+    /// documentation for more information.
+    pub fn trusted_proxies(&self) -> &TrustedProxies {
+        &self.0.trusted_proxies
+    }
 }
 
 impl Default for AppConfig {
@@ -132,3 +132,71 @@ impl<T: 'static> DataFactory for Data<T> {
         }
     }
 }
+
+/// Request-local data.
+///
+/// Request data is an arbitrary data attached to a request, usually by a
+/// middleware with `req.extensions_mut().insert(value)`.
+///
+/// Request data could be accessed by using `ReqData<T>` extractor where `T`
+/// is data type. The data is cloned out of the request extensions, so it
+/// should be cheap to clone.
+///
+/// If request data is not set for a request, using `ReqData<T>` extractor
+/// would cause *Internal Server Error* response.
+///
+/// ```rust
+/// use kayrx::web::{self, App, HttpResponse, ReqData};
+///
+/// #[derive(Clone)]
+/// struct User(String);
+///
+/// async fn index(user: ReqData<User>) -> HttpResponse {
+///     HttpResponse::Ok().body(format!("Hello, {}!", user.0))
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(
+///         web::resource("/index.html").route(
+///             web::get().to(index)));
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ReqData<T: Clone + 'static>(T);
+
+impl<T: Clone + 'static> ReqData<T> {
+    /// Consumes the `ReqData`, returning its wrapped data.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T: Clone + 'static> Deref for ReqData<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: Clone + 'static> FromRequest for ReqData<T> {
+    type Config = ();
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        if let Some(st) = req.extensions().get::<T>() {
+            ok(ReqData(st.clone()))
+        } else {
+            log::debug!(
+                "Failed to construct ReqData extractor. \
+                 Request path: {:?}",
+                req.path()
+            );
+            err(ErrorInternalServerError(
+                "Request data is not set, it has to be inserted into request extensions",
+            ))
+        }
+    }
+}
@@ -362,6 +362,20 @@ impl Files {
         self
     }
 
+    #[inline]
+    /// Specifies whether to serve precompressed variants of files.
+    ///
+    /// When enabled, a request for `file.js` is answered with `file.js.br` or
+    /// `file.js.gz` if such a file exists and the client accepts its encoding.
+    /// Responses carry a matching `Content-Encoding` header and
+    /// `Vary: Accept-Encoding`.
+    ///
+    /// Default is false.
+    pub fn use_precompressed(mut self, value: bool) -> Self {
+        self.file_flags.set(named::Flags::PRECOMPRESSED, value);
+        self
+    }
+
     /// Specifies custom guards to use for directory listings and files.
     ///
     /// Default behaviour allows GET and HEAD.
@@ -466,6 +480,14 @@ pub struct FilesService {
 }
 
 impl FilesService {
+    fn open_file(&self, path: PathBuf, req: &ServiceRequest) -> io::Result<NamedFile> {
+        if self.file_flags.contains(named::Flags::PRECOMPRESSED) {
+            NamedFile::open_precompressed(path, req.headers())
+        } else {
+            NamedFile::open(path)
+        }
+    }
+
     fn handle_err(
         &mut self,
         e: io::Error,
@@ -541,7 +563,7 @@ impl Service for FilesService {
 
                 let path = path.join(redir_index);
 
-                match NamedFile::open(path) {
+                match self.open_file(path, &req) {
                     Ok(mut named_file) => {
                         if let Some(ref mime_override) = self.mime_override {
                             let new_disposition =
@@ -573,7 +595,7 @@ impl Service for FilesService {
                 )))
             }
         } else {
-            match NamedFile::open(path) {
+            match self.open_file(path, &req) {
                 Ok(mut named_file) => {
                     if let Some(ref mime_override) = self.mime_override {
                         let new_disposition =
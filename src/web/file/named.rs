@@ -1,3 +1,5 @@
+use std::cmp;
+use std::ffi::OsString;
 use std::fs::{File, Metadata};
 use std::io;
 use std::ops::{Deref, DerefMut};
@@ -14,6 +16,7 @@ use crate::http::body::SizedStream;
 use crate::web::dev::BodyEncoding;
 use crate::http::header::{
     self, Charset, ContentDisposition, ContentEncoding, DispositionParam, DispositionType, ExtendedValue,
+    HeaderMap,
 };
 use crate::http::{error::Error, StatusCode, Response as HttpResponse, HttpMessage};
 use crate::web::{HttpRequest, Responder};
@@ -27,12 +30,13 @@ bitflags! {
         const ETAG = 0b0000_0001;
         const LAST_MD = 0b0000_0010;
         const CONTENT_DISPOSITION = 0b0000_0100;
+        const PRECOMPRESSED = 0b0000_1000;
     }
 }
 
 impl Default for Flags {
     fn default() -> Self {
-        Flags::all()
+        Flags::ETAG | Flags::LAST_MD | Flags::CONTENT_DISPOSITION
     }
 }
 
@@ -48,6 +52,7 @@ pub struct NamedFile {
     pub(crate) content_type: mime::Mime,
     pub(crate) content_disposition: header::ContentDisposition,
     pub(crate) encoding: Option<ContentEncoding>,
+    pub(crate) precompressed: Option<ContentEncoding>,
 }
 
 impl NamedFile {
@@ -120,6 +125,7 @@ impl NamedFile {
             md,
             modified,
             encoding,
+            precompressed: None,
             status_code: StatusCode::OK,
             flags: Flags::default(),
         })
@@ -138,6 +144,48 @@ impl NamedFile {
         Self::from_file(File::open(&path)?, path)
     }
 
+    /// Attempts to open a precompressed variant of the file at `path`.
+    ///
+    /// Looks for `.br` and `.gz` siblings of `path` in the order of preference
+    /// of the `Accept-Encoding` header, and falls back to `path` itself. A
+    /// variant modified before the original file is considered stale and
+    /// ignored. Content type and disposition are taken from `path`.
+    pub(crate) fn open_precompressed<P: AsRef<Path>>(
+        path: P,
+        headers: &HeaderMap,
+    ) -> io::Result<NamedFile> {
+        let path = path.as_ref();
+        let encodings = accepted_encodings(headers);
+        if encodings.is_empty() {
+            return Self::open(path);
+        }
+
+        let modified = path.metadata()?.modified().ok();
+        for encoding in encodings {
+            let mut variant = OsString::from(path.as_os_str());
+            variant.push(match encoding {
+                ContentEncoding::Br => ".br",
+                _ => ".gz",
+            });
+
+            let file = match File::open(&variant) {
+                Ok(file) => file,
+                Err(_) => continue,
+            };
+            let md = file.metadata()?;
+            let stale = match (md.modified().ok(), modified) {
+                (Some(variant), Some(original)) => variant < original,
+                _ => false,
+            };
+            if md.is_file() && !stale {
+                let mut named_file = Self::from_file(file, path)?;
+                named_file.precompressed = Some(encoding);
+                return Ok(named_file);
+            }
+        }
+        Self::open(path)
+    }
+
     /// Returns reference to the underlying `File` object.
     #[inline]
     pub fn file(&self) -> &File {
@@ -336,6 +384,14 @@ impl NamedFile {
         if let Some(current_encoding) = self.encoding {
             resp.encoding(current_encoding);
         }
+        // precompressed variant, must not be compressed again
+        if let Some(precompressed) = self.precompressed {
+            resp.header(header::CONTENT_ENCODING, precompressed.as_str())
+                .encoding(ContentEncoding::Identity);
+        }
+        if self.flags.contains(Flags::PRECOMPRESSED) {
+            resp.header(header::VARY, "Accept-Encoding");
+        }
 
         resp.if_some(last_modified, |lm, resp| {
             resp.set(header::LastModified(lm));
@@ -409,6 +465,56 @@ impl DerefMut for NamedFile {
     }
 }
 
+/// Returns the precompressed encodings acceptable by the `Accept-Encoding`
+/// header, most preferred first. Brotli wins over gzip on equal quality.
+fn accepted_encodings(headers: &HeaderMap) -> Vec<ContentEncoding> {
+    let accept = match headers
+        .get(&header::ACCEPT_ENCODING)
+        .and_then(|val| val.to_str().ok())
+    {
+        Some(accept) => accept,
+        None => return Vec::new(),
+    };
+
+    let (mut br, mut gzip, mut any) = (None, None, None);
+    for item in accept.split(',') {
+        let mut params = item.split(';');
+        let name = params.next().unwrap_or("").trim();
+        let quality = params
+            .filter_map(|param| {
+                let param = param.trim();
+                if param.starts_with("q=") {
+                    param[2..].parse::<f32>().ok()
+                } else {
+                    None
+                }
+            })
+            .next()
+            .unwrap_or(1.0);
+
+        if name.eq_ignore_ascii_case("br") {
+            br = Some(quality);
+        } else if name.eq_ignore_ascii_case("gzip") {
+            gzip = Some(quality);
+        } else if name == "*" {
+            any = Some(quality);
+        }
+    }
+
+    let mut encodings: Vec<_> = vec![
+        (ContentEncoding::Br, br.or(any)),
+        (ContentEncoding::Gzip, gzip.or(any)),
+    ]
+    .into_iter()
+    .filter_map(|(encoding, quality)| match quality {
+        Some(quality) if quality > 0.0 => Some((encoding, quality)),
+        _ => None,
+    })
+    .collect();
+    encodings.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(cmp::Ordering::Equal));
+    encodings.into_iter().map(|(encoding, _)| encoding).collect()
+}
+
 /// Returns true if `req` has no `If-Match` header or one which matches `etag`.
 fn any_match(etag: Option<&header::EntityTag>, req: &HttpRequest) -> bool {
     match req.get_header::<header::IfMatch>() {
@@ -1,4 +1,6 @@
 use std::cell::Ref;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
 
 use crate::web::dev::{AppConfig, RequestHead};
 use crate::http::header::{self, HeaderName};
@@ -7,6 +9,201 @@ const X_FORWARDED_FOR: &[u8] = b"x-forwarded-for";
 const X_FORWARDED_HOST: &[u8] = b"x-forwarded-host";
 const X_FORWARDED_PROTO: &[u8] = b"x-forwarded-proto";
 
+/// Peers allowed to set forwarded headers
+///
+/// `ConnectionInfo` honours `Forwarded` and `X-Forwarded-*` headers only if
+/// the request peer is trusted. In the multi-hop `X-Forwarded-For` and
+/// `Forwarded` headers, addresses are checked from right to left and the
+/// first untrusted one is the client address.
+///
+/// By default no peer is trusted. Use `HttpServer::trusted_proxies()` to
+/// set the proxies in front of the server, or `TrustedProxies::any()` to
+/// trust every peer.
+///
+/// ```rust
+/// use kayrx::web::TrustedProxies;
+///
+/// let proxies = TrustedProxies::new().add("10.0.0.0/8").add("::1");
+/// assert!(proxies.is_trusted("10.1.2.3".parse().unwrap()));
+/// assert!(!proxies.is_trusted("192.0.2.60".parse().unwrap()));
+/// ```
+#[derive(Debug, Clone)]
+pub struct TrustedProxies(Option<Vec<IpNet>>);
+
+impl TrustedProxies {
+    /// Create empty proxies list, no peer is trusted
+    pub fn new() -> Self {
+        TrustedProxies(Some(Vec::new()))
+    }
+
+    /// Trust all peers
+    ///
+    /// Any client is able to spoof its address, scheme and host then, use
+    /// it only if the server is not reachable other than through proxies.
+    pub fn any() -> Self {
+        TrustedProxies(None)
+    }
+
+    /// Trust peers of the network, `"10.0.0.0/8"`, or a single address,
+    /// `"127.0.0.1"`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `net` is not a valid address or network.
+    pub fn add(mut self, net: &str) -> Self {
+        let net = net
+            .parse()
+            .unwrap_or_else(|_| panic!("Invalid trusted proxy network: {}", net));
+        self.0.get_or_insert_with(Vec::new).push(net);
+        self
+    }
+
+    /// Check if the address belongs to a trusted proxy
+    pub fn is_trusted(&self, addr: IpAddr) -> bool {
+        match self.0 {
+            None => true,
+            Some(ref nets) => {
+                let addr = canonical(addr);
+                nets.iter().any(|net| net.contains(addr))
+            }
+        }
+    }
+}
+
+impl Default for TrustedProxies {
+    fn default() -> Self {
+        TrustedProxies::new()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct IpNet {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl IpNet {
+    fn contains(&self, addr: IpAddr) -> bool {
+        match (self.addr, addr) {
+            (IpAddr::V4(net), IpAddr::V4(addr)) => {
+                let mask = u32::max_value()
+                    .checked_shl(32 - u32::from(self.prefix))
+                    .unwrap_or(0);
+                u32::from(net) & mask == u32::from(addr) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(addr)) => {
+                let mask = u128::max_value()
+                    .checked_shl(128 - u32::from(self.prefix))
+                    .unwrap_or(0);
+                u128::from(net) & mask == u128::from(addr) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for IpNet {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        let mut parts = s.trim().splitn(2, '/');
+        let addr = canonical(parts.next().unwrap().parse().map_err(|_| ())?);
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = match parts.next() {
+            Some(prefix) => prefix.parse().map_err(|_| ())?,
+            None => max,
+        };
+        if prefix > max {
+            return Err(());
+        }
+        Ok(IpNet { addr, prefix })
+    }
+}
+
+/// IPv4-mapped IPv6 addresses are checked as IPv4
+fn canonical(addr: IpAddr) -> IpAddr {
+    match addr {
+        IpAddr::V6(v6) => match v6.segments() {
+            [0, 0, 0, 0, 0, 0xffff, ..] => IpAddr::V4(v6.to_ipv4().unwrap()),
+            _ => addr,
+        },
+        addr => addr,
+    }
+}
+
+/// Parse address of a forwarded node, `192.0.2.43:47011`,
+/// `"[2001:db8:cafe::17]:4711"` or a bare address.
+fn node_addr(node: &str) -> Option<IpAddr> {
+    let node = node.trim_matches('"');
+    if let Ok(addr) = node.parse::<IpAddr>() {
+        return Some(addr);
+    }
+    if let Ok(addr) = node.parse::<SocketAddr>() {
+        return Some(addr.ip());
+    }
+    node.trim_start_matches('[')
+        .split(']')
+        .next()
+        .and_then(|addr| addr.parse().ok())
+}
+
+/// Index of the client hop of the forwarded chain, the rightmost hop which
+/// is not a trusted proxy. Unknown or obfuscated nodes are never trusted.
+fn client_hop(nodes: &[Option<&str>], proxies: &TrustedProxies) -> Option<usize> {
+    if nodes.is_empty() {
+        return None;
+    }
+    let idx = nodes
+        .iter()
+        .rposition(|node| match node.and_then(node_addr) {
+            Some(addr) => !proxies.is_trusted(addr),
+            None => true,
+        });
+    Some(idx.unwrap_or(0))
+}
+
+/// Value of a multi-hop `X-Forwarded-*` header set by the same proxy as the
+/// client hop, `hop` is counted from the right. Falls back to the value of
+/// the last proxy if the proxies do not append to the header.
+fn hop_value<'a>(values: &[&'a str], hop: usize) -> Option<&'a str> {
+    match values.len().checked_sub(hop + 1) {
+        Some(idx) => Some(values[idx]),
+        None => values.last().copied(),
+    }
+}
+
+/// Comma separated values of all the headers with the name
+fn header_values<'a>(req: &'a RequestHead, name: &[u8]) -> Vec<&'a str> {
+    let mut values = Vec::new();
+    for hdr in req
+        .headers
+        .get_all(&HeaderName::from_lowercase(name).unwrap())
+    {
+        if let Ok(h) = hdr.to_str() {
+            values.extend(h.split(',').map(|v| v.trim()).filter(|v| !v.is_empty()));
+        }
+    }
+    values
+}
+
+/// Strip quotes of the `Forwarded` node, and brackets of a bare IPv6 address.
+fn unquote(node: &str) -> &str {
+    let node = node.trim_matches('"');
+    if node.starts_with('[') && node.ends_with(']') {
+        &node[1..node.len() - 1]
+    } else {
+        node
+    }
+}
+
+/// Element of the `Forwarded` header, added by a single proxy
+#[derive(Default)]
+struct Hop<'a> {
+    node: Option<&'a str>,
+    proto: Option<&'a str>,
+    host: Option<&'a str>,
+}
+
 /// `HttpRequest` connection information
 #[derive(Debug, Clone, Default)]
 pub struct ConnectionInfo {
@@ -30,103 +227,94 @@ impl ConnectionInfo {
         let mut host = None;
         let mut scheme = None;
         let mut remote = None;
-        let mut peer = None;
-
-        // load forwarded header
-        for hdr in req.headers.get_all(&header::FORWARDED) {
-            if let Ok(val) = hdr.to_str() {
-                for pair in val.split(';') {
-                    for el in pair.split(',') {
-                        let mut items = el.trim().splitn(2, '=');
-                        if let Some(name) = items.next() {
-                            if let Some(val) = items.next() {
-                                match &name.to_lowercase() as &str {
-                                    "for" => {
-                                        if remote.is_none() {
-                                            remote = Some(val.trim());
-                                        }
-                                    }
-                                    "proto" => {
-                                        if scheme.is_none() {
-                                            scheme = Some(val.trim());
-                                        }
-                                    }
-                                    "host" => {
-                                        if host.is_none() {
-                                            host = Some(val.trim());
-                                        }
+        let peer = req.peer_addr.map(|addr| format!("{}", addr));
+
+        // forwarded headers are honoured only if they are set by a trusted proxy
+        let trusted = match req.peer_addr {
+            Some(addr) => cfg.trusted_proxies().is_trusted(addr.ip()),
+            None => cfg.trusted_proxies().0.is_none(),
+        };
+
+        if trusted {
+            let proxies = cfg.trusted_proxies();
+
+            // load forwarded header, scheme and host are taken from the
+            // element of the client hop, elements on the left of it are set
+            // by the client
+            let mut hops = Vec::new();
+            for hdr in req.headers.get_all(&header::FORWARDED) {
+                if let Ok(val) = hdr.to_str() {
+                    for el in val.split(',') {
+                        let mut hop = Hop::default();
+                        for pair in el.split(';') {
+                            let mut items = pair.trim().splitn(2, '=');
+                            if let Some(name) = items.next() {
+                                if let Some(val) = items.next() {
+                                    match &name.to_lowercase() as &str {
+                                        "for" => hop.node = Some(val.trim()),
+                                        "proto" => hop.proto = Some(val.trim()),
+                                        "host" => hop.host = Some(val.trim()),
+                                        _ => (),
                                     }
-                                    _ => (),
                                 }
                             }
                         }
+                        hops.push(hop);
                     }
                 }
             }
+            let nodes = hops.iter().map(|hop| hop.node).collect::<Vec<_>>();
+            if let Some(idx) = client_hop(&nodes, proxies) {
+                remote = hops[idx].node;
+                scheme = hops[idx].proto;
+                host = hops[idx].host;
+            }
+
+            // x-forwarded headers, values are appended by every proxy
+            let nodes = header_values(req, X_FORWARDED_FOR);
+            let hop = client_hop(&nodes.iter().map(|n| Some(*n)).collect::<Vec<_>>(), proxies)
+                .map_or(0, |idx| nodes.len() - idx - 1);
+
+            // scheme
+            if scheme.is_none() {
+                scheme = hop_value(&header_values(req, X_FORWARDED_PROTO), hop);
+            }
+
+            // host
+            if host.is_none() {
+                host = hop_value(&header_values(req, X_FORWARDED_HOST), hop);
+            }
+
+            // remote addr
+            if remote.is_none() {
+                remote = nodes.len().checked_sub(hop + 1).map(|idx| nodes[idx]);
+            }
         }
 
-        // scheme
         if scheme.is_none() {
-            if let Some(h) = req
-                .headers
-                .get(&HeaderName::from_lowercase(X_FORWARDED_PROTO).unwrap())
-            {
-                if let Ok(h) = h.to_str() {
-                    scheme = h.split(',').next().map(|v| v.trim());
-                }
-            }
-            if scheme.is_none() {
-                scheme = req.uri.scheme().map(|a| a.as_str());
-                if scheme.is_none() && cfg.secure() {
-                    scheme = Some("https")
-                }
+            scheme = req.uri.scheme().map(|a| a.as_str());
+            if scheme.is_none() && cfg.secure() {
+                scheme = Some("https")
             }
         }
 
-        // host
         if host.is_none() {
-            if let Some(h) = req
-                .headers
-                .get(&HeaderName::from_lowercase(X_FORWARDED_HOST).unwrap())
-            {
-                if let Ok(h) = h.to_str() {
-                    host = h.split(',').next().map(|v| v.trim());
-                }
+            if let Some(h) = req.headers.get(&header::HOST) {
+                host = h.to_str().ok();
             }
             if host.is_none() {
-                if let Some(h) = req.headers.get(&header::HOST) {
-                    host = h.to_str().ok();
-                }
+                host = req.uri.authority().map(|a| a.as_str());
                 if host.is_none() {
-                    host = req.uri.authority().map(|a| a.as_str());
-                    if host.is_none() {
-                        host = Some(cfg.host());
-                    }
-                }
-            }
-        }
-
-        // remote addr
-        if remote.is_none() {
-            if let Some(h) = req
-                .headers
-                .get(&HeaderName::from_lowercase(X_FORWARDED_FOR).unwrap())
-            {
-                if let Ok(h) = h.to_str() {
-                    remote = h.split(',').next().map(|v| v.trim());
+                    host = Some(cfg.host());
                 }
             }
-            if remote.is_none() {
-                // get peeraddr from socketaddr
-                peer = req.peer_addr.map(|addr| format!("{}", addr));
-            }
         }
 
         ConnectionInfo {
             peer,
             scheme: scheme.unwrap_or("http").to_owned(),
             host: host.unwrap_or("localhost").to_owned(),
-            remote: remote.map(|s| s.to_owned()),
+            remote: remote.map(|s| unquote(s).to_owned()),
         }
     }
 
@@ -163,11 +351,19 @@ impl ConnectionInfo {
     /// - X-Forwarded-For
     /// - peer name of opened socket
     ///
+    /// In multi-hop headers the client is the rightmost address which is not
+    /// a trusted proxy.
+    ///
+    /// Quotes of the `Forwarded` node and brackets of a bare IPv6 address are
+    /// stripped.
+    ///
     /// # Security
-    /// Do not use this function for security purposes, unless you can ensure the Forwarded and
-    /// X-Forwarded-For headers cannot be spoofed by the client. If you want the client's socket
-    /// address explicitly, use
-    /// [`HttpRequest::peer_addr()`](../web/struct.HttpRequest.html#method.peer_addr) instead.
+    /// Forwarded headers are honoured only if the peer is a trusted proxy, by default no
+    /// peer is trusted. Do not use this function for security purposes if all peers are
+    /// trusted, see
+    /// [`HttpServer::trusted_proxies()`](../struct.HttpServer.html#method.trusted_proxies).
+    /// If you want the client's socket address explicitly, use
+    /// [`HttpRequest::peer_addr()`](../struct.HttpRequest.html#method.peer_addr) instead.
     #[inline]
     pub fn remote(&self) -> Option<&str> {
         if let Some(ref r) = self.remote {
@@ -193,6 +389,7 @@ mod tests {
         assert_eq!(info.host(), "localhost:8080");
 
         let req = TestRequest::default()
+            .trusted_proxies(TrustedProxies::any())
             .header(
                 header::FORWARDED,
                 "for=192.0.2.60; proto=https; by=203.0.113.43; host=rust-lang.org",
@@ -205,6 +402,7 @@ mod tests {
         assert_eq!(info.remote(), Some("192.0.2.60"));
 
         let req = TestRequest::default()
+            .trusted_proxies(TrustedProxies::any())
             .header(header::HOST, "rust-lang.org")
             .to_http_request();
 
@@ -214,12 +412,14 @@ mod tests {
         assert_eq!(info.remote(), None);
 
         let req = TestRequest::default()
+            .trusted_proxies(TrustedProxies::any())
             .header(X_FORWARDED_FOR, "192.0.2.60")
             .to_http_request();
         let info = req.connection_info();
         assert_eq!(info.remote(), Some("192.0.2.60"));
 
         let req = TestRequest::default()
+            .trusted_proxies(TrustedProxies::any())
             .header(X_FORWARDED_HOST, "192.0.2.60")
             .to_http_request();
         let info = req.connection_info();
@@ -227,9 +427,151 @@ mod tests {
         assert_eq!(info.remote(), None);
 
         let req = TestRequest::default()
+            .trusted_proxies(TrustedProxies::any())
             .header(X_FORWARDED_PROTO, "https")
             .to_http_request();
         let info = req.connection_info();
         assert_eq!(info.scheme(), "https");
+
+        // forwarded headers are ignored by default
+        let req = TestRequest::default()
+            .header(X_FORWARDED_FOR, "192.0.2.60")
+            .header(X_FORWARDED_PROTO, "https")
+            .to_http_request();
+        let info = req.connection_info();
+        assert_eq!(info.scheme(), "http");
+        assert_eq!(info.remote(), None);
+    }
+
+    #[test]
+    fn test_trusted_proxies() {
+        let proxies = TrustedProxies::new().add("10.0.0.0/8").add("2001:db8::/32");
+        assert!(proxies.is_trusted("10.0.0.1".parse().unwrap()));
+        assert!(proxies.is_trusted("::ffff:10.1.1.1".parse().unwrap()));
+        assert!(proxies.is_trusted("2001:db8::1".parse().unwrap()));
+        assert!(!proxies.is_trusted("11.0.0.1".parse().unwrap()));
+        assert!(!proxies.is_trusted("2001:db9::1".parse().unwrap()));
+        assert!(!TrustedProxies::new().is_trusted("127.0.0.1".parse().unwrap()));
+        assert!(TrustedProxies::any().is_trusted("127.0.0.1".parse().unwrap()));
+
+        assert!("0.0.0.0/0".parse::<IpNet>().unwrap().contains("1.2.3.4".parse().unwrap()));
+        assert!("10.0.0.0/33".parse::<IpNet>().is_err());
+        assert!("localhost".parse::<IpNet>().is_err());
+    }
+
+    #[test]
+    fn test_forwarded_untrusted_peer() {
+        let req = TestRequest::default()
+            .trusted_proxies(TrustedProxies::new().add("10.0.0.0/8"))
+            .peer_addr("192.0.2.1:8000".parse().unwrap())
+            .header(header::HOST, "rust-lang.org")
+            .header(header::FORWARDED, "for=192.0.2.60; proto=https; host=example.com")
+            .header(X_FORWARDED_FOR, "192.0.2.60")
+            .header(X_FORWARDED_PROTO, "https")
+            .to_http_request();
+        let info = req.connection_info();
+        assert_eq!(info.scheme(), "http");
+        assert_eq!(info.host(), "rust-lang.org");
+        assert_eq!(info.remote(), Some("192.0.2.1:8000"));
+
+        // no peer address
+        let req = TestRequest::default()
+            .trusted_proxies(TrustedProxies::new().add("10.0.0.0/8"))
+            .header(X_FORWARDED_FOR, "192.0.2.60")
+            .to_http_request();
+        assert_eq!(req.connection_info().remote(), None);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_forwarded_trusted_peer() {
+        let proxies = TrustedProxies::new().add("10.0.0.0/8");
+
+        let req = TestRequest::default()
+            .trusted_proxies(proxies.clone())
+            .peer_addr("10.0.0.2:8000".parse().unwrap())
+            .header(X_FORWARDED_FOR, "203.0.113.7, 192.0.2.60, 10.0.0.5")
+            .header(X_FORWARDED_PROTO, "https")
+            .to_http_request();
+        let info = req.connection_info();
+        assert_eq!(info.scheme(), "https");
+        assert_eq!(info.remote(), Some("192.0.2.60"));
+
+        // all hops are trusted
+        let req = TestRequest::default()
+            .trusted_proxies(proxies.clone())
+            .peer_addr("10.0.0.2:8000".parse().unwrap())
+            .header(X_FORWARDED_FOR, "10.0.0.7, 10.0.0.5")
+            .to_http_request();
+        assert_eq!(req.connection_info().remote(), Some("10.0.0.7"));
+
+        let req = TestRequest::default()
+            .trusted_proxies(proxies.clone())
+            .peer_addr("10.0.0.2:8000".parse().unwrap())
+            .header(
+                header::FORWARDED,
+                "for=192.0.2.43, for=\"[2001:db8:cafe::17]:4711\";proto=https, for=10.0.0.3",
+            )
+            .to_http_request();
+        let info = req.connection_info();
+        assert_eq!(info.scheme(), "https");
+        assert_eq!(info.remote(), Some("[2001:db8:cafe::17]:4711"));
+
+        let req = TestRequest::default()
+            .trusted_proxies(proxies.clone())
+            .peer_addr("10.0.0.2:8000".parse().unwrap())
+            .header(header::FORWARDED, "for=\"[2001:db8::1]\"")
+            .to_http_request();
+        assert_eq!(req.connection_info().remote(), Some("2001:db8::1"));
+
+        // obfuscated identifiers are never trusted
+        let req = TestRequest::default()
+            .trusted_proxies(proxies)
+            .peer_addr("10.0.0.2:8000".parse().unwrap())
+            .header(header::FORWARDED, "for=192.0.2.43, for=_hidden, for=10.0.0.3")
+            .to_http_request();
+        assert_eq!(req.connection_info().remote(), Some("_hidden"));
+    }
+
+    #[test]
+    fn test_forwarded_appended_chain() {
+        let proxies = TrustedProxies::new().add("10.0.0.0/8");
+
+        // the client sent its own values, the proxy appended the real ones
+        let req = TestRequest::default()
+            .trusted_proxies(proxies.clone())
+            .peer_addr("10.0.0.2:8000".parse().unwrap())
+            .header(
+                header::FORWARDED,
+                "for=198.51.100.1;proto=https;host=evil.com, \
+                 for=192.0.2.60;proto=http;host=rust-lang.org",
+            )
+            .to_http_request();
+        let info = req.connection_info();
+        assert_eq!(info.remote(), Some("192.0.2.60"));
+        assert_eq!(info.scheme(), "http");
+        assert_eq!(info.host(), "rust-lang.org");
+
+        let req = TestRequest::default()
+            .trusted_proxies(proxies.clone())
+            .peer_addr("10.0.0.2:8000".parse().unwrap())
+            .header(X_FORWARDED_FOR, "198.51.100.1, 192.0.2.60, 10.0.0.5")
+            .header(X_FORWARDED_PROTO, "https, http, https")
+            .header(X_FORWARDED_HOST, "evil.com, rust-lang.org, internal")
+            .to_http_request();
+        let info = req.connection_info();
+        assert_eq!(info.remote(), Some("192.0.2.60"));
+        assert_eq!(info.scheme(), "http");
+        assert_eq!(info.host(), "rust-lang.org");
+
+        // the proxies set the header instead of appending to it
+        let req = TestRequest::default()
+            .trusted_proxies(proxies)
+            .peer_addr("10.0.0.2:8000".parse().unwrap())
+            .header(X_FORWARDED_FOR, "198.51.100.1, 192.0.2.60")
+            .header(X_FORWARDED_PROTO, "https")
+            .to_http_request();
+        let info = req.connection_info();
+        assert_eq!(info.remote(), Some("192.0.2.60"));
+        assert_eq!(info.scheme(), "https");
+    }
+}
@@ -0,0 +1,214 @@
+use std::borrow::Cow;
+use std::fmt;
+
+use futures_util::future::{ready, Ready};
+
+use crate::http::error::ResponseError;
+use crate::http::header::{
+    Authorization, Basic, BasicChallenge, Bearer, BearerChallenge, BearerError, Challenge, Header,
+    WWW_AUTHENTICATE,
+};
+use crate::http::{Payload, Response, StatusCode};
+use crate::web::{FromRequest, HttpRequest};
+
+/// Authentication error, responds with the challenge in the
+/// `WWW-Authenticate` header.
+///
+/// The status code is `401 Unauthorized` unless set otherwise.
+#[derive(Debug)]
+pub struct AuthenticationError<C: Challenge> {
+    challenge: C,
+    status_code: StatusCode,
+}
+
+impl<C: Challenge> AuthenticationError<C> {
+    /// Create `401 Unauthorized` error with the challenge
+    pub fn new(challenge: C) -> AuthenticationError<C> {
+        AuthenticationError {
+            challenge,
+            status_code: StatusCode::UNAUTHORIZED,
+        }
+    }
+
+    /// Returns mutable reference to the challenge
+    pub fn challenge_mut(&mut self) -> &mut C {
+        &mut self.challenge
+    }
+
+    /// Set status code of the response
+    pub fn with_status(mut self, status_code: StatusCode) -> Self {
+        self.status_code = status_code;
+        self
+    }
+}
+
+impl AuthenticationError<BearerChallenge> {
+    /// Set error code of the challenge, the status code is set accordingly
+    pub fn with_error(mut self, error: BearerError) -> Self {
+        self.challenge = self.challenge.error(error);
+        self.status_code = error.status_code();
+        self
+    }
+}
+
+impl<C: Challenge> fmt::Display for AuthenticationError<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.status_code, f)
+    }
+}
+
+impl<C: Challenge> ResponseError for AuthenticationError<C> {
+    fn status_code(&self) -> StatusCode {
+        self.status_code
+    }
+
+    fn error_response(&self) -> Response {
+        Response::build(self.status_code)
+            .header(WWW_AUTHENTICATE, self.challenge.clone())
+            .finish()
+    }
+}
+
+/// `BasicAuth` extractor configuration, the challenge of the responses to
+/// requests without valid credentials.
+///
+/// ```rust
+/// use kayrx::web::middleware::auth::{BasicAuth, BasicConfig};
+/// use kayrx::web::{self, App};
+///
+/// async fn index(auth: BasicAuth) -> String {
+///     format!("Hello, {}!", auth.user_id())
+/// }
+///
+/// fn main() {
+///     let app = App::new()
+///         .app_data(BasicConfig::default().realm("Restricted area"))
+///         .service(web::resource("/").to(index));
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct BasicConfig(BasicChallenge);
+
+impl BasicConfig {
+    /// Set protection space of the challenge
+    pub fn realm<T: Into<Cow<'static, str>>>(mut self, value: T) -> Self {
+        self.0 = self.0.realm(value);
+        self
+    }
+}
+
+impl<'a> From<&'a BasicConfig> for AuthenticationError<BasicChallenge> {
+    fn from(config: &'a BasicConfig) -> Self {
+        AuthenticationError::new(config.0.clone())
+    }
+}
+
+/// Extractor of the `Basic` scheme credentials.
+///
+/// Requests without valid credentials are answered with
+/// `401 Unauthorized` and the challenge configured by `BasicConfig`.
+#[derive(Clone, Debug)]
+pub struct BasicAuth(Basic);
+
+impl BasicAuth {
+    /// User id
+    pub fn user_id(&self) -> &Cow<'static, str> {
+        self.0.user_id()
+    }
+
+    /// Password
+    pub fn password(&self) -> Option<&Cow<'static, str>> {
+        self.0.password()
+    }
+}
+
+impl FromRequest for BasicAuth {
+    type Error = AuthenticationError<BasicChallenge>;
+    type Future = Ready<Result<Self, Self::Error>>;
+    type Config = BasicConfig;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(
+            Authorization::<Basic>::parse(req)
+                .map(|auth| BasicAuth(auth.into_scheme()))
+                .map_err(|_| {
+                    req.app_data::<BasicConfig>()
+                        .map(AuthenticationError::from)
+                        .unwrap_or_else(|| AuthenticationError::new(BasicChallenge::default()))
+                }),
+        )
+    }
+}
+
+/// `BearerAuth` extractor configuration, the challenge of the responses to
+/// requests without valid credentials.
+#[derive(Clone, Debug, Default)]
+pub struct BearerConfig(BearerChallenge);
+
+impl BearerConfig {
+    /// Set protection space of the challenge
+    pub fn realm<T: Into<Cow<'static, str>>>(mut self, value: T) -> Self {
+        self.0 = self.0.realm(value);
+        self
+    }
+
+    /// Set space-delimited list of scopes required to access the resource
+    pub fn scope<T: Into<Cow<'static, str>>>(mut self, value: T) -> Self {
+        self.0 = self.0.scope(value);
+        self
+    }
+}
+
+impl<'a> From<&'a BearerConfig> for AuthenticationError<BearerChallenge> {
+    fn from(config: &'a BearerConfig) -> Self {
+        AuthenticationError::new(config.0.clone())
+    }
+}
+
+/// Extractor of the `Bearer` scheme token.
+///
+/// Requests without a token are answered with `401 Unauthorized` and the
+/// challenge configured by `BearerConfig`, malformed tokens with
+/// `400 Bad Request` and the `invalid_request` error.
+#[derive(Clone, Debug)]
+pub struct BearerAuth(Bearer);
+
+impl BearerAuth {
+    /// Bearer token
+    pub fn token(&self) -> &str {
+        self.0.token()
+    }
+}
+
+impl FromRequest for BearerAuth {
+    type Error = AuthenticationError<BearerChallenge>;
+    type Future = Ready<Result<Self, Self::Error>>;
+    type Config = BearerConfig;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(
+            Authorization::<Bearer>::parse(req)
+                .map(|auth| BearerAuth(auth.into_scheme()))
+                .map_err(|_| {
+                    let err = req
+                        .app_data::<BearerConfig>()
+                        .map(AuthenticationError::from)
+                        .unwrap_or_else(|| AuthenticationError::new(BearerChallenge::default()));
+
+                    // no error code if the request lacks bearer credentials
+                    let bearer = req
+                        .headers()
+                        .get(Authorization::<Bearer>::name())
+                        .and_then(|value| value.to_str().ok())
+                        .and_then(|value| value.trim().get(..6))
+                        .map(|scheme| scheme.eq_ignore_ascii_case("bearer"))
+                        .unwrap_or(false);
+                    if bearer {
+                        err.with_error(BearerError::InvalidRequest)
+                    } else {
+                        err
+                    }
+                }),
+        )
+    }
+}
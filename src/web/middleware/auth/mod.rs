@@ -0,0 +1,168 @@
+//! HTTP authentication for `web` applications.
+//!
+//! `BasicAuth` and `BearerAuth` extract credentials of the `Basic` and
+//! `Bearer` schemes from the `Authorization` header. Requests without valid
+//! credentials are answered with an `AuthenticationError`, which carries the
+//! challenge of the scheme in the `WWW-Authenticate` header.
+//!
+//! The `HttpAuthentication` middleware extracts the credentials and passes
+//! them to an async validator before the request is handled, so a `Scope` or
+//! a `Resource` could be protected as a whole:
+//!
+//! ```rust
+//! use kayrx::http::error::Error;
+//! use kayrx::web::dev::ServiceRequest;
+//! use kayrx::web::middleware::auth::{AuthenticationError, BearerAuth, BearerConfig, HttpAuthentication};
+//! use kayrx::web::{self, App, HttpResponse};
+//!
+//! async fn validator(req: ServiceRequest, auth: BearerAuth) -> Result<ServiceRequest, Error> {
+//!     if auth.token() == "mF_9.B5f-4.1JqM" {
+//!         Ok(req)
+//!     } else {
+//!         Err(AuthenticationError::from(&BearerConfig::default().realm("api")).into())
+//!     }
+//! }
+//!
+//! fn main() {
+//!     let app = App::new().service(
+//!         web::scope("/api")
+//!             .wrap(HttpAuthentication::bearer(validator))
+//!             .route("/", web::get().to(|| HttpResponse::Ok())),
+//!     );
+//! }
+//! ```
+
+mod extractors;
+
+pub use self::extractors::{AuthenticationError, BasicAuth, BasicConfig, BearerAuth, BearerConfig};
+
+use std::cell::RefCell;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+use futures_util::future::{ok, FutureExt, LocalBoxFuture, Ready};
+
+use crate::service::{Service, Transform};
+use crate::web::dev::{ServiceRequest, ServiceResponse};
+use crate::web::error::Error;
+use crate::web::FromRequest;
+
+/// `Middleware` for authenticating requests.
+///
+/// Credentials of type `T` are extracted from the request and passed to the
+/// validator along with the request. The validator returns the request to
+/// continue with, or an error to respond with, usually an
+/// `AuthenticationError`. Requests without valid credentials are answered
+/// with the extractor error.
+///
+/// See the [module documentation](index.html) for an example.
+pub struct HttpAuthentication<T, F> {
+    validator: Rc<F>,
+    _t: PhantomData<T>,
+}
+
+impl<T, F, O> HttpAuthentication<T, F>
+where
+    T: FromRequest,
+    F: Fn(ServiceRequest, T) -> O,
+    O: Future<Output = Result<ServiceRequest, Error>>,
+{
+    /// Construct `HttpAuthentication` middleware with the credentials
+    /// extractor and the validator.
+    pub fn with_fn(validator: F) -> Self {
+        HttpAuthentication {
+            validator: Rc::new(validator),
+            _t: PhantomData,
+        }
+    }
+}
+
+impl<F, O> HttpAuthentication<BasicAuth, F>
+where
+    F: Fn(ServiceRequest, BasicAuth) -> O,
+    O: Future<Output = Result<ServiceRequest, Error>>,
+{
+    /// Construct `HttpAuthentication` middleware for the `Basic` scheme.
+    pub fn basic(validator: F) -> Self {
+        HttpAuthentication::with_fn(validator)
+    }
+}
+
+impl<F, O> HttpAuthentication<BearerAuth, F>
+where
+    F: Fn(ServiceRequest, BearerAuth) -> O,
+    O: Future<Output = Result<ServiceRequest, Error>>,
+{
+    /// Construct `HttpAuthentication` middleware for the `Bearer` scheme.
+    pub fn bearer(validator: F) -> Self {
+        HttpAuthentication::with_fn(validator)
+    }
+}
+
+impl<S, B, T, F, O> Transform<S> for HttpAuthentication<T, F>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    T: FromRequest + 'static,
+    F: Fn(ServiceRequest, T) -> O + 'static,
+    O: Future<Output = Result<ServiceRequest, Error>> + 'static,
+    B: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = HttpAuthenticationMiddleware<S, T, F>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(HttpAuthenticationMiddleware {
+            service: Rc::new(RefCell::new(service)),
+            validator: self.validator.clone(),
+            _t: PhantomData,
+        })
+    }
+}
+
+#[doc(hidden)]
+pub struct HttpAuthenticationMiddleware<S, T, F> {
+    service: Rc<RefCell<S>>,
+    validator: Rc<F>,
+    _t: PhantomData<T>,
+}
+
+impl<S, B, T, F, O> Service for HttpAuthenticationMiddleware<S, T, F>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    T: FromRequest + 'static,
+    F: Fn(ServiceRequest, T) -> O + 'static,
+    O: Future<Output = Result<ServiceRequest, Error>> + 'static,
+    B: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        let mut srv = self.service.clone();
+        let validator = self.validator.clone();
+
+        async move {
+            let credentials = match T::extract(req.request()).await {
+                Ok(credentials) => credentials,
+                Err(e) => return Ok(req.error_response(e)),
+            };
+            let req = validator(req, credentials).await?;
+            srv.call(req).await
+        }
+        .boxed_local()
+    }
+}
@@ -7,7 +7,7 @@ use std::str::FromStr;
 use std::task::{Context, Poll};
 
 use crate::http::body::MessageBody;
-use crate::http::encoding::Encoder;
+use crate::http::encoding::{CompressionLevels, Encoder};
 use crate::http::header::{ContentEncoding, ACCEPT_ENCODING};
 use crate::http::error::Error;
 use crate::service::{Service, Transform};
@@ -22,6 +22,7 @@ use crate::web::service::{ServiceRequest, ServiceResponse};
 ///
 /// Use `BodyEncoding` trait for overriding response compression.
 /// To disable compression set encoding to `ContentEncoding::Identity` value.
+/// Compression level of each encoding can be changed with `Compress::level()`.
 ///
 /// ```rust
 /// use kayrx::web::{self, middleware, App, HttpResponse};
@@ -36,12 +37,33 @@ use crate::web::service::{ServiceRequest, ServiceResponse};
 ///         );
 /// }
 /// ```
-pub struct Compress(ContentEncoding);
+pub struct Compress {
+    encoding: ContentEncoding,
+    levels: CompressionLevels,
+}
 
 impl Compress {
     /// Create new `Compress` middleware with default encoding.
     pub fn new(encoding: ContentEncoding) -> Self {
-        Compress(encoding)
+        Compress {
+            encoding,
+            levels: CompressionLevels::default(),
+        }
+    }
+
+    /// Set compression level for `encoding`.
+    ///
+    /// ```rust
+    /// use kayrx::http::header::ContentEncoding;
+    /// use kayrx::web::middleware::Compress;
+    ///
+    /// let compress = Compress::default()
+    ///     .level(ContentEncoding::Gzip, 6)
+    ///     .level(ContentEncoding::Zstd, 10);
+    /// ```
+    pub fn level(mut self, encoding: ContentEncoding, level: u32) -> Self {
+        self.levels = self.levels.set(encoding, level);
+        self
     }
 }
 
@@ -66,7 +88,8 @@ where
     fn new_transform(&self, service: S) -> Self::Future {
         ok(CompressMiddleware {
             service,
-            encoding: self.0,
+            encoding: self.encoding,
+            levels: self.levels,
         })
     }
 }
@@ -74,6 +97,7 @@ where
 pub struct CompressMiddleware<S> {
     service: S,
     encoding: ContentEncoding,
+    levels: CompressionLevels,
 }
 
 impl<S, B> Service for CompressMiddleware<S>
@@ -104,6 +128,7 @@ where
 
         CompressResponse {
             encoding,
+            levels: self.levels,
             fut: self.service.call(req),
             _t: PhantomData,
         }
@@ -120,6 +145,7 @@ where
     #[pin]
     fut: S::Future,
     encoding: ContentEncoding,
+    levels: CompressionLevels,
     _t: PhantomData<B>,
 }
 
@@ -141,9 +167,10 @@ where
                     *this.encoding
                 };
 
-                Poll::Ready(Ok(
-                    resp.map_body(move |head, body| Encoder::response(enc, head, body))
-                ))
+                let levels = *this.levels;
+                Poll::Ready(Ok(resp.map_body(move |head, body| {
+                    Encoder::response_with_levels(enc, levels, head, body)
+                })))
             }
             Err(e) => Poll::Ready(Err(e)),
         }
@@ -0,0 +1,492 @@
+//! Cross-site request forgery protection for `web` applications.
+//!
+//! The `Csrf` middleware attaches a random token to every client, handlers
+//! get it with the `CsrfToken` extractor to render it into forms. Requests
+//! with unsafe methods, anything but `GET`, `HEAD`, `OPTIONS` and `TRACE`,
+//! have to send the token back in the `X-CSRF-Token` header or in the
+//! `csrf_token` field of an `application/x-www-form-urlencoded` body.
+//! Requests without a token are accepted only if their `Origin` or, lacking
+//! that, `Referer` header names the origin of the application or a trusted
+//! origin. Rejected requests are answered with `403 Forbidden`.
+//!
+//! The token is kept either in a cookie, the double-submit cookie pattern, or
+//! in the session, the synchronizer token pattern. The latter requires a
+//! `SessionMiddleware` that wraps the `Csrf` middleware.
+//!
+//! ```rust
+//! use kayrx::web::middleware::csrf::{Csrf, CsrfToken};
+//! use kayrx::web::{self, App, HttpResponse};
+//!
+//! async fn form(token: CsrfToken) -> HttpResponse {
+//!     HttpResponse::Ok().content_type("text/html").body(format!(
+//!         "<form method=\"post\">\
+//!            <input type=\"hidden\" name=\"csrf_token\" value=\"{}\">\
+//!            <input type=\"submit\">\
+//!          </form>",
+//!         token.token()
+//!     ))
+//! }
+//!
+//! fn main() {
+//!     let app = App::new()
+//!         .wrap(Csrf::double_submit().secure(true).exempt("/webhooks"))
+//!         .route("/", web::get().to(form))
+//!         .route("/", web::post().to(|| HttpResponse::Ok()))
+//!         .route("/webhooks/github", web::post().to(|| HttpResponse::Ok()));
+//! }
+//! ```
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+use bytes::BytesMut;
+use derive_more::Display;
+use futures_util::future::{err, ok, FutureExt, LocalBoxFuture, Ready};
+use futures_util::StreamExt;
+use rand::{thread_rng, Rng};
+use ring::constant_time::verify_slices_are_equal;
+
+use crate::http::error::coo_kie::{Cookie, SameSite};
+use crate::http::error::{ErrorInternalServerError, PayloadError, ResponseError};
+use crate::http::header::{HeaderName, HeaderValue, ORIGIN, REFERER, SET_COOKIE};
+use crate::http::{h1, HttpMessage, Payload, StatusCode};
+use crate::service::{Service, Transform};
+use crate::web::dev::{ServiceRequest, ServiceResponse};
+use crate::web::error::Error;
+use crate::web::middleware::session::Session;
+use crate::web::{FromRequest, HttpRequest};
+
+/// Key of the token in the session.
+const SESSION_KEY: &str = "csrf-token";
+
+/// Errors of requests rejected by the `Csrf` middleware
+#[derive(Debug, Display)]
+pub enum CsrfError {
+    /// Request has neither a token nor a trusted origin
+    #[display(fmt = "CSRF token is missing")]
+    MissingToken,
+
+    /// Token of the request does not match the token of the client
+    #[display(fmt = "CSRF token is invalid")]
+    InvalidToken,
+
+    /// Request without a token comes from an untrusted origin
+    #[display(fmt = "Request origin is not trusted")]
+    UntrustedOrigin,
+}
+
+impl ResponseError for CsrfError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::FORBIDDEN
+    }
+}
+
+/// The CSRF token of the client.
+///
+/// `CsrfToken` is an extractor, it is available to handlers of applications
+/// wrapped with a `Csrf` middleware. Without the middleware, using the
+/// extractor causes an *Internal Server Error* response.
+#[derive(Clone, Debug)]
+pub struct CsrfToken(String);
+
+impl CsrfToken {
+    /// The token to send back with unsafe requests.
+    pub fn token(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromRequest for CsrfToken {
+    type Error = Error;
+    type Future = Ready<Result<CsrfToken, Error>>;
+    type Config = ();
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        match req.extensions().get::<CsrfToken>() {
+            Some(token) => ok(token.clone()),
+            None => err(ErrorInternalServerError(
+                "CSRF token is not set, the Csrf middleware has to wrap the service",
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Storage {
+    Cookie,
+    Session,
+}
+
+/// `Middleware` that protects against cross-site request forgery.
+///
+/// See the [module documentation](index.html) for the checks.
+///
+/// With the double-submit cookie pattern the token cookie is named
+/// `csrf-token` and has a `SameSite=Strict` policy. It is not http only, so
+/// that scripts could copy it into the `X-CSRF-Token` header.
+pub struct Csrf {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    storage: Storage,
+    name: String,
+    path: String,
+    domain: Option<String>,
+    secure: bool,
+    http_only: bool,
+    same_site: Option<SameSite>,
+    header: HeaderName,
+    field: String,
+    form_limit: usize,
+    exempt: Vec<String>,
+    origins: Vec<String>,
+}
+
+impl Csrf {
+    fn new(storage: Storage) -> Self {
+        Csrf {
+            inner: Rc::new(Inner {
+                storage,
+                name: "csrf-token".to_owned(),
+                path: "/".to_owned(),
+                domain: None,
+                secure: false,
+                http_only: false,
+                same_site: Some(SameSite::Strict),
+                header: HeaderName::from_static("x-csrf-token"),
+                field: "csrf_token".to_owned(),
+                form_limit: 65_536,
+                exempt: Vec::new(),
+                origins: Vec::new(),
+            }),
+        }
+    }
+
+    /// Construct `Csrf` middleware keeping the token in a cookie.
+    pub fn double_submit() -> Self {
+        Csrf::new(Storage::Cookie)
+    }
+
+    /// Construct `Csrf` middleware keeping the token in the session.
+    pub fn synchronizer() -> Self {
+        Csrf::new(Storage::Session)
+    }
+
+    /// Sets the name of the token cookie.
+    pub fn name<S: Into<String>>(mut self, value: S) -> Self {
+        Rc::get_mut(&mut self.inner).unwrap().name = value.into();
+        self
+    }
+
+    /// Sets the `path` field of the token cookie, `/` by default.
+    pub fn path<S: Into<String>>(mut self, value: S) -> Self {
+        Rc::get_mut(&mut self.inner).unwrap().path = value.into();
+        self
+    }
+
+    /// Sets the `domain` field of the token cookie.
+    pub fn domain<S: Into<String>>(mut self, value: S) -> Self {
+        Rc::get_mut(&mut self.inner).unwrap().domain = Some(value.into());
+        self
+    }
+
+    /// Sets the `secure` field of the token cookie.
+    pub fn secure(mut self, value: bool) -> Self {
+        Rc::get_mut(&mut self.inner).unwrap().secure = value;
+        self
+    }
+
+    /// Sets the `http_only` field of the token cookie.
+    pub fn http_only(mut self, value: bool) -> Self {
+        Rc::get_mut(&mut self.inner).unwrap().http_only = value;
+        self
+    }
+
+    /// Sets the `same_site` field of the token cookie.
+    pub fn same_site(mut self, value: SameSite) -> Self {
+        Rc::get_mut(&mut self.inner).unwrap().same_site = Some(value);
+        self
+    }
+
+    /// Sets the name of the header carrying the token, `X-CSRF-Token` by
+    /// default.
+    pub fn header_name(mut self, value: HeaderName) -> Self {
+        Rc::get_mut(&mut self.inner).unwrap().header = value;
+        self
+    }
+
+    /// Sets the name of the form field carrying the token, `csrf_token` by
+    /// default.
+    pub fn form_field<S: Into<String>>(mut self, value: S) -> Self {
+        Rc::get_mut(&mut self.inner).unwrap().field = value.into();
+        self
+    }
+
+    /// Sets the maximum size of form bodies searched for the token, 64KiB by
+    /// default. Larger forms are answered with `413 Payload Too Large`.
+    pub fn form_limit(mut self, value: usize) -> Self {
+        Rc::get_mut(&mut self.inner).unwrap().form_limit = value;
+        self
+    }
+
+    /// Exempts the path and the paths below it from the checks.
+    pub fn exempt<S: Into<String>>(mut self, path: S) -> Self {
+        Rc::get_mut(&mut self.inner)
+            .unwrap()
+            .exempt
+            .push(path.into());
+        self
+    }
+
+    /// Trusts requests without a token from the origin, e.g.
+    /// `https://www.example.com`, in addition to the origin of the
+    /// application.
+    pub fn trusted_origin<S: Into<String>>(mut self, origin: S) -> Self {
+        let origin = origin.into().trim_end_matches('/').to_ascii_lowercase();
+        Rc::get_mut(&mut self.inner).unwrap().origins.push(origin);
+        self
+    }
+}
+
+impl<S, B> Transform<S> for Csrf
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = CsrfMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(CsrfMiddleware {
+            service: Rc::new(RefCell::new(service)),
+            inner: self.inner.clone(),
+        })
+    }
+}
+
+#[doc(hidden)]
+pub struct CsrfMiddleware<S> {
+    service: Rc<RefCell<S>>,
+    inner: Rc<Inner>,
+}
+
+impl<S, B> Service for CsrfMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: ServiceRequest) -> Self::Future {
+        let mut srv = self.service.clone();
+        let inner = self.inner.clone();
+
+        async move {
+            let session = match inner.storage {
+                Storage::Session => Some(Session::extract(req.request()).await?),
+                Storage::Cookie => None,
+            };
+            let current = match session {
+                Some(ref session) => session.get::<String>(SESSION_KEY).unwrap_or(None),
+                None => req.cookie(&inner.name).map(|c| c.value().to_owned()),
+            }
+            .filter(|token| !token.is_empty());
+
+            if !req.method().is_safe() && !inner.is_exempt(req.path()) {
+                let submitted = match inner.submitted_token(&mut req).await {
+                    Ok(submitted) => submitted,
+                    Err(e) => return Ok(req.error_response(e)),
+                };
+                let checked = match (submitted, current.as_ref()) {
+                    (Some(submitted), Some(current)) => {
+                        verify_slices_are_equal(submitted.as_bytes(), current.as_bytes())
+                            .map_err(|_| CsrfError::InvalidToken)
+                    }
+                    (Some(_), None) => Err(CsrfError::InvalidToken),
+                    (None, _) => inner.check_origin(&req),
+                };
+                if let Err(e) = checked {
+                    log::debug!("Rejected {} {}: {}", req.method(), req.path(), e);
+                    return Ok(req.error_response(e));
+                }
+            }
+
+            let (token, new) = match current {
+                Some(token) => (token, false),
+                None => (generate_token(), true),
+            };
+            if let (true, Some(session)) = (new, session) {
+                session.set(SESSION_KEY, &token)?;
+            }
+            req.extensions_mut().insert(CsrfToken(token.clone()));
+
+            let mut res = srv.call(req).await?;
+
+            if new && inner.storage == Storage::Cookie {
+                inner.set_cookie(&mut res, token)?;
+            }
+            Ok(res)
+        }
+        .boxed_local()
+    }
+}
+
+impl Inner {
+    fn is_exempt(&self, path: &str) -> bool {
+        self.exempt.iter().any(|prefix| {
+            path.starts_with(prefix.as_str())
+                && (prefix.ends_with('/')
+                    || path.len() == prefix.len()
+                    || path.as_bytes()[prefix.len()] == b'/')
+        })
+    }
+
+    /// Reads the token from the header or from the form body, the body is
+    /// put back for the handler.
+    async fn submitted_token(&self, req: &mut ServiceRequest) -> Result<Option<String>, Error> {
+        if let Some(value) = req.headers().get(&self.header) {
+            // a malformed token is not a missing one, it must not fall back to the origin
+            return match value.to_str() {
+                Ok(value) => Ok(Some(value.to_owned())),
+                Err(_) => Err(CsrfError::InvalidToken.into()),
+            };
+        }
+        if req.content_type() != "application/x-www-form-urlencoded" {
+            return Ok(None);
+        }
+
+        let mut payload = req.take_payload();
+        let mut body = BytesMut::new();
+        while let Some(chunk) = payload.next().await {
+            let chunk = chunk?;
+            if body.len() + chunk.len() > self.form_limit {
+                return Err(PayloadError::Overflow.into());
+            }
+            body.extend_from_slice(&chunk);
+        }
+        let body = body.freeze();
+
+        let token = serde_urlencoded::from_bytes::<Vec<(String, String)>>(&body)
+            .ok()
+            .and_then(|fields| {
+                fields
+                    .into_iter()
+                    .find(|(name, _)| *name == self.field)
+                    .map(|(_, value)| value)
+            });
+
+        let (_, mut pl) = h1::Payload::create(true);
+        pl.unread_data(body);
+        req.set_payload(pl.into());
+
+        Ok(token)
+    }
+
+    /// Checks the `Origin` header, or the origin of the `Referer` header.
+    fn check_origin(&self, req: &ServiceRequest) -> Result<(), CsrfError> {
+        let origin = match req.headers().get(ORIGIN) {
+            Some(origin) => origin.to_str().ok().map(|s| s.to_owned()),
+            None => match req.headers().get(REFERER) {
+                Some(referer) => referer.to_str().ok().and_then(referer_origin),
+                None => return Err(CsrfError::MissingToken),
+            },
+        };
+        let origin = match origin {
+            Some(origin) => origin.to_ascii_lowercase(),
+            None => return Err(CsrfError::UntrustedOrigin),
+        };
+
+        let info = req.connection_info();
+        let own = format!("{}://{}", info.scheme(), info.host()).to_ascii_lowercase();
+        if origin == own || self.origins.iter().any(|o| *o == origin) {
+            Ok(())
+        } else {
+            Err(CsrfError::UntrustedOrigin)
+        }
+    }
+
+    fn set_cookie<B>(&self, res: &mut ServiceResponse<B>, token: String) -> Result<(), Error> {
+        let mut cookie = Cookie::new(self.name.clone(), token);
+        cookie.set_path(self.path.clone());
+        cookie.set_secure(self.secure);
+        cookie.set_http_only(self.http_only);
+
+        if let Some(ref domain) = self.domain {
+            cookie.set_domain(domain.clone());
+        }
+
+        if let Some(same_site) = self.same_site {
+            cookie.set_same_site(same_site);
+        }
+
+        let value = HeaderValue::from_str(&cookie.encoded().to_string())?;
+        res.headers_mut().append(SET_COOKIE, value);
+        Ok(())
+    }
+}
+
+/// Returns `scheme://host[:port]` of the referer url.
+fn referer_origin(referer: &str) -> Option<String> {
+    let idx = referer.find("://")?;
+    let rest = &referer[idx + 3..];
+    let end = rest
+        .find(|c| c == '/' || c == '?' || c == '#')
+        .unwrap_or(rest.len());
+    if end == 0 {
+        return None;
+    }
+    Some(format!("{}://{}", &referer[..idx], &rest[..end]))
+}
+
+/// Generates 32 random bytes, base64url encoded.
+fn generate_token() -> String {
+    let mut token = [0u8; 32];
+    thread_rng().fill(&mut token);
+    base64::encode_config(&token, base64::URL_SAFE_NO_PAD)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_referer_origin() {
+        assert_eq!(
+            referer_origin("https://example.com:8443/path?q#f").unwrap(),
+            "https://example.com:8443"
+        );
+        assert_eq!(
+            referer_origin("http://example.com").unwrap(),
+            "http://example.com"
+        );
+        assert!(referer_origin("/relative").is_none());
+        assert!(referer_origin("https:///path").is_none());
+    }
+
+    #[test]
+    fn test_exempt() {
+        let csrf = Csrf::double_submit().exempt("/hooks").exempt("/api/");
+        assert!(csrf.inner.is_exempt("/hooks"));
+        assert!(csrf.inner.is_exempt("/hooks/github"));
+        assert!(!csrf.inner.is_exempt("/hooksx"));
+        assert!(csrf.inner.is_exempt("/api/v1"));
+        assert!(!csrf.inner.is_exempt("/api"));
+        assert!(!csrf.inner.is_exempt("/"));
+    }
+}
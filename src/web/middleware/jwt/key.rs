@@ -0,0 +1,366 @@
+use std::path::Path;
+use std::str::FromStr;
+
+use ring::{hmac, signature};
+use serde::Deserialize;
+
+use crate::web::client::Client;
+
+use super::JwtError;
+
+/// Signature algorithm of a token
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Algorithm {
+    /// HMAC using SHA-256
+    HS256,
+    /// RSASSA-PKCS1-v1_5 using SHA-256
+    RS256,
+    /// ECDSA using P-256 and SHA-256
+    ES256,
+}
+
+impl FromStr for Algorithm {
+    type Err = JwtError;
+
+    fn from_str(s: &str) -> Result<Algorithm, JwtError> {
+        match s {
+            "HS256" => Ok(Algorithm::HS256),
+            "RS256" => Ok(Algorithm::RS256),
+            "ES256" => Ok(Algorithm::ES256),
+            _ => Err(JwtError::UnsupportedAlgorithm),
+        }
+    }
+}
+
+/// Key verifying token signatures
+#[derive(Clone, Debug)]
+pub struct DecodingKey(KeyKind);
+
+#[derive(Clone, Debug)]
+enum KeyKind {
+    Secret(Vec<u8>),
+    Rsa { n: Vec<u8>, e: Vec<u8> },
+    Ec(Vec<u8>),
+}
+
+impl DecodingKey {
+    /// Create HMAC key from the shared secret
+    pub fn from_secret(secret: &[u8]) -> DecodingKey {
+        DecodingKey(KeyKind::Secret(secret.to_vec()))
+    }
+
+    /// Create RSA key from the big-endian modulus and exponent
+    pub fn from_rsa_components(n: &[u8], e: &[u8]) -> DecodingKey {
+        DecodingKey(KeyKind::Rsa {
+            n: strip_zeros(n).to_vec(),
+            e: strip_zeros(e).to_vec(),
+        })
+    }
+
+    /// Create RSA key from DER encoded PKCS#1 `RSAPublicKey`
+    pub fn from_rsa_der(der: &[u8]) -> Result<DecodingKey, JwtError> {
+        let mut key = Der::new(der).sequence()?;
+        let n = key.integer()?;
+        let e = key.integer()?;
+        Ok(DecodingKey::from_rsa_components(n, e))
+    }
+
+    /// Create RSA key from PEM encoded PKCS#1 `RSA PUBLIC KEY` or
+    /// `PUBLIC KEY` subject public key info
+    pub fn from_rsa_pem(pem: &[u8]) -> Result<DecodingKey, JwtError> {
+        let (label, der) = pem_decode(pem)?;
+        match label.as_str() {
+            "RSA PUBLIC KEY" => DecodingKey::from_rsa_der(&der),
+            "PUBLIC KEY" => DecodingKey::from_rsa_der(spki_key(&der)?),
+            _ => Err(JwtError::InvalidKey),
+        }
+    }
+
+    /// Create P-256 key from the uncompressed point, `0x04 || x || y`
+    pub fn from_ec_point(point: &[u8]) -> DecodingKey {
+        DecodingKey(KeyKind::Ec(point.to_vec()))
+    }
+
+    /// Create P-256 key from PEM encoded `PUBLIC KEY` subject public key
+    /// info
+    pub fn from_ec_pem(pem: &[u8]) -> Result<DecodingKey, JwtError> {
+        let (label, der) = pem_decode(pem)?;
+        if label != "PUBLIC KEY" {
+            return Err(JwtError::InvalidKey);
+        }
+        Ok(DecodingKey::from_ec_point(spki_key(&der)?))
+    }
+
+    fn supports(&self, alg: Algorithm) -> bool {
+        match (&self.0, alg) {
+            (KeyKind::Secret(_), Algorithm::HS256) => true,
+            (KeyKind::Rsa { .. }, Algorithm::RS256) => true,
+            (KeyKind::Ec(_), Algorithm::ES256) => true,
+            _ => false,
+        }
+    }
+
+    pub(super) fn verify(&self, alg: Algorithm, message: &[u8], sig: &[u8]) -> bool {
+        if !self.supports(alg) {
+            return false;
+        }
+        match self.0 {
+            KeyKind::Secret(ref secret) => {
+                let key = hmac::Key::new(hmac::HMAC_SHA256, secret);
+                hmac::verify(&key, message, sig).is_ok()
+            }
+            KeyKind::Rsa { ref n, ref e } => signature::RsaPublicKeyComponents { n, e }
+                .verify(&signature::RSA_PKCS1_2048_8192_SHA256, message, sig)
+                .is_ok(),
+            KeyKind::Ec(ref point) => {
+                signature::UnparsedPublicKey::new(&signature::ECDSA_P256_SHA256_FIXED, point)
+                    .verify(message, sig)
+                    .is_ok()
+            }
+        }
+    }
+}
+
+/// Set of keys verifying token signatures
+///
+/// If the token header contains key id, only the keys with the same id are
+/// tried, otherwise all keys of the token algorithm are tried.
+#[derive(Clone, Debug, Default)]
+pub struct KeySet {
+    keys: Vec<Key>,
+}
+
+#[derive(Clone, Debug)]
+struct Key {
+    kid: Option<String>,
+    alg: Algorithm,
+    key: DecodingKey,
+}
+
+#[derive(Deserialize)]
+struct JwkSet {
+    keys: Vec<Jwk>,
+}
+
+#[derive(Deserialize)]
+struct Jwk {
+    kty: String,
+    kid: Option<String>,
+    alg: Option<String>,
+    #[serde(rename = "use")]
+    use_: Option<String>,
+    k: Option<String>,
+    n: Option<String>,
+    e: Option<String>,
+    crv: Option<String>,
+    x: Option<String>,
+    y: Option<String>,
+}
+
+impl KeySet {
+    /// Create empty key set
+    pub fn new() -> KeySet {
+        KeySet::default()
+    }
+
+    /// Add key of the algorithm
+    pub fn key(mut self, alg: Algorithm, key: DecodingKey) -> Self {
+        self.keys.push(Key {
+            kid: None,
+            alg,
+            key,
+        });
+        self
+    }
+
+    /// Add key of the algorithm with key id
+    pub fn key_with_id<T: Into<String>>(
+        mut self,
+        kid: T,
+        alg: Algorithm,
+        key: DecodingKey,
+    ) -> Self {
+        self.keys.push(Key {
+            kid: Some(kid.into()),
+            alg,
+            key,
+        });
+        self
+    }
+
+    /// Returns the number of keys
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Returns `true` if there are no keys
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Create key set from JWK set document, defined in
+    /// [RFC7517](https://tools.ietf.org/html/rfc7517#section-5)
+    ///
+    /// Keys of unsupported types and encryption keys are skipped.
+    pub fn from_jwks(jwks: &[u8]) -> Result<KeySet, JwtError> {
+        let jwks: JwkSet = serde_json::from_slice(jwks).map_err(|_| JwtError::InvalidKey)?;
+
+        let mut keys = KeySet::new();
+        for jwk in jwks.keys {
+            if jwk.use_.as_ref().map(|u| u != "sig").unwrap_or(false) {
+                continue;
+            }
+            let (alg, key) = match jwk.kty.as_str() {
+                "oct" => (Algorithm::HS256, DecodingKey::from_secret(&b64(&jwk.k)?)),
+                "RSA" => (
+                    Algorithm::RS256,
+                    DecodingKey::from_rsa_components(&b64(&jwk.n)?, &b64(&jwk.e)?),
+                ),
+                "EC" if jwk.crv.as_ref().map(|c| c == "P-256").unwrap_or(false) => {
+                    let mut point = vec![4];
+                    point.extend(b64(&jwk.x)?);
+                    point.extend(b64(&jwk.y)?);
+                    (Algorithm::ES256, DecodingKey::from_ec_point(&point))
+                }
+                _ => continue,
+            };
+            let alg = match jwk.alg {
+                Some(ref alg) => match alg.parse() {
+                    Ok(alg) => alg,
+                    Err(_) => continue,
+                },
+                None => alg,
+            };
+            if !key.supports(alg) {
+                continue;
+            }
+            keys.keys.push(Key {
+                kid: jwk.kid,
+                alg,
+                key,
+            });
+        }
+        Ok(keys)
+    }
+
+    /// Load key set from JWK set document file
+    pub fn from_jwks_file<P: AsRef<Path>>(path: P) -> Result<KeySet, JwtError> {
+        KeySet::from_jwks(&std::fs::read(path)?)
+    }
+
+    /// Fetch key set from JWK set document url
+    pub async fn fetch_jwks(client: &Client, url: &str) -> Result<KeySet, JwtError> {
+        let mut res = client
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| JwtError::Fetch(e.to_string()))?;
+        if !res.status().is_success() {
+            return Err(JwtError::Fetch(format!(
+                "Unexpected status {}",
+                res.status()
+            )));
+        }
+        let body = res
+            .body()
+            .await
+            .map_err(|e| JwtError::Fetch(e.to_string()))?;
+        KeySet::from_jwks(&body)
+    }
+
+    pub(super) fn find<'a>(
+        &'a self,
+        kid: Option<&'a str>,
+        alg: Algorithm,
+    ) -> impl Iterator<Item = &'a DecodingKey> + 'a {
+        self.keys
+            .iter()
+            .filter(move |key| key.alg == alg && (kid.is_none() || key.kid.as_deref() == kid))
+            .map(|key| &key.key)
+    }
+}
+
+fn b64(value: &Option<String>) -> Result<Vec<u8>, JwtError> {
+    let value = value.as_ref().ok_or(JwtError::InvalidKey)?;
+    base64::decode_config(value, base64::URL_SAFE_NO_PAD).map_err(|_| JwtError::InvalidKey)
+}
+
+fn strip_zeros(mut value: &[u8]) -> &[u8] {
+    while value.len() > 1 && value[0] == 0 {
+        value = &value[1..];
+    }
+    value
+}
+
+/// Decodes PEM document, returns the label and the content.
+fn pem_decode(pem: &[u8]) -> Result<(String, Vec<u8>), JwtError> {
+    let pem = std::str::from_utf8(pem).map_err(|_| JwtError::InvalidKey)?;
+    let mut lines = pem.lines().map(str::trim).filter(|line| !line.is_empty());
+
+    let label = lines
+        .next()
+        .and_then(|line| line.strip_prefix("-----BEGIN "))
+        .and_then(|line| line.strip_suffix("-----"))
+        .ok_or(JwtError::InvalidKey)?
+        .to_owned();
+    let end = format!("-----END {}-----", label);
+
+    let mut content = String::new();
+    for line in lines {
+        if line == end {
+            let der = base64::decode(&content).map_err(|_| JwtError::InvalidKey)?;
+            return Ok((label, der));
+        }
+        content.push_str(line);
+    }
+    Err(JwtError::InvalidKey)
+}
+
+/// Returns the key of DER encoded subject public key info
+fn spki_key(der: &[u8]) -> Result<&[u8], JwtError> {
+    let mut spki = Der::new(der).sequence()?;
+    let _algorithm = spki.sequence()?;
+    match spki.bit_string()? {
+        [0, key @ ..] => Ok(key),
+        _ => Err(JwtError::InvalidKey),
+    }
+}
+
+/// Minimal DER reader
+struct Der<'a>(&'a [u8]);
+
+impl<'a> Der<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Der(data)
+    }
+
+    fn read(&mut self, tag: u8) -> Result<&'a [u8], JwtError> {
+        let data = self.0;
+        if data.len() < 2 || data[0] != tag {
+            return Err(JwtError::InvalidKey);
+        }
+        let (len, offset) = match data[1] {
+            len if len < 0x80 => (len as usize, 2),
+            0x81 if data.len() > 2 => (data[2] as usize, 3),
+            0x82 if data.len() > 3 => (((data[2] as usize) << 8) | data[3] as usize, 4),
+            _ => return Err(JwtError::InvalidKey),
+        };
+        if data.len() < offset + len {
+            return Err(JwtError::InvalidKey);
+        }
+        self.0 = &data[offset + len..];
+        Ok(&data[offset..offset + len])
+    }
+
+    fn sequence(&mut self) -> Result<Der<'a>, JwtError> {
+        self.read(0x30).map(Der)
+    }
+
+    fn integer(&mut self) -> Result<&'a [u8], JwtError> {
+        self.read(0x02)
+    }
+
+    fn bit_string(&mut self) -> Result<&'a [u8], JwtError> {
+        self.read(0x03)
+    }
+}
@@ -0,0 +1,245 @@
+//! JSON Web Token authentication for `web` applications.
+//!
+//! The `JwtAuth` middleware extracts the `Bearer` token of the request,
+//! verifies its signature with the keys of a `KeySet` and checks the `exp`,
+//! `nbf`, `aud` and `iss` claims according to the `Validation` settings. The
+//! claims of valid tokens are deserialized into the claims type and inserted
+//! into the request extensions, handlers access them with the `ReqData`
+//! extractor. Requests without a valid token are answered with
+//! `401 Unauthorized` and the `invalid_token` error of the `Bearer` challenge,
+//! configured by `BearerConfig`.
+//!
+//! `HS256`, `RS256` and `ES256` signatures are supported. Keys are added to
+//! the key set explicitly or loaded from a JWK set document, read from a file
+//! or fetched with the `web::client::Client`.
+//!
+//! ```rust
+//! use kayrx::web::middleware::jwt::{Algorithm, DecodingKey, JwtAuth, KeySet, Validation};
+//! use kayrx::web::{self, App, ReqData};
+//! use serde::Deserialize;
+//!
+//! #[derive(Clone, Deserialize)]
+//! struct Claims {
+//!     sub: String,
+//! }
+//!
+//! async fn index(claims: ReqData<Claims>) -> String {
+//!     format!("Hello, {}!", claims.sub)
+//! }
+//!
+//! fn main() {
+//!     let keys = KeySet::new().key(Algorithm::HS256, DecodingKey::from_secret(b"secret"));
+//!
+//!     let app = App::new().service(
+//!         web::scope("/api")
+//!             .wrap(
+//!                 JwtAuth::<Claims>::new(keys)
+//!                     .validation(Validation::new().leeway(60).audience("api")),
+//!             )
+//!             .route("/", web::get().to(index)),
+//!     );
+//! }
+//! ```
+
+mod key;
+mod validation;
+
+pub use self::key::{Algorithm, DecodingKey, KeySet};
+pub use self::validation::{decode, Validation};
+
+use std::cell::RefCell;
+use std::io;
+use std::marker::PhantomData;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+use derive_more::{Display, From};
+use futures_util::future::{ok, FutureExt, LocalBoxFuture, Ready};
+use serde::de::DeserializeOwned;
+
+use crate::http::header::{BearerChallenge, BearerError};
+use crate::http::HttpMessage;
+use crate::service::{Service, Transform};
+use crate::web::dev::{ServiceRequest, ServiceResponse};
+use crate::web::error::Error;
+use crate::web::middleware::auth::{AuthenticationError, BearerAuth, BearerConfig};
+use crate::web::FromRequest;
+
+/// Errors which can occur when decoding a token or loading keys
+#[derive(Debug, Display, From)]
+pub enum JwtError {
+    /// Token is not a valid JWS compact serialization
+    #[display(fmt = "Malformed token")]
+    Malformed,
+
+    /// Signature does not match any key
+    #[display(fmt = "Invalid signature")]
+    InvalidSignature,
+
+    /// Signature algorithm is not supported or not accepted
+    #[display(fmt = "Unsupported algorithm")]
+    UnsupportedAlgorithm,
+
+    /// Key set has no key for the token
+    #[display(fmt = "Unknown key")]
+    UnknownKey,
+
+    /// Token is expired
+    #[display(fmt = "Token is expired")]
+    Expired,
+
+    /// Token is not valid yet
+    #[display(fmt = "Token is not valid yet")]
+    NotYetValid,
+
+    /// Token audience is not accepted
+    #[display(fmt = "Invalid audience")]
+    InvalidAudience,
+
+    /// Token issuer is not accepted
+    #[display(fmt = "Invalid issuer")]
+    InvalidIssuer,
+
+    /// Required claim is missing
+    #[display(fmt = "Missing required claim: {}", _0)]
+    #[from(ignore)]
+    MissingClaim(&'static str),
+
+    /// Claims could not be deserialized
+    #[display(fmt = "Invalid claims: {}", _0)]
+    #[from(ignore)]
+    InvalidClaims(String),
+
+    /// Key could not be parsed
+    #[display(fmt = "Invalid key")]
+    InvalidKey,
+
+    /// Key set could not be read
+    #[display(fmt = "{}", _0)]
+    Io(io::Error),
+
+    /// Key set could not be fetched
+    #[display(fmt = "Failed to fetch key set: {}", _0)]
+    #[from(ignore)]
+    Fetch(String),
+}
+
+impl std::error::Error for JwtError {}
+
+/// `Middleware` for authenticating requests with JSON Web Tokens.
+///
+/// Claims of valid tokens are deserialized into `C` and inserted into the
+/// request extensions.
+///
+/// See the [module documentation](index.html) for an example.
+pub struct JwtAuth<C> {
+    inner: Rc<Inner>,
+    _t: PhantomData<C>,
+}
+
+struct Inner {
+    keys: KeySet,
+    validation: Validation,
+}
+
+impl<C> JwtAuth<C>
+where
+    C: DeserializeOwned + Clone + 'static,
+{
+    /// Construct `JwtAuth` middleware verifying tokens with the key set and
+    /// the default validation settings.
+    pub fn new(keys: KeySet) -> Self {
+        JwtAuth {
+            inner: Rc::new(Inner {
+                keys,
+                validation: Validation::default(),
+            }),
+            _t: PhantomData,
+        }
+    }
+
+    /// Set validation settings
+    pub fn validation(mut self, validation: Validation) -> Self {
+        Rc::get_mut(&mut self.inner).unwrap().validation = validation;
+        self
+    }
+}
+
+impl<S, B, C> Transform<S> for JwtAuth<C>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    C: DeserializeOwned + Clone + 'static,
+    B: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = JwtAuthMiddleware<S, C>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(JwtAuthMiddleware {
+            service: Rc::new(RefCell::new(service)),
+            inner: self.inner.clone(),
+            _t: PhantomData,
+        })
+    }
+}
+
+#[doc(hidden)]
+pub struct JwtAuthMiddleware<S, C> {
+    service: Rc<RefCell<S>>,
+    inner: Rc<Inner>,
+    _t: PhantomData<C>,
+}
+
+impl<S, B, C> Service for JwtAuthMiddleware<S, C>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    C: DeserializeOwned + Clone + 'static,
+    B: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        let mut srv = self.service.clone();
+        let inner = self.inner.clone();
+
+        async move {
+            let auth = match BearerAuth::extract(req.request()).await {
+                Ok(auth) => auth,
+                Err(e) => return Ok(req.error_response(e)),
+            };
+
+            match decode::<C>(auth.token(), &inner.keys, &inner.validation) {
+                Ok(claims) => {
+                    req.extensions_mut().insert(claims);
+                    srv.call(req).await
+                }
+                Err(e) => {
+                    log::debug!("Rejected bearer token: {}", e);
+                    let mut err = req
+                        .request()
+                        .app_data::<BearerConfig>()
+                        .map(AuthenticationError::from)
+                        .unwrap_or_else(|| AuthenticationError::new(BearerChallenge::default()))
+                        .with_error(BearerError::InvalidToken);
+                    let challenge = err.challenge_mut();
+                    *challenge = challenge.clone().error_description(e.to_string());
+                    Ok(req.error_response(err))
+                }
+            }
+        }
+        .boxed_local()
+    }
+}
@@ -0,0 +1,273 @@
+use std::collections::HashSet;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use super::key::{Algorithm, KeySet};
+use super::JwtError;
+
+/// Token validation settings
+///
+/// By default the signature and the `exp` claim are required, the `nbf`
+/// claim is checked if present and no leeway is allowed.
+#[derive(Clone, Debug)]
+pub struct Validation {
+    leeway: u64,
+    validate_exp: bool,
+    validate_nbf: bool,
+    audience: Option<HashSet<String>>,
+    issuer: Option<HashSet<String>>,
+    algorithms: Vec<Algorithm>,
+}
+
+impl Default for Validation {
+    fn default() -> Validation {
+        Validation {
+            leeway: 0,
+            validate_exp: true,
+            validate_nbf: true,
+            audience: None,
+            issuer: None,
+            algorithms: vec![Algorithm::HS256, Algorithm::RS256, Algorithm::ES256],
+        }
+    }
+}
+
+impl Validation {
+    /// Create default validation settings
+    pub fn new() -> Validation {
+        Validation::default()
+    }
+
+    /// Set leeway in seconds allowed for clock skew when checking the `exp`
+    /// and `nbf` claims
+    pub fn leeway(mut self, seconds: u64) -> Self {
+        self.leeway = seconds;
+        self
+    }
+
+    /// Require and check the `exp` claim, enabled by default
+    pub fn validate_exp(mut self, value: bool) -> Self {
+        self.validate_exp = value;
+        self
+    }
+
+    /// Check the `nbf` claim if present, enabled by default
+    pub fn validate_nbf(mut self, value: bool) -> Self {
+        self.validate_nbf = value;
+        self
+    }
+
+    /// Add accepted audience, the `aud` claim is required if any audience
+    /// is set
+    pub fn audience<T: Into<String>>(mut self, aud: T) -> Self {
+        self.audience
+            .get_or_insert_with(HashSet::new)
+            .insert(aud.into());
+        self
+    }
+
+    /// Add accepted issuer, the `iss` claim is required if any issuer is set
+    pub fn issuer<T: Into<String>>(mut self, iss: T) -> Self {
+        self.issuer
+            .get_or_insert_with(HashSet::new)
+            .insert(iss.into());
+        self
+    }
+
+    /// Set accepted signature algorithms, all supported algorithms are
+    /// accepted by default
+    pub fn algorithms(mut self, algorithms: &[Algorithm]) -> Self {
+        self.algorithms = algorithms.to_vec();
+        self
+    }
+
+    fn validate_claims(&self, claims: &Map<String, Value>, now: u64) -> Result<(), JwtError> {
+        let leeway = self.leeway as f64;
+        let now = now as f64;
+
+        if self.validate_exp {
+            match claims.get("exp") {
+                Some(exp) => {
+                    let exp = exp
+                        .as_f64()
+                        .ok_or(JwtError::InvalidClaims("exp is not a number".to_owned()))?;
+                    if exp + leeway <= now {
+                        return Err(JwtError::Expired);
+                    }
+                }
+                None => return Err(JwtError::MissingClaim("exp")),
+            }
+        }
+
+        if self.validate_nbf {
+            if let Some(nbf) = claims.get("nbf") {
+                let nbf = nbf
+                    .as_f64()
+                    .ok_or(JwtError::InvalidClaims("nbf is not a number".to_owned()))?;
+                if nbf > now + leeway {
+                    return Err(JwtError::NotYetValid);
+                }
+            }
+        }
+
+        if let Some(ref audience) = self.audience {
+            let accepted = match claims.get("aud") {
+                Some(Value::String(aud)) => audience.contains(aud),
+                Some(Value::Array(auds)) => auds.iter().any(|aud| {
+                    aud.as_str()
+                        .map(|aud| audience.contains(aud))
+                        .unwrap_or(false)
+                }),
+                Some(_) => false,
+                None => return Err(JwtError::MissingClaim("aud")),
+            };
+            if !accepted {
+                return Err(JwtError::InvalidAudience);
+            }
+        }
+
+        if let Some(ref issuer) = self.issuer {
+            match claims.get("iss") {
+                Some(Value::String(iss)) if issuer.contains(iss) => (),
+                Some(_) => return Err(JwtError::InvalidIssuer),
+                None => return Err(JwtError::MissingClaim("iss")),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+struct Header {
+    alg: String,
+    kid: Option<String>,
+}
+
+fn b64_json<T: DeserializeOwned>(part: &str) -> Result<T, JwtError> {
+    let data =
+        base64::decode_config(part, base64::URL_SAFE_NO_PAD).map_err(|_| JwtError::Malformed)?;
+    serde_json::from_slice(&data).map_err(|_| JwtError::Malformed)
+}
+
+/// Decode and validate the token in JWS compact serialization, returns the
+/// claims.
+///
+/// The signature is verified with the keys of the key set, then the
+/// registered claims are checked according to the validation settings.
+pub fn decode<C: DeserializeOwned>(
+    token: &str,
+    keys: &KeySet,
+    validation: &Validation,
+) -> Result<C, JwtError> {
+    let mut parts = token.splitn(3, '.');
+    let (header, payload, signature) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(header), Some(payload), Some(signature)) if !signature.contains('.') => {
+            (header, payload, signature)
+        }
+        _ => return Err(JwtError::Malformed),
+    };
+
+    let message = &token[..header.len() + 1 + payload.len()];
+
+    let header: Header = b64_json(header)?;
+    let alg: Algorithm = header.alg.parse()?;
+    if !validation.algorithms.contains(&alg) {
+        return Err(JwtError::UnsupportedAlgorithm);
+    }
+
+    let signature = base64::decode_config(signature, base64::URL_SAFE_NO_PAD)
+        .map_err(|_| JwtError::Malformed)?;
+
+    let mut keys = keys.find(header.kid.as_deref(), alg).peekable();
+    if keys.peek().is_none() {
+        return Err(JwtError::UnknownKey);
+    }
+    if !keys.any(|key| key.verify(alg, message.as_bytes(), &signature)) {
+        return Err(JwtError::InvalidSignature);
+    }
+
+    let claims: Map<String, Value> = b64_json(payload)?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    validation.validate_claims(&claims, now)?;
+
+    serde_json::from_value(Value::Object(claims))
+        .map_err(|e| JwtError::InvalidClaims(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn claims(value: Value) -> Map<String, Value> {
+        match value {
+            Value::Object(map) => map,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_exp_nbf() {
+        let validation = Validation::new();
+        assert!(validation
+            .validate_claims(&claims(json!({"exp": 1000})), 999)
+            .is_ok());
+        match validation.validate_claims(&claims(json!({"exp": 1000})), 1000) {
+            Err(JwtError::Expired) => (),
+            res => panic!("{:?}", res),
+        }
+        match validation.validate_claims(&claims(json!({})), 0) {
+            Err(JwtError::MissingClaim("exp")) => (),
+            res => panic!("{:?}", res),
+        }
+        match validation.validate_claims(&claims(json!({"exp": 2000, "nbf": 1001})), 1000) {
+            Err(JwtError::NotYetValid) => (),
+            res => panic!("{:?}", res),
+        }
+
+        let validation = Validation::new().leeway(30);
+        assert!(validation
+            .validate_claims(&claims(json!({"exp": 1000, "nbf": 1020})), 1010)
+            .is_ok());
+        assert!(Validation::new()
+            .validate_exp(false)
+            .validate_claims(&claims(json!({})), 0)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_aud_iss() {
+        let validation = Validation::new()
+            .validate_exp(false)
+            .audience("api")
+            .audience("web")
+            .issuer("https://auth.example.com");
+
+        let iss = "https://auth.example.com";
+        assert!(validation
+            .validate_claims(&claims(json!({"aud": "web", "iss": iss})), 0)
+            .is_ok());
+        assert!(validation
+            .validate_claims(&claims(json!({"aud": ["other", "api"], "iss": iss})), 0)
+            .is_ok());
+        match validation.validate_claims(&claims(json!({"aud": ["other"], "iss": iss})), 0) {
+            Err(JwtError::InvalidAudience) => (),
+            res => panic!("{:?}", res),
+        }
+        match validation.validate_claims(&claims(json!({"iss": iss})), 0) {
+            Err(JwtError::MissingClaim("aud")) => (),
+            res => panic!("{:?}", res),
+        }
+        match validation.validate_claims(&claims(json!({"aud": "api", "iss": "other"})), 0) {
+            Err(JwtError::InvalidIssuer) => (),
+            res => panic!("{:?}", res),
+        }
+    }
+}
@@ -18,7 +18,7 @@ use time::OffsetDateTime;
 
 use crate::web::dev::{BodySize, MessageBody, ResponseBody};
 use crate::web::error::{Error, Result};
-use crate::http::{HeaderName, StatusCode};
+use crate::http::{HeaderMap, HeaderName, StatusCode};
 use crate::web::service::{ServiceRequest, ServiceResponse};
 use crate::http::Response as HttpResponse;
 
@@ -273,6 +273,10 @@ impl<B: MessageBody> MessageBody for StreamLog<B> {
             val => val,
         }
     }
+
+    fn take_trailers(&mut self) -> Option<HeaderMap> {
+        self.body.take_trailers()
+    }
 }
 
 /// A formatting style for the `Logger`, consisting of multiple
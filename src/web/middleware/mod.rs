@@ -1,19 +1,33 @@
 //! Middlewares
 
+pub mod auth;
 mod compress;
 mod condition;
 mod cors;
+#[cfg(feature = "cookie")]
+pub mod csrf;
 mod defaultheaders;
 pub mod errhandlers;
+pub mod jwt;
 mod logger;
 mod normalize;
+pub mod ratelimit;
+pub mod secureheaders;
+#[cfg(feature = "cookie")]
+pub mod session;
 
+pub use self::auth::HttpAuthentication;
 pub use self::cors::Cors;
+#[cfg(feature = "cookie")]
+pub use self::csrf::Csrf;
 pub use self::compress::Compress;
 pub use self::condition::Condition;
 pub use self::defaultheaders::DefaultHeaders;
+pub use self::jwt::JwtAuth;
 pub use self::logger::Logger;
 pub use self::normalize::NormalizePath;
+pub use self::ratelimit::RateLimiter;
+pub use self::secureheaders::SecureHeaders;
 
 pub mod dev {
     pub use super::logger::{Format, FormatDisplay};
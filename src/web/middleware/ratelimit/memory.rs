@@ -0,0 +1,447 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex, Weak};
+use std::task::{Poll, Waker};
+use std::time::{Duration, Instant};
+
+use futures_util::future::{ok, poll_fn, FutureExt, LocalBoxFuture};
+
+use crate::fiber::Arbiter;
+use crate::timer::DelayQueue;
+use crate::web::error::Error;
+
+use super::{Algorithm, Decision, Quota, RateLimitStore};
+
+/// Rate limit store that keeps the state of quotas in memory.
+///
+/// Keys are spread over shards, each guarded by its own lock. Clones of the
+/// store share the same state, so a single store has to be created outside
+/// of the application factory and cloned into it:
+///
+/// ```rust
+/// use std::time::Duration;
+/// use kayrx::web::middleware::ratelimit::{MemoryRateLimitStore, Quota, RateLimiter};
+/// use kayrx::web::{App, HttpServer};
+///
+/// # fn main() {
+/// let store = MemoryRateLimitStore::new();
+///
+/// HttpServer::new(move || {
+///     App::new().wrap(RateLimiter::new(
+///         store.clone(),
+///         Quota::token_bucket(10, Duration::from_secs(1)),
+///     ))
+/// });
+/// # }
+/// ```
+///
+/// Keys are dropped once their quota is fully available again, by a
+/// background task driven by a `timer::DelayQueue`. The task is spawned on
+/// the worker which uses the store first, and again on the next new key if
+/// that worker has stopped.
+#[derive(Clone)]
+pub struct MemoryRateLimitStore(Arc<Shared>);
+
+struct Shared {
+    shards: Vec<Mutex<HashMap<String, Entry>>>,
+    reaper: Mutex<Reaper>,
+}
+
+struct Entry {
+    state: State,
+    expires: Instant,
+}
+
+#[derive(Default)]
+struct Reaper {
+    /// Keys the reaper has not put into its delay queue yet
+    scheduled: Vec<(usize, String, Instant)>,
+    waker: Option<Waker>,
+    /// Liveness of the arbiter the reaper runs on, unset once the reaper exits
+    arbiter: Option<Weak<()>>,
+}
+
+impl Default for MemoryRateLimitStore {
+    fn default() -> Self {
+        MemoryRateLimitStore::with_shards(16)
+    }
+}
+
+impl MemoryRateLimitStore {
+    /// Construct new, empty `MemoryRateLimitStore` instance.
+    pub fn new() -> MemoryRateLimitStore {
+        MemoryRateLimitStore::default()
+    }
+
+    /// Construct new, empty `MemoryRateLimitStore` instance with the number
+    /// of shards.
+    ///
+    /// By default the store has 16 shards.
+    pub fn with_shards(shards: usize) -> MemoryRateLimitStore {
+        assert!(shards > 0, "Number of shards must be greater than zero");
+        MemoryRateLimitStore(Arc::new(Shared {
+            shards: (0..shards).map(|_| Mutex::new(HashMap::new())).collect(),
+            reaper: Mutex::new(Reaper::default()),
+        }))
+    }
+
+    /// Returns the number of keys, including expired ones that were not
+    /// dropped yet.
+    pub fn len(&self) -> usize {
+        self.0
+            .shards
+            .iter()
+            .map(|shard| shard.lock().unwrap().len())
+            .sum()
+    }
+
+    /// Returns `true` if there are no keys.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn shard(&self, key: &str) -> usize {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        (hasher.finish() % self.0.shards.len() as u64) as usize
+    }
+
+    fn schedule(&self, idx: usize, key: String, expires: Instant) {
+        let mut reaper = self.0.reaper.lock().unwrap();
+        let running = reaper
+            .arbiter
+            .as_ref()
+            .map_or(false, |arbiter| arbiter.upgrade().is_some());
+
+        if running {
+            reaper.scheduled.push((idx, key, expires));
+            if let Some(ref waker) = reaper.waker {
+                waker.wake_by_ref();
+            }
+        } else {
+            let arbiter = Arbiter::liveness();
+            reaper.arbiter = Some(arbiter.clone());
+            reaper.waker = None;
+            // the delay queue of a previous reaper is gone along with it
+            reaper.scheduled.clear();
+            for (idx, shard) in self.0.shards.iter().enumerate() {
+                let shard = shard.lock().unwrap();
+                reaper.scheduled.extend(
+                    shard
+                        .iter()
+                        .map(|(key, entry)| (idx, key.clone(), entry.expires)),
+                );
+            }
+            crate::fiber::spawn(reap(Arc::downgrade(&self.0), arbiter));
+        }
+    }
+}
+
+impl RateLimitStore for MemoryRateLimitStore {
+    fn acquire(
+        &self,
+        key: &str,
+        quota: &Quota,
+    ) -> LocalBoxFuture<'static, Result<Decision, Error>> {
+        let idx = self.shard(key);
+        let now = Instant::now();
+
+        let (decision, new) = {
+            let mut shard = self.0.shards[idx].lock().unwrap();
+            match shard.get_mut(key) {
+                Some(entry) => {
+                    let (decision, expires) = entry.state.acquire(quota, now);
+                    entry.expires = expires;
+                    (decision, None)
+                }
+                None => {
+                    let mut state = State::new(quota, now);
+                    let (decision, expires) = state.acquire(quota, now);
+                    shard.insert(key.to_owned(), Entry { state, expires });
+                    (decision, Some(expires))
+                }
+            }
+        };
+
+        if let Some(expires) = new {
+            self.schedule(idx, key.to_owned(), expires);
+        }
+        ok(decision).boxed_local()
+    }
+}
+
+impl Drop for Shared {
+    fn drop(&mut self) {
+        // let the reaper task exit
+        if let Some(waker) = self.reaper.get_mut().unwrap().waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Drops keys once they expire.
+///
+/// A key is put into the delay queue once, when it is created. If the key was
+/// used in the meantime, it is put back with its new expiration.
+async fn reap(shared: Weak<Shared>, arbiter: Weak<()>) {
+    let _guard = ReaperGuard {
+        shared: shared.clone(),
+        arbiter,
+    };
+    let mut expirations = DelayQueue::new();
+
+    poll_fn(|cx| {
+        let shared = match shared.upgrade() {
+            Some(shared) => shared,
+            None => return Poll::Ready(()),
+        };
+
+        let now = Instant::now();
+        {
+            let mut reaper = shared.reaper.lock().unwrap();
+            reaper.waker = Some(cx.waker().clone());
+            for (idx, key, expires) in reaper.scheduled.drain(..) {
+                expirations.insert((idx, key), expires.saturating_duration_since(now));
+            }
+        }
+
+        loop {
+            match expirations.poll_expired(cx) {
+                Poll::Ready(Some(Ok(expired))) => {
+                    let (idx, key) = expired.into_inner();
+                    let mut shard = shared.shards[idx].lock().unwrap();
+                    let now = Instant::now();
+                    match shard.get(&key).map(|entry| entry.expires) {
+                        Some(expires) if expires > now => {
+                            drop(shard);
+                            expirations.insert((idx, key), expires - now);
+                        }
+                        Some(_) => {
+                            shard.remove(&key);
+                        }
+                        None => (),
+                    }
+                }
+                Poll::Ready(Some(Err(err))) => {
+                    log::error!("Rate limit store timer error: {}", err);
+                    return Poll::Ready(());
+                }
+                Poll::Ready(None) | Poll::Pending => return Poll::Pending,
+            }
+        }
+    })
+    .await
+}
+
+/// Lets the next new key start a new reaper once the task exits.
+struct ReaperGuard {
+    shared: Weak<Shared>,
+    arbiter: Weak<()>,
+}
+
+impl Drop for ReaperGuard {
+    fn drop(&mut self) {
+        if let Some(shared) = self.shared.upgrade() {
+            let mut reaper = shared.reaper.lock().unwrap();
+            // another reaper may have replaced this one
+            if let Some(ref arbiter) = reaper.arbiter {
+                if arbiter.ptr_eq(&self.arbiter) {
+                    reaper.arbiter = None;
+                    reaper.waker = None;
+                }
+            }
+        }
+    }
+}
+
+/// State of a quota
+#[derive(Debug)]
+enum State {
+    TokenBucket {
+        tokens: f64,
+        updated: Instant,
+    },
+    SlidingWindow {
+        start: Instant,
+        previous: u32,
+        current: u32,
+    },
+}
+
+impl State {
+    fn new(quota: &Quota, now: Instant) -> State {
+        match quota.algorithm {
+            Algorithm::TokenBucket => State::TokenBucket {
+                tokens: f64::from(quota.limit),
+                updated: now,
+            },
+            Algorithm::SlidingWindow => State::SlidingWindow {
+                start: now,
+                previous: 0,
+                current: 0,
+            },
+        }
+    }
+
+    /// Takes one request, returns the decision and the instant the state
+    /// expires at.
+    fn acquire(&mut self, quota: &Quota, now: Instant) -> (Decision, Instant) {
+        match self {
+            State::TokenBucket { .. } if quota.algorithm == Algorithm::TokenBucket => (),
+            State::SlidingWindow { .. } if quota.algorithm == Algorithm::SlidingWindow => (),
+            // the quota of the key was changed
+            _ => *self = State::new(quota, now),
+        }
+
+        let limit = f64::from(quota.limit);
+        let period = quota.period.as_secs_f64();
+
+        match self {
+            State::TokenBucket { tokens, updated } => {
+                let rate = limit / period;
+                let elapsed = now.saturating_duration_since(*updated).as_secs_f64();
+                *tokens = (*tokens + elapsed * rate).min(limit);
+                *updated = now;
+
+                let allowed = *tokens >= 1.0;
+                if allowed {
+                    *tokens -= 1.0;
+                }
+                let reset = Duration::from_secs_f64((limit - *tokens) / rate);
+                let retry_after = if allowed {
+                    None
+                } else {
+                    Some(Duration::from_secs_f64((1.0 - *tokens) / rate))
+                };
+
+                let decision = Decision {
+                    allowed,
+                    limit: quota.limit,
+                    remaining: tokens.floor() as u32,
+                    reset,
+                    retry_after,
+                };
+                (decision, now + reset)
+            }
+            State::SlidingWindow {
+                start,
+                previous,
+                current,
+            } => {
+                let elapsed = now.saturating_duration_since(*start);
+                if elapsed >= quota.period * 2 {
+                    *start = now;
+                    *previous = 0;
+                    *current = 0;
+                } else if elapsed >= quota.period {
+                    *start += quota.period;
+                    *previous = *current;
+                    *current = 0;
+                }
+
+                let elapsed = now.saturating_duration_since(*start).as_secs_f64();
+                let mut estimate =
+                    f64::from(*previous) * (1.0 - elapsed / period) + f64::from(*current);
+
+                let allowed = estimate + 1.0 <= limit;
+                let retry_after = if allowed {
+                    *current += 1;
+                    estimate += 1.0;
+                    None
+                } else {
+                    // the estimate has to drop to `limit - 1`
+                    let slack = limit - 1.0;
+                    let wait = if f64::from(*current) > slack {
+                        // only in one of the next windows
+                        period - elapsed + period * (1.0 - slack / f64::from(*current))
+                    } else {
+                        period * (1.0 - (slack - f64::from(*current)) / f64::from(*previous))
+                            - elapsed
+                    };
+                    Some(Duration::from_secs_f64(wait.max(0.0)))
+                };
+
+                let decision = Decision {
+                    allowed,
+                    limit: quota.limit,
+                    remaining: (limit - estimate).max(0.0).floor() as u32,
+                    reset: (*start + quota.period).saturating_duration_since(now),
+                    retry_after,
+                };
+                (decision, *start + quota.period * 2)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_bucket() {
+        let quota = Quota::token_bucket(2, Duration::from_secs(2));
+        let now = Instant::now();
+        let mut state = State::new(&quota, now);
+
+        let (decision, expires) = state.acquire(&quota, now);
+        assert!(decision.allowed);
+        assert_eq!(decision.remaining, 1);
+        assert_eq!(decision.reset, Duration::from_secs(1));
+        assert_eq!(expires, now + Duration::from_secs(1));
+
+        let (decision, _) = state.acquire(&quota, now);
+        assert!(decision.allowed);
+        assert_eq!(decision.remaining, 0);
+        assert_eq!(decision.reset, Duration::from_secs(2));
+
+        let (decision, _) = state.acquire(&quota, now + Duration::from_millis(500));
+        assert!(!decision.allowed);
+        assert_eq!(decision.retry_after, Some(Duration::from_millis(500)));
+
+        // one and a half tokens refilled
+        let (decision, _) = state.acquire(&quota, now + Duration::from_millis(1500));
+        assert!(decision.allowed);
+        assert_eq!(decision.remaining, 0);
+        assert_eq!(decision.reset, Duration::from_millis(1500));
+    }
+
+    #[test]
+    fn test_sliding_window() {
+        let quota = Quota::sliding_window(4, Duration::from_secs(10));
+        let now = Instant::now();
+        let mut state = State::new(&quota, now);
+
+        for remaining in (0..4).rev() {
+            let (decision, expires) = state.acquire(&quota, now);
+            assert!(decision.allowed);
+            assert_eq!(decision.remaining, remaining);
+            assert_eq!(expires, now + Duration::from_secs(20));
+        }
+        let (decision, _) = state.acquire(&quota, now + Duration::from_secs(5));
+        assert!(!decision.allowed);
+        assert_eq!(decision.reset, Duration::from_secs(5));
+        // in the next window the estimate is 4 * (1 - t / 10)
+        assert_eq!(decision.retry_after, Some(Duration::from_millis(7500)));
+
+        // previous window weighted by 0.75
+        let (decision, _) = state.acquire(&quota, now + Duration::from_millis(12500));
+        assert!(decision.allowed);
+        assert_eq!(decision.remaining, 0);
+
+        let (decision, _) = state.acquire(&quota, now + Duration::from_secs(15));
+        assert!(decision.allowed);
+        assert_eq!(decision.remaining, 0);
+
+        let (decision, _) = state.acquire(&quota, now + Duration::from_secs(16));
+        assert!(!decision.allowed);
+        // the estimate 4 * (1 - t / 10) + 2 drops to 3 at 17.5s
+        assert_eq!(decision.retry_after, Some(Duration::from_millis(1500)));
+
+        // both windows passed
+        let (decision, _) = state.acquire(&quota, now + Duration::from_secs(40));
+        assert!(decision.allowed);
+        assert_eq!(decision.remaining, 3);
+    }
+}
@@ -0,0 +1,441 @@
+//! Rate limiting for `web` applications.
+//!
+//! The `RateLimiter` middleware takes one request from the `Quota` of the
+//! request key before the request is handled. Requests over the quota are
+//! answered with `429 Too Many Requests` and a `Retry-After` header, all
+//! responses carry `RateLimit-Limit`, `RateLimit-Remaining` and
+//! `RateLimit-Reset` headers.
+//!
+//! The key is computed by a closure over the `ServiceRequest`, by default it
+//! is the client ip address. Counters are kept by a `RateLimitStore`, the
+//! `MemoryRateLimitStore` keeps them in memory of the process.
+//!
+//! ```rust
+//! use std::time::Duration;
+//! use kayrx::web::middleware::ratelimit::{self, MemoryRateLimitStore, Quota, RateLimiter};
+//! use kayrx::web::{self, App, HttpResponse, HttpServer};
+//!
+//! # fn main() {
+//! let store = MemoryRateLimitStore::new();
+//!
+//! HttpServer::new(move || {
+//!     App::new()
+//!         .wrap(
+//!             RateLimiter::new(
+//!                 store.clone(),
+//!                 Quota::token_bucket(100, Duration::from_secs(60)),
+//!             )
+//!             .scope("/upload", Quota::token_bucket(10, Duration::from_secs(60)))
+//!             .resource("/status", Quota::token_bucket(600, Duration::from_secs(60))),
+//!         )
+//!         .service(
+//!             web::scope("/api")
+//!                 .wrap(
+//!                     RateLimiter::new(
+//!                         store.clone(),
+//!                         Quota::sliding_window(1000, Duration::from_secs(3600)),
+//!                     )
+//!                     .key(ratelimit::header("x-api-key")),
+//!                 )
+//!                 .route("/", web::get().to(|| HttpResponse::Ok())),
+//!         )
+//! });
+//! # }
+//! ```
+//!
+//! `RateLimiter::scope()` and `RateLimiter::resource()` override the quota
+//! of the limiter for the requests of a `Scope` or a `Resource`, the override
+//! replaces the quota of the limiter and could be looser or tighter than it.
+//!
+//! Limiters of a `Scope` or a `Resource` are applied in addition to the
+//! limiters of the enclosing application or scope, each limiter counts
+//! requests separately, so they could only tighten the quota. The
+//! `RateLimit-*` headers of the response describe the quota with the least
+//! remaining requests. A key closure returning `None` skips the limiter.
+
+mod memory;
+
+pub use self::memory::MemoryRateLimitStore;
+
+use std::cell::RefCell;
+use std::convert::TryFrom;
+use std::net::{IpAddr, SocketAddr};
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures_util::future::{ok, FutureExt, LocalBoxFuture, Ready};
+
+use crate::http::header::{HeaderName, HeaderValue, RETRY_AFTER};
+use crate::http::{HeaderMap, Response};
+use crate::router::ResourceDef;
+use crate::service::{Service, Transform};
+use crate::web::dev::{ServiceRequest, ServiceResponse};
+use crate::web::error::Error;
+
+/// Rate limiting algorithm
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Algorithm {
+    /// The bucket holds up to `limit` tokens, a request takes one token and
+    /// the bucket is refilled at the rate of `limit` tokens per `period`.
+    /// Allows bursts up to `limit` requests.
+    TokenBucket,
+    /// Requests are counted in windows of `period`, the count of the
+    /// previous window is weighted by its overlap with the sliding window
+    /// ending at the current instant.
+    SlidingWindow,
+}
+
+/// Number of requests allowed per period.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Quota {
+    algorithm: Algorithm,
+    limit: u32,
+    period: Duration,
+}
+
+impl Quota {
+    /// Create quota of `limit` requests per `period`.
+    ///
+    /// Panics if `limit` or `period` is zero.
+    pub fn new(algorithm: Algorithm, limit: u32, period: Duration) -> Quota {
+        assert!(limit > 0, "Quota limit must be greater than zero");
+        assert!(
+            period > Duration::from_secs(0),
+            "Quota period must not be zero"
+        );
+        Quota {
+            algorithm,
+            limit,
+            period,
+        }
+    }
+
+    /// Create token bucket quota.
+    pub fn token_bucket(limit: u32, period: Duration) -> Quota {
+        Quota::new(Algorithm::TokenBucket, limit, period)
+    }
+
+    /// Create sliding window quota.
+    pub fn sliding_window(limit: u32, period: Duration) -> Quota {
+        Quota::new(Algorithm::SlidingWindow, limit, period)
+    }
+
+    /// Rate limiting algorithm
+    pub fn algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+
+    /// Number of requests allowed per period
+    pub fn limit(&self) -> u32 {
+        self.limit
+    }
+
+    /// Period of the quota
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+}
+
+/// Outcome of taking a request from a quota.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Decision {
+    /// Whether the request is allowed
+    pub allowed: bool,
+    /// Number of requests allowed per period
+    pub limit: u32,
+    /// Number of requests left
+    pub remaining: u32,
+    /// Time until the quota is fully available again
+    pub reset: Duration,
+    /// Time until the next request would be allowed, set only if the request
+    /// is not allowed.
+    pub retry_after: Option<Duration>,
+}
+
+/// Storage backend of the `RateLimiter`.
+///
+/// A store keeps the state of a quota for every key. It is shared by the
+/// limiters of all workers, so a single store has to be created outside of
+/// the application factory and cloned into it.
+pub trait RateLimitStore: 'static {
+    /// Takes one request from the quota of `key`.
+    ///
+    /// The state of the key is updated only if the request is allowed.
+    fn acquire(&self, key: &str, quota: &Quota)
+        -> LocalBoxFuture<'static, Result<Decision, Error>>;
+}
+
+/// Key of the client ip address.
+///
+/// The key is the peer address of the connection. Only if the peer is one of
+/// the proxies set with
+/// [`HttpServer::trusted_proxies()`](../../struct.HttpServer.html#method.trusted_proxies),
+/// the client address is taken from the forwarded headers by
+/// [`ConnectionInfo::remote()`](../../dev/struct.ConnectionInfo.html#method.remote).
+pub fn client_ip(req: &ServiceRequest) -> Option<String> {
+    if let Some(addr) = req.peer_addr() {
+        if !req.app_config().trusted_proxies().is_trusted(addr.ip()) {
+            return Some(addr.ip().to_string());
+        }
+    }
+
+    let info = req.connection_info();
+    let remote = info.remote()?;
+    if let Ok(addr) = remote.parse::<SocketAddr>() {
+        Some(addr.ip().to_string())
+    } else if let Ok(ip) = remote.parse::<IpAddr>() {
+        Some(ip.to_string())
+    } else {
+        Some(remote.to_owned())
+    }
+}
+
+/// Key of the request header value.
+///
+/// Requests without the header are not limited.
+pub fn header<K>(name: K) -> impl Fn(&ServiceRequest) -> Option<String>
+where
+    HeaderName: TryFrom<K>,
+{
+    let name = match HeaderName::try_from(name) {
+        Ok(name) => name,
+        Err(_) => panic!("Can not create header name"),
+    };
+    move |req: &ServiceRequest| {
+        req.headers()
+            .get(&name)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_owned())
+    }
+}
+
+/// Key of the path parameter.
+///
+/// Parameters are available only for the limiters of a `Scope` or a
+/// `Resource` which declares them. Requests without the parameter are not
+/// limited.
+pub fn path_param(name: &'static str) -> impl Fn(&ServiceRequest) -> Option<String> {
+    move |req: &ServiceRequest| req.match_info().get(name).map(|value| value.to_owned())
+}
+
+/// `Middleware` for limiting the rate of requests.
+///
+/// See the [module documentation](index.html) for an example.
+pub struct RateLimiter<T> {
+    inner: Rc<Inner<T>>,
+}
+
+struct Inner<T> {
+    store: T,
+    quota: Quota,
+    overrides: Vec<Override>,
+    namespace: String,
+    key: Box<dyn Fn(&ServiceRequest) -> Option<String>>,
+}
+
+/// Quota of a `Scope` or a `Resource`
+struct Override {
+    rdef: ResourceDef,
+    prefix: bool,
+    quota: Quota,
+}
+
+impl<T> Inner<T> {
+    /// Quota of the request, and the suffix of its namespace
+    fn quota(&self, path: &str) -> (&Quota, &str) {
+        for item in &self.overrides {
+            let matched = if item.prefix {
+                item.rdef.is_prefix_match(path).is_some()
+            } else {
+                item.rdef.is_match(path)
+            };
+            if matched {
+                return (&item.quota, item.rdef.pattern());
+            }
+        }
+        (&self.quota, "")
+    }
+}
+
+impl<T: RateLimitStore> RateLimiter<T> {
+    /// Construct new `RateLimiter` instance on top of `store`.
+    pub fn new(store: T, quota: Quota) -> Self {
+        RateLimiter {
+            inner: Rc::new(Inner {
+                store,
+                quota,
+                overrides: Vec::new(),
+                namespace: format!(
+                    "{:?}-{}-{}",
+                    quota.algorithm,
+                    quota.limit,
+                    quota.period.as_millis()
+                ),
+                key: Box::new(client_ip),
+            }),
+        }
+    }
+
+    /// Sets the function computing the key of a request.
+    ///
+    /// Requests without a key are not limited. By default the key is the
+    /// client ip address.
+    pub fn key<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&ServiceRequest) -> Option<String> + 'static,
+    {
+        Rc::get_mut(&mut self.inner).unwrap().key = Box::new(f);
+        self
+    }
+
+    /// Uses `quota` instead of the quota of the limiter for the requests of
+    /// the `Scope` at `path`.
+    ///
+    /// The path is the full path of the scope, including the paths of the
+    /// enclosing scopes. Overrides are checked in the order they are added.
+    pub fn scope(self, path: &str, quota: Quota) -> Self {
+        self.add_override(ResourceDef::root_prefix(path), true, quota)
+    }
+
+    /// Uses `quota` instead of the quota of the limiter for the requests of
+    /// the `Resource` at `path`.
+    ///
+    /// The path is the full path of the resource, including the paths of the
+    /// enclosing scopes. Overrides are checked in the order they are added.
+    pub fn resource(self, path: &str, quota: Quota) -> Self {
+        self.add_override(ResourceDef::new(path), false, quota)
+    }
+
+    fn add_override(mut self, rdef: ResourceDef, prefix: bool, quota: Quota) -> Self {
+        Rc::get_mut(&mut self.inner)
+            .unwrap()
+            .overrides
+            .push(Override {
+                rdef,
+                prefix,
+                quota,
+            });
+        self
+    }
+
+    /// Sets the prefix of the keys in the store.
+    ///
+    /// Limiters with the same namespace share counters. By default the
+    /// namespace is derived from the quota, so limiters with different
+    /// quotas are counted separately.
+    pub fn namespace<S: Into<String>>(mut self, value: S) -> Self {
+        Rc::get_mut(&mut self.inner).unwrap().namespace = value.into();
+        self
+    }
+}
+
+impl<S, T, B> Transform<S> for RateLimiter<T>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    T: RateLimitStore,
+    B: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RateLimiterMiddleware<S, T>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(RateLimiterMiddleware {
+            service: Rc::new(RefCell::new(service)),
+            inner: self.inner.clone(),
+        })
+    }
+}
+
+#[doc(hidden)]
+pub struct RateLimiterMiddleware<S, T> {
+    service: Rc<RefCell<S>>,
+    inner: Rc<Inner<T>>,
+}
+
+impl<S, T, B> Service for RateLimiterMiddleware<S, T>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    T: RateLimitStore,
+    B: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        let (quota, suffix) = self.inner.quota(req.path());
+        let key = match (self.inner.key)(&req) {
+            Some(key) => format!("{}{}:{}", self.inner.namespace, suffix, key),
+            None => return self.service.call(req).boxed_local(),
+        };
+        let quota = *quota;
+        let mut srv = self.service.clone();
+        let inner = self.inner.clone();
+
+        async move {
+            let decision = inner.store.acquire(&key, &quota).await?;
+
+            if !decision.allowed {
+                let mut res = Response::TooManyRequests().finish().into_body();
+                set_headers(res.headers_mut(), &decision);
+                return Ok(req.into_response(res));
+            }
+
+            let mut res = srv.call(req).await?;
+            set_headers(res.headers_mut(), &decision);
+            Ok(res)
+        }
+        .boxed_local()
+    }
+}
+
+/// Sets `RateLimit-*` headers, unless the headers of a limiter with less
+/// remaining requests are already set.
+fn set_headers(headers: &mut HeaderMap, decision: &Decision) {
+    let remaining = headers
+        .get("ratelimit-remaining")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u32>().ok());
+    if let Some(remaining) = remaining {
+        if remaining <= decision.remaining {
+            return;
+        }
+    }
+
+    headers.insert(
+        HeaderName::from_static("ratelimit-limit"),
+        HeaderValue::from(decision.limit),
+    );
+    headers.insert(
+        HeaderName::from_static("ratelimit-remaining"),
+        HeaderValue::from(decision.remaining),
+    );
+    headers.insert(
+        HeaderName::from_static("ratelimit-reset"),
+        HeaderValue::from(seconds(decision.reset)),
+    );
+    if let Some(retry_after) = decision.retry_after {
+        headers.insert(RETRY_AFTER, HeaderValue::from(seconds(retry_after)));
+    }
+}
+
+/// Whole seconds, rounded up
+fn seconds(dur: Duration) -> u64 {
+    if dur.subsec_nanos() > 0 {
+        dur.as_secs() + 1
+    } else {
+        dur.as_secs()
+    }
+}
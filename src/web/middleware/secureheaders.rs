@@ -0,0 +1,544 @@
+//! Middleware for setting security response headers
+//!
+//! `SecureHeaders` sets `Strict-Transport-Security` on responses to https
+//! requests, and `X-Frame-Options`, `X-Content-Type-Options`,
+//! `Referrer-Policy`, `Permissions-Policy` and `Content-Security-Policy` on
+//! all responses. Headers already set by the handler are kept.
+//!
+//! The content security policy could allow inline scripts and styles by a
+//! nonce. A fresh nonce is generated for every request, handlers get it with
+//! the `CspNonce` extractor to render it into the `nonce` attribute of the
+//! elements.
+//!
+//! ```rust
+//! use kayrx::web::middleware::secureheaders::{
+//!     ContentSecurityPolicy, CspNonce, PermissionsPolicy, SecureHeaders,
+//! };
+//! use kayrx::web::{self, App, HttpResponse};
+//!
+//! async fn index(nonce: CspNonce) -> HttpResponse {
+//!     HttpResponse::Ok().content_type("text/html").body(format!(
+//!         "<script nonce=\"{}\">console.log(\"hello\")</script>",
+//!         nonce.nonce()
+//!     ))
+//! }
+//!
+//! fn main() {
+//!     let app = App::new()
+//!         .wrap(
+//!             SecureHeaders::new()
+//!                 .content_security_policy(
+//!                     ContentSecurityPolicy::new()
+//!                         .directive("default-src", &["'self'"])
+//!                         .directive("img-src", &["'self'", "data:"])
+//!                         .nonce("script-src"),
+//!                 )
+//!                 .permissions_policy(PermissionsPolicy::new().deny("camera")),
+//!         )
+//!         .route("/", web::get().to(index));
+//! }
+//! ```
+
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use bytes::Bytes;
+use futures_util::future::{err, ok, FutureExt, LocalBoxFuture, Ready};
+use rand::{thread_rng, Rng};
+
+use crate::http::error::{Error, ErrorInternalServerError};
+use crate::http::header::{
+    HeaderName, HeaderValue, CONTENT_SECURITY_POLICY, CONTENT_SECURITY_POLICY_REPORT_ONLY,
+    REFERRER_POLICY, STRICT_TRANSPORT_SECURITY, X_CONTENT_TYPE_OPTIONS, X_FRAME_OPTIONS,
+};
+use crate::http::{HttpMessage, Payload};
+use crate::service::{Service, Transform};
+use crate::web::service::{ServiceRequest, ServiceResponse};
+use crate::web::{FromRequest, HttpRequest};
+
+/// `Strict-Transport-Security` header settings.
+#[derive(Clone, Debug)]
+pub struct Hsts {
+    max_age: Duration,
+    include_subdomains: bool,
+    preload: bool,
+}
+
+impl Hsts {
+    /// Create settings that tell browsers to use https for `max_age`.
+    pub fn new(max_age: Duration) -> Hsts {
+        Hsts {
+            max_age,
+            include_subdomains: false,
+            preload: false,
+        }
+    }
+
+    /// Apply the policy to the subdomains too.
+    pub fn include_subdomains(mut self) -> Self {
+        self.include_subdomains = true;
+        self
+    }
+
+    /// Allow the domain to be included in the browsers' preload lists.
+    pub fn preload(mut self) -> Self {
+        self.preload = true;
+        self
+    }
+
+    fn to_value(&self) -> HeaderValue {
+        let mut value = format!("max-age={}", self.max_age.as_secs());
+        if self.include_subdomains {
+            value.push_str("; includeSubDomains");
+        }
+        if self.preload {
+            value.push_str("; preload");
+        }
+        HeaderValue::from_str(&value).unwrap()
+    }
+}
+
+/// `X-Frame-Options` header value.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FrameOptions {
+    /// The page can not be displayed in a frame
+    Deny,
+    /// The page can only be displayed in a frame of the same origin
+    SameOrigin,
+}
+
+impl FrameOptions {
+    fn to_value(self) -> HeaderValue {
+        HeaderValue::from_static(match self {
+            FrameOptions::Deny => "DENY",
+            FrameOptions::SameOrigin => "SAMEORIGIN",
+        })
+    }
+}
+
+/// `Referrer-Policy` header value.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReferrerPolicy {
+    /// `no-referrer`
+    NoReferrer,
+    /// `no-referrer-when-downgrade`
+    NoReferrerWhenDowngrade,
+    /// `origin`
+    Origin,
+    /// `origin-when-cross-origin`
+    OriginWhenCrossOrigin,
+    /// `same-origin`
+    SameOrigin,
+    /// `strict-origin`
+    StrictOrigin,
+    /// `strict-origin-when-cross-origin`
+    StrictOriginWhenCrossOrigin,
+    /// `unsafe-url`
+    UnsafeUrl,
+}
+
+impl ReferrerPolicy {
+    fn to_value(self) -> HeaderValue {
+        HeaderValue::from_static(match self {
+            ReferrerPolicy::NoReferrer => "no-referrer",
+            ReferrerPolicy::NoReferrerWhenDowngrade => "no-referrer-when-downgrade",
+            ReferrerPolicy::Origin => "origin",
+            ReferrerPolicy::OriginWhenCrossOrigin => "origin-when-cross-origin",
+            ReferrerPolicy::SameOrigin => "same-origin",
+            ReferrerPolicy::StrictOrigin => "strict-origin",
+            ReferrerPolicy::StrictOriginWhenCrossOrigin => "strict-origin-when-cross-origin",
+            ReferrerPolicy::UnsafeUrl => "unsafe-url",
+        })
+    }
+}
+
+/// `Permissions-Policy` header builder.
+///
+/// ```rust
+/// use kayrx::web::middleware::secureheaders::PermissionsPolicy;
+///
+/// // camera=(), geolocation=(self "https://maps.example.com")
+/// let policy = PermissionsPolicy::new()
+///     .deny("camera")
+///     .allow("geolocation", &["self", "https://maps.example.com"]);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct PermissionsPolicy {
+    features: Vec<(String, Vec<String>)>,
+}
+
+impl PermissionsPolicy {
+    /// Create empty policy.
+    pub fn new() -> PermissionsPolicy {
+        PermissionsPolicy::default()
+    }
+
+    /// Allow the feature for the origins, `self`, `*` or urls.
+    pub fn allow(mut self, feature: &str, origins: &[&str]) -> Self {
+        let origins = origins
+            .iter()
+            .map(|origin| match *origin {
+                "self" | "*" => (*origin).to_owned(),
+                _ => format!("\"{}\"", origin),
+            })
+            .collect();
+        self.features.push((feature.to_owned(), origins));
+        self
+    }
+
+    /// Disable the feature.
+    pub fn deny(self, feature: &str) -> Self {
+        self.allow(feature, &[])
+    }
+
+    fn to_value(&self) -> HeaderValue {
+        let value = self
+            .features
+            .iter()
+            .map(|(feature, origins)| match origins.as_slice() {
+                [origin] if origin == "*" => format!("{}=*", feature),
+                _ => format!("{}=({})", feature, origins.join(" ")),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        HeaderValue::from_str(&value).expect("Invalid permissions policy")
+    }
+}
+
+/// `Content-Security-Policy` header builder.
+///
+/// Directives are rendered in the order they were added. Directives marked
+/// with `nonce` get the `'nonce-...'` source of the request.
+#[derive(Clone, Debug, Default)]
+pub struct ContentSecurityPolicy {
+    directives: Vec<(String, Vec<String>, bool)>,
+    report_only: bool,
+}
+
+impl ContentSecurityPolicy {
+    /// Create empty policy.
+    pub fn new() -> ContentSecurityPolicy {
+        ContentSecurityPolicy::default()
+    }
+
+    /// Add sources to the directive, e.g. `script-src`. Directives without
+    /// sources, like `upgrade-insecure-requests`, are added with no sources.
+    pub fn directive(mut self, name: &str, sources: &[&str]) -> Self {
+        let sources = sources.iter().map(|s| (*s).to_owned());
+        match self.directives.iter_mut().find(|d| d.0 == name) {
+            Some(directive) => directive.1.extend(sources),
+            None => self
+                .directives
+                .push((name.to_owned(), sources.collect(), false)),
+        }
+        self
+    }
+
+    /// Add the nonce of the request to the sources of the directive, e.g.
+    /// `script-src` or `style-src`.
+    pub fn nonce(mut self, name: &str) -> Self {
+        self = self.directive(name, &[]);
+        if let Some(directive) = self.directives.iter_mut().find(|d| d.0 == name) {
+            directive.2 = true;
+        }
+        self
+    }
+
+    /// Send the policy in the `Content-Security-Policy-Report-Only` header,
+    /// violations are reported but not enforced.
+    pub fn report_only(mut self, value: bool) -> Self {
+        self.report_only = value;
+        self
+    }
+
+    /// Render the policy, nonces are added per request.
+    fn render(&self) -> RenderedCsp {
+        let mut parts = vec![String::new()];
+        for (idx, (name, sources, with_nonce)) in self.directives.iter().enumerate() {
+            let part = parts.last_mut().unwrap();
+            if idx > 0 {
+                part.push_str("; ");
+            }
+            part.push_str(name);
+            for source in sources {
+                part.push(' ');
+                part.push_str(source);
+            }
+            if *with_nonce {
+                part.push_str(" 'nonce-");
+                parts.push("'".to_owned());
+            }
+        }
+
+        // nonces are base64, they can not make the value invalid
+        let value = HeaderValue::from_str(&parts.join("AAAAAAAAAAAAAAAAAAAAAA=="))
+            .expect("Invalid content security policy");
+        RenderedCsp {
+            name: if self.report_only {
+                CONTENT_SECURITY_POLICY_REPORT_ONLY
+            } else {
+                CONTENT_SECURITY_POLICY
+            },
+            value,
+            parts,
+        }
+    }
+}
+
+/// Content security policy, rendered when it is set
+#[derive(Clone)]
+struct RenderedCsp {
+    name: HeaderName,
+    /// The whole value if the policy has no nonce
+    value: HeaderValue,
+    /// Parts of the value between the nonces
+    parts: Vec<String>,
+}
+
+impl RenderedCsp {
+    fn has_nonce(&self) -> bool {
+        self.parts.len() > 1
+    }
+
+    fn to_value(&self, nonce: Option<&str>) -> HeaderValue {
+        match nonce {
+            Some(nonce) if self.has_nonce() => {
+                let value = Bytes::from(self.parts.join(nonce));
+                // parts are validated by `render()`, nonces are base64
+                unsafe { HeaderValue::from_maybe_shared_unchecked(value) }
+            }
+            _ => self.value.clone(),
+        }
+    }
+}
+
+/// The content security policy nonce of the request.
+///
+/// `CspNonce` is an extractor, it is available to handlers of applications
+/// wrapped with a `SecureHeaders` middleware whose content security policy
+/// uses a nonce. Otherwise using the extractor causes an
+/// *Internal Server Error* response.
+#[derive(Clone, Debug)]
+pub struct CspNonce(String);
+
+impl CspNonce {
+    /// The nonce, to render into the `nonce` attribute of elements.
+    pub fn nonce(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromRequest for CspNonce {
+    type Error = Error;
+    type Future = Ready<Result<CspNonce, Error>>;
+    type Config = ();
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        match req.extensions().get::<CspNonce>() {
+            Some(nonce) => ok(nonce.clone()),
+            None => err(ErrorInternalServerError(
+                "CSP nonce is not set, the content security policy has no nonce",
+            )),
+        }
+    }
+}
+
+/// `Middleware` for setting security response headers.
+///
+/// By default the middleware sets:
+///
+/// * `Strict-Transport-Security: max-age=31536000` on responses to https
+///   requests,
+/// * `X-Frame-Options: DENY`,
+/// * `X-Content-Type-Options: nosniff`,
+/// * `Referrer-Policy: strict-origin-when-cross-origin`.
+///
+/// `Permissions-Policy` and `Content-Security-Policy` are set if configured.
+/// Passing `None` to a setter disables the header.
+///
+/// See the [module documentation](index.html) for an example.
+#[derive(Clone)]
+pub struct SecureHeaders {
+    inner: Rc<Inner>,
+}
+
+#[derive(Clone)]
+struct Inner {
+    hsts: Option<HeaderValue>,
+    frame_options: Option<HeaderValue>,
+    content_type_options: bool,
+    referrer_policy: Option<HeaderValue>,
+    permissions_policy: Option<HeaderValue>,
+    csp: Option<RenderedCsp>,
+}
+
+impl Default for SecureHeaders {
+    fn default() -> Self {
+        SecureHeaders {
+            inner: Rc::new(Inner {
+                hsts: Some(Hsts::new(Duration::from_secs(365 * 24 * 60 * 60)).to_value()),
+                frame_options: Some(FrameOptions::Deny.to_value()),
+                content_type_options: true,
+                referrer_policy: Some(ReferrerPolicy::StrictOriginWhenCrossOrigin.to_value()),
+                permissions_policy: None,
+                csp: None,
+            }),
+        }
+    }
+}
+
+impl SecureHeaders {
+    /// Construct `SecureHeaders` middleware with the default headers.
+    pub fn new() -> SecureHeaders {
+        SecureHeaders::default()
+    }
+
+    /// Set *STRICT-TRANSPORT-SECURITY* header of responses to https
+    /// requests.
+    pub fn hsts<T: Into<Option<Hsts>>>(mut self, value: T) -> Self {
+        Rc::make_mut(&mut self.inner).hsts = value.into().map(|hsts| hsts.to_value());
+        self
+    }
+
+    /// Set *X-FRAME-OPTIONS* header.
+    pub fn frame_options<T: Into<Option<FrameOptions>>>(mut self, value: T) -> Self {
+        Rc::make_mut(&mut self.inner).frame_options = value.into().map(FrameOptions::to_value);
+        self
+    }
+
+    /// Set *X-CONTENT-TYPE-OPTIONS: nosniff* header, enabled by default.
+    pub fn content_type_options(mut self, value: bool) -> Self {
+        Rc::make_mut(&mut self.inner).content_type_options = value;
+        self
+    }
+
+    /// Set *REFERRER-POLICY* header.
+    pub fn referrer_policy<T: Into<Option<ReferrerPolicy>>>(mut self, value: T) -> Self {
+        Rc::make_mut(&mut self.inner).referrer_policy = value.into().map(ReferrerPolicy::to_value);
+        self
+    }
+
+    /// Set *PERMISSIONS-POLICY* header.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the policy is not a valid header value.
+    pub fn permissions_policy<T: Into<Option<PermissionsPolicy>>>(mut self, value: T) -> Self {
+        Rc::make_mut(&mut self.inner).permissions_policy =
+            value.into().map(|policy| policy.to_value());
+        self
+    }
+
+    /// Set *CONTENT-SECURITY-POLICY* header.
+    ///
+    /// The policy is rendered once, only the nonce is added to it per
+    /// request.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the policy is not a valid header value.
+    pub fn content_security_policy<T: Into<Option<ContentSecurityPolicy>>>(
+        mut self,
+        value: T,
+    ) -> Self {
+        Rc::make_mut(&mut self.inner).csp = value.into().map(|csp| csp.render());
+        self
+    }
+}
+
+impl<S, B> Transform<S> for SecureHeaders
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = SecureHeadersMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(SecureHeadersMiddleware {
+            service,
+            inner: self.inner.clone(),
+        })
+    }
+}
+
+#[doc(hidden)]
+pub struct SecureHeadersMiddleware<S> {
+    service: S,
+    inner: Rc<Inner>,
+}
+
+impl<S, B> Service for SecureHeadersMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        let inner = self.inner.clone();
+        let https = req.connection_info().scheme() == "https";
+
+        let nonce = match inner.csp {
+            Some(ref csp) if csp.has_nonce() => {
+                let nonce = generate_nonce();
+                req.extensions_mut().insert(CspNonce(nonce.clone()));
+                Some(nonce)
+            }
+            _ => None,
+        };
+        let fut = self.service.call(req);
+
+        async move {
+            let mut res = fut.await?;
+            let headers = res.headers_mut();
+
+            let mut set = |name: HeaderName, value: HeaderValue| {
+                if !headers.contains_key(&name) {
+                    headers.insert(name, value);
+                }
+            };
+
+            if let (true, Some(ref hsts)) = (https, &inner.hsts) {
+                set(STRICT_TRANSPORT_SECURITY, hsts.clone());
+            }
+            if let Some(ref value) = inner.frame_options {
+                set(X_FRAME_OPTIONS, value.clone());
+            }
+            if inner.content_type_options {
+                set(X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff"));
+            }
+            if let Some(ref value) = inner.referrer_policy {
+                set(REFERRER_POLICY, value.clone());
+            }
+            if let Some(ref value) = inner.permissions_policy {
+                set(HeaderName::from_static("permissions-policy"), value.clone());
+            }
+            if let Some(ref csp) = inner.csp {
+                set(csp.name.clone(), csp.to_value(nonce.as_deref()));
+            }
+            Ok(res)
+        }
+        .boxed_local()
+    }
+}
+
+/// Generates 16 random bytes, base64 encoded.
+fn generate_nonce() -> String {
+    let mut nonce = [0u8; 16];
+    thread_rng().fill(&mut nonce);
+    base64::encode(&nonce)
+}
@@ -0,0 +1,176 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use futures_util::future::{ok, FutureExt, LocalBoxFuture};
+use serde::{Deserialize, Serialize};
+
+use crate::http::error::coo_kie::{Cookie, CookieJar, Key};
+use crate::web::error::Error;
+
+use super::{SessionState, SessionStore};
+
+/// Name under which the state gets signed or encrypted.
+const NAME: &str = "kayrx-session";
+
+enum Security {
+    Signed,
+    Private,
+}
+
+/// Session store that keeps the whole state in the session cookie.
+///
+/// The state is serialized to JSON and either signed, so that the client can
+/// read it but not change it, or encrypted, so that the client can neither
+/// read nor change it. The cookie also carries the expiry time of the session,
+/// so an old cookie is rejected even if the client keeps it.
+///
+/// Cookies are limited to 4096 bytes, storing more fails with an internal
+/// server error. Since nothing is kept on the server, purging a session only
+/// removes the cookie from the client.
+///
+/// The store is only available with the `cookie` feature.
+pub struct CookieSessionStore {
+    key: Key,
+    security: Security,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Payload {
+    /// Expiry time, in seconds since the unix epoch
+    expires: u64,
+    state: SessionState,
+}
+
+impl CookieSessionStore {
+    /// Construct new *signed* `CookieSessionStore` instance.
+    ///
+    /// Panics if the key is less than 32 bytes long.
+    pub fn signed(key: &[u8]) -> CookieSessionStore {
+        CookieSessionStore {
+            key: Key::from_master(key),
+            security: Security::Signed,
+        }
+    }
+
+    /// Construct new *private* `CookieSessionStore` instance.
+    ///
+    /// Panics if the key is less than 32 bytes long.
+    pub fn private(key: &[u8]) -> CookieSessionStore {
+        CookieSessionStore {
+            key: Key::from_master(key),
+            security: Security::Private,
+        }
+    }
+
+    fn decode(&self, value: &str) -> Option<SessionState> {
+        let mut jar = CookieJar::new();
+        jar.add_original(Cookie::new(NAME, value.to_owned()));
+
+        let cookie = match self.security {
+            Security::Signed => jar.signed(&self.key).get(NAME),
+            Security::Private => jar.private(&self.key).get(NAME),
+        }?;
+
+        let payload: Payload = serde_json::from_str(cookie.value()).ok()?;
+        if payload.expires <= now() {
+            return None;
+        }
+        Some(payload.state)
+    }
+
+    fn encode(&self, state: SessionState, ttl: Duration) -> Result<String, Error> {
+        let payload = Payload {
+            expires: now() + ttl.as_secs(),
+            state,
+        };
+        let cookie = Cookie::new(NAME, serde_json::to_string(&payload)?);
+
+        let mut jar = CookieJar::new();
+        match self.security {
+            Security::Signed => jar.signed(&self.key).add(cookie),
+            Security::Private => jar.private(&self.key).add(cookie),
+        }
+
+        let value = jar.get(NAME).unwrap().value().to_owned();
+        if value.len() > 4064 {
+            return Err(crate::http::error::ErrorInternalServerError(
+                "size of the session cookie is limited to 4064 bytes",
+            ));
+        }
+        Ok(value)
+    }
+}
+
+impl SessionStore for CookieSessionStore {
+    fn load(&self, key: &str) -> LocalBoxFuture<'static, Result<Option<SessionState>, Error>> {
+        ok(self.decode(key)).boxed_local()
+    }
+
+    fn save(
+        &self,
+        _: Option<&str>,
+        state: SessionState,
+        ttl: Duration,
+    ) -> LocalBoxFuture<'static, Result<String, Error>> {
+        futures_util::future::ready(self.encode(state, ttl)).boxed_local()
+    }
+
+    fn delete(&self, _: &str) -> LocalBoxFuture<'static, Result<(), Error>> {
+        ok(()).boxed_local()
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state() -> SessionState {
+        vec![("counter".to_string(), "1".to_string())]
+            .into_iter()
+            .collect()
+    }
+
+    #[test]
+    fn signed() {
+        let store = CookieSessionStore::signed(&[0; 32]);
+        let value = store.encode(state(), Duration::from_secs(60)).unwrap();
+
+        assert!(value.contains("counter"));
+        assert_eq!(store.decode(&value), Some(state()));
+        assert_eq!(store.decode(&value.replace("\"1\"", "\"2\"")), None);
+        assert_eq!(CookieSessionStore::signed(&[1; 32]).decode(&value), None);
+    }
+
+    #[test]
+    fn private() {
+        let store = CookieSessionStore::private(&[0; 32]);
+        let value = store.encode(state(), Duration::from_secs(60)).unwrap();
+
+        assert!(!value.contains("counter"));
+        assert_eq!(store.decode(&value), Some(state()));
+        assert_eq!(CookieSessionStore::private(&[1; 32]).decode(&value), None);
+    }
+
+    #[test]
+    fn expired() {
+        let store = CookieSessionStore::signed(&[0; 32]);
+        let value = store.encode(state(), Duration::from_secs(0)).unwrap();
+
+        assert_eq!(store.decode(&value), None);
+    }
+
+    #[test]
+    fn too_large() {
+        let store = CookieSessionStore::private(&[0; 32]);
+        let mut state = SessionState::new();
+        state.insert("data".to_string(), "x".repeat(5000));
+
+        assert!(store.encode(state, Duration::from_secs(60)).is_err());
+    }
+}
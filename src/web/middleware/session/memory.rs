@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use futures_util::future::{ok, FutureExt, LocalBoxFuture};
+use rand::distributions::Alphanumeric;
+use rand::{thread_rng, Rng};
+
+use crate::web::error::Error;
+
+use super::{SessionState, SessionStore};
+
+/// Length of the random session keys.
+const KEY_LEN: usize = 32;
+
+/// Minimum interval between two sweeps of expired sessions.
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Session store that keeps the state of sessions in memory.
+///
+/// The session cookie only holds a random key. Clones of the store share the
+/// same sessions, so a single store has to be created outside of the
+/// application factory and cloned into it:
+///
+/// ```rust
+/// use kayrx::web::middleware::session::{MemorySessionStore, SessionMiddleware};
+/// use kayrx::web::{App, HttpServer};
+///
+/// # fn main() {
+/// let store = MemorySessionStore::new();
+///
+/// HttpServer::new(move || {
+///     App::new().wrap(SessionMiddleware::new(store.clone()))
+/// });
+/// # }
+/// ```
+///
+/// Sessions are lost when the process exits. Expired sessions are dropped
+/// when they are looked up, and all of them when a new session is created,
+/// at most once a minute.
+#[derive(Clone, Default)]
+pub struct MemorySessionStore {
+    sessions: Arc<Mutex<Sessions>>,
+}
+
+#[derive(Default)]
+struct Sessions {
+    entries: HashMap<String, Entry>,
+    /// When expired sessions were last dropped
+    swept: Option<Instant>,
+}
+
+struct Entry {
+    state: SessionState,
+    expires: Instant,
+}
+
+impl MemorySessionStore {
+    /// Construct new, empty `MemorySessionStore` instance.
+    pub fn new() -> MemorySessionStore {
+        MemorySessionStore::default()
+    }
+
+    /// Returns the number of sessions, including expired ones that were not
+    /// dropped yet.
+    pub fn len(&self) -> usize {
+        self.sessions.lock().unwrap().entries.len()
+    }
+
+    /// Returns `true` if there are no sessions.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl SessionStore for MemorySessionStore {
+    fn load(&self, key: &str) -> LocalBoxFuture<'static, Result<Option<SessionState>, Error>> {
+        let mut sessions = self.sessions.lock().unwrap();
+
+        let state = match sessions.entries.get(key) {
+            Some(entry) if entry.expires > Instant::now() => Some(entry.state.clone()),
+            Some(_) => {
+                sessions.entries.remove(key);
+                None
+            }
+            None => None,
+        };
+        ok(state).boxed_local()
+    }
+
+    fn save(
+        &self,
+        key: Option<&str>,
+        state: SessionState,
+        ttl: Duration,
+    ) -> LocalBoxFuture<'static, Result<String, Error>> {
+        let mut sessions = self.sessions.lock().unwrap();
+        let now = Instant::now();
+
+        let key = match key {
+            Some(key) => key.to_owned(),
+            None => {
+                if sessions
+                    .swept
+                    .map_or(true, |swept| now - swept >= SWEEP_INTERVAL)
+                {
+                    sessions.swept = Some(now);
+                    sessions.entries.retain(|_, entry| entry.expires > now);
+                }
+
+                loop {
+                    let key: String = thread_rng()
+                        .sample_iter(&Alphanumeric)
+                        .take(KEY_LEN)
+                        .collect();
+                    if !sessions.entries.contains_key(&key) {
+                        break key;
+                    }
+                }
+            }
+        };
+
+        sessions.entries.insert(
+            key.clone(),
+            Entry {
+                state,
+                expires: now + ttl,
+            },
+        );
+        ok(key).boxed_local()
+    }
+
+    fn delete(&self, key: &str) -> LocalBoxFuture<'static, Result<(), Error>> {
+        self.sessions.lock().unwrap().entries.remove(key);
+        ok(()).boxed_local()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sweep() {
+        let store = MemorySessionStore::new();
+        let save = |ttl| {
+            store
+                .save(None, SessionState::new(), ttl)
+                .now_or_never()
+                .unwrap()
+                .unwrap()
+        };
+
+        save(Duration::from_secs(0));
+        save(Duration::from_secs(0));
+        assert_eq!(store.len(), 2);
+
+        // the next sweep is due
+        let swept = Instant::now().checked_sub(SWEEP_INTERVAL);
+        store.sessions.lock().unwrap().swept = swept;
+        save(Duration::from_secs(60));
+        assert_eq!(store.len(), 1);
+    }
+}
@@ -0,0 +1,538 @@
+//! Sessions for `web` applications.
+//!
+//! The `SessionMiddleware` loads the session of every request from a
+//! `SessionStore`, hands it to handlers through the `Session` extractor and
+//! persists it once the response is ready. A session cookie identifies the
+//! session: depending on the store it carries the session state itself or the
+//! key of the state kept on the server.
+//!
+//! Two stores are provided:
+//!
+//! * `CookieSessionStore` keeps the whole state in a signed or encrypted cookie.
+//! * `MemorySessionStore` keeps the state in memory and the cookie only holds a
+//!   random session key.
+//!
+//! ```rust
+//! use kayrx::web::middleware::session::{CookieSessionStore, Session, SessionMiddleware};
+//! use kayrx::http::error::Error;
+//! use kayrx::web::{self, App, HttpResponse};
+//!
+//! async fn index(session: Session) -> Result<HttpResponse, Error> {
+//!     let counter = session.get::<i32>("counter")?.unwrap_or(0) + 1;
+//!     session.set("counter", counter)?;
+//!
+//!     Ok(HttpResponse::Ok().body(format!("Counter: {}", counter)))
+//! }
+//!
+//! fn main() {
+//!     let app = App::new()
+//!         .wrap(SessionMiddleware::new(CookieSessionStore::signed(&[0; 32])))
+//!         .service(web::resource("/").to(index));
+//! }
+//! ```
+
+mod cookie;
+mod memory;
+
+pub use self::cookie::CookieSessionStore;
+pub use self::memory::MemorySessionStore;
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures_util::future::{ok, FutureExt, LocalBoxFuture, Ready};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::http::error::coo_kie::{Cookie, SameSite};
+use crate::http::header::SET_COOKIE;
+use crate::http::{Extensions, HeaderValue, HttpMessage, Payload};
+use crate::service::{Service, Transform};
+use crate::web::dev::{ServiceRequest, ServiceResponse};
+use crate::web::error::Error;
+use crate::web::{FromRequest, HttpRequest};
+
+/// The state of a session, values are serialized to JSON.
+pub type SessionState = HashMap<String, String>;
+
+/// Storage backend of the `SessionMiddleware`.
+///
+/// A store maps the value of the session cookie, the session key, to the
+/// state of a session.
+pub trait SessionStore: 'static {
+    /// Loads the state of the session with the given key.
+    ///
+    /// Resolves to `None` if the key is unknown, has expired or was tampered
+    /// with, in which case a new session is started.
+    fn load(&self, key: &str) -> LocalBoxFuture<'static, Result<Option<SessionState>, Error>>;
+
+    /// Saves the state of a session for `ttl`, and resolves to the session key.
+    ///
+    /// `key` is the current key of the session, if there is one that can be
+    /// kept.
+    fn save(
+        &self,
+        key: Option<&str>,
+        state: SessionState,
+        ttl: Duration,
+    ) -> LocalBoxFuture<'static, Result<String, Error>>;
+
+    /// Deletes the session with the given key.
+    fn delete(&self, key: &str) -> LocalBoxFuture<'static, Result<(), Error>>;
+}
+
+/// What happened to a session while handling a request.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SessionStatus {
+    /// The state was not changed
+    Unchanged,
+    /// The state was changed
+    Changed,
+    /// The session gets a new key, see `Session::renew`
+    Renewed,
+    /// The session gets deleted, see `Session::purge`
+    Purged,
+}
+
+impl Default for SessionStatus {
+    fn default() -> SessionStatus {
+        SessionStatus::Unchanged
+    }
+}
+
+#[derive(Default)]
+struct SessionInner {
+    state: SessionState,
+    status: SessionStatus,
+}
+
+/// The session of a request.
+///
+/// `Session` is an extractor, it is available to handlers of applications
+/// wrapped with a `SessionMiddleware`. Without the middleware, the session is
+/// empty and changes are discarded.
+#[derive(Clone)]
+pub struct Session(Rc<RefCell<SessionInner>>);
+
+impl Session {
+    /// Returns the value stored under `key`.
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, Error> {
+        match self.0.borrow().state.get(key) {
+            Some(value) => Ok(Some(serde_json::from_str(value)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Stores `value` under `key`.
+    pub fn set<T: Serialize>(&self, key: &str, value: T) -> Result<(), Error> {
+        let value = serde_json::to_string(&value)?;
+
+        let mut inner = self.0.borrow_mut();
+        if inner.status != SessionStatus::Purged {
+            inner.state.insert(key.to_owned(), value);
+            inner.mark_changed();
+        }
+        Ok(())
+    }
+
+    /// Removes the value stored under `key`.
+    pub fn remove(&self, key: &str) {
+        let mut inner = self.0.borrow_mut();
+        if inner.state.remove(key).is_some() {
+            inner.mark_changed();
+        }
+    }
+
+    /// Removes all values of the session.
+    pub fn clear(&self) {
+        let mut inner = self.0.borrow_mut();
+        if !inner.state.is_empty() {
+            inner.state.clear();
+            inner.mark_changed();
+        }
+    }
+
+    /// Keeps the state of the session under a new session key.
+    ///
+    /// Renewing the session after a login prevents session fixation.
+    pub fn renew(&self) {
+        let mut inner = self.0.borrow_mut();
+        if inner.status != SessionStatus::Purged {
+            inner.status = SessionStatus::Renewed;
+        }
+    }
+
+    /// Deletes the session from the store and removes the session cookie,
+    /// typically on logout.
+    pub fn purge(&self) {
+        let mut inner = self.0.borrow_mut();
+        inner.state.clear();
+        inner.status = SessionStatus::Purged;
+    }
+
+    /// Returns what happened to the session so far.
+    pub fn status(&self) -> SessionStatus {
+        self.0.borrow().status
+    }
+
+    /// Returns a copy of the state of the session.
+    pub fn entries(&self) -> SessionState {
+        self.0.borrow().state.clone()
+    }
+
+    fn set_session(state: SessionState, req: &ServiceRequest) {
+        let session = Session::get_session(&mut *req.extensions_mut());
+        session.0.borrow_mut().state = state;
+    }
+
+    fn get_changes<B>(res: &mut ServiceResponse<B>) -> (SessionStatus, SessionState) {
+        match res
+            .request()
+            .extensions()
+            .get::<Rc<RefCell<SessionInner>>>()
+        {
+            Some(inner) => {
+                let inner = inner.borrow();
+                (inner.status, inner.state.clone())
+            }
+            None => (SessionStatus::Unchanged, SessionState::new()),
+        }
+    }
+
+    fn get_session(extensions: &mut Extensions) -> Session {
+        if let Some(inner) = extensions.get::<Rc<RefCell<SessionInner>>>() {
+            return Session(Rc::clone(inner));
+        }
+        let inner = Rc::new(RefCell::new(SessionInner::default()));
+        extensions.insert(inner.clone());
+        Session(inner)
+    }
+}
+
+impl SessionInner {
+    fn mark_changed(&mut self) {
+        if self.status == SessionStatus::Unchanged {
+            self.status = SessionStatus::Changed;
+        }
+    }
+}
+
+/// Extractor implementation for `Session`.
+///
+/// ```rust
+/// use kayrx::web::middleware::session::Session;
+/// use kayrx::http::error::Error;
+///
+/// async fn index(session: Session) -> Result<&'static str, Error> {
+///     // access session data
+///     if let Some(count) = session.get::<i32>("counter")? {
+///         session.set("counter", count + 1)?;
+///     } else {
+///         session.set("counter", 1)?;
+///     }
+///
+///     Ok("Welcome!")
+/// }
+/// # fn main() {}
+/// ```
+impl FromRequest for Session {
+    type Error = Error;
+    type Future = Ready<Result<Session, Error>>;
+    type Config = ();
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ok(Session::get_session(&mut *req.extensions_mut()))
+    }
+}
+
+/// `Middleware` that manages sessions.
+///
+/// The session cookie is named `kayrx-session` by default, is http only and
+/// has a `SameSite=Lax` policy. Sessions expire after a day without changes,
+/// see `SessionMiddleware::ttl`.
+///
+/// The middleware is cloned for every worker thread, so stores that keep
+/// state on the server have to share it between clones, which
+/// `MemorySessionStore` does.
+pub struct SessionMiddleware<T> {
+    inner: Rc<Inner<T>>,
+}
+
+struct Inner<T> {
+    store: T,
+    name: String,
+    path: String,
+    domain: Option<String>,
+    secure: bool,
+    http_only: bool,
+    same_site: Option<SameSite>,
+    ttl: Duration,
+    refresh: bool,
+}
+
+impl<T: SessionStore> SessionMiddleware<T> {
+    /// Construct new `SessionMiddleware` instance on top of `store`.
+    pub fn new(store: T) -> Self {
+        SessionMiddleware {
+            inner: Rc::new(Inner {
+                store,
+                name: "kayrx-session".to_owned(),
+                path: "/".to_owned(),
+                domain: None,
+                secure: false,
+                http_only: true,
+                same_site: Some(SameSite::Lax),
+                ttl: Duration::from_secs(24 * 60 * 60),
+                refresh: false,
+            }),
+        }
+    }
+
+    /// Sets the name of the session cookie.
+    pub fn name<S: Into<String>>(mut self, value: S) -> Self {
+        Rc::get_mut(&mut self.inner).unwrap().name = value.into();
+        self
+    }
+
+    /// Sets the `path` field of the session cookie, `/` by default.
+    pub fn path<S: Into<String>>(mut self, value: S) -> Self {
+        Rc::get_mut(&mut self.inner).unwrap().path = value.into();
+        self
+    }
+
+    /// Sets the `domain` field of the session cookie.
+    pub fn domain<S: Into<String>>(mut self, value: S) -> Self {
+        Rc::get_mut(&mut self.inner).unwrap().domain = Some(value.into());
+        self
+    }
+
+    /// Sets the `secure` field of the session cookie.
+    ///
+    /// If the `secure` field is set, the cookie is only sent over https.
+    pub fn secure(mut self, value: bool) -> Self {
+        Rc::get_mut(&mut self.inner).unwrap().secure = value;
+        self
+    }
+
+    /// Sets the `http_only` field of the session cookie.
+    pub fn http_only(mut self, value: bool) -> Self {
+        Rc::get_mut(&mut self.inner).unwrap().http_only = value;
+        self
+    }
+
+    /// Sets the `same_site` field of the session cookie.
+    pub fn same_site(mut self, value: SameSite) -> Self {
+        Rc::get_mut(&mut self.inner).unwrap().same_site = Some(value);
+        self
+    }
+
+    /// Sets how long sessions live, which is also the `max-age` of the
+    /// session cookie.
+    pub fn ttl(mut self, value: Duration) -> Self {
+        Rc::get_mut(&mut self.inner).unwrap().ttl = value;
+        self
+    }
+
+    /// Saves sessions on every request, not only when they change, so that
+    /// they expire after `ttl` of inactivity instead of `ttl` after the last
+    /// change.
+    pub fn refresh_on_access(mut self, value: bool) -> Self {
+        Rc::get_mut(&mut self.inner).unwrap().refresh = value;
+        self
+    }
+}
+
+impl<S, T, B> Transform<S> for SessionMiddleware<T>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    T: SessionStore,
+    B: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = SessionMiddlewareService<S, T>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(SessionMiddlewareService {
+            service: Rc::new(RefCell::new(service)),
+            inner: self.inner.clone(),
+        })
+    }
+}
+
+#[doc(hidden)]
+pub struct SessionMiddlewareService<S, T> {
+    service: Rc<RefCell<S>>,
+    inner: Rc<Inner<T>>,
+}
+
+impl<S, T, B> Service for SessionMiddlewareService<S, T>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    T: SessionStore,
+    B: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        let mut srv = self.service.clone();
+        let inner = self.inner.clone();
+
+        async move {
+            let cookie = req
+                .cookie(&inner.name)
+                .map(|cookie| cookie.value().to_owned());
+            let has_cookie = cookie.is_some();
+
+            let (key, state) = match cookie {
+                Some(key) => match inner.store.load(&key).await? {
+                    Some(state) => (Some(key), state),
+                    None => (None, SessionState::new()),
+                },
+                None => (None, SessionState::new()),
+            };
+            Session::set_session(state, &req);
+
+            let mut res = srv.call(req).await?;
+
+            match Session::get_changes(&mut res) {
+                (SessionStatus::Unchanged, state) => {
+                    if inner.refresh && key.is_some() {
+                        let key = inner.store.save(key.as_deref(), state, inner.ttl).await?;
+                        inner.set_cookie(&mut res, key)?;
+                    } else if has_cookie && key.is_none() {
+                        // the session expired or the cookie is invalid
+                        inner.remove_cookie(&mut res)?;
+                    }
+                }
+                (SessionStatus::Changed, state) => {
+                    let key = inner.store.save(key.as_deref(), state, inner.ttl).await?;
+                    inner.set_cookie(&mut res, key)?;
+                }
+                (SessionStatus::Renewed, state) => {
+                    if let Some(key) = key {
+                        inner.store.delete(&key).await?;
+                    }
+                    let key = inner.store.save(None, state, inner.ttl).await?;
+                    inner.set_cookie(&mut res, key)?;
+                }
+                (SessionStatus::Purged, _) => {
+                    if let Some(key) = key {
+                        inner.store.delete(&key).await?;
+                    }
+                    if has_cookie {
+                        inner.remove_cookie(&mut res)?;
+                    }
+                }
+            }
+
+            Ok(res)
+        }
+        .boxed_local()
+    }
+}
+
+impl<T> Inner<T> {
+    fn cookie(&self, value: String) -> Cookie<'static> {
+        let mut cookie = Cookie::new(self.name.clone(), value);
+        cookie.set_path(self.path.clone());
+        cookie.set_secure(self.secure);
+        cookie.set_http_only(self.http_only);
+
+        if let Some(ref domain) = self.domain {
+            cookie.set_domain(domain.clone());
+        }
+
+        if let Some(same_site) = self.same_site {
+            cookie.set_same_site(same_site);
+        }
+
+        cookie
+    }
+
+    fn set_cookie<B>(&self, res: &mut ServiceResponse<B>, key: String) -> Result<(), Error> {
+        let mut cookie = self.cookie(key);
+        cookie.set_max_age(time::Duration::seconds(self.ttl.as_secs() as i64));
+
+        // Request cookies are percent-decoded, see `HttpMessage::cookies`.
+        let value = HeaderValue::from_str(&cookie.encoded().to_string())?;
+        res.headers_mut().append(SET_COOKIE, value);
+        Ok(())
+    }
+
+    fn remove_cookie<B>(&self, res: &mut ServiceResponse<B>) -> Result<(), Error> {
+        let mut cookie = self.cookie(String::new());
+        cookie.set_max_age(time::Duration::zero());
+
+        let value = HeaderValue::from_str(&cookie.to_string())?;
+        res.headers_mut().append(SET_COOKIE, value);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::web::test;
+
+    #[test]
+    fn session() {
+        let req = test::TestRequest::default().to_srv_request();
+
+        Session::set_session(
+            vec![("key".to_string(), "\"value\"".to_string())]
+                .into_iter()
+                .collect(),
+            &req,
+        );
+        let session = Session::get_session(&mut *req.extensions_mut());
+        assert_eq!(session.status(), SessionStatus::Unchanged);
+
+        let res = session.get::<String>("key").unwrap();
+        assert_eq!(res, Some("value".to_string()));
+
+        session.set("key2", "value2".to_string()).unwrap();
+        session.remove("key");
+        assert_eq!(session.status(), SessionStatus::Changed);
+
+        let mut res = req.into_response(crate::http::Response::Ok().finish());
+        let (status, state) = Session::get_changes(&mut res);
+        assert_eq!(status, SessionStatus::Changed);
+        assert_eq!(state.get("key2"), Some(&"\"value2\"".to_string()));
+        assert!(state.get("key").is_none());
+    }
+
+    #[test]
+    fn renew_and_purge() {
+        let req = test::TestRequest::default().to_srv_request();
+        let session = Session::get_session(&mut *req.extensions_mut());
+
+        session.set("key", 1).unwrap();
+        session.renew();
+        session.set("other", 2).unwrap();
+        assert_eq!(session.status(), SessionStatus::Renewed);
+        assert_eq!(session.get::<i32>("key").unwrap(), Some(1));
+
+        session.purge();
+        session.set("key", 3).unwrap();
+        session.renew();
+        assert_eq!(session.status(), SessionStatus::Purged);
+        assert!(session.entries().is_empty());
+    }
+}
@@ -47,7 +47,7 @@
 //! * Streaming and pipelining
 //! * Keep-alive and slow requests handling
 //! * `WebSockets` server/client
-//! * Transparent content compression/decompression (br, gzip, deflate)
+//! * Transparent content compression/decompression (br, zstd, gzip, deflate)
 //! * Configurable request routing
 //! * Multipart streams
 //! * SSL support with Rustls
@@ -89,13 +89,14 @@ pub mod types;
 pub use kayrx_macro::{connect, delete, get, post, head, options, patch, put, trace};
 pub use self::app::App;
 pub use self::config::ServiceConfig;
-pub use self::data::Data;
+pub use self::data::{Data, ReqData};
 pub use self::extract::FromRequest;
 pub use self::request::HttpRequest;
 pub use self::resource::Resource;
 pub use self::responder::{Either, Responder};
 pub use self::route::Route;
 pub use self::scope::Scope;
+pub use self::info::TrustedProxies;
 pub use self::server::HttpServer;
 pub use self::service::WebService;
 pub use self::web::*;
@@ -5,12 +5,13 @@ use net2::TcpBuilder;
 use futures_util::future::ok;
 
 use crate::http::{body::MessageBody, error::Error, HttpService, KeepAlive, Request, Response};
-use crate::server::{Server, ServerBuilder};
+use crate::server::{inherit, Server, ServerBuilder};
 use crate::service::{map_config, IntoServiceFactory, Service, ServiceFactory};
 use crate::http::Protocol;
 use crate::service::pipeline_factory;
 use crate::secure::tls::ServerConfig as RustlsServerConfig;
 use crate::web::config::AppConfig;
+use crate::web::info::TrustedProxies;
 
 struct Socket {
     scheme: &'static str,
@@ -22,6 +23,7 @@ struct Config {
     keep_alive: KeepAlive,
     client_timeout: u64,
     client_shutdown: u64,
+    trusted_proxies: TrustedProxies,
 }
 
 /// An HTTP Server.
@@ -79,6 +81,7 @@ where
                 keep_alive: KeepAlive::Timeout(5),
                 client_timeout: 5000,
                 client_shutdown: 5000,
+                trusted_proxies: TrustedProxies::new(),
             })),
             backlog: 1024,
             sockets: Vec::new(),
@@ -181,6 +184,19 @@ where
         self
     }
 
+    /// Set peers allowed to set forwarded headers.
+    ///
+    /// `Forwarded` and `X-Forwarded-*` headers are used by
+    /// [ConnectionInfo](./dev/struct.ConnectionInfo.html) only if the
+    /// connection peer is a trusted proxy.
+    ///
+    /// By default no peer is trusted, pass `TrustedProxies::any()` to trust
+    /// all of them.
+    pub fn trusted_proxies(self, proxies: TrustedProxies) -> Self {
+        self.config.lock().unwrap().trusted_proxies = proxies;
+        self
+    }
+
     /// Stop kayrx system.
     pub fn system_exit(mut self) -> Self {
         self.builder = self.builder.system_exit();
@@ -228,20 +244,22 @@ where
         let cfg = self.config.clone();
         let factory = self.factory.clone();
         let addr = lst.local_addr().unwrap();
+        let name = self.listener_name();
         self.sockets.push(Socket {
             addr,
             scheme: "http",
         });
 
         self.builder = self.builder.listen(
-            format!("kayrx-service-{}", addr),
+            name,
             lst,
             move || {
                 let c = cfg.lock().unwrap();
-                let cfg = AppConfig::new(
+                let cfg = AppConfig::with_trusted_proxies(
                     false,
                     addr,
                     c.host.clone().unwrap_or_else(|| format!("{}", addr)),
+                    c.trusted_proxies.clone(),
                 );
 
                 HttpService::build()
@@ -274,20 +292,22 @@ where
         let factory = self.factory.clone();
         let cfg = self.config.clone();
         let addr = lst.local_addr().unwrap();
+        let name = self.listener_name();
         self.sockets.push(Socket {
             addr,
             scheme: "https",
         });
 
         self.builder = self.builder.listen(
-            format!("kayrx-service-{}", addr),
+            name,
             lst,
             move || {
                 let c = cfg.lock().unwrap();
-                let cfg = AppConfig::new(
+                let cfg = AppConfig::with_trusted_proxies(
                     true,
                     addr,
                     c.host.clone().unwrap_or_else(|| format!("{}", addr)),
+                    c.trusted_proxies.clone(),
                 );
                 HttpService::build()
                     .keep_alive(c.keep_alive)
@@ -313,6 +333,15 @@ where
         Ok(self)
     }
 
+    /// Name of the next tcp listener.
+    ///
+    /// Listeners are named by their index, a new process adopts them if it
+    /// binds the same addresses in the same order. Names of bound addresses
+    /// would not match if the port is chosen by the system.
+    fn listener_name(&self) -> String {
+        format!("kayrx-service-{}", self.sockets.len())
+    }
+
     fn bind2<A: net::ToSocketAddrs>(
         &self,
         addr: A,
@@ -321,6 +350,13 @@ where
         let mut succ = false;
         let mut sockets = Vec::new();
         for addr in addr.to_socket_addrs()? {
+            // listener inherited from the parent process
+            let name = format!("kayrx-service-{}", self.sockets.len() + sockets.len());
+            if let Some(lst) = take_inherited(&name, addr) {
+                succ = true;
+                sockets.push(lst);
+                continue;
+            }
             match create_tcp_listener(addr, self.backlog) {
                 Ok(lst) => {
                     succ = true;
@@ -360,6 +396,118 @@ where
         Ok(self)
     }
 
+    /// Use listener for accepting incoming connections with the PROXY
+    /// protocol header.
+    ///
+    /// Connections must start with the v1 or v2 PROXY protocol header,
+    /// connections without a valid header are dropped. Peer address of the
+    /// requests is the client address from the header.
+    pub fn listen_proxy(mut self, lst: net::TcpListener) -> io::Result<Self> {
+        let cfg = self.config.clone();
+        let factory = self.factory.clone();
+        let addr = lst.local_addr().unwrap();
+        let name = self.listener_name();
+        self.sockets.push(Socket {
+            addr,
+            scheme: "http",
+        });
+
+        self.builder = self.builder.listen_proxy(
+            name,
+            lst,
+            move || {
+                let c = cfg.lock().unwrap();
+                let cfg = AppConfig::with_trusted_proxies(
+                    false,
+                    addr,
+                    c.host.clone().unwrap_or_else(|| format!("{}", addr)),
+                    c.trusted_proxies.clone(),
+                );
+
+                HttpService::build()
+                    .keep_alive(c.keep_alive)
+                    .client_timeout(c.client_timeout)
+                    .local_addr(addr)
+                    .finish(map_config(factory(), move |_| cfg.clone()))
+                    .proxy()
+            },
+        )?;
+        Ok(self)
+    }
+
+    /// Start listening for incoming connections with the PROXY protocol
+    /// header.
+    ///
+    /// Check [`listen_proxy()`](#method.listen_proxy) for details.
+    pub fn bind_proxy<A: net::ToSocketAddrs>(mut self, addr: A) -> io::Result<Self> {
+        let sockets = self.bind2(addr)?;
+        for lst in sockets {
+            self = self.listen_proxy(lst)?;
+        }
+        Ok(self)
+    }
+
+    /// Use listener for accepting incoming tls connections with the PROXY
+    /// protocol header.
+    ///
+    /// The header is read before the tls handshake, check
+    /// [`listen_proxy()`](#method.listen_proxy) for details.
+    ///
+    /// This method sets alpn protocols to "h2" and "http/1.1"
+    pub fn listen_rustls_proxy(
+        mut self,
+        lst: net::TcpListener,
+        config: RustlsServerConfig,
+    ) -> io::Result<Self> {
+        let factory = self.factory.clone();
+        let cfg = self.config.clone();
+        let addr = lst.local_addr().unwrap();
+        let name = self.listener_name();
+        self.sockets.push(Socket {
+            addr,
+            scheme: "https",
+        });
+
+        self.builder = self.builder.listen_proxy(
+            name,
+            lst,
+            move || {
+                let c = cfg.lock().unwrap();
+                let cfg = AppConfig::with_trusted_proxies(
+                    true,
+                    addr,
+                    c.host.clone().unwrap_or_else(|| format!("{}", addr)),
+                    c.trusted_proxies.clone(),
+                );
+                HttpService::build()
+                    .keep_alive(c.keep_alive)
+                    .client_timeout(c.client_timeout)
+                    .client_disconnect(c.client_shutdown)
+                    .local_addr(addr)
+                    .finish(map_config(factory(), move |_| cfg.clone()))
+                    .rustls_proxy(config.clone())
+            },
+        )?;
+        Ok(self)
+    }
+
+    /// Start listening for incoming tls connections with the PROXY protocol
+    /// header.
+    ///
+    /// Check [`listen_rustls_proxy()`](#method.listen_rustls_proxy) for
+    /// details.
+    pub fn bind_rustls_proxy<A: net::ToSocketAddrs>(
+        mut self,
+        addr: A,
+        config: RustlsServerConfig,
+    ) -> io::Result<Self> {
+        let sockets = self.bind2(addr)?;
+        for lst in sockets {
+            self = self.listen_rustls_proxy(lst, config.clone())?;
+        }
+        Ok(self)
+    }
+
     /// Start listening for unix domain connections on existing listener.
     ///
     /// This method is available with `uds` feature.
@@ -384,10 +532,11 @@ where
 
         self.builder = self.builder.listen_uds(addr, lst, move || {
             let c = cfg.lock().unwrap();
-            let config = AppConfig::new(
+            let config = AppConfig::with_trusted_proxies(
                 false,
                 socket_addr,
                 c.host.clone().unwrap_or_else(|| format!("{}", socket_addr)),
+                c.trusted_proxies.clone(),
             );
             pipeline_factory(|io: UnixStream| ok((io, Protocol::Http1, None))).and_then(
                 HttpService::build()
@@ -424,10 +573,11 @@ where
             addr,
             move || {
                 let c = cfg.lock().unwrap();
-                let config = AppConfig::new(
+                let config = AppConfig::with_trusted_proxies(
                     false,
                     socket_addr,
                     c.host.clone().unwrap_or_else(|| format!("{}", socket_addr)),
+                    c.trusted_proxies.clone(),
                 );
                 pipeline_factory(|io: UnixStream| ok((io, Protocol::Http1, None)))
                     .and_then(
@@ -491,3 +641,26 @@ fn create_tcp_listener(
     builder.bind(addr)?;
     Ok(builder.listen(backlog)?)
 }
+
+/// Take the inherited listener with the name if it listens on the address,
+/// port 0 matches any port.
+fn take_inherited(name: &str, addr: net::SocketAddr) -> Option<net::TcpListener> {
+    let lst = inherit::take_tcp(name)?.into_iter().next()?;
+    let local = lst.local_addr();
+    match local {
+        Ok(local)
+            if local.ip() == addr.ip() && (addr.port() == 0 || local.port() == addr.port()) =>
+        {
+            Some(lst)
+        }
+        _ => {
+            log::warn!(
+                "Inherited listener {:?} is bound to {:?} instead of {}",
+                name,
+                local,
+                addr
+            );
+            None
+        }
+    }
+}
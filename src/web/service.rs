@@ -120,6 +120,12 @@ impl ServiceRequest {
         self.0.head_mut()
     }
 
+    /// This method returns reference to the http request
+    #[inline]
+    pub fn request(&self) -> &HttpRequest {
+        &self.0
+    }
+
     /// Request's uri.
     #[inline]
     pub fn uri(&self) -> &Uri {
@@ -30,6 +30,7 @@ use crate::service::{
 use crate::web::client::error::PayloadError;
 use crate::web::client::{Client, ClientRequest, ClientResponse, Connector};
 use crate::web::config::AppConfig;
+use crate::web::info::TrustedProxies;
 use crate::web::data::Data;
 use crate::web::dev::{Body, MessageBody, Payload, Server};
 use crate::web::request::HttpRequestPool;
@@ -466,6 +467,17 @@ impl TestRequest {
         self
     }
 
+    /// Set peers allowed to set forwarded headers
+    pub fn trusted_proxies(mut self, proxies: TrustedProxies) -> Self {
+        self.config = AppConfig::with_trusted_proxies(
+            self.config.secure(),
+            self.config.local_addr(),
+            self.config.host().to_owned(),
+            proxies,
+        );
+        self
+    }
+
     /// TEST
     /// Set request config
     pub fn rmap(mut self, rmap: ResourceMap) -> Self {
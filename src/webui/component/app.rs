@@ -0,0 +1,141 @@
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::rc::{Rc, Weak};
+
+use web_sys::{Element, Node};
+
+use crate::webui::component::scheduler::schedule;
+use crate::webui::component::scope::Lifecycle;
+use crate::webui::component::{Component, ComponentHandle};
+use crate::webui::vdom::{DomUpdater, VNode};
+
+/// Drives a root component and commits its output.
+///
+/// Every time a component of the tree is marked dirty, a commit is scheduled:
+/// the dirty components are rendered again, the expanded tree is diffed against
+/// the previous one and patched into the DOM, and the lifecycle hooks are
+/// called. Dropping the `App` unmounts all components.
+pub struct App<C: Component> {
+    inner: Rc<AppInner<C>>,
+}
+
+struct AppInner<C: Component> {
+    root: ComponentHandle<C>,
+    /// Updates the DOM, `None` for a detached app
+    updater: RefCell<Option<DomUpdater>>,
+    /// Last committed tree
+    vdom: RefCell<VNode>,
+    /// Whether a commit is already queued
+    scheduled: Cell<bool>,
+}
+
+impl<C: Component> App<C> {
+    /// Creates an app that is not attached to the DOM.
+    ///
+    /// The committed tree is available through `App::vdom`, which is useful
+    /// for rendering on the server and for tests.
+    pub fn new(props: C::Properties) -> Self {
+        Self::create(props, |_| None)
+    }
+
+    /// Creates an app and appends its root node to `mount`.
+    pub fn mount(props: C::Properties, mount: &Element) -> Self {
+        Self::create(props, |vdom| {
+            Some(DomUpdater::new_append_to_mount(vdom.clone(), mount))
+        })
+    }
+
+    /// Creates an app that adopts `root_node`, which was rendered on the server
+    /// from the same properties.
+    pub fn hydrate(props: C::Properties, root_node: Node) -> Self {
+        Self::create(props, |vdom| {
+            Some(DomUpdater::new_hydrate(vdom.clone(), root_node))
+        })
+    }
+
+    fn create<F>(props: C::Properties, updater: F) -> Self
+    where
+        F: FnOnce(&VNode) -> Option<DomUpdater>,
+    {
+        let root = ComponentHandle::new(props);
+
+        let mut lifecycle = Lifecycle::default();
+        let vdom = root.mounted().expand(&mut lifecycle);
+        let updater = updater(&vdom);
+
+        let inner = Rc::new(AppInner {
+            root,
+            updater: RefCell::new(updater),
+            vdom: RefCell::new(vdom),
+            scheduled: Cell::new(false),
+        });
+
+        let weak = Rc::downgrade(&inner);
+        inner
+            .root
+            .set_on_change(Rc::new(move || AppInner::schedule_commit(&weak)));
+
+        // Messages sent by the `mounted` hooks are handled after the hooks ran.
+        schedule(move || lifecycle.run());
+
+        App { inner }
+    }
+
+    /// Returns the root component.
+    pub fn root(&self) -> &ComponentHandle<C> {
+        &self.inner.root
+    }
+
+    /// Returns the last committed tree.
+    pub fn vdom(&self) -> VNode {
+        self.inner.vdom.borrow().clone()
+    }
+}
+
+impl<C: Component> AppInner<C> {
+    fn schedule_commit(this: &Weak<Self>) {
+        let inner = match this.upgrade() {
+            Some(inner) => inner,
+            None => return,
+        };
+        if inner.scheduled.replace(true) {
+            return;
+        }
+
+        let this = this.clone();
+        schedule(move || {
+            if let Some(inner) = this.upgrade() {
+                inner.commit();
+            }
+        });
+    }
+
+    fn commit(&self) {
+        self.scheduled.set(false);
+
+        let mut lifecycle = Lifecycle::default();
+        let vdom = self.root.mounted().expand(&mut lifecycle);
+
+        if let Some(ref mut updater) = *self.updater.borrow_mut() {
+            updater.update(vdom.clone());
+        }
+        *self.vdom.borrow_mut() = vdom;
+
+        lifecycle.run();
+    }
+}
+
+impl<C: Component> Drop for App<C> {
+    fn drop(&mut self) {
+        self.inner.root.mounted().unmount();
+    }
+}
+
+impl<C: Component> fmt::Debug for App<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("App")
+            .field("root", &self.inner.root)
+            .field("vdom", &*self.inner.vdom.borrow())
+            .finish()
+    }
+}
@@ -0,0 +1,471 @@
+//! Components with local state and lifecycle hooks.
+//!
+//! A [`Component`] owns some local state, receives properties from its parent and
+//! renders itself into a [`VNode`]. State changes go through messages sent with a
+//! [`ComponentLink`]: when [`Component::update`] returns `true` the component is
+//! marked dirty and re-rendered on the next commit. Components that are not dirty
+//! reuse their previous output, so only the dirty subtrees are rendered again.
+//!
+//! Child components are created with [`ComponentHandle::new`], usually kept in
+//! a field of the parent, and nested into the parent's view with a braced block:
+//!
+//! ```ignore
+//! fn render(&self) -> VNode {
+//!     html! { <div> { &self.counter } </div> }
+//! }
+//! ```
+//!
+//! `html!` has no component tags, `<Counter count=1 />` is parsed as an element
+//! named `Counter`. New properties are passed down with
+//! [`ComponentHandle::set_props`] instead, and the handle can be nested at any
+//! depth: a child's view may contain handles of its own children.
+//!
+//! The root component is driven by an [`App`], which commits every change to
+//! the DOM through a [`DomUpdater`] and calls the `mounted`, `updated` and
+//! `unmounted` hooks.
+//!
+//! [`VNode`]: ../vdom/enum.VNode.html
+//! [`DomUpdater`]: ../vdom/struct.DomUpdater.html
+//! [`Component`]: trait.Component.html
+//! [`Component::update`]: trait.Component.html#tymethod.update
+//! [`ComponentLink`]: struct.ComponentLink.html
+//! [`ComponentHandle::new`]: struct.ComponentHandle.html#method.new
+//! [`ComponentHandle::set_props`]: struct.ComponentHandle.html#method.set_props
+//! [`App`]: struct.App.html
+
+mod app;
+mod scheduler;
+mod scope;
+
+pub use self::app::App;
+pub use self::scope::{ComponentHandle, ComponentLink};
+
+use crate::webui::vdom::VNode;
+
+/// Whether a component has to be rendered again.
+pub type ShouldRender = bool;
+
+/// A piece of user interface with its own state.
+///
+/// # Example
+///
+/// ```ignore
+/// struct Counter {
+///     link: ComponentLink<Self>,
+///     count: i32,
+/// }
+///
+/// enum Msg {
+///     Increment,
+/// }
+///
+/// impl Component for Counter {
+///     type Message = Msg;
+///     type Properties = i32;
+///
+///     fn create(count: i32, link: ComponentLink<Self>) -> Self {
+///         Counter { link, count }
+///     }
+///
+///     fn update(&mut self, msg: Msg) -> ShouldRender {
+///         match msg {
+///             Msg::Increment => self.count += 1,
+///         }
+///         true
+///     }
+///
+///     fn render(&self) -> VNode {
+///         let link = self.link.clone();
+///         html! {
+///             <button onclick=move |_: MouseEvent| link.send_message(Msg::Increment)>
+///                 { self.count.to_string() }
+///             </button>
+///         }
+///     }
+/// }
+/// ```
+pub trait Component: Sized + 'static {
+    /// Messages that change the state of the component.
+    type Message: 'static;
+
+    /// Properties passed down by the parent.
+    type Properties: 'static;
+
+    /// Creates the component from its initial properties.
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self;
+
+    /// Handles a message, returns `true` if the component has to be rendered again.
+    fn update(&mut self, msg: Self::Message) -> ShouldRender;
+
+    /// Handles new properties from the parent, returns `true` if the component has to be
+    /// rendered again.
+    ///
+    /// The default implementation ignores the new properties.
+    fn change(&mut self, _props: Self::Properties) -> ShouldRender {
+        false
+    }
+
+    /// Renders the component.
+    fn render(&self) -> VNode;
+
+    /// Called once the first render of the component has been committed.
+    fn mounted(&mut self) {}
+
+    /// Called after a re-render of the component has been committed.
+    fn updated(&mut self) {}
+
+    /// Called when the component is no longer part of the rendered tree.
+    fn unmounted(&mut self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::webui::state::Store;
+    use crate::webui::vdom::{VElement, VText};
+    use std::cell::{Cell, RefCell};
+    use std::rc::Rc;
+
+    type Log = Rc<RefCell<Vec<String>>>;
+
+    struct Label {
+        name: &'static str,
+        text: String,
+        renders: Rc<Cell<usize>>,
+        log: Log,
+    }
+
+    impl Component for Label {
+        type Message = String;
+        type Properties = (&'static str, Rc<Cell<usize>>, Log);
+
+        fn create((name, renders, log): Self::Properties, _: ComponentLink<Self>) -> Self {
+            Label {
+                name,
+                text: name.to_string(),
+                renders,
+                log,
+            }
+        }
+
+        fn update(&mut self, text: String) -> ShouldRender {
+            self.text = text;
+            true
+        }
+
+        fn render(&self) -> VNode {
+            self.renders.set(self.renders.get() + 1);
+            VText::new(self.text.as_str()).into()
+        }
+
+        fn mounted(&mut self) {
+            self.log.borrow_mut().push(format!("mounted {}", self.name));
+        }
+
+        fn updated(&mut self) {
+            self.log.borrow_mut().push(format!("updated {}", self.name));
+        }
+
+        fn unmounted(&mut self) {
+            self.log.borrow_mut().push(format!("unmounted {}", self.name));
+        }
+    }
+
+    struct List {
+        first: ComponentHandle<Label>,
+        second: ComponentHandle<Label>,
+        show_second: bool,
+        renders: Rc<Cell<usize>>,
+        log: Log,
+    }
+
+    impl Component for List {
+        type Message = bool;
+        type Properties = (Rc<Cell<usize>>, Log);
+
+        fn create((renders, log): Self::Properties, _: ComponentLink<Self>) -> Self {
+            List {
+                first: ComponentHandle::new(("first", Rc::new(Cell::new(0)), log.clone())),
+                second: ComponentHandle::new(("second", Rc::new(Cell::new(0)), log.clone())),
+                show_second: true,
+                renders,
+                log,
+            }
+        }
+
+        fn update(&mut self, show_second: bool) -> ShouldRender {
+            self.show_second = show_second;
+            true
+        }
+
+        fn render(&self) -> VNode {
+            self.renders.set(self.renders.get() + 1);
+            let mut ul = VElement::new("ul");
+            ul.children.push(self.first.view());
+            if self.show_second {
+                ul.children.push(self.second.view());
+            }
+            ul.into()
+        }
+
+        fn mounted(&mut self) {
+            self.log.borrow_mut().push("mounted list".to_string());
+        }
+
+        fn updated(&mut self) {
+            self.log.borrow_mut().push("updated list".to_string());
+        }
+
+        fn unmounted(&mut self) {
+            self.log.borrow_mut().push("unmounted list".to_string());
+        }
+    }
+
+    fn list() -> (App<List>, Rc<Cell<usize>>, Log) {
+        let renders = Rc::new(Cell::new(0));
+        let log = Log::default();
+        let app = App::new((renders.clone(), log.clone()));
+        (app, renders, log)
+    }
+
+    #[test]
+    fn test_render_tree() {
+        let (app, renders, log) = list();
+
+        assert_eq!(app.vdom().to_string(), "<ul>first<!--ptns-->second</ul>");
+        assert_eq!(renders.get(), 1);
+        assert_eq!(
+            *log.borrow(),
+            vec!["mounted first", "mounted second", "mounted list"]
+        );
+    }
+
+    #[test]
+    fn test_render_dirty_only() {
+        let (app, renders, log) = list();
+        log.borrow_mut().clear();
+
+        let first = app.root().with(|list| list.first.link());
+        first.send_message("changed".to_string());
+
+        assert_eq!(app.vdom().to_string(), "<ul>changed<!--ptns-->second</ul>");
+        assert_eq!(renders.get(), 1);
+        app.root().with(|list| {
+            assert_eq!(list.first.with(|label| label.renders.get()), 2);
+            assert_eq!(list.second.with(|label| label.renders.get()), 1);
+        });
+        assert_eq!(*log.borrow(), vec!["updated first"]);
+    }
+
+    #[test]
+    fn test_unmount() {
+        let (app, renders, log) = list();
+        log.borrow_mut().clear();
+
+        app.root().link().send_message(false);
+        assert_eq!(app.vdom().to_string(), "<ul>first</ul>");
+        assert_eq!(renders.get(), 2);
+        assert_eq!(*log.borrow(), vec!["unmounted second", "updated list"]);
+
+        log.borrow_mut().clear();
+        app.root().link().send_message(true);
+        assert_eq!(app.vdom().to_string(), "<ul>first<!--ptns-->second</ul>");
+        assert_eq!(*log.borrow(), vec!["mounted second", "updated list"]);
+
+        log.borrow_mut().clear();
+        drop(app);
+        assert_eq!(
+            *log.borrow(),
+            vec!["unmounted first", "unmounted second", "unmounted list"]
+        );
+    }
+
+    #[test]
+    fn test_render_nested_blocks() {
+        use crate::webui::html;
+        use crate::webui::vdom::{
+            IterableNodes, VElement as VirtualElement, VNode as VirtualNode, VText as VirtualText,
+        };
+
+        struct Panel {
+            title: &'static str,
+            labels: Vec<ComponentHandle<Label>>,
+        }
+
+        impl Component for Panel {
+            type Message = ();
+            type Properties = (&'static str, Log);
+
+            fn create((title, log): Self::Properties, _: ComponentLink<Self>) -> Self {
+                let label = |name| ComponentHandle::new((name, Rc::new(Cell::new(0)), log.clone()));
+                Panel {
+                    title,
+                    labels: vec![label("a"), label("b")],
+                }
+            }
+
+            fn update(&mut self, _: ()) -> ShouldRender {
+                false
+            }
+
+            fn render(&self) -> VNode {
+                let title = self.title;
+                html! {
+                    <section>
+                        <h2>{ title }</h2>
+                        <p>{ &self.labels[0] }</p>
+                        <p>{ &self.labels[1] }</p>
+                    </section>
+                }
+            }
+        }
+
+        struct Page {
+            panel: ComponentHandle<Panel>,
+        }
+
+        impl Component for Page {
+            type Message = ();
+            type Properties = Log;
+
+            fn create(log: Log, _: ComponentLink<Self>) -> Self {
+                Page {
+                    panel: ComponentHandle::new(("panel", log)),
+                }
+            }
+
+            fn update(&mut self, _: ()) -> ShouldRender {
+                false
+            }
+
+            fn render(&self) -> VNode {
+                html! { <main>{ &self.panel }</main> }
+            }
+        }
+
+        let log = Log::default();
+        let app = App::<Page>::new(log.clone());
+        assert_eq!(
+            app.vdom().to_string(),
+            "<main><section><h2>panel</h2><p>a</p><p>b</p></section></main>"
+        );
+        assert_eq!(*log.borrow(), vec!["mounted a", "mounted b"]);
+
+        // the grandchildren re-render on their own
+        let b = app
+            .root()
+            .with(|page| page.panel.with(|panel| panel.labels[1].link()));
+        b.send_message("changed".to_string());
+        assert_eq!(
+            app.vdom().to_string(),
+            "<main><section><h2>panel</h2><p>a</p><p>changed</p></section></main>"
+        );
+    }
+
+    #[test]
+    fn test_set_props() {
+        struct Echo(String);
+
+        impl Component for Echo {
+            type Message = ();
+            type Properties = String;
+
+            fn create(props: String, _: ComponentLink<Self>) -> Self {
+                Echo(props)
+            }
+
+            fn update(&mut self, _: ()) -> ShouldRender {
+                false
+            }
+
+            fn change(&mut self, props: String) -> ShouldRender {
+                self.0 = props;
+                true
+            }
+
+            fn render(&self) -> VNode {
+                VText::new(self.0.as_str()).into()
+            }
+        }
+
+        let app = App::<Echo>::new("a".to_string());
+        app.root().set_props("b".to_string());
+        assert_eq!(app.vdom().to_string(), "b");
+    }
+
+    #[test]
+    fn test_store_subscription() {
+        struct Counter {
+            count: i32,
+        }
+
+        impl Component for Counter {
+            type Message = i32;
+            type Properties = ();
+
+            fn create(_: (), _: ComponentLink<Self>) -> Self {
+                Counter { count: 0 }
+            }
+
+            fn update(&mut self, count: i32) -> ShouldRender {
+                self.count = count;
+                true
+            }
+
+            fn render(&self) -> VNode {
+                VText::new(self.count.to_string()).into()
+            }
+        }
+
+        fn reducer(state: &i32, action: &i32) -> i32 {
+            state + action
+        }
+
+        let app = App::<Counter>::new(());
+        let mut store = Store::new(reducer, 0);
+        app.root().link().subscribe(&mut store, |state| *state);
+
+        store.dispatch(2);
+        store.dispatch(3);
+        assert_eq!(app.vdom().to_string(), "5");
+
+        drop(app);
+        store.dispatch(1);
+    }
+
+    #[test]
+    fn test_message_from_hooks() {
+        struct Loader {
+            link: ComponentLink<Self>,
+            loaded: bool,
+        }
+
+        impl Component for Loader {
+            type Message = ();
+            type Properties = ();
+
+            fn create(_: (), link: ComponentLink<Self>) -> Self {
+                Loader {
+                    link,
+                    loaded: false,
+                }
+            }
+
+            fn update(&mut self, _: ()) -> ShouldRender {
+                self.loaded = true;
+                true
+            }
+
+            fn render(&self) -> VNode {
+                VText::new(if self.loaded { "loaded" } else { "loading" }).into()
+            }
+
+            fn mounted(&mut self) {
+                self.link.send_message(());
+            }
+        }
+
+        let app = App::<Loader>::new(());
+        assert_eq!(app.vdom().to_string(), "loaded");
+    }
+}
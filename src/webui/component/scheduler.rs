@@ -0,0 +1,42 @@
+//! Runs component updates one at a time.
+//!
+//! Messages and commits are queued instead of being run in place, so that a
+//! component can send messages from its own `update`, `render` or lifecycle
+//! hooks without borrowing itself twice.
+
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+
+type Task = Box<dyn FnOnce()>;
+
+thread_local! {
+    static QUEUE: RefCell<VecDeque<Task>> = RefCell::new(VecDeque::new());
+    static RUNNING: Cell<bool> = Cell::new(false);
+}
+
+/// Queues `task`, and runs the queue unless it is already being run further up
+/// the stack.
+pub(super) fn schedule<F>(task: F)
+where
+    F: FnOnce() + 'static,
+{
+    QUEUE.with(|queue| queue.borrow_mut().push_back(Box::new(task)));
+
+    if RUNNING.with(|running| running.replace(true)) {
+        return;
+    }
+
+    struct Reset;
+
+    impl Drop for Reset {
+        fn drop(&mut self) {
+            RUNNING.with(|running| running.set(false));
+        }
+    }
+
+    let _reset = Reset;
+
+    while let Some(task) = QUEUE.with(|queue| queue.borrow_mut().pop_front()) {
+        task();
+    }
+}
@@ -0,0 +1,436 @@
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::rc::{Rc, Weak};
+
+use crate::webui::component::scheduler::schedule;
+use crate::webui::component::Component;
+use crate::webui::state::Store;
+use crate::webui::vdom::{IterableNodes, VElement, VNode};
+
+/// Tag of the element that stands in for a child component in the output of
+/// its parent, until the tree is expanded.
+const PLACEHOLDER_TAG: &str = "kayrx-component";
+const PLACEHOLDER_ID: &str = "data-component-id";
+
+thread_local! {
+    static NEXT_ID: Cell<u64> = Cell::new(0);
+
+    /// Components whose `render` is running, with the children they have
+    /// rendered so far.
+    static RENDERING: RefCell<Vec<(Rc<dyn Mounted>, Vec<Rc<dyn Mounted>>)>> = RefCell::new(Vec::new());
+}
+
+/// A mounted component, independent of its type.
+pub(super) trait Mounted {
+    fn id(&self) -> u64;
+
+    /// Renders the component if it is dirty and returns its output with all
+    /// child components expanded.
+    fn expand(&self, lifecycle: &mut Lifecycle) -> VNode;
+
+    /// A descendant has to be rendered again.
+    fn child_changed(&self);
+
+    fn set_parent(&self, parent: Weak<dyn Mounted>);
+
+    fn mounted(&self);
+
+    fn updated(&self);
+
+    fn unmount(&self);
+}
+
+/// Lifecycle hooks to call once the expanded tree has been committed.
+#[derive(Default)]
+pub(super) struct Lifecycle {
+    mounted: Vec<Rc<dyn Mounted>>,
+    updated: Vec<Rc<dyn Mounted>>,
+}
+
+impl Lifecycle {
+    pub(super) fn run(self) {
+        for scope in self.mounted {
+            scope.mounted();
+        }
+        for scope in self.updated {
+            scope.updated();
+        }
+    }
+}
+
+/// Owning handle to a component.
+///
+/// Parents keep handles to their children and nest them into their own view
+/// with `{ &self.child }` inside `html!`.
+pub struct ComponentHandle<C: Component> {
+    scope: Rc<Scope<C>>,
+}
+
+/// Sends messages to a component.
+///
+/// The link only holds a weak reference, messages sent after the component has
+/// been dropped are discarded.
+pub struct ComponentLink<C: Component> {
+    scope: Weak<Scope<C>>,
+}
+
+struct Scope<C: Component> {
+    id: u64,
+    this: Weak<Scope<C>>,
+    component: RefCell<Option<C>>,
+    /// Messages sent while the component is being created
+    pending: RefCell<Vec<C::Message>>,
+    /// Output of the last render, `None` if the component is dirty
+    rendered: RefCell<Option<VNode>>,
+    /// Child components used by the last render
+    children: RefCell<Vec<Rc<dyn Mounted>>>,
+    parent: RefCell<Option<Weak<dyn Mounted>>>,
+    /// Called when the component or a descendant becomes dirty and has no parent
+    on_change: RefCell<Option<Rc<dyn Fn()>>>,
+    is_mounted: Cell<bool>,
+}
+
+impl<C: Component> ComponentHandle<C> {
+    /// Creates a component from its properties.
+    pub fn new(props: C::Properties) -> Self {
+        let id = NEXT_ID.with(|next| {
+            let id = next.get();
+            next.set(id + 1);
+            id
+        });
+        let scope = Rc::new_cyclic(|this| Scope {
+            id,
+            this: this.clone(),
+            component: RefCell::new(None),
+            pending: RefCell::new(Vec::new()),
+            rendered: RefCell::new(None),
+            children: RefCell::new(Vec::new()),
+            parent: RefCell::new(None),
+            on_change: RefCell::new(None),
+            is_mounted: Cell::new(false),
+        });
+
+        let component = C::create(props, ComponentLink::new(&scope));
+        *scope.component.borrow_mut() = Some(component);
+
+        let pending = std::mem::replace(&mut *scope.pending.borrow_mut(), Vec::new());
+        for msg in pending {
+            let link = ComponentLink::new(&scope);
+            schedule(move || link.update(msg));
+        }
+
+        ComponentHandle { scope }
+    }
+
+    /// Returns a link to the component.
+    pub fn link(&self) -> ComponentLink<C> {
+        ComponentLink::new(&self.scope)
+    }
+
+    /// Passes new properties to the component, which is marked dirty if
+    /// `Component::change` returns `true`.
+    pub fn set_props(&self, props: C::Properties) {
+        let changed = self
+            .scope
+            .component
+            .borrow_mut()
+            .as_mut()
+            .map(|component| component.change(props))
+            .unwrap_or(false);
+
+        if changed {
+            self.scope.mark_dirty();
+        }
+    }
+
+    /// Runs `f` with a reference to the component.
+    pub fn with<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&C) -> R,
+    {
+        f(self
+            .scope
+            .component
+            .borrow()
+            .as_ref()
+            .expect("component is being created"))
+    }
+
+    /// Returns the view of the component.
+    ///
+    /// Inside the `render` of another component this returns a placeholder
+    /// that is filled in with the output of this component, which makes this
+    /// component a child of the rendering one. Elsewhere it returns the output
+    /// of this component with all of its children.
+    pub fn view(&self) -> VNode {
+        let parent = RENDERING.with(|rendering| {
+            rendering.borrow_mut().last_mut().map(|(parent, children)| {
+                children.push(self.scope.clone());
+                parent.clone()
+            })
+        });
+
+        match parent {
+            Some(parent) => {
+                self.scope.set_parent(Rc::downgrade(&parent));
+
+                let mut placeholder = VElement::new(PLACEHOLDER_TAG);
+                placeholder
+                    .attrs
+                    .insert(PLACEHOLDER_ID.to_string(), self.scope.id.to_string());
+                placeholder.into()
+            }
+            None => self.scope.expand(&mut Lifecycle::default()),
+        }
+    }
+
+    pub(super) fn mounted(&self) -> Rc<dyn Mounted> {
+        self.scope.clone()
+    }
+
+    pub(super) fn set_on_change(&self, on_change: Rc<dyn Fn()>) {
+        *self.scope.on_change.borrow_mut() = Some(on_change);
+    }
+}
+
+impl<C: Component> ComponentLink<C> {
+    fn new(scope: &Rc<Scope<C>>) -> Self {
+        ComponentLink {
+            scope: Rc::downgrade(scope),
+        }
+    }
+
+    /// Sends a message to the component.
+    ///
+    /// The message is handled right away, unless another component update is
+    /// in progress, in which case it is handled right after.
+    pub fn send_message(&self, msg: C::Message) {
+        let link = self.clone();
+        schedule(move || link.update(msg));
+    }
+
+    /// Creates a callback that sends the message built by `f`, for use as an
+    /// event handler.
+    pub fn callback<IN, F>(&self, f: F) -> impl Fn(IN)
+    where
+        F: Fn(IN) -> C::Message,
+    {
+        let link = self.clone();
+        move |input| link.send_message(f(input))
+    }
+
+    /// Sends the message built by `f` from the new state every time `store`
+    /// dispatches an action.
+    pub fn subscribe<S, A, F>(&self, store: &mut Store<S, A>, f: F)
+    where
+        F: Fn(&S) -> C::Message + 'static,
+    {
+        let link = self.clone();
+        store.subscribe(move |state: &S| link.send_message(f(state)));
+    }
+
+    fn update(&self, msg: C::Message) {
+        let scope = match self.scope.upgrade() {
+            Some(scope) => scope,
+            None => return,
+        };
+
+        let should_render = match *scope.component.borrow_mut() {
+            Some(ref mut component) => component.update(msg),
+            None => {
+                scope.pending.borrow_mut().push(msg);
+                false
+            }
+        };
+
+        if should_render {
+            scope.mark_dirty();
+        }
+    }
+}
+
+impl<C: Component> Clone for ComponentLink<C> {
+    fn clone(&self) -> Self {
+        ComponentLink {
+            scope: self.scope.clone(),
+        }
+    }
+}
+
+impl<C: Component> Scope<C> {
+    fn mark_dirty(&self) {
+        *self.rendered.borrow_mut() = None;
+        self.child_changed();
+    }
+
+    fn render(&self) -> VNode {
+        struct Pop;
+
+        impl Drop for Pop {
+            fn drop(&mut self) {
+                RENDERING.with(|rendering| rendering.borrow_mut().pop());
+            }
+        }
+
+        let this: Rc<dyn Mounted> = self.this.upgrade().unwrap();
+        RENDERING.with(|rendering| rendering.borrow_mut().push((this, Vec::new())));
+        let pop = Pop;
+
+        let node = self
+            .component
+            .borrow()
+            .as_ref()
+            .expect("component is being created")
+            .render();
+
+        let children = RENDERING.with(|rendering| {
+            let mut rendering = rendering.borrow_mut();
+            std::mem::replace(&mut rendering.last_mut().unwrap().1, Vec::new())
+        });
+        drop(pop);
+
+        // Children that are no longer rendered are unmounted.
+        let old = std::mem::replace(&mut *self.children.borrow_mut(), children);
+        for child in old {
+            if !self.children.borrow().iter().any(|c| c.id() == child.id()) {
+                child.unmount();
+            }
+        }
+
+        node
+    }
+
+    /// Replaces the placeholders in `node` with the expanded children.
+    fn expand_children(&self, node: &mut VNode, lifecycle: &mut Lifecycle) {
+        let element = match node {
+            VNode::Element(element) => element,
+            VNode::Text(_) => return,
+        };
+
+        if element.tag == PLACEHOLDER_TAG {
+            let id = element
+                .attrs
+                .get(PLACEHOLDER_ID)
+                .and_then(|id| id.parse().ok());
+            let child = self
+                .children
+                .borrow()
+                .iter()
+                .find(|child| Some(child.id()) == id)
+                .cloned();
+
+            if let Some(child) = child {
+                *node = child.expand(lifecycle);
+            }
+            return;
+        }
+
+        for child in element.children.iter_mut() {
+            self.expand_children(child, lifecycle);
+        }
+    }
+}
+
+impl<C: Component> Mounted for Scope<C> {
+    fn id(&self) -> u64 {
+        self.id
+    }
+
+    fn expand(&self, lifecycle: &mut Lifecycle) -> VNode {
+        let rendered = self.rendered.borrow().clone();
+        let (mut node, is_rendered) = match rendered {
+            Some(node) => (node, false),
+            None => {
+                let node = self.render();
+                *self.rendered.borrow_mut() = Some(node.clone());
+                (node, true)
+            }
+        };
+
+        self.expand_children(&mut node, lifecycle);
+
+        let this = self.this.upgrade().unwrap();
+        if !self.is_mounted.get() {
+            lifecycle.mounted.push(this);
+        } else if is_rendered {
+            lifecycle.updated.push(this);
+        }
+
+        node
+    }
+
+    fn child_changed(&self) {
+        let parent = self.parent.borrow().as_ref().and_then(|p| p.upgrade());
+
+        match parent {
+            Some(parent) => parent.child_changed(),
+            None => {
+                let on_change = self.on_change.borrow().clone();
+                if let Some(on_change) = on_change {
+                    on_change();
+                }
+            }
+        }
+    }
+
+    fn set_parent(&self, parent: Weak<dyn Mounted>) {
+        *self.parent.borrow_mut() = Some(parent);
+    }
+
+    fn mounted(&self) {
+        if self.is_mounted.replace(true) {
+            return;
+        }
+        if let Some(ref mut component) = *self.component.borrow_mut() {
+            component.mounted();
+        }
+    }
+
+    fn updated(&self) {
+        if let Some(ref mut component) = *self.component.borrow_mut() {
+            component.updated();
+        }
+    }
+
+    fn unmount(&self) {
+        if !self.is_mounted.replace(false) {
+            return;
+        }
+
+        let children = self.children.borrow().clone();
+        for child in children {
+            child.unmount();
+        }
+
+        if let Some(ref mut component) = *self.component.borrow_mut() {
+            component.unmounted();
+        }
+    }
+}
+
+impl<'a, C: Component> From<&'a ComponentHandle<C>> for IterableNodes {
+    fn from(handle: &'a ComponentHandle<C>) -> Self {
+        handle.view().into()
+    }
+}
+
+impl<'a, C: Component> From<&'a ComponentHandle<C>> for VNode {
+    fn from(handle: &'a ComponentHandle<C>) -> Self {
+        handle.view()
+    }
+}
+
+impl<C: Component> fmt::Debug for ComponentHandle<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ComponentHandle")
+            .field("id", &self.scope.id)
+            .field("mounted", &self.scope.is_mounted.get())
+            .finish()
+    }
+}
+
+impl<C: Component> fmt::Debug for ComponentLink<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ComponentLink").finish()
+    }
+}
@@ -1,4 +1,4 @@
-use crate::webui::state::{Middleware, Reducer, Subscription};
+use crate::webui::state::{Middleware, Reducer};
 use std::vec::Vec;
 
 
@@ -9,7 +9,7 @@ pub struct Store<State, Action> {
     reducer: Reducer<State, Action>,
     state: State,
     middleware: Vec<Middleware<State, Action>>,
-    subscriptions: Vec<Subscription<State>>
+    subscriptions: Vec<Box<dyn Fn(&State)>>
 }
 
 impl<State, Action> Store<State, Action> {
@@ -127,7 +127,8 @@ impl<State, Action> Store<State, Action> {
     ///
     /// Subscriptions will be called, whenever an action is dispatched.
     ///
-    /// See [`Subscription`](type.Subscription.html).
+    /// See [`Subscription`](type.Subscription.html). Besides plain functions, closures
+    /// can subscribe too, for example to forward the state to a component.
     ///
     /// # Example
     ///
@@ -149,8 +150,11 @@ impl<State, Action> Store<State, Action> {
     ///
     /// store.subscribe(listener);
     /// ```
-    pub fn subscribe(&mut self, callback: Subscription<State>) {
-        self.subscriptions.push(callback);
+    pub fn subscribe<F>(&mut self, callback: F)
+    where
+        F: Fn(&State) + 'static,
+    {
+        self.subscriptions.push(Box::new(callback));
     }
 
     /// Adds a custom middleware to the store.
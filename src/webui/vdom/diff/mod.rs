@@ -1,5 +1,5 @@
 use std::cmp::min;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::mem;
 
 use crate::webui::vdom::patch::Patch;
@@ -30,13 +30,10 @@ fn diff_recursive<'a, 'b>(
             replace = true;
         }
 
-        // Replace if two elements have different keys
-        // TODO: More robust key support. This is just an early stopgap to allow you to force replace
-        // an element... say if it's event changed. Just change the key name for now.
-        // In the future we want keys to be used to create a Patch::ReOrder to re-order siblings
-        if old_element.attrs.get("key").is_some()
-            && old_element.attrs.get("key") != new_element.attrs.get("key")
-        {
+        // Replace if two elements have different keys. Among keyed siblings this
+        // never happens since they are matched by key, but changing the key of any
+        // other element is a way to force a replace... say if its events changed.
+        if old_element.key().is_some() && old_element.key() != new_element.key() {
             replace = true;
         }
     }
@@ -107,6 +104,20 @@ fn diff_recursive<'a, 'b>(
                 patches.push(Patch::RemoveAttributes(*cur_node_idx, remove_attributes));
             }
 
+            if let (Some(old_keys), Some(new_keys)) = (
+                child_keys(&old_element.children),
+                child_keys(&new_element.children),
+            ) {
+                patches.append(&mut diff_keyed_children(
+                    &old_element.children,
+                    &new_element.children,
+                    &old_keys,
+                    &new_keys,
+                    cur_node_idx,
+                ));
+                return patches;
+            }
+
             let old_child_count = old_element.children.len();
             let new_child_count = new_element.children.len();
 
@@ -143,6 +154,105 @@ fn diff_recursive<'a, 'b>(
     patches
 }
 
+/// Diff the children of two elements by matching them by key.
+///
+/// Old children whose key is gone get removed, then the new order is built from
+/// left to right by moving the old children that are out of place and inserting
+/// the new ones. Children that are kept are diffed against their old selves.
+///
+/// All node indices refer to the old tree, so on return `cur_node_idx` points to the
+/// last node of the old parent's subtree, just like for children diffed by index.
+fn diff_keyed_children<'a, 'b>(
+    old_children: &'a [VNode],
+    new_children: &'a [VNode],
+    old_keys: &[&'a str],
+    new_keys: &[&'a str],
+    cur_node_idx: &'b mut usize,
+) -> Vec<Patch<'a>> {
+    let mut patches = vec![];
+    let parent_idx = *cur_node_idx;
+
+    // The index of every old child along with its position, by key
+    let mut old_nodes: HashMap<&str, (usize, usize)> = HashMap::new();
+    for (position, (child, key)) in old_children.iter().zip(old_keys).enumerate() {
+        let child_idx = *cur_node_idx + 1;
+        increment_node_idx_for_children(child, cur_node_idx);
+        old_nodes.insert(key, (child_idx, position));
+    }
+    let last_node_idx = *cur_node_idx;
+
+    let new_key_set: HashSet<&str> = new_keys.iter().cloned().collect();
+
+    // The keys in the order the DOM will be in, as patches get applied
+    let mut current: Vec<&str> = vec![];
+    for key in old_keys {
+        if new_key_set.contains(key) {
+            current.push(key);
+        } else {
+            patches.push(Patch::RemoveChild(old_nodes[key].0));
+        }
+    }
+
+    for (position, (new_child, key)) in new_children.iter().zip(new_keys).enumerate() {
+        if current.get(position) == Some(key) {
+            continue;
+        }
+
+        match old_nodes.get(key) {
+            Some((child_idx, _)) => {
+                let from = current
+                    .iter()
+                    .position(|current_key| current_key == key)
+                    .expect("Kept keyed child");
+                current.remove(from);
+                current.insert(position, key);
+                patches.push(Patch::MoveChild(*child_idx, position));
+            }
+            None => {
+                current.insert(position, key);
+                patches.push(Patch::InsertChild(parent_idx, position, new_child));
+            }
+        }
+    }
+
+    for (new_child, key) in new_children.iter().zip(new_keys) {
+        if let Some((child_idx, position)) = old_nodes.get(key) {
+            let mut child_idx = *child_idx;
+            patches.append(&mut diff_recursive(
+                &old_children[*position],
+                new_child,
+                &mut child_idx,
+            ));
+        }
+    }
+
+    *cur_node_idx = last_node_idx;
+    patches
+}
+
+/// The keys of the given siblings if they are all elements with distinct keys.
+fn child_keys(children: &[VNode]) -> Option<Vec<&str>> {
+    if children.is_empty() {
+        return None;
+    }
+
+    let mut seen = HashSet::new();
+    let mut keys = Vec::with_capacity(children.len());
+
+    for child in children {
+        let key = match child {
+            VNode::Element(element) => element.key()?,
+            VNode::Text(_) => return None,
+        };
+        if !seen.insert(key) {
+            return None;
+        }
+        keys.push(key);
+    }
+
+    Some(keys)
+}
+
 fn increment_node_idx_for_children<'a, 'b>(old: &'a VNode, cur_node_idx: &'b mut usize) {
     *cur_node_idx += 1;
     if let VNode::Element(element_node) = old {
@@ -161,6 +271,9 @@ use self::diff_test_case::*;
 mod tests {
     use super::*;
     use crate::webui::vdom::{VText, VNode};
+    use crate::webui::vdom::{
+        IterableNodes, VElement as VirtualElement, VNode as VirtualNode, VText as VirtualText,
+    };
     use crate::webui::html;
     use std::collections::HashMap;
 
@@ -317,38 +430,75 @@ mod tests {
         .test()
     }
 
-    //    // TODO: Key support
-    //    #[test]
-    //    fn reorder_chldren() {
-    //        let mut attributes = HashMap::new();
-    //        attributes.insert("class", "foo");
-    //
-    //        let old_children = vec![
-    //            // old node 0
-    //            html! { <div key="hello", id="same-id", style="",></div> },
-    //            // removed
-    //            html! { <div key="gets-removed",> { "This node gets removed"} </div>},
-    //            // old node 2
-    //            html! { <div key="world", class="changed-class",></div>},
-    //            // removed
-    //            html! { <div key="this-got-removed",> { "This node gets removed"} </div>},
-    //        ];
-    //
-    //        let new_children = vec![
-    //            html! { <div key="world", class="foo",></div> },
-    //            html! { <div key="new",> </div>},
-    //            html! { <div key="hello", id="same-id",></div>},
-    //        ];
-    //
-    //        test(DiffTestCase {
-    //            old: html! { <div> { old_children } </div> },
-    //            new: html! { <div> { new_children } </div> },
-    //            expected: vec![
-    //                // TODO: Come up with the patch structure for keyed nodes..
-    //                // keying should only work if all children have keys..
-    //            ],
-    //            description: "Add attributes",
-    //        })
-    //    }
+    #[test]
+    fn keyed_children() {
+        DiffTestCase {
+            description: "Insert a keyed child at the top of a list",
+            old: html! { <ul> <li key="a">a</li> <li key="b">b</li> </ul> },
+            new: html! { <ul> <li key="c">c</li> <li key="a">a</li> <li key="b">b</li> </ul> },
+            expected: vec![Patch::InsertChild(0, 0, &html! { <li key="c">c</li> })],
+        }
+        .test();
+        DiffTestCase {
+            description: "Remove a keyed child from the middle of a list",
+            old: html! { <ul> <li key="a">a</li> <li key="b">b</li> <li key="c">c</li> </ul> },
+            new: html! { <ul> <li key="a">a</li> <li key="c">c</li> </ul> },
+            expected: vec![Patch::RemoveChild(3)],
+        }
+        .test();
+        DiffTestCase {
+            description: "Move a keyed child and diff it against its old self",
+            old: html! { <ul> <li key="a">a</li> <li key="b">b</li> <li key="c">c</li> </ul> },
+            new: html! { <ul> <li key="c">changed</li> <li key="a">a</li> <li key="b">b</li> </ul> },
+            expected: vec![
+                Patch::MoveChild(5, 0),
+                Patch::ChangeText(6, &VText::new("changed")),
+            ],
+        }
+        .test();
+    }
+
+    #[test]
+    fn reorder_keyed_children() {
+        let mut attributes = HashMap::new();
+        attributes.insert("class", "foo");
+
+        DiffTestCase {
+            description: "Remove, move, insert and patch keyed children",
+            old: html! {
+            <div>
+             <div key="hello" id="same-id"></div>
+             // This node gets removed
+             <div key="gets-removed"> { "This node gets removed" } </div>
+             <div key="world" class="changed-class"></div>
+             // This node gets removed
+             <div key="this-got-removed"> { "This node gets removed" } </div>
+            </div> },
+            new: html! {
+            <div>
+             <div key="world" class="foo"></div>
+             <div key="new"></div>
+             <div key="hello" id="same-id"></div>
+            </div> },
+            expected: vec![
+                Patch::RemoveChild(2),
+                Patch::RemoveChild(5),
+                Patch::MoveChild(4, 0),
+                Patch::InsertChild(0, 1, &html! { <div key="new"></div> }),
+                Patch::AddAttributes(4, attributes),
+            ],
+        }
+        .test();
+    }
 
+    #[test]
+    fn unkeyed_children_diffed_by_index() {
+        DiffTestCase {
+            description: "Children are diffed by index unless all of them have distinct keys",
+            old: html! { <ul> <li key="a"></li> <li key="a"></li> </ul> },
+            new: html! { <ul> <li key="b"></li> <li key="a"></li> </ul> },
+            expected: vec![Patch::Replace(1, &html! { <li key="b"></li> })],
+        }
+        .test();
+    }
 }
@@ -80,6 +80,20 @@ impl DomUpdater {
         }
     }
 
+    /// Create a new `DomUpdater` in hydration mode.
+    ///
+    /// The passed in root `Node` was rendered from `current_vdom`, typically on the
+    /// server, and gets adopted instead of being created again. Only the event closures
+    /// are attached, along with any part of the DOM that doesn't match `current_vdom`.
+    pub fn new_hydrate(current_vdom: VNode, root_node: Node) -> DomUpdater {
+        let hydrated_node = current_vdom.hydrate_dom_node(root_node);
+        DomUpdater {
+            current_vdom,
+            active_closures: hydrated_node.closures,
+            root_node: hydrated_node.node,
+        }
+    }
+
     /// Diff the current virtual dom with the new virtual dom that is being passed in.
     ///
     /// Then use that diff to patch the real DOM in the user's browser so that they are
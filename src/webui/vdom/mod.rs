@@ -7,6 +7,7 @@ mod diff;
 mod dom_updater;
 mod node;
 mod patch;
+mod responder;
 mod validation;
 
 pub use self::diff::diff;
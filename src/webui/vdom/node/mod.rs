@@ -42,7 +42,7 @@ lazy_static! {
 /// TODO: Make all of these fields private and create accessor methods
 /// TODO: Create a builder to create instances of VNode::Element with
 /// attrs and children without having to explicitly create a VElement
-#[derive(PartialEq)]
+#[derive(Clone, PartialEq)]
 pub enum VNode {
     /// An element node (node type `ELEMENT_NODE`).
     Element(VElement),
@@ -54,7 +54,7 @@ pub enum VNode {
     Text(VText),
 }
 
-#[derive(PartialEq)]
+#[derive(Clone, PartialEq)]
 pub struct VElement {
     /// The HTML tag, such as "div"
     pub tag: String,
@@ -67,7 +67,7 @@ pub struct VElement {
     pub children: Vec<VNode>,
 }
 
-#[derive(PartialEq)]
+#[derive(Clone, PartialEq)]
 pub struct VText {
     pub text: String,
 }
@@ -160,6 +160,40 @@ impl VNode {
         }
     }
 
+    /// Adopt an existing DOM `Node` that was rendered from this virtual node, typically by
+    /// calling `.to_string()` on the server, instead of creating a new one.
+    ///
+    /// Event closures get attached to the existing elements. Any part of the DOM that
+    /// doesn't match this virtual node gets created again and replaces the stale node.
+    pub fn hydrate_dom_node(&self, node: Node) -> CreatedNode<Node> {
+        match self {
+            VNode::Text(text_node) if node.node_type() == Node::TEXT_NODE => {
+                if node.node_value().as_ref() != Some(&text_node.text) {
+                    node.set_node_value(Some(&text_node.text));
+                }
+
+                CreatedNode::without_closures(node)
+            }
+            VNode::Element(element_node)
+                if node.node_type() == Node::ELEMENT_NODE
+                    && node.node_name().eq_ignore_ascii_case(&element_node.tag) =>
+            {
+                element_node.hydrate_element_node(node.unchecked_into()).into()
+            }
+            _ => {
+                let created_node = self.create_dom_node();
+
+                if let Some(parent) = node.parent_node() {
+                    parent
+                        .replace_child(&created_node.node, &node)
+                        .expect("Could not replace node while hydrating");
+                }
+
+                created_node
+            }
+        }
+    }
+
     /// Used by html-macro to insert space before text that is inside of a block that came after
     /// an open tag.
     ///
@@ -205,6 +239,14 @@ impl VElement {
         }
     }
 
+    /// The `key` attribute of this element, if any.
+    ///
+    /// Keys identify siblings across renders, so that children whose keys are
+    /// all distinct get moved around instead of being rewritten by index.
+    pub fn key(&self) -> Option<&str> {
+        self.attrs.get("key").map(|key| key.as_str())
+    }
+
     /// Build a DOM element by recursively creating DOM nodes for this element and it's
     /// children, it's children's children, etc.
     pub fn create_element_node(&self) -> CreatedNode<Element> {
@@ -232,34 +274,7 @@ impl VElement {
                 .expect("Set element attribute in create element");
         });
 
-        if self.events.0.len() > 0 {
-            let unique_id = create_unique_identifier();
-
-            element
-                .set_attribute("data-vdom-id".into(), &unique_id.to_string())
-                .expect("Could not set attribute on element");
-
-            closures.insert(unique_id, vec![]);
-
-            self.events.0.iter().for_each(|(onevent, callback)| {
-                // onclick -> click
-                let event = &onevent[2..];
-
-                let current_elem: &EventTarget = element.dyn_ref().unwrap();
-
-                current_elem
-                    .add_event_listener_with_callback(
-                        event,
-                        callback.as_ref().as_ref().unchecked_ref(),
-                    )
-                    .unwrap();
-
-                closures
-                    .get_mut(&unique_id)
-                    .unwrap()
-                    .push(Rc::clone(callback));
-            });
-        }
+        self.attach_events(&element, &mut closures);
 
         let mut previous_node_was_text = false;
 
@@ -300,19 +315,110 @@ impl VElement {
             }
         });
 
-        if let Some(on_create_elem) = self.events.0.get("on_create_elem") {
-            let on_create_elem: &js_sys::Function =
-                on_create_elem.as_ref().as_ref().unchecked_ref();
-            on_create_elem
-                .call1(&wasm_bindgen::JsValue::NULL, &element)
-                .unwrap();
+        self.call_on_create_elem(&element);
+
+        CreatedNode {
+            node: element,
+            closures,
         }
+    }
+
+    /// Adopt an existing DOM `Element` that was rendered from this virtual element and
+    /// attach the event closures of this element and all of its children to it.
+    pub fn hydrate_element_node(&self, element: Element) -> CreatedNode<Element> {
+        let mut closures = HashMap::new();
+
+        self.attach_events(&element, &mut closures);
+
+        // The server rendered the inner HTML as is, there are no children to adopt.
+        if !self.attrs.contains_key("unsafe_inner_html") {
+            // Skip the `<!--ptns-->` text node separators, just like the patching does.
+            let dom_children = element.child_nodes();
+            let mut dom_children = (0..dom_children.length())
+                .filter_map(|index| dom_children.item(index))
+                .filter(|child| child.node_type() != Node::COMMENT_NODE)
+                .peekable();
+
+            for child in self.children.iter() {
+                // Empty text renders to nothing, so there is no DOM node to adopt. Create one
+                // in its place to keep the DOM children in line with the virtual ones.
+                let is_empty_text = match child {
+                    VNode::Text(text_node) => text_node.text.is_empty(),
+                    VNode::Element(_) => false,
+                };
+
+                let created_node = match dom_children.peek() {
+                    Some(_) if !is_empty_text => {
+                        let dom_child = dom_children.next().unwrap();
+                        child.hydrate_dom_node(dom_child)
+                    }
+                    next_dom_child => {
+                        let created_node = child.create_dom_node();
+                        element
+                            .insert_before(&created_node.node, next_dom_child)
+                            .unwrap();
+                        created_node
+                    }
+                };
+
+                closures.extend(created_node.closures);
+            }
+
+            for dom_child in dom_children {
+                element.remove_child(&dom_child).unwrap();
+            }
+        }
+
+        self.call_on_create_elem(&element);
 
         CreatedNode {
             node: element,
             closures,
         }
     }
+
+    /// Add the event listeners of this element to a DOM element and keep track of the
+    /// closures, keyed by the unique id that we set on the DOM element.
+    fn attach_events(&self, element: &Element, closures: &mut HashMap<u32, Vec<DynClosure>>) {
+        if self.events.0.len() > 0 {
+            let unique_id = create_unique_identifier();
+
+            element
+                .set_attribute("data-vdom-id".into(), &unique_id.to_string())
+                .expect("Could not set attribute on element");
+
+            closures.insert(unique_id, vec![]);
+
+            self.events.0.iter().for_each(|(onevent, callback)| {
+                // onclick -> click
+                let event = &onevent[2..];
+
+                let current_elem: &EventTarget = element.dyn_ref().unwrap();
+
+                current_elem
+                    .add_event_listener_with_callback(
+                        event,
+                        callback.as_ref().as_ref().unchecked_ref(),
+                    )
+                    .unwrap();
+
+                closures
+                    .get_mut(&unique_id)
+                    .unwrap()
+                    .push(Rc::clone(callback));
+            });
+        }
+    }
+
+    fn call_on_create_elem(&self, element: &Element) {
+        if let Some(on_create_elem) = self.events.0.get("on_create_elem") {
+            let on_create_elem: &js_sys::Function =
+                on_create_elem.as_ref().as_ref().unchecked_ref();
+            on_create_elem
+                .call1(&wasm_bindgen::JsValue::NULL, element)
+                .unwrap();
+        }
+    }
 }
 
 impl VText {
@@ -528,17 +634,45 @@ impl fmt::Debug for VText {
 
 impl fmt::Display for VElement {
     // Turn a VElement and all of it's children (recursively) into an HTML string
+    //
+    // The output can be hydrated on the client, so neighboring text nodes get the same
+    // `<!--ptns-->` separator that `create_element_node` inserts between them.
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "<{}", self.tag).unwrap();
+        write!(f, "<{}", self.tag)?;
 
         for (attr, value) in self.attrs.iter() {
-            write!(f, r#" {}="{}""#, attr, value)?;
+            if attr == "unsafe_inner_html" {
+                continue;
+            }
+
+            write!(f, r#" {}="{}""#, attr, Escaped(value, true))?;
         }
 
         write!(f, ">")?;
 
-        for child in self.children.iter() {
-            write!(f, "{}", child.to_string())?;
+        match self.attrs.get("unsafe_inner_html") {
+            Some(inner_html) => write!(f, "{}", inner_html)?,
+            None => {
+                let mut previous_node_was_text = false;
+
+                for child in self.children.iter() {
+                    // The content of `<script>` and `<style>` is not HTML, comments and
+                    // character references are not parsed in it.
+                    if let (true, Some(text)) = (is_raw_text(&self.tag), child.as_vtext_ref()) {
+                        write!(f, "{}", RawText(&text.text, &self.tag))?;
+                        continue;
+                    }
+
+                    let is_text = child.as_vtext_ref().is_some();
+
+                    if previous_node_was_text && is_text {
+                        write!(f, "<!--ptns-->")?;
+                    }
+                    previous_node_was_text = is_text;
+
+                    write!(f, "{}", child)?;
+                }
+            }
         }
 
         if !validation::is_self_closing(&self.tag) {
@@ -552,7 +686,62 @@ impl fmt::Display for VElement {
 // Turn a VText into an HTML string
 impl fmt::Display for VText {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.text)
+        write!(f, "{}", Escaped(&self.text, false))
+    }
+}
+
+/// Elements whose text content is written without escaping.
+fn is_raw_text(tag: &str) -> bool {
+    tag.eq_ignore_ascii_case("script") || tag.eq_ignore_ascii_case("style")
+}
+
+/// Text of a `<script>` or `<style>` element for an HTML string.
+///
+/// The text is not escaped, only the `/` of a closing tag of the element is written as
+/// `\/`, which means the same in JavaScript strings and CSS.
+struct RawText<'a>(&'a str, &'a str);
+
+impl<'a> fmt::Display for RawText<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let RawText(text, tag) = *self;
+        let mut last = 0;
+
+        for (index, _) in text.match_indices("</") {
+            let name = text[index + 2..].as_bytes();
+            if name.len() >= tag.len() && name[..tag.len()].eq_ignore_ascii_case(tag.as_bytes()) {
+                f.write_str(&text[last..index + 1])?;
+                f.write_str("\\")?;
+                last = index + 1;
+            }
+        }
+
+        f.write_str(&text[last..])
+    }
+}
+
+/// Escapes text, or an attribute value, for an HTML string.
+struct Escaped<'a>(&'a str, bool);
+
+impl<'a> fmt::Display for Escaped<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Escaped(text, is_attr) = *self;
+        let mut last = 0;
+
+        for (index, c) in text.char_indices() {
+            let escaped = match c {
+                '&' => "&amp;",
+                '<' => "&lt;",
+                '>' => "&gt;",
+                '"' if is_attr => "&quot;",
+                _ => continue,
+            };
+
+            f.write_str(&text[last..index])?;
+            f.write_str(escaped)?;
+            last = index + 1;
+        }
+
+        f.write_str(&text[last..])
     }
 }
 
@@ -572,6 +761,7 @@ pub type DynClosure = Rc<dyn AsRef<JsValue>>;
 
 /// We need a custom implementation of fmt::Debug since JsValue doesn't
 /// implement debug.
+#[derive(Clone)]
 pub struct Events(pub HashMap<String, DynClosure>);
 
 impl PartialEq for Events {
@@ -621,4 +811,57 @@ mod tests {
 
         assert_eq!(node.to_string(), expected);
     }
+
+    #[test]
+    fn to_string_escapes() {
+        let mut node = VElement::new("a");
+        node.attrs.insert("title".into(), r#"say "<hi>""#.into());
+        node.children.push(VNode::text("1 < 2 & 3 > 2"));
+
+        assert_eq!(
+            node.to_string(),
+            r#"<a title="say &quot;&lt;hi&gt;&quot;">1 &lt; 2 &amp; 3 &gt; 2</a>"#
+        );
+    }
+
+    #[test]
+    fn to_string_separates_text_nodes() {
+        let mut node = VElement::new("div");
+        node.children.push(VNode::text("Hello"));
+        node.children.push(VNode::text("world"));
+        node.children.push(VNode::element("br"));
+        node.children.push(VNode::text("!"));
+
+        assert_eq!(node.to_string(), "<div>Hello<!--ptns-->world<br>!</div>");
+    }
+
+    #[test]
+    fn to_string_raw_text() {
+        let mut node = VElement::new("script");
+        node.children
+            .push(VNode::text(r#"if (a < b && c > d) { s = "</SCRIPT>"; }"#));
+
+        assert_eq!(
+            node.to_string(),
+            r#"<script>if (a < b && c > d) { s = "<\/SCRIPT>"; }</script>"#
+        );
+
+        let mut node = VElement::new("style");
+        node.children.push(VNode::text("a > b { content: \"&\"; }"));
+        node.children.push(VNode::text("i { }"));
+
+        assert_eq!(
+            node.to_string(),
+            "<style>a > b { content: \"&\"; }i { }</style>"
+        );
+    }
+
+    #[test]
+    fn to_string_inner_html() {
+        let mut node = VElement::new("div");
+        node.attrs
+            .insert("unsafe_inner_html".into(), "<b>bold</b>".into());
+
+        assert_eq!(node.to_string(), "<div><b>bold</b></div>");
+    }
 }
@@ -193,6 +193,34 @@ fn apply_element_patch(node: &Element, patch: &Patch) -> Result<ActiveClosures,
 
             Ok(active_closures)
         }
+        Patch::RemoveChild(_node_idx) => {
+            node.remove();
+
+            Ok(active_closures)
+        }
+        Patch::MoveChild(_node_idx, position) => {
+            let parent = node.parent_node().expect("Keyed child without a parent");
+            let parent: Element = parent.unchecked_into();
+
+            // Every child before `position` is already in place, so the child that is
+            // currently there is either `node` itself or the one `node` has to precede.
+            let reference = parent.children().item(*position as u32);
+            let reference = reference.as_ref().map(|element| element.as_ref());
+
+            parent.insert_before(node, reference)?;
+
+            Ok(active_closures)
+        }
+        Patch::InsertChild(_node_idx, position, new_node) => {
+            let created_node = new_node.create_dom_node();
+
+            let reference = node.children().item(*position as u32);
+            let reference = reference.as_ref().map(|element| element.as_ref());
+
+            node.insert_before(&created_node.node, reference)?;
+
+            Ok(created_node.closures)
+        }
         Patch::ChangeText(_node_idx, _new_node) => {
             unreachable!("Elements should not receive ChangeText patches.")
         }
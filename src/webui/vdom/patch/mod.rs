@@ -55,6 +55,15 @@ pub enum Patch<'a> {
     RemoveAttributes(NodeIdx, Vec<&'a str>),
     /// Change the text of a Text node.
     ChangeText(NodeIdx, &'a VText),
+    /// Remove a node from its parent. Used for keyed children that are gone.
+    RemoveChild(NodeIdx),
+    /// Move a node so that it becomes the child at the given position of its parent.
+    ///
+    /// Positions only count element children and refer to the order that is being
+    /// built, so the moves and inserts of one parent have to be applied in order.
+    MoveChild(NodeIdx, usize),
+    /// Insert a new node as the child at the given position of a parent node id.
+    InsertChild(NodeIdx, usize, &'a VNode),
 }
 
 type NodeIdx = usize;
@@ -71,6 +80,9 @@ impl<'a> Patch<'a> {
             Patch::AddAttributes(node_idx, _) => *node_idx,
             Patch::RemoveAttributes(node_idx, _) => *node_idx,
             Patch::ChangeText(node_idx, _) => *node_idx,
+            Patch::RemoveChild(node_idx) => *node_idx,
+            Patch::MoveChild(node_idx, _) => *node_idx,
+            Patch::InsertChild(node_idx, _, _) => *node_idx,
         }
     }
 }
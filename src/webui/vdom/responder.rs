@@ -0,0 +1,37 @@
+//! Serve rendered views from `web::App` handlers.
+
+use futures_util::future::{ok, Ready};
+
+use crate::http::{error::Error, Response, StatusCode};
+use crate::web::{HttpRequest, Responder};
+use crate::webui::vdom::node::VNode;
+
+/// Renders the node into an HTML response.
+///
+/// The HTML is the `Display` output of the node, which a `DomUpdater` created with
+/// `DomUpdater::new_hydrate` can adopt on the client.
+///
+/// ```rust
+/// use kayrx::web::{self, App};
+/// use kayrx::webui::vdom::VNode;
+///
+/// async fn index() -> VNode {
+///     let mut node = VNode::element("h1");
+///     node.as_velement_mut().unwrap().children.push(VNode::text("Hello"));
+///     node
+/// }
+///
+/// fn main() {
+///     let app = App::new().route("/", web::get().to(index));
+/// }
+/// ```
+impl Responder for VNode {
+    type Error = Error;
+    type Future = Ready<Result<Response, Error>>;
+
+    fn respond_to(self, _: &HttpRequest) -> Self::Future {
+        ok(Response::build(StatusCode::OK)
+            .content_type("text/html; charset=utf-8")
+            .body(self.to_string()))
+    }
+}
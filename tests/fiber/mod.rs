@@ -0,0 +1 @@
+mod thread_pool;
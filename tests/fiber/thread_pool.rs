@@ -0,0 +1,186 @@
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Barrier, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use kayrx::fiber::{self, System};
+use kayrx::timer::delay_for;
+
+#[test]
+fn test_take_runs_on_worker_threads() {
+    let mut sys = System::builder()
+        .name("pool")
+        .threaded_scheduler()
+        .core_threads(2)
+        .build();
+
+    let main = thread::current().id();
+    let name = sys.block_on(async {
+        fiber::take(async { thread::current().name().map(String::from) })
+            .await
+            .unwrap()
+    });
+
+    assert_ne!(name, None);
+    assert!(name.unwrap().starts_with("pool-worker"));
+    assert_eq!(main, thread::current().id());
+}
+
+#[test]
+fn test_take_spreads_across_workers() {
+    const WORKERS: usize = 4;
+
+    let mut sys = System::builder()
+        .threaded_scheduler()
+        .core_threads(WORKERS)
+        .build();
+
+    // Every fiber blocks until all of them are running, which only
+    // completes if they are spread over all workers.
+    let barrier = Arc::new(Barrier::new(WORKERS));
+    let threads = Arc::new(Mutex::new(HashSet::new()));
+
+    let handles: Vec<_> = (0..WORKERS)
+        .map(|_| {
+            let barrier = barrier.clone();
+            let threads = threads.clone();
+            fiber::take(async move {
+                threads.lock().unwrap().insert(thread::current().id());
+                barrier.wait();
+            })
+        })
+        .collect();
+
+    sys.block_on(async move {
+        for handle in handles {
+            handle.await.unwrap();
+        }
+    });
+
+    assert_eq!(threads.lock().unwrap().len(), WORKERS);
+}
+
+#[test]
+fn test_spawned_from_worker_are_stolen() {
+    const WORKERS: usize = 4;
+
+    let mut sys = System::builder()
+        .threaded_scheduler()
+        .core_threads(WORKERS)
+        .build();
+
+    let (parent, children) = sys.block_on(async {
+        fiber::take(async {
+            // Spawned from a worker, so all fibers land in its local queue.
+            let barrier = Arc::new(Barrier::new(WORKERS));
+            let threads = Arc::new(Mutex::new(HashSet::new()));
+
+            let handles: Vec<_> = (1..WORKERS)
+                .map(|_| {
+                    let barrier = barrier.clone();
+                    let threads = threads.clone();
+                    fiber::take(async move {
+                        threads.lock().unwrap().insert(thread::current().id());
+                        barrier.wait();
+                    })
+                })
+                .collect();
+
+            // The parent keeps its worker busy, so the children only make it
+            // past the barrier if the other workers steal them.
+            let parent = thread::current().id();
+            barrier.wait();
+            for handle in handles {
+                handle.await.unwrap();
+            }
+
+            let threads = threads.lock().unwrap().clone();
+            (parent, threads)
+        })
+        .await
+        .unwrap()
+    });
+
+    assert_eq!(children.len(), WORKERS - 1);
+    assert!(!children.contains(&parent));
+}
+
+#[test]
+fn test_timers_and_many_fibers() {
+    let mut sys = System::builder()
+        .threaded_scheduler()
+        .core_threads(3)
+        .build();
+
+    let counter = Arc::new(AtomicUsize::new(0));
+
+    let c = counter.clone();
+    sys.block_on(async move {
+        let handles: Vec<_> = (0..100)
+            .map(|i| {
+                let c = c.clone();
+                fiber::take(async move {
+                    delay_for(Duration::from_millis(i % 10)).await;
+                    c.fetch_add(1, Ordering::SeqCst);
+                    i
+                })
+            })
+            .collect();
+
+        let mut sum = 0;
+        for handle in handles {
+            sum += handle.await.unwrap();
+        }
+        assert_eq!(sum, (0..100).sum::<u64>());
+    });
+
+    assert_eq!(counter.load(Ordering::SeqCst), 100);
+}
+
+#[test]
+fn test_spawn_stays_on_arbiter() {
+    let mut sys = System::builder().threaded_scheduler().build();
+
+    let main = thread::current().id();
+    let (tx, rx) = futures::channel::oneshot::channel();
+
+    sys.block_on(async move {
+        // `!Send` futures are still accepted by `fiber::spawn`.
+        let rc = std::rc::Rc::new(());
+        fiber::spawn(async move {
+            let _rc = rc;
+            let _ = tx.send(thread::current().id());
+        });
+        assert_eq!(rx.await.unwrap(), main);
+    });
+}
+
+#[test]
+fn test_pending_fibers_are_dropped_on_shutdown() {
+    struct Guard(Arc<AtomicUsize>);
+
+    impl Drop for Guard {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let dropped = Arc::new(AtomicUsize::new(0));
+    let mut sys = System::builder()
+        .threaded_scheduler()
+        .core_threads(2)
+        .build();
+
+    let guard = Guard(dropped.clone());
+    sys.block_on(async move {
+        let _ = fiber::take(async move {
+            let _guard = guard;
+            futures::future::pending::<()>().await;
+        });
+    });
+    assert_eq!(dropped.load(Ordering::SeqCst), 0);
+
+    drop(sys);
+    assert_eq!(dropped.load(Ordering::SeqCst), 1);
+}
@@ -0,0 +1,202 @@
+use std::time::Duration;
+
+use futures::{stream, StreamExt};
+use kayrx::grpc::{self, Code, Request, Response, Status, Streaming};
+use kayrx::http::header::{HeaderName, HeaderValue};
+use kayrx::krse::net::TcpStream;
+use kayrx::service::{fn_service, map_config};
+use kayrx::timer::delay_for;
+use kayrx::web::dev::AppConfig;
+use kayrx::web::test;
+
+fn server() -> grpc::Server {
+    grpc::Server::new()
+        .unary(
+            "/test.Test/Hello",
+            fn_service(|req: Request<String>| async move {
+                let name = req.metadata().get("x-name").cloned();
+                let mut res = Response::new(format!("Hello {}", req.into_inner()));
+                if let Some(name) = name {
+                    res.metadata_mut()
+                        .insert(HeaderName::from_static("x-name"), name);
+                }
+                Ok::<_, Status>(res)
+            }),
+        )
+        .unary(
+            "/test.Test/Fail",
+            fn_service(|_: Request<String>| async move {
+                Err::<Response<String>, _>(Status::new(Code::PermissionDenied, "not allowed: ✗"))
+            }),
+        )
+        .unary(
+            "/test.Test/Sleep",
+            fn_service(|req: Request<u64>| async move {
+                delay_for(Duration::from_millis(*req.get_ref())).await;
+                Ok::<_, Status>(Response::new(*req.get_ref()))
+            }),
+        )
+        .server_streaming(
+            "/test.Test/Count",
+            fn_service(|req: Request<u32>| async move {
+                let n = req.into_inner();
+                let items = (0..n)
+                    .map(Ok)
+                    .chain(std::iter::once(Err(Status::new(Code::Aborted, "done"))));
+                Ok::<_, Status>(Response::new(stream::iter(items)))
+            }),
+        )
+        .client_streaming(
+            "/test.Test/Sum",
+            fn_service(|req: Request<Streaming<u32>>| async move {
+                let mut messages = req.into_inner();
+                let mut sum = 0;
+                while let Some(n) = messages.message().await? {
+                    sum += n;
+                }
+                Ok::<_, Status>(Response::new(sum))
+            }),
+        )
+        .streaming(
+            "/test.Test/Echo",
+            fn_service(|req: Request<Streaming<String>>| async move {
+                Ok::<_, Status>(Response::new(
+                    req.into_inner()
+                        .map(|msg| msg.map(|msg| msg.to_uppercase())),
+                ))
+            }),
+        )
+}
+
+fn start() -> test::TestServer {
+    test::start_with(test::config().h2(), || {
+        map_config(server(), |_: AppConfig| ())
+    })
+}
+
+async fn client(srv: &test::TestServer) -> grpc::Client {
+    let io = TcpStream::connect(srv.addr()).await.unwrap();
+    let uri = format!("http://{}", srv.addr()).parse().unwrap();
+    grpc::Client::handshake(io, uri).await.unwrap()
+}
+
+#[kayrx::test]
+async fn test_unary() {
+    let srv = start();
+    let client = client(&srv).await;
+
+    let mut req = Request::new("world".to_owned());
+    req.metadata_mut().insert(
+        HeaderName::from_static("x-name"),
+        HeaderValue::from_static("kayrx"),
+    );
+    let res = client
+        .unary::<_, String>("/test.Test/Hello", req)
+        .await
+        .unwrap();
+    assert_eq!(res.metadata().get("x-name").unwrap(), "kayrx");
+    assert_eq!(res.into_inner(), "Hello world");
+}
+
+#[kayrx::test]
+async fn test_status() {
+    let srv = start();
+    let client = client(&srv).await;
+
+    let err = client
+        .unary::<_, String>("/test.Test/Fail", Request::new(String::new()))
+        .await
+        .err()
+        .unwrap();
+    assert_eq!(err.code(), Code::PermissionDenied);
+    assert_eq!(err.message(), "not allowed: ✗");
+
+    let err = client
+        .unary::<_, String>("/test.Test/Missing", Request::new(String::new()))
+        .await
+        .err()
+        .unwrap();
+    assert_eq!(err.code(), Code::Unimplemented);
+
+    // message of a wrong type
+    let err = client
+        .unary::<_, String>("/test.Test/Count", Request::new("3".to_owned()))
+        .await
+        .err()
+        .unwrap();
+    assert_eq!(err.code(), Code::InvalidArgument);
+}
+
+#[kayrx::test]
+async fn test_deadline() {
+    let srv = start();
+    let client = client(&srv).await;
+
+    let mut req = Request::new(2000u64);
+    req.set_timeout(Duration::from_millis(100));
+    let err = client
+        .unary::<_, u64>("/test.Test/Sleep", req)
+        .await
+        .err()
+        .unwrap();
+    assert_eq!(err.code(), Code::DeadlineExceeded);
+
+    let mut req = Request::new(10u64);
+    req.set_timeout(Duration::from_secs(5));
+    let res = client
+        .unary::<_, u64>("/test.Test/Sleep", req)
+        .await
+        .unwrap();
+    assert_eq!(res.into_inner(), 10);
+}
+
+#[kayrx::test]
+async fn test_server_streaming() {
+    let srv = start();
+    let client = client(&srv).await;
+
+    let res = client
+        .server_streaming::<_, u32>("/test.Test/Count", Request::new(3u32))
+        .await
+        .unwrap();
+    let mut messages = res.into_inner();
+    assert_eq!(messages.message().await.unwrap(), Some(0));
+    assert_eq!(messages.message().await.unwrap(), Some(1));
+    assert_eq!(messages.message().await.unwrap(), Some(2));
+    let err = messages.message().await.err().unwrap();
+    assert_eq!(err.code(), Code::Aborted);
+    assert_eq!(err.message(), "done");
+}
+
+#[kayrx::test]
+async fn test_client_streaming() {
+    let srv = start();
+    let client = client(&srv).await;
+
+    let req = Request::new(stream::iter(vec![1u32, 2, 3, 4]));
+    let res = client
+        .client_streaming::<_, _, u32>("/test.Test/Sum", req)
+        .await
+        .unwrap();
+    assert_eq!(res.into_inner(), 10);
+}
+
+#[kayrx::test]
+async fn test_bidi_streaming() {
+    let srv = start();
+    let client = client(&srv).await;
+
+    let (tx, rx) = futures::channel::mpsc::unbounded();
+    let res = client
+        .streaming::<_, _, String>("/test.Test/Echo", Request::new(rx))
+        .await
+        .unwrap();
+    let mut messages = res.into_inner();
+
+    tx.unbounded_send("a".to_owned()).unwrap();
+    assert_eq!(messages.message().await.unwrap().unwrap(), "A");
+    tx.unbounded_send("b".to_owned()).unwrap();
+    assert_eq!(messages.message().await.unwrap().unwrap(), "B");
+    drop(tx);
+    assert_eq!(messages.message().await.unwrap(), None);
+}
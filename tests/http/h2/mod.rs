@@ -0,0 +1,2 @@
+mod push;
+mod trailers;
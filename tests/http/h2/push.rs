@@ -0,0 +1,154 @@
+use bytes::Bytes;
+
+use kayrx::http::h2::client::{self, Builder, ResponseFuture, SendRequest};
+use kayrx::http::h2::RecvStream;
+use kayrx::http::{header, Response as HttpResponse};
+use kayrx::krse::net::TcpStream;
+use kayrx::web::{self, test, App, HttpRequest};
+
+fn start_server() -> test::TestServer {
+    test::start_with(test::config().h2(), || {
+        App::new()
+            .service(web::resource("/").to(|| {
+                HttpResponse::Ok()
+                    .push("/style.css")
+                    .push("/app.js?v=1")
+                    .body("index")
+            }))
+            .service(web::resource("/preload").to(|| {
+                HttpResponse::Ok()
+                    .header(
+                        header::LINK,
+                        "</style.css>; rel=preload; as=style, </app.js?v=1>; rel=preload; nopush",
+                    )
+                    .body("preload")
+            }))
+            .service(web::resource("/style.css").to(|req: HttpRequest| {
+                let encoding = req
+                    .headers()
+                    .get(header::ACCEPT_ENCODING)
+                    .map(|h| h.to_str().unwrap().to_owned())
+                    .unwrap_or_default();
+                HttpResponse::Ok().body(format!("css {}", encoding))
+            }))
+            .service(web::resource("/app.js").to(|req: HttpRequest| {
+                HttpResponse::Ok().body(format!("js {}", req.query_string()))
+            }))
+    })
+}
+
+async fn connect(srv: &test::TestServer, enable_push: bool) -> SendRequest<Bytes> {
+    let io = TcpStream::connect(srv.addr()).await.unwrap();
+    let (client, connection) = Builder::new()
+        .enable_push(enable_push)
+        .handshake(io)
+        .await
+        .unwrap();
+    kayrx::fiber::spawn(async move {
+        let _ = connection.await;
+    });
+    client
+}
+
+fn get(client: &mut SendRequest<Bytes>, srv: &test::TestServer, path: &str) -> ResponseFuture {
+    let req = http::Request::get(format!("http://{}{}", srv.addr(), path))
+        .header(header::ACCEPT_ENCODING, "gzip")
+        .body(())
+        .unwrap();
+    client.send_request(req, true).unwrap().0
+}
+
+async fn read_body(mut body: RecvStream) -> Bytes {
+    let mut buf = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.unwrap();
+        let _ = body.flow_control().release_capacity(chunk.len());
+        buf.extend_from_slice(&chunk);
+    }
+    Bytes::from(buf)
+}
+
+/// Collects the paths and bodies of the pushed responses.
+async fn pushed(mut promises: client::PushPromises) -> Vec<(String, Bytes)> {
+    let mut pushed = Vec::new();
+    while let Some(promise) = promises.push_promise().await {
+        let (req, res) = promise.unwrap().into_parts();
+        assert_eq!(req.method(), http::Method::GET);
+        let res = res.await.unwrap();
+        assert!(res.status().is_success());
+        pushed.push((
+            req.uri().path_and_query().unwrap().to_string(),
+            read_body(res.into_body()).await,
+        ));
+    }
+    pushed
+}
+
+#[kayrx::test]
+async fn test_h2_push() {
+    let srv = start_server();
+    let mut client = connect(&srv, true).await;
+
+    let mut res = get(&mut client, &srv, "/");
+    let promises = res.push_promises();
+    let res = res.await.unwrap();
+    assert_eq!(
+        read_body(res.into_body()).await,
+        Bytes::from_static(b"index")
+    );
+
+    assert_eq!(
+        pushed(promises).await,
+        vec![
+            ("/style.css".to_owned(), Bytes::from_static(b"css gzip")),
+            ("/app.js?v=1".to_owned(), Bytes::from_static(b"js v=1")),
+        ]
+    );
+}
+
+#[kayrx::test]
+async fn test_h2_push_link_preload() {
+    let srv = start_server();
+    let mut client = connect(&srv, true).await;
+
+    let mut res = get(&mut client, &srv, "/preload");
+    let promises = res.push_promises();
+    let res = res.await.unwrap();
+    assert!(res.headers().contains_key(header::LINK));
+    assert_eq!(
+        read_body(res.into_body()).await,
+        Bytes::from_static(b"preload")
+    );
+
+    assert_eq!(
+        pushed(promises).await,
+        vec![("/style.css".to_owned(), Bytes::from_static(b"css gzip"))]
+    );
+}
+
+#[kayrx::test]
+async fn test_h2_push_disabled() {
+    let srv = start_server();
+    let mut client = connect(&srv, false).await;
+
+    let mut res = get(&mut client, &srv, "/");
+    let promises = res.push_promises();
+    let res = res.await.unwrap();
+    assert_eq!(
+        read_body(res.into_body()).await,
+        Bytes::from_static(b"index")
+    );
+    assert!(pushed(promises).await.is_empty());
+}
+
+#[kayrx::test]
+async fn test_h1_push_ignored() {
+    let srv = test::start_with(test::config().h1(), || {
+        App::new()
+            .service(web::resource("/").to(|| HttpResponse::Ok().push("/style.css").body("index")))
+    });
+
+    let mut res = srv.get("/").send().await.unwrap();
+    assert!(res.status().is_success());
+    assert_eq!(res.body().await.unwrap(), Bytes::from_static(b"index"));
+}
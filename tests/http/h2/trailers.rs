@@ -0,0 +1,47 @@
+use bytes::Bytes;
+
+use kayrx::http::body::{Body, WithTrailers};
+use kayrx::http::h2::client::{Builder, SendRequest};
+use kayrx::http::header::{HeaderMap, HeaderName, HeaderValue};
+use kayrx::http::Response as HttpResponse;
+use kayrx::krse::net::TcpStream;
+use kayrx::web::{self, test, App};
+
+#[kayrx::test]
+async fn test_h2_trailers() {
+    let srv = test::start_with(test::config().h2(), || {
+        App::new().service(web::resource("/").to(|| {
+            let mut trailers = HeaderMap::new();
+            trailers.insert(
+                HeaderName::from_static("grpc-status"),
+                HeaderValue::from_static("0"),
+            );
+            HttpResponse::Ok().body(Body::from_message(WithTrailers::new("data", trailers)))
+        }))
+    });
+
+    let io = TcpStream::connect(srv.addr()).await.unwrap();
+    let (mut client, connection): (SendRequest<Bytes>, _) =
+        Builder::new().handshake(io).await.unwrap();
+    kayrx::fiber::spawn(async move {
+        let _ = connection.await;
+    });
+
+    let req = http::Request::get(format!("http://{}/", srv.addr()))
+        .body(())
+        .unwrap();
+    let res = client.send_request(req, true).unwrap().0.await.unwrap();
+    assert!(res.status().is_success());
+
+    let mut body = res.into_body();
+    let mut buf = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.unwrap();
+        let _ = body.flow_control().release_capacity(chunk.len());
+        buf.extend_from_slice(&chunk);
+    }
+    assert_eq!(Bytes::from(buf), Bytes::from_static(b"data"));
+
+    let trailers = body.trailers().await.unwrap().unwrap();
+    assert_eq!(trailers.get("grpc-status").unwrap(), "0");
+}
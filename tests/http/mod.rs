@@ -1,3 +1,4 @@
 mod h1;
+mod h2;
 mod config;
 mod body;
\ No newline at end of file
@@ -0,0 +1,115 @@
+use kayrx::jrpc::common::{Params, Response};
+use kayrx::jrpc::raw::{RawClient, RawServer};
+use kayrx::jrpc::transport::http::{HttpTransportClient, HttpTransportServer};
+use kayrx::jrpc::{Client, Server};
+use kayrx::web::{test, App};
+use serde_json::{json, Value};
+
+fn start_server(transport: HttpTransportServer) {
+    let server = Server::from(RawServer::new(transport));
+    let mut hello = server.register_method("hello".to_owned()).unwrap();
+
+    kayrx::fiber::spawn(async move {
+        // Keep the server alive for as long as the method is being served.
+        let _server = server;
+        loop {
+            let request = hello.next().await;
+            let name: String = request.params().clone().parse::<(String,)>().unwrap().0;
+            request
+                .respond(Ok(Value::from(format!("hello {}", name))))
+                .await;
+        }
+    });
+}
+
+#[kayrx::test]
+async fn test_request() {
+    let transport = HttpTransportServer::bind(&"127.0.0.1:0".parse().unwrap())
+        .await
+        .unwrap();
+    let addr = *transport.local_addr().unwrap();
+    start_server(transport);
+
+    let client = Client::from(RawClient::new(HttpTransportClient::new(format!(
+        "http://{}/",
+        addr
+    ))));
+    let response: String = client
+        .request("hello", Params::Array(vec![Value::from("kayrx")]))
+        .await
+        .unwrap();
+    assert_eq!(response, "hello kayrx");
+}
+
+#[kayrx::test]
+async fn test_request_with_client() {
+    let transport = HttpTransportServer::bind(&"127.0.0.1:0".parse().unwrap())
+        .await
+        .unwrap();
+    let addr = *transport.local_addr().unwrap();
+    start_server(transport);
+
+    let transport = HttpTransportClient::with_client(format!("http://{}/", addr), || {
+        kayrx::web::client::Client::build()
+            .header("x-client", "custom")
+            .finish()
+    });
+    let client = Client::from(RawClient::new(transport));
+    let response: String = client
+        .request("hello", Params::Array(vec![Value::from("kayrx")]))
+        .await
+        .unwrap();
+    assert_eq!(response, "hello kayrx");
+}
+
+#[kayrx::test]
+async fn test_app_resource_batch() {
+    let transport = HttpTransportServer::new();
+    let handle = transport.handle();
+    start_server(transport);
+
+    let srv = test::start(move || App::new().service(handle.resource("/rpc")));
+
+    let mut response = srv
+        .post("/rpc")
+        .send_json(&json!([
+            {"jsonrpc": "2.0", "method": "hello", "params": ["a"], "id": 1},
+            {"jsonrpc": "2.0", "method": "hello", "params": ["b"], "id": 2},
+        ]))
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+
+    let body = response.body().await.unwrap();
+    let mut outputs: Vec<Value> = match serde_json::from_slice(&body).unwrap() {
+        Response::Batch(outputs) => outputs
+            .into_iter()
+            .map(|output| serde_json::to_value(output).unwrap())
+            .collect(),
+        response => panic!("unexpected response: {:?}", response),
+    };
+    outputs.sort_by_key(|output| output["id"].as_u64());
+    assert_eq!(outputs[0]["result"], "hello a");
+    assert_eq!(outputs[1]["result"], "hello b");
+
+    // Requests are only accepted with `POST`.
+    let response = srv.get("/rpc").send().await.unwrap();
+    assert_eq!(response.status().as_u16(), 405);
+}
+
+#[kayrx::test]
+async fn test_parse_error() {
+    let transport = HttpTransportServer::new();
+    let handle = transport.handle();
+    start_server(transport);
+
+    let srv = test::start(move || App::new().service(handle.resource("/")));
+
+    let mut response = srv.post("/").send_body("{ not json").await.unwrap();
+    let body: Value = serde_json::from_slice(&response.body().await.unwrap()).unwrap();
+    assert_eq!(body["error"]["code"], -32700);
+
+    let mut response = srv.post("/").send_json(&json!({"foo": 1})).await.unwrap();
+    let body: Value = serde_json::from_slice(&response.body().await.unwrap()).unwrap();
+    assert_eq!(body["error"]["code"], -32600);
+}
@@ -0,0 +1,131 @@
+use futures::{SinkExt, StreamExt};
+use kayrx::jrpc::common::{Params, Request, Response};
+use kayrx::jrpc::raw::{RawClient, RawServer};
+use kayrx::jrpc::transport::ws::{WsTransportClient, WsTransportServer};
+use kayrx::jrpc::transport::{TransportServer, TransportServerEvent};
+use kayrx::jrpc::{Client, Server};
+use kayrx::web::client::ws;
+use kayrx::web::{test, App};
+use serde_json::{json, Value};
+
+#[kayrx::test]
+async fn test_request_and_subscription() {
+    let transport = WsTransportServer::bind(&"127.0.0.1:0".parse().unwrap())
+        .await
+        .unwrap();
+    let addr = *transport.local_addr().unwrap();
+
+    let server = Server::from(RawServer::new(transport));
+    let mut hello = server.register_method("hello".to_owned()).unwrap();
+    let mut ticks = server
+        .register_subscription("subscribe_ticks".to_owned(), "unsubscribe_ticks".to_owned())
+        .unwrap();
+
+    kayrx::fiber::spawn(async move {
+        let _server = server;
+        loop {
+            let request = hello.next().await;
+            request.respond(Ok(Value::from("hello"))).await;
+        }
+    });
+    kayrx::fiber::spawn(async move {
+        let mut n = 0u64;
+        loop {
+            kayrx::timer::delay_for(std::time::Duration::from_millis(10)).await;
+            ticks.send(Value::from(n)).await;
+            n += 1;
+        }
+    });
+
+    let client = Client::from(RawClient::new(
+        WsTransportClient::new(format!("ws://{}/", addr))
+            .await
+            .unwrap(),
+    ));
+
+    let response: String = client.request("hello", Params::None).await.unwrap();
+    assert_eq!(response, "hello");
+
+    let mut subscription = client
+        .subscribe::<u64>("subscribe_ticks", Params::None, "unsubscribe_ticks")
+        .await
+        .unwrap();
+    let first = subscription.next().await;
+    let second = subscription.next().await;
+    assert!(second > first);
+}
+
+#[kayrx::test]
+async fn test_closed_connection() {
+    let mut transport = WsTransportServer::new();
+    let handle = transport.handle();
+    let mut srv = test::start(move || App::new().service(handle.resource("/ws")));
+
+    let mut framed = srv.ws_at("/ws").await.unwrap();
+    framed
+        .send(ws::Message::Text(
+            json!({"jsonrpc": "2.0", "method": "sub", "id": 1}).to_string(),
+        ))
+        .await
+        .unwrap();
+
+    let id = match transport.next_request().await {
+        TransportServerEvent::Request { id, request } => {
+            match request {
+                Request::Single(_) => {}
+                request => panic!("unexpected request: {:?}", request),
+            }
+            id
+        }
+        event => panic!("unexpected event: {:?}", event),
+    };
+
+    // Requests can be answered multiple times.
+    assert_eq!(transport.supports_resuming(&id), Ok(true));
+    let response = Response::from_json(r#"{"jsonrpc":"2.0","result":1,"id":1}"#).unwrap();
+    transport.send(&id, &response).await.unwrap();
+    transport.send(&id, &response).await.unwrap();
+    for _ in 0..2 {
+        match framed.next().await.unwrap().unwrap() {
+            ws::Frame::Text(text) => {
+                assert_eq!(
+                    Response::from_json(std::str::from_utf8(&text).unwrap()).unwrap(),
+                    response
+                )
+            }
+            frame => panic!("unexpected frame: {:?}", frame),
+        }
+    }
+
+    // Closing the connection closes the pending requests.
+    framed
+        .send(ws::Message::Close(Some(ws::CloseCode::Normal.into())))
+        .await
+        .unwrap();
+    assert_eq!(
+        transport.next_request().await,
+        TransportServerEvent::Closed(id)
+    );
+    assert!(transport.send(&id, &response).await.is_err());
+}
+
+#[kayrx::test]
+async fn test_parse_error() {
+    let transport = WsTransportServer::new();
+    let handle = transport.handle();
+    let mut srv = test::start(move || App::new().service(handle.resource("/")));
+
+    let mut framed = srv.ws().await.unwrap();
+    framed
+        .send(ws::Message::Text("{ not json".to_owned()))
+        .await
+        .unwrap();
+    match framed.next().await.unwrap().unwrap() {
+        ws::Frame::Text(text) => {
+            let body: Value = serde_json::from_slice(&text).unwrap();
+            assert_eq!(body["error"]["code"], -32700);
+        }
+        frame => panic!("unexpected frame: {:?}", frame),
+    }
+    drop(transport);
+}
@@ -1,6 +1,11 @@
+mod fiber;
+mod grpc;
 mod http;
+mod jrpc;
 mod krse;
+mod server;
 mod service;
+mod udba;
 mod util;
 mod web;
 mod webui;
\ No newline at end of file
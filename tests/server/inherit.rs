@@ -0,0 +1,123 @@
+use std::io::{Read, Write};
+use std::os::unix::io::AsRawFd;
+use std::os::unix::process::CommandExt;
+use std::process::Command;
+use std::time::Duration;
+use std::{env, net, thread};
+
+use kayrx::fiber::System;
+use kayrx::krse::io::AsyncWriteExt;
+use kayrx::krse::net::TcpStream;
+use kayrx::server::Server;
+use kayrx::service::fn_service;
+use kayrx::web::{self, App, HttpServer};
+
+/// Runs in the child process, the listener of the parent is at fd 3.
+fn inherited_server(addr: net::SocketAddr) {
+    let sys = System::new("test");
+    let srv = Server::build()
+        .workers(1)
+        .disable_signals()
+        .bind("web", "127.0.0.1:0", || {
+            fn_service(
+                |mut io: TcpStream| async move { io.write_all(b"inherited").await.map_err(drop) },
+            )
+        })
+        .unwrap()
+        .start();
+
+    // the variables are not passed further
+    assert!(env::var_os("LISTEN_FDS").is_none());
+    assert!(env::var_os("LISTEN_FDNAMES").is_none());
+
+    let system = System::current();
+    let client = thread::spawn(move || {
+        let mut io = net::TcpStream::connect(addr).unwrap();
+        io.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let mut buf = Vec::new();
+        let res = io.read_to_end(&mut buf);
+        system.stop();
+        res.map(|_| buf)
+    });
+    sys.run().unwrap();
+    drop(srv);
+    assert_eq!(client.join().unwrap().unwrap(), b"inherited");
+}
+
+/// Runs the test in a child process which inherits the listener under the name.
+fn run_inherited(test: &str, name: &str) {
+    let lst = net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let fd = lst.as_raw_fd();
+    let mut cmd = Command::new(env::current_exe().unwrap());
+    cmd.args(&["--exact", test])
+        .env(
+            "KAYRX_INHERITED_ADDR",
+            lst.local_addr().unwrap().to_string(),
+        )
+        .env("LISTEN_FDS", "1")
+        .env("LISTEN_FDNAMES", name);
+    unsafe {
+        cmd.pre_exec(move || {
+            // dup2() keeps close-on-exec flag if the descriptor is 3 already
+            if libc::dup2(fd, 3) < 0 || libc::fcntl(3, libc::F_SETFD, 0) < 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+    let output = cmd.output().unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    // the test has run in the child
+    assert!(String::from_utf8_lossy(&output.stdout).contains("1 passed"));
+}
+
+#[test]
+fn test_inherited_listener() {
+    match env::var("KAYRX_INHERITED_ADDR") {
+        Ok(addr) => inherited_server(addr.parse().unwrap()),
+        Err(_) => run_inherited("server::inherit::test_inherited_listener", "web"),
+    }
+}
+
+/// Runs in the child process, binding port 0 adopts the first listener.
+fn inherited_http_server(addr: net::SocketAddr) {
+    let sys = System::new("test");
+    let srv = HttpServer::new(|| App::new().route("/", web::get().to(|| async { "inherited" })))
+        .workers(1)
+        .disable_signals()
+        .bind("127.0.0.1:0")
+        .unwrap();
+    assert_eq!(srv.addrs(), vec![addr]);
+    let srv = srv.run();
+
+    let system = System::current();
+    let client = thread::spawn(move || {
+        let mut io = net::TcpStream::connect(addr).unwrap();
+        io.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        io.write_all(b"GET / HTTP/1.1\r\nconnection: close\r\n\r\n")
+            .unwrap();
+        let mut buf = Vec::new();
+        let res = io.read_to_end(&mut buf);
+        system.stop();
+        res.map(|_| buf)
+    });
+    sys.run().unwrap();
+    drop(srv);
+    let res = client.join().unwrap().unwrap();
+    assert!(res.ends_with(b"\r\n\r\ninherited"));
+}
+
+#[test]
+fn test_inherited_http_listener() {
+    match env::var("KAYRX_INHERITED_ADDR") {
+        Ok(addr) => inherited_http_server(addr.parse().unwrap()),
+        Err(_) => run_inherited(
+            "server::inherit::test_inherited_http_listener",
+            "kayrx-service-0",
+        ),
+    }
+}
@@ -0,0 +1,248 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use futures::future::{err, ok, pending, FutureExt, LocalBoxFuture};
+use kayrx::fiber::System;
+use kayrx::http::StatusCode;
+use kayrx::timer::delay_for;
+use kayrx::udba::pool::{Manager, Pool, PoolError, PooledConnection, State};
+use kayrx::udba::postgres::PostgresConnectionManager;
+use kayrx::web::{self, test, App, HttpResponse};
+
+#[derive(Default)]
+struct TestManager {
+    created: AtomicUsize,
+    healthy: AtomicBool,
+    hang_check: AtomicBool,
+    fail_connect: AtomicBool,
+}
+
+impl TestManager {
+    fn new() -> Self {
+        TestManager {
+            healthy: AtomicBool::new(true),
+            ..TestManager::default()
+        }
+    }
+}
+
+#[derive(Debug)]
+struct TestConn {
+    id: usize,
+    broken: bool,
+}
+
+impl Manager for TestManager {
+    type Connection = TestConn;
+    type Error = &'static str;
+
+    fn connect(&self) -> LocalBoxFuture<'_, Result<TestConn, &'static str>> {
+        if self.fail_connect.load(Ordering::SeqCst) {
+            return err("connect failed").boxed_local();
+        }
+        let id = self.created.fetch_add(1, Ordering::SeqCst);
+        ok(TestConn { id, broken: false }).boxed_local()
+    }
+
+    fn check<'a>(&'a self, _: &'a mut TestConn) -> LocalBoxFuture<'a, Result<(), &'static str>> {
+        if self.hang_check.load(Ordering::SeqCst) {
+            pending().boxed_local()
+        } else if self.healthy.load(Ordering::SeqCst) {
+            ok(()).boxed_local()
+        } else {
+            err("unhealthy").boxed_local()
+        }
+    }
+
+    fn has_broken(&self, conn: &mut TestConn) -> bool {
+        conn.broken
+    }
+}
+
+fn state(connections: usize, idle_connections: usize) -> State {
+    State {
+        connections,
+        idle_connections,
+    }
+}
+
+#[test]
+fn test_send_sync() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Pool<TestManager>>();
+    assert_send_sync::<Pool<PostgresConnectionManager>>();
+}
+
+#[kayrx::test]
+async fn test_reuse_and_max_size() {
+    let pool = Pool::builder()
+        .max_size(2)
+        .connection_timeout(Duration::from_millis(100))
+        .build(TestManager::new());
+
+    let c1 = pool.get().await.unwrap();
+    let c2 = pool.get().await.unwrap();
+    assert_eq!((c1.id, c2.id), (0, 1));
+    assert_eq!(pool.state(), state(2, 0));
+
+    // pool is full
+    match pool.get().await {
+        Err(PoolError::Timeout) => (),
+        res => panic!("unexpected {:?}", res.map(|_| ())),
+    }
+
+    // a waiter gets the returned connection
+    let waiter = {
+        let pool = pool.clone();
+        async move { pool.get().await.unwrap().id }
+    };
+    let release = async move {
+        delay_for(Duration::from_millis(20)).await;
+        drop(c2);
+    };
+    let (id, _) = futures::join!(waiter, release);
+    assert_eq!(id, 1);
+    assert_eq!(pool.state(), state(2, 1));
+
+    // most recently used connection first
+    drop(c1);
+    assert_eq!(pool.get().await.unwrap().id, 0);
+    assert_eq!(pool.manager().created.load(Ordering::SeqCst), 2);
+}
+
+#[kayrx::test]
+async fn test_broken_and_unhealthy() {
+    let pool = Pool::builder().max_size(2).build(TestManager::new());
+
+    let mut conn = pool.get().await.unwrap();
+    conn.broken = true;
+    drop(conn);
+    assert_eq!(pool.state(), state(0, 0));
+
+    drop(pool.get().await.unwrap());
+    assert_eq!(pool.state(), state(1, 1));
+    pool.manager().healthy.store(false, Ordering::SeqCst);
+    assert_eq!(pool.get().await.unwrap().id, 2);
+    assert_eq!(pool.state(), state(1, 1));
+
+    pool.manager().healthy.store(true, Ordering::SeqCst);
+    pool.manager().fail_connect.store(true, Ordering::SeqCst);
+    let conn = pool.get().await.unwrap();
+    assert_eq!(conn.id, 2);
+    match pool.get().await {
+        Err(PoolError::Backend("connect failed")) => (),
+        res => panic!("unexpected {:?}", res.map(|_| ())),
+    }
+    assert_eq!(pool.state(), state(1, 0));
+
+    // detached connections no longer count towards the pool size
+    let conn = conn.detach();
+    assert_eq!(conn.id, 2);
+    assert_eq!(pool.state(), state(0, 0));
+}
+
+#[kayrx::test]
+async fn test_cancelled_check() {
+    let pool = Pool::builder()
+        .connection_timeout(Duration::from_millis(50))
+        .build(TestManager::new());
+
+    drop(pool.get().await.unwrap());
+    pool.manager().hang_check.store(true, Ordering::SeqCst);
+    match pool.get().await {
+        Err(PoolError::Timeout) => (),
+        res => panic!("unexpected {:?}", res.map(|_| ())),
+    }
+    // the connection was in the middle of its check, it is not reused
+    assert_eq!(pool.state(), state(0, 0));
+}
+
+#[kayrx::test]
+async fn test_idle_timeout_and_max_lifetime() {
+    let pool = Pool::builder()
+        .idle_timeout(Some(Duration::from_millis(50)))
+        .max_lifetime(Some(Duration::from_millis(200)))
+        .build(TestManager::new());
+
+    drop(pool.get().await.unwrap());
+    assert_eq!(pool.state(), state(1, 1));
+    delay_for(Duration::from_millis(150)).await;
+    assert_eq!(pool.state(), state(0, 0));
+
+    let conn = pool.get().await.unwrap();
+    assert_eq!(conn.id, 1);
+    delay_for(Duration::from_millis(250)).await;
+    // expired while checked out
+    drop(conn);
+    assert_eq!(pool.state(), state(0, 0));
+
+    let pool = Pool::builder()
+        .idle_timeout(None)
+        .max_lifetime(Some(Duration::from_millis(50)))
+        .build(TestManager::new());
+    drop(pool.get().await.unwrap());
+    delay_for(Duration::from_millis(150)).await;
+    assert_eq!(pool.state(), state(0, 0));
+}
+
+#[test]
+fn test_reaper_restart() {
+    let pool = Pool::builder()
+        .idle_timeout(Some(Duration::from_millis(50)))
+        .build(TestManager::new());
+
+    // the reaper stops along with the thread it was spawned on
+    let p = pool.clone();
+    thread::spawn(move || {
+        System::new("test").block_on(async move {
+            drop(p.get().await.unwrap());
+        })
+    })
+    .join()
+    .unwrap();
+    assert_eq!(pool.state(), state(1, 1));
+
+    System::new("test").block_on(async move {
+        drop(pool.get().await.unwrap());
+        delay_for(Duration::from_millis(150)).await;
+        assert_eq!(pool.state(), state(0, 0));
+    });
+}
+
+#[kayrx::test]
+async fn test_extractor() {
+    let pool = Pool::builder()
+        .max_size(1)
+        .connection_timeout(Duration::from_millis(50))
+        .build(TestManager::new());
+
+    let mut srv = test::init_service(App::new().data(pool.clone()).route(
+        "/",
+        web::get().to(|conn: PooledConnection<TestManager>| async move {
+            HttpResponse::Ok().body(conn.id.to_string())
+        }),
+    ))
+    .await;
+
+    let req = test::TestRequest::get().uri("/").to_request();
+    let body = test::read_response(&mut srv, req).await;
+    assert_eq!(body, "0");
+    assert_eq!(pool.state(), state(1, 1));
+
+    let held = pool.get().await.unwrap();
+    let req = test::TestRequest::get().uri("/").to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+    drop(held);
+
+    // pool is not registered
+    let mut srv = test::init_service(App::new().route(
+        "/",
+        web::get().to(|_: PooledConnection<TestManager>| async { HttpResponse::Ok().finish() }),
+    ))
+    .await;
+    let req = test::TestRequest::get().uri("/").to_request();
+    let resp = test::call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+}
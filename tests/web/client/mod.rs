@@ -1,2 +1,5 @@
+mod proxy;
 mod response;
-mod ws;
\ No newline at end of file
+mod socks5;
+mod trailers;
+mod ws;
@@ -0,0 +1,158 @@
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::mpsc;
+use std::thread;
+
+use kayrx::http::client::Proxy;
+use kayrx::http::header;
+use kayrx::http::Response as HttpResponse;
+use kayrx::web::client::Client;
+use kayrx::web::{self, test, App, HttpRequest};
+
+/// Starts a proxy that accepts a single `CONNECT` request.
+///
+/// The request head is sent to the returned channel. With `status` 200 the
+/// connection is tunnelled to the requested host, otherwise the status is
+/// returned and the connection closed.
+fn start_connect_proxy(status: u16) -> (SocketAddr, mpsc::Receiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (tx, rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        let (mut client, _) = listener.accept().unwrap();
+
+        let mut head = Vec::new();
+        let mut byte = [0; 1];
+        while !head.ends_with(b"\r\n\r\n") {
+            client.read_exact(&mut byte).unwrap();
+            head.push(byte[0]);
+        }
+        let head = String::from_utf8(head).unwrap();
+        let target = head.split(' ').nth(1).unwrap().to_owned();
+        tx.send(head).unwrap();
+
+        if status != 200 {
+            let _ = write!(client, "HTTP/1.1 {} Proxy Error\r\n\r\n", status);
+            let _ = client.shutdown(Shutdown::Both);
+            return;
+        }
+
+        let mut server = TcpStream::connect(target).unwrap();
+        client
+            .write_all(b"HTTP/1.1 200 Connection established\r\n\r\n")
+            .unwrap();
+
+        let (mut client_rd, mut server_wr) =
+            (client.try_clone().unwrap(), server.try_clone().unwrap());
+        thread::spawn(move || {
+            let _ = io::copy(&mut client_rd, &mut server_wr);
+            let _ = server_wr.shutdown(Shutdown::Write);
+        });
+        let _ = io::copy(&mut server, &mut client);
+        let _ = client.shutdown(Shutdown::Write);
+    });
+
+    (addr, rx)
+}
+
+#[kayrx::test]
+async fn test_forward_proxy() {
+    // the test server plays the proxy and echoes what it received
+    let srv = test::start(|| {
+        App::new().default_service(web::to(|req: HttpRequest| {
+            let auth = req
+                .headers()
+                .get(header::PROXY_AUTHORIZATION)
+                .map(|h| h.to_str().unwrap().to_owned())
+                .unwrap_or_default();
+            HttpResponse::Ok().body(format!("{} {}", req.uri(), auth))
+        }))
+    });
+
+    let proxy = Proxy::new(format!("http://{}", srv.addr()))
+        .unwrap()
+        .basic_auth("username", Some("password"));
+    let client = Client::build().proxy(proxy).finish();
+
+    let mut res = client
+        .get("http://example.invalid/path?query=1")
+        .send()
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+
+    let body = res.body().await.unwrap();
+    assert_eq!(
+        body,
+        "http://example.invalid/path?query=1 Basic dXNlcm5hbWU6cGFzc3dvcmQ="
+    );
+}
+
+#[kayrx::test]
+async fn test_no_proxy() {
+    let srv = test::start(|| {
+        App::new().service(web::resource("/").to(|| HttpResponse::Ok().body("direct")))
+    });
+
+    // nothing listens on the proxy address
+    let addr = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let proxy = Proxy::new(format!("http://{}", addr))
+        .unwrap()
+        .no_proxy("localhost");
+    let client = Client::build().proxy(proxy).finish();
+
+    let mut res = client.get(srv.url("/")).send().await.unwrap();
+    assert!(res.status().is_success());
+    assert_eq!(res.body().await.unwrap(), "direct");
+}
+
+#[kayrx::test]
+async fn test_connect_tunnel() {
+    let srv = test::start(|| {
+        App::new().service(
+            web::resource("/")
+                .to(|req: HttpRequest| kayrx::websocket::handshake(req.head()).unwrap().finish()),
+        )
+    });
+    let (addr, rx) = start_connect_proxy(200);
+
+    let proxy = Proxy::new(format!("http://{}", addr))
+        .unwrap()
+        .basic_auth("username", None);
+    let client = Client::build().proxy(proxy).finish();
+
+    let (res, _framed) = client
+        .ws(format!("ws://{}/", srv.addr()))
+        .connect()
+        .await
+        .unwrap();
+    assert_eq!(res.status().as_u16(), 101);
+
+    let head = rx.recv().unwrap();
+    assert!(head.starts_with(&format!("CONNECT {} HTTP/1.1\r\n", srv.addr())));
+    assert!(head.contains("proxy-authorization: Basic dXNlcm5hbWU6\r\n"));
+}
+
+#[kayrx::test]
+async fn test_connect_tunnel_refused() {
+    let (addr, rx) = start_connect_proxy(407);
+    let client = Client::build()
+        .proxy(Proxy::new(format!("http://{}", addr)).unwrap())
+        .finish();
+
+    let err = client
+        .ws("ws://example.invalid/")
+        .connect()
+        .await
+        .err()
+        .unwrap();
+    assert!(err.to_string().contains("407"));
+    assert!(rx
+        .recv()
+        .unwrap()
+        .starts_with("CONNECT example.invalid:80 HTTP/1.1\r\n"));
+}
@@ -0,0 +1,154 @@
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::mpsc;
+use std::thread;
+
+use kayrx::connect::Socks5Connector;
+use kayrx::http::client::Connector;
+use kayrx::http::Response as HttpResponse;
+use kayrx::web::client::Client;
+use kayrx::web::{self, test, App};
+
+/// Starts a SOCKS5 proxy that accepts a single connection.
+///
+/// `credentials` enables username/password authentication, `reply` is the
+/// reply code sent to the connect request. The requested target is sent to
+/// the returned channel.
+fn start_socks5_proxy(
+    credentials: Option<(&'static str, &'static str)>,
+    reply: u8,
+) -> (SocketAddr, mpsc::Receiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (tx, rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        let (mut client, _) = listener.accept().unwrap();
+        let mut buf = [0; 2];
+
+        client.read_exact(&mut buf).unwrap();
+        assert_eq!(buf[0], 5);
+        let mut methods = vec![0; buf[1] as usize];
+        client.read_exact(&mut methods).unwrap();
+
+        if let Some((username, password)) = credentials {
+            assert!(methods.contains(&2));
+            client.write_all(&[5, 2]).unwrap();
+
+            let mut ver = [0; 1];
+            client.read_exact(&mut ver).unwrap();
+            let read_str = |client: &mut TcpStream| {
+                let mut len = [0; 1];
+                client.read_exact(&mut len).unwrap();
+                let mut s = vec![0; len[0] as usize];
+                client.read_exact(&mut s).unwrap();
+                String::from_utf8(s).unwrap()
+            };
+            let user = read_str(&mut client);
+            let pass = read_str(&mut client);
+            if user != username || pass != password {
+                client.write_all(&[1, 1]).unwrap();
+                return;
+            }
+            client.write_all(&[1, 0]).unwrap();
+        } else {
+            client.write_all(&[5, 0]).unwrap();
+        }
+
+        let mut head = [0; 4];
+        client.read_exact(&mut head).unwrap();
+        assert_eq!(&head[..3], &[5, 1, 0]);
+        assert_eq!(head[3], 3, "host names are resolved by the proxy");
+        let mut len = [0; 1];
+        client.read_exact(&mut len).unwrap();
+        let mut host = vec![0; len[0] as usize];
+        client.read_exact(&mut host).unwrap();
+        let mut port = [0; 2];
+        client.read_exact(&mut port).unwrap();
+        let target = format!(
+            "{}:{}",
+            String::from_utf8(host).unwrap(),
+            u16::from_be_bytes(port)
+        );
+        tx.send(target.clone()).unwrap();
+
+        client
+            .write_all(&[5, reply, 0, 1, 127, 0, 0, 1, 0, 0])
+            .unwrap();
+        if reply != 0 {
+            return;
+        }
+
+        let mut server = TcpStream::connect(target).unwrap();
+        let (mut client_rd, mut server_wr) =
+            (client.try_clone().unwrap(), server.try_clone().unwrap());
+        thread::spawn(move || {
+            let _ = io::copy(&mut client_rd, &mut server_wr);
+            let _ = server_wr.shutdown(Shutdown::Write);
+        });
+        let _ = io::copy(&mut server, &mut client);
+        let _ = client.shutdown(Shutdown::Write);
+    });
+
+    (addr, rx)
+}
+
+fn client(connector: Socks5Connector<kayrx::http::Uri>) -> Client {
+    Client::build()
+        .connector(Connector::new().connector(connector).finish())
+        .finish()
+}
+
+fn start_server() -> test::TestServer {
+    test::start(|| App::new().service(web::resource("/").to(|| HttpResponse::Ok().body("socks"))))
+}
+
+#[kayrx::test]
+async fn test_no_auth() {
+    let srv = start_server();
+    let (addr, rx) = start_socks5_proxy(None, 0);
+
+    let client = client(Socks5Connector::new(addr));
+    let mut res = client.get(srv.url("/")).send().await.unwrap();
+    assert!(res.status().is_success());
+    assert_eq!(res.body().await.unwrap(), "socks");
+
+    assert_eq!(
+        rx.recv().unwrap(),
+        format!("localhost:{}", srv.addr().port())
+    );
+}
+
+#[kayrx::test]
+async fn test_username_password() {
+    let srv = start_server();
+    let (addr, _rx) = start_socks5_proxy(Some(("user", "secret")), 0);
+
+    let client = client(Socks5Connector::new(addr).credentials("user", "secret"));
+    let mut res = client.get(srv.url("/")).send().await.unwrap();
+    assert_eq!(res.body().await.unwrap(), "socks");
+}
+
+#[kayrx::test]
+async fn test_authentication_failed() {
+    let (addr, _rx) = start_socks5_proxy(Some(("user", "secret")), 0);
+
+    let client = client(Socks5Connector::new(addr).credentials("user", "wrong"));
+    let err = client.get("http://localhost/").send().await.err().unwrap();
+    assert!(err.to_string().contains("Authentication failed"));
+}
+
+#[kayrx::test]
+async fn test_connection_refused() {
+    let (addr, rx) = start_socks5_proxy(None, 5);
+
+    let client = client(Socks5Connector::new(addr));
+    let err = client
+        .get("http://example.invalid:8080/")
+        .send()
+        .await
+        .err()
+        .unwrap();
+    assert!(err.to_string().contains("connection refused"));
+    assert_eq!(rx.recv().unwrap(), "example.invalid:8080");
+}
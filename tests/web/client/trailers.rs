@@ -0,0 +1,71 @@
+use bytes::Bytes;
+use futures::stream;
+
+use kayrx::http::body::{Body, BodyStream, WithTrailers};
+use kayrx::http::header::{HeaderMap, HeaderName, HeaderValue};
+use kayrx::http::Response as HttpResponse;
+use kayrx::web::{self, test, App};
+
+fn trailers() -> HeaderMap {
+    let mut trailers = HeaderMap::new();
+    trailers.insert(
+        HeaderName::from_static("grpc-status"),
+        HeaderValue::from_static("0"),
+    );
+    trailers.insert(
+        HeaderName::from_static("grpc-message"),
+        HeaderValue::from_static("done"),
+    );
+    trailers
+}
+
+fn start_server() -> test::TestServer {
+    test::start(|| {
+        App::new()
+            .service(web::resource("/").to(|| {
+                HttpResponse::Ok().body(Body::from_message(WithTrailers::new(
+                    "trailers",
+                    trailers(),
+                )))
+            }))
+            .service(web::resource("/stream").to(|| {
+                let body = BodyStream::new(stream::iter(vec![
+                    Ok::<_, kayrx::http::error::Error>(Bytes::from_static(b"first ")),
+                    Ok(Bytes::from_static(b"second")),
+                ]));
+                HttpResponse::Ok().body(Body::from_message(WithTrailers::new(body, trailers())))
+            }))
+            .service(web::resource("/plain").to(|| HttpResponse::Ok().body("plain")))
+    })
+}
+
+#[kayrx::test]
+async fn test_h1_trailers() {
+    let srv = start_server();
+
+    let mut res = srv.get("/").send().await.unwrap();
+    assert!(res.status().is_success());
+    assert!(res.trailers().is_none());
+    assert_eq!(res.body().await.unwrap(), Bytes::from_static(b"trailers"));
+
+    let trailers = res.trailers().unwrap();
+    assert_eq!(trailers.get("grpc-status").unwrap(), "0");
+    assert_eq!(trailers.get("grpc-message").unwrap(), "done");
+}
+
+#[kayrx::test]
+async fn test_h1_stream_trailers() {
+    let srv = start_server();
+
+    let mut res = srv.get("/stream").send().await.unwrap();
+    assert_eq!(
+        res.body().await.unwrap(),
+        Bytes::from_static(b"first second")
+    );
+    assert_eq!(res.trailers().unwrap().get("grpc-status").unwrap(), "0");
+
+    // the connection is reused after the trailers
+    let mut res = srv.get("/plain").send().await.unwrap();
+    assert_eq!(res.body().await.unwrap(), Bytes::from_static(b"plain"));
+    assert!(res.trailers().is_none());
+}
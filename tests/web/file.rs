@@ -786,4 +786,178 @@ async fn test_path_buf() {
         PathBufWrp::get_pathbuf("/seg1/../seg2/").unwrap().0,
         PathBuf::from_iter(vec!["seg2"])
     );
-}
\ No newline at end of file
+}
+/// Creates a directory with `app.js` and its precompressed variants.
+fn precompressed_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("kayrx-precompressed-{}", name));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("app.js"), "console.log('identity');").unwrap();
+    fs::write(dir.join("app.js.gz"), "gzip").unwrap();
+    fs::write(dir.join("app.js.br"), "brotli").unwrap();
+    fs::write(dir.join("plain.txt"), "plain").unwrap();
+    dir
+}
+
+#[kayrx::test]
+async fn test_files_precompressed() {
+    let dir = precompressed_dir("negotiation");
+    let mut srv = test::init_service(
+        App::new().service(Files::new("/", &dir).use_precompressed(true)),
+    )
+    .await;
+
+    let cases = vec![
+        ("gzip, deflate, br", Some("br"), "brotli"),
+        ("gzip", Some("gzip"), "gzip"),
+        ("br;q=0.5, gzip", Some("gzip"), "gzip"),
+        ("*", Some("br"), "brotli"),
+        ("br;q=0, gzip;q=0", None, "console.log('identity');"),
+        ("deflate", None, "console.log('identity');"),
+    ];
+    for (accept, encoding, body) in cases {
+        let request = TestRequest::get()
+            .uri("/app.js")
+            .header(header::ACCEPT_ENCODING, accept)
+            .to_request();
+        let response = test::call_service(&mut srv, request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response
+                .headers()
+                .get(header::CONTENT_ENCODING)
+                .map(|h| h.to_str().unwrap()),
+            encoding,
+            "{}",
+            accept
+        );
+        assert_eq!(
+            response.headers().get(header::VARY).unwrap(),
+            "Accept-Encoding"
+        );
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            file_extension_to_mime("js").as_ref()
+        );
+        assert_eq!(test::read_body(response).await, Bytes::from(body));
+    }
+
+    // no variants
+    let request = TestRequest::get()
+        .uri("/plain.txt")
+        .header(header::ACCEPT_ENCODING, "gzip, br")
+        .to_request();
+    let response = test::call_service(&mut srv, request).await;
+    assert!(!response.headers().contains_key(header::CONTENT_ENCODING));
+    assert_eq!(test::read_body(response).await, Bytes::from("plain"));
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[kayrx::test]
+async fn test_files_precompressed_disabled() {
+    let dir = precompressed_dir("disabled");
+    let mut srv = test::init_service(App::new().service(Files::new("/", &dir))).await;
+
+    let request = TestRequest::get()
+        .uri("/app.js")
+        .header(header::ACCEPT_ENCODING, "gzip, br")
+        .to_request();
+    let response = test::call_service(&mut srv, request).await;
+    assert!(!response.headers().contains_key(header::CONTENT_ENCODING));
+    assert!(!response.headers().contains_key(header::VARY));
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[kayrx::test]
+async fn test_files_precompressed_compress_middleware() {
+    let dir = precompressed_dir("compress");
+    let mut srv = test::init_service(
+        App::new()
+            .wrap(Compress::default())
+            .service(Files::new("/", &dir).use_precompressed(true)),
+    )
+    .await;
+
+    let request = TestRequest::get()
+        .uri("/app.js")
+        .header(header::ACCEPT_ENCODING, "gzip")
+        .to_request();
+    let response = test::call_service(&mut srv, request).await;
+    assert_eq!(
+        response.headers().get(header::CONTENT_ENCODING).unwrap(),
+        "gzip"
+    );
+    // served as is, not compressed twice
+    assert_eq!(test::read_body(response).await, Bytes::from("gzip"));
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[kayrx::test]
+async fn test_files_precompressed_etag_and_range() {
+    let dir = precompressed_dir("etag");
+    let mut srv = test::init_service(
+        App::new().service(Files::new("/", &dir).use_precompressed(true)),
+    )
+    .await;
+
+    let get = |accept: &str| {
+        TestRequest::get()
+            .uri("/app.js")
+            .header(header::ACCEPT_ENCODING, accept)
+    };
+
+    let response = test::call_service(&mut srv, get("br").to_request()).await;
+    let br_etag = response.headers().get(header::ETAG).unwrap().clone();
+    let response = test::call_service(&mut srv, get("identity").to_request()).await;
+    let etag = response.headers().get(header::ETAG).unwrap().clone();
+    assert_ne!(br_etag, etag);
+
+    // conditional request against the selected variant
+    let request = get("br").header(header::IF_NONE_MATCH, br_etag.clone()).to_request();
+    let response = test::call_service(&mut srv, request).await;
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(response.headers().get(header::CONTENT_ENCODING).unwrap(), "br");
+
+    let request = get("gzip").header(header::IF_NONE_MATCH, br_etag).to_request();
+    let response = test::call_service(&mut srv, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // ranges apply to the encoded bytes
+    let request = get("br").header(header::RANGE, "bytes=1-3").to_request();
+    let response = test::call_service(&mut srv, request).await;
+    assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(
+        response.headers().get(header::CONTENT_RANGE).unwrap(),
+        "bytes 1-3/6"
+    );
+    assert_eq!(response.headers().get(header::CONTENT_ENCODING).unwrap(), "br");
+    assert_eq!(test::read_body(response).await, Bytes::from("rot"));
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[kayrx::test]
+async fn test_files_precompressed_stale_variant() {
+    let dir = precompressed_dir("stale");
+    // make the original newer than its variants
+    std::thread::sleep(Duration::from_millis(20));
+    fs::write(dir.join("app.js"), "updated").unwrap();
+
+    let mut srv = test::init_service(
+        App::new().service(Files::new("/", &dir).use_precompressed(true)),
+    )
+    .await;
+
+    let request = TestRequest::get()
+        .uri("/app.js")
+        .header(header::ACCEPT_ENCODING, "gzip, br")
+        .to_request();
+    let response = test::call_service(&mut srv, request).await;
+    assert!(!response.headers().contains_key(header::CONTENT_ENCODING));
+    assert_eq!(test::read_body(response).await, Bytes::from("updated"));
+
+    let _ = fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,106 @@
+use bytes::Bytes;
+use futures::stream::{self, StreamExt};
+
+use kayrx::http::error::PayloadError;
+use kayrx::http::header::{self, ContentEncoding};
+use kayrx::http::Response as HttpResponse;
+use kayrx::web::dev::Decompress;
+use kayrx::web::middleware::Compress;
+use kayrx::web::test::{self, call_service, init_service, read_body, TestRequest};
+use kayrx::web::{self, App};
+
+const STR: &str = "Hello World Hello World Hello World Hello World Hello World \
+                   Hello World Hello World Hello World Hello World Hello World";
+
+#[kayrx::test]
+async fn test_compress_zstd() {
+    let mut srv = init_service(
+        App::new()
+            .wrap(Compress::default())
+            .service(web::resource("/").to(|| HttpResponse::Ok().body(STR))),
+    )
+    .await;
+
+    let req = TestRequest::with_header(header::ACCEPT_ENCODING, "zstd").to_request();
+    let res = call_service(&mut srv, req).await;
+    assert_eq!(res.headers().get(header::CONTENT_ENCODING).unwrap(), "zstd");
+
+    let body = read_body(res).await;
+    assert_eq!(zstd::decode_all(&body[..]).unwrap(), STR.as_bytes());
+}
+
+#[kayrx::test]
+async fn test_compress_negotiation() {
+    let mut srv = init_service(
+        App::new()
+            .wrap(Compress::default())
+            .service(web::resource("/").to(|| HttpResponse::Ok().body(STR))),
+    )
+    .await;
+
+    let req = TestRequest::with_header(header::ACCEPT_ENCODING, "gzip, zstd, deflate").to_request();
+    let res = call_service(&mut srv, req).await;
+    assert_eq!(res.headers().get(header::CONTENT_ENCODING).unwrap(), "zstd");
+
+    let req = TestRequest::with_header(header::ACCEPT_ENCODING, "br, zstd").to_request();
+    let res = call_service(&mut srv, req).await;
+    assert_eq!(res.headers().get(header::CONTENT_ENCODING).unwrap(), "br");
+}
+
+#[kayrx::test]
+async fn test_compress_level() {
+    let mut srv = init_service(
+        App::new()
+            .wrap(
+                Compress::new(ContentEncoding::Zstd)
+                    .level(ContentEncoding::Zstd, 19)
+                    .level(ContentEncoding::Gzip, 9),
+            )
+            .service(web::resource("/").to(|| HttpResponse::Ok().body(STR))),
+    )
+    .await;
+
+    let req = TestRequest::with_header(header::ACCEPT_ENCODING, "zstd").to_request();
+    let res = call_service(&mut srv, req).await;
+    let body = read_body(res).await;
+    assert_eq!(zstd::decode_all(&body[..]).unwrap(), STR.as_bytes());
+
+    // only the configured encoding is used
+    let req = TestRequest::with_header(header::ACCEPT_ENCODING, "gzip").to_request();
+    let res = call_service(&mut srv, req).await;
+    assert!(!res.headers().contains_key(header::CONTENT_ENCODING));
+}
+
+#[kayrx::test]
+async fn test_decompress_zstd_payload() {
+    let data = zstd::encode_all(STR.as_bytes(), 3).unwrap();
+    let (first, second) = data.split_at(data.len() / 2);
+    let payload = stream::iter(vec![
+        Ok::<_, PayloadError>(Bytes::copy_from_slice(first)),
+        Ok(Bytes::copy_from_slice(second)),
+    ]);
+
+    let mut decoder = Decompress::new(payload, ContentEncoding::Zstd);
+    let mut body = Vec::new();
+    while let Some(chunk) = decoder.next().await {
+        body.extend_from_slice(&chunk.unwrap());
+    }
+    assert_eq!(body, STR.as_bytes());
+}
+
+#[kayrx::test]
+async fn test_client_zstd_decompress() {
+    let srv = test::start(|| {
+        App::new()
+            .wrap(Compress::new(ContentEncoding::Zstd))
+            .service(web::resource("/").to(|| HttpResponse::Ok().body(STR)))
+    });
+
+    let mut res = srv.get("/").send().await.unwrap();
+    assert!(res.status().is_success());
+    assert_eq!(res.headers().get(header::CONTENT_ENCODING).unwrap(), "zstd");
+    assert_eq!(
+        res.body().await.unwrap(),
+        Bytes::from_static(STR.as_bytes())
+    );
+}
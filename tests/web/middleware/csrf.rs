@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+
+use kayrx::http::error::coo_kie::Cookie;
+use kayrx::http::header::{HeaderValue, ORIGIN, REFERER, SET_COOKIE};
+use kayrx::http::{Method, StatusCode};
+use kayrx::web::dev::ServiceResponse;
+use kayrx::web::middleware::csrf::{Csrf, CsrfToken};
+use kayrx::web::middleware::session::{CookieSessionStore, SessionMiddleware};
+use kayrx::web::test::{call_service, init_service, read_body, TestRequest};
+use kayrx::web::types::Form;
+use kayrx::web::{self, App, HttpResponse};
+
+fn cookie<B>(res: &ServiceResponse<B>, name: &str) -> Option<Cookie<'static>> {
+    res.headers()
+        .get_all(SET_COOKIE)
+        .map(|value| Cookie::parse_encoded(value.to_str().unwrap().to_owned()).unwrap())
+        .find(|cookie| cookie.name() == name)
+}
+
+async fn token(token: CsrfToken) -> String {
+    token.token().to_owned()
+}
+
+async fn submit(form: Form<HashMap<String, String>>) -> String {
+    form.get("name").cloned().unwrap_or_default()
+}
+
+#[kayrx::test]
+async fn test_double_submit() {
+    let mut app = init_service(
+        App::new()
+            .wrap(Csrf::double_submit().exempt("/hooks"))
+            .route("/", web::get().to(token))
+            .route("/", web::post().to(submit))
+            .route("/hooks/push", web::post().to(|| HttpResponse::Ok())),
+    )
+    .await;
+
+    let res = call_service(&mut app, TestRequest::with_uri("/").to_request()).await;
+    let cookie = cookie(&res, "csrf-token").unwrap();
+    assert_ne!(cookie.http_only(), Some(true));
+    assert_eq!(read_body(res).await, cookie.value());
+
+    // the token is kept while the cookie is sent
+    let req = TestRequest::with_uri("/")
+        .cookie(cookie.clone())
+        .to_request();
+    let res = call_service(&mut app, req).await;
+    assert!(res.headers().get(SET_COOKIE).is_none());
+    assert_eq!(read_body(res).await, cookie.value());
+
+    let req = TestRequest::with_uri("/")
+        .method(Method::POST)
+        .cookie(cookie.clone())
+        .header("x-csrf-token", cookie.value())
+        .set_form(&[("name", "header")])
+        .to_request();
+    let res = call_service(&mut app, req).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(read_body(res).await, "header");
+
+    // the form body is still available to the handler
+    let req = TestRequest::with_uri("/")
+        .method(Method::POST)
+        .cookie(cookie.clone())
+        .set_form(&[("csrf_token", cookie.value()), ("name", "form")])
+        .to_request();
+    let res = call_service(&mut app, req).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(read_body(res).await, "form");
+
+    let req = TestRequest::with_uri("/")
+        .method(Method::POST)
+        .cookie(cookie.clone())
+        .header("x-csrf-token", "forged")
+        .to_request();
+    let res = call_service(&mut app, req).await;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    // a token without the cookie is rejected
+    let req = TestRequest::with_uri("/")
+        .method(Method::POST)
+        .header("x-csrf-token", cookie.value())
+        .to_request();
+    let res = call_service(&mut app, req).await;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    let req = TestRequest::with_uri("/hooks/push")
+        .method(Method::POST)
+        .to_request();
+    let res = call_service(&mut app, req).await;
+    assert_eq!(res.status(), StatusCode::OK);
+}
+
+#[kayrx::test]
+async fn test_origin() {
+    let mut app = init_service(
+        App::new()
+            .wrap(Csrf::double_submit().trusted_origin("https://www.example.com/"))
+            .route("/", web::post().to(|| HttpResponse::Ok())),
+    )
+    .await;
+
+    let post = |name, value| {
+        TestRequest::with_uri("/")
+            .method(Method::POST)
+            .header("host", "example.com")
+            .header(name, value)
+            .to_request()
+    };
+
+    let res = call_service(&mut app, post(ORIGIN, "http://example.com")).await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = call_service(&mut app, post(ORIGIN, "https://www.example.com")).await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = call_service(&mut app, post(ORIGIN, "https://evil.com")).await;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    let res = call_service(&mut app, post(ORIGIN, "null")).await;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    let res = call_service(&mut app, post(REFERER, "http://example.com/form?x=1")).await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = call_service(&mut app, post(REFERER, "http://example.com.evil.com/")).await;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    let req = TestRequest::with_uri("/").method(Method::POST).to_request();
+    let res = call_service(&mut app, req).await;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    // a malformed token does not fall back to the origin
+    let req = TestRequest::with_uri("/")
+        .method(Method::POST)
+        .header("host", "example.com")
+        .header(ORIGIN, "http://example.com")
+        .header("x-csrf-token", HeaderValue::from_bytes(b"\xfftoken").unwrap())
+        .to_request();
+    let res = call_service(&mut app, req).await;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+}
+
+#[kayrx::test]
+async fn test_synchronizer() {
+    let mut app = init_service(
+        App::new()
+            .wrap(Csrf::synchronizer())
+            .wrap(SessionMiddleware::new(CookieSessionStore::signed(&[0; 32])))
+            .route("/", web::get().to(token))
+            .route("/", web::post().to(submit)),
+    )
+    .await;
+
+    let res = call_service(&mut app, TestRequest::with_uri("/").to_request()).await;
+    assert!(cookie(&res, "csrf-token").is_none());
+    let session = cookie(&res, "kayrx-session").unwrap();
+    let token = String::from_utf8(read_body(res).await.to_vec()).unwrap();
+
+    let req = TestRequest::with_uri("/")
+        .cookie(session.clone())
+        .to_request();
+    let res = call_service(&mut app, req).await;
+    assert_eq!(read_body(res).await, token.as_bytes());
+
+    let req = TestRequest::with_uri("/")
+        .method(Method::POST)
+        .cookie(session.clone())
+        .set_form(&[("csrf_token", token.as_str()), ("name", "session")])
+        .to_request();
+    let res = call_service(&mut app, req).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(read_body(res).await, "session");
+
+    // the token of another session is rejected
+    let req = TestRequest::with_uri("/")
+        .method(Method::PUT)
+        .header("x-csrf-token", token.as_str())
+        .to_request();
+    let res = call_service(&mut app, req).await;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+}
@@ -0,0 +1,388 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use kayrx::http::header::{AUTHORIZATION, WWW_AUTHENTICATE};
+use kayrx::http::StatusCode;
+use kayrx::web::client::Client;
+use kayrx::web::middleware::auth::BearerConfig;
+use kayrx::web::middleware::jwt::{
+    decode, Algorithm, DecodingKey, JwtAuth, JwtError, KeySet, Validation,
+};
+use kayrx::web::test::{self, call_service, init_service, read_body, TestRequest};
+use kayrx::web::{self, App, HttpResponse, ReqData};
+use ring::hmac;
+use ring::rand::SystemRandom;
+use ring::signature::{self, EcdsaKeyPair, KeyPair, RsaKeyPair};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+const RSA_PRIVATE_KEY: &str = "\
+MIIEvAIBADANBgkqhkiG9w0BAQEFAASCBKYwggSiAgEAAoIBAQCtZv+FztsRAT54
+1mf4kMvijyj1pJ52WP0OeaUjU2eifCpyS1Okg0ulhQMfEg4JjdESfXqe4TS2A0kN
+9N3q0Qmwtn4xllcTr6NIzjk1v4EAQOomM0RZtUNIdvn/5vU8uZo8M70tM+RgtLhE
+tj3XyAyYkDec7fiA1ilrKY4CKxL0agSIIzbmBzLnYzp0RqycH0n3RdNYmvDtsDWq
+IfUrQytW14elRneRP8TRz1viGui/lvwT/zi9ONWV+xDDAQBsBkZB3SlUVPKuj68Y
+HwFTE6bP5AUupHFi5u71E5wPsXYii74Jj6J8BRKiY/IBPjfrUeORhrj0Dy6u7C2x
+GKpe0RTfAgMBAAECggEAGAmoMGdtjNzsSRoHzbhc2ypXVj4czXEmm2QXlV8Rhb/K
+ro9sp+ifivF2bk81bPOg4PxODEpR4U1xSiMAxHV372xM8BjUN2FotEYKPj4u5tcY
+3nTK69ip3BjKLURaCLH+BIxzlBmWdiV3SectiYUs4dVUMEf44/tMQJPBIOHRvdnd
+xqUoClrHAghSS827kE6bVg61xkrOjjo/unBXKSHfo73/GQV3xH0N+n2c7jEF92/N
+QyZJe8aqkwqaMfMEymyB1HdkZ8gqdMA3NNMCZhANhbUJqlA+q0dlekSq/YoouzDN
+q786OksLoJJCDclm9aHrt8mBbbH2/bfGtIXd+9fiwQKBgQDq17VYoF0fbzDYclhp
+kh9gvkAccZ+1nvrPv/d1jSHV+U5qwmRNIyBcffhXN6ckWXziwQ9u93ueMF/xPXKW
+7+SLZfzrECjDDqLHvj0vs56Scjn3+up95b5tCwTmODheI20Q5H+xezCAVmPJO9Vo
+rqvb0B8BaDT8OqNy98KSbiXLIQKBgQC9BkMhb1Epysc2IWB8hY6IEsh1pCd51Ywe
+POVGkktaj55kWJF/RrwCa1ol8y6HUs5rolsZ/oraFBv51O2367zYKkMDB9Okn5i1
+RvCw0kAIDwfuZKy1MzdrsOhhaeIihoBnE/X9aktJ+VQkJgxV3zZvLZqEMyLLE7IF
+E/98vfrf/wKBgG+zUippfGGrPYx2Nt9gBVS6qTveP5zflWSjZkyfaGyiPE5DT/nw
+rz/PGwRBEFsVxXUFZxXc2QuQ7tGL7KkmSYqUZAxaj0jX+U0Qi4HY6Z+2fSQaUNa5
+JC4xg48NRqJgL0xNov8EHTmt3aCluQglrNDM2TKOIkWmjn/soLuNcKSBAoGAeavz
+QC1QOC9/pZc8bUrm4S39/MOKaYEM7VXTW2bFvGhf00WzehLE6GXDpFPxakfh+SSL
+saLIK/HvxBPMZKwIZbhmZCgAkqHsgRtFyPu9HbLXLxt8lDKAhQBdxH6Mgh7DvhX6
+LIHkLpygDZi0Lf49IrlfEaN8zO1DlMM3nEsJlm8CgYAzRFGh1AwaF10NnGy94Uv1
+1/O8jAhhQMjBvVn/ZrAN/QZ57dDB6ZGorHHObS4A7QFfqN9/YVhjJdm8FCwfEvdv
+IbJmVT0XRH5dZ/lndP6Wo2sykpE5L+K1hEC9O90nYEM+unLJpUw/fiOEIdtxkm+v
+WNmvNs0e4+10xxuXUZMJHg==";
+
+const RSA_PUBLIC_KEY: &str = "\
+-----BEGIN PUBLIC KEY-----
+MIIBIjANBgkqhkiG9w0BAQEFAAOCAQ8AMIIBCgKCAQEArWb/hc7bEQE+eNZn+JDL
+4o8o9aSedlj9DnmlI1NnonwqcktTpINLpYUDHxIOCY3REn16nuE0tgNJDfTd6tEJ
+sLZ+MZZXE6+jSM45Nb+BAEDqJjNEWbVDSHb5/+b1PLmaPDO9LTPkYLS4RLY918gM
+mJA3nO34gNYpaymOAisS9GoEiCM25gcy52M6dEasnB9J90XTWJrw7bA1qiH1K0Mr
+VteHpUZ3kT/E0c9b4hrov5b8E/84vTjVlfsQwwEAbAZGQd0pVFTyro+vGB8BUxOm
+z+QFLqRxYubu9ROcD7F2Iou+CY+ifAUSomPyAT4361HjkYa49A8uruwtsRiqXtEU
+3wIDAQAB
+-----END PUBLIC KEY-----
+";
+
+const EC_PRIVATE_KEY: &str = "\
+MIGHAgEAMBMGByqGSM49AgEGCCqGSM49AwEHBG0wawIBAQQgipSh60meRgAD+jUG
+pcFnTdfGmrZsX2z2T02/3T5wuCWhRANCAASIqV5YUTG+HiVilt6C3US6m8HafVbv
+SfSOHzgUTe1zwSwgUf7i7SfNUGyqqS9/68iTBglC/RfV6JanpBsjhrYz";
+
+const EC_PUBLIC_KEY: &str = "\
+-----BEGIN PUBLIC KEY-----
+MFkwEwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAEiKleWFExvh4lYpbegt1EupvB2n1W
+70n0jh84FE3tc8EsIFH+4u0nzVBsqqkvf+vIkwYJQv0X1eiWp6QbI4a2Mw==
+-----END PUBLIC KEY-----
+";
+
+#[derive(Clone, Debug, Deserialize)]
+struct Claims {
+    sub: String,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+fn b64(data: &[u8]) -> String {
+    base64::encode_config(data, base64::URL_SAFE_NO_PAD)
+}
+
+fn pkcs8(key: &str) -> Vec<u8> {
+    base64::decode(&key.replace('\n', "")).unwrap()
+}
+
+/// Encodes the token, signs it with the signer
+fn token<F: FnOnce(&[u8]) -> Vec<u8>>(header: Value, claims: Value, sign: F) -> String {
+    let message = format!(
+        "{}.{}",
+        b64(header.to_string().as_bytes()),
+        b64(claims.to_string().as_bytes())
+    );
+    let signature = sign(message.as_bytes());
+    format!("{}.{}", message, b64(&signature))
+}
+
+fn hs256(secret: &[u8], kid: Option<&str>, claims: Value) -> String {
+    let mut header = json!({"alg": "HS256", "typ": "JWT"});
+    if let Some(kid) = kid {
+        header["kid"] = json!(kid);
+    }
+    token(header, claims, |msg| {
+        let key = hmac::Key::new(hmac::HMAC_SHA256, secret);
+        hmac::sign(&key, msg).as_ref().to_vec()
+    })
+}
+
+fn rs256(claims: Value) -> String {
+    token(json!({"alg": "RS256"}), claims, |msg| {
+        let key = RsaKeyPair::from_pkcs8(&pkcs8(RSA_PRIVATE_KEY)).unwrap();
+        let mut signature = vec![0; key.public_modulus_len()];
+        key.sign(
+            &signature::RSA_PKCS1_SHA256,
+            &SystemRandom::new(),
+            msg,
+            &mut signature,
+        )
+        .unwrap();
+        signature
+    })
+}
+
+fn es256(key: &EcdsaKeyPair, kid: &str, claims: Value) -> String {
+    token(json!({"alg": "ES256", "kid": kid}), claims, |msg| {
+        key.sign(&SystemRandom::new(), msg)
+            .unwrap()
+            .as_ref()
+            .to_vec()
+    })
+}
+
+fn ec_key() -> EcdsaKeyPair {
+    EcdsaKeyPair::from_pkcs8(
+        &signature::ECDSA_P256_SHA256_FIXED_SIGNING,
+        &pkcs8(EC_PRIVATE_KEY),
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_decode() {
+    let keys = KeySet::new()
+        .key(Algorithm::HS256, DecodingKey::from_secret(b"secret"))
+        .key(
+            Algorithm::RS256,
+            DecodingKey::from_rsa_pem(RSA_PUBLIC_KEY.as_bytes()).unwrap(),
+        )
+        .key_with_id(
+            "ec",
+            Algorithm::ES256,
+            DecodingKey::from_ec_pem(EC_PUBLIC_KEY.as_bytes()).unwrap(),
+        );
+    let validation = Validation::new();
+    let exp = now() + 60;
+
+    let claims: Claims = decode(
+        &hs256(b"secret", None, json!({"sub": "hs", "exp": exp})),
+        &keys,
+        &validation,
+    )
+    .unwrap();
+    assert_eq!(claims.sub, "hs");
+
+    let claims: Claims =
+        decode(&rs256(json!({"sub": "rs", "exp": exp})), &keys, &validation).unwrap();
+    assert_eq!(claims.sub, "rs");
+
+    let claims: Claims = decode(
+        &es256(&ec_key(), "ec", json!({"sub": "es", "exp": exp})),
+        &keys,
+        &validation,
+    )
+    .unwrap();
+    assert_eq!(claims.sub, "es");
+
+    let token = hs256(b"other", None, json!({"sub": "hs", "exp": exp}));
+    match decode::<Claims>(&token, &keys, &validation) {
+        Err(JwtError::InvalidSignature) => (),
+        res => panic!("{:?}", res),
+    }
+
+    let token = es256(&ec_key(), "unknown", json!({"sub": "es", "exp": exp}));
+    match decode::<Claims>(&token, &keys, &validation) {
+        Err(JwtError::UnknownKey) => (),
+        res => panic!("{:?}", res),
+    }
+
+    // the secret key is not used for other algorithms
+    let token = token_with_alg("RS256", b"secret", json!({"sub": "hs", "exp": exp}));
+    match decode::<Claims>(&token, &keys, &validation) {
+        Err(JwtError::InvalidSignature) => (),
+        res => panic!("{:?}", res),
+    }
+
+    let token = hs256(b"secret", None, json!({"sub": "hs", "exp": exp}));
+    match decode::<Claims>(
+        &token,
+        &keys,
+        &Validation::new().algorithms(&[Algorithm::RS256]),
+    ) {
+        Err(JwtError::UnsupportedAlgorithm) => (),
+        res => panic!("{:?}", res),
+    }
+
+    match decode::<Claims>("a.b", &keys, &validation) {
+        Err(JwtError::Malformed) => (),
+        res => panic!("{:?}", res),
+    }
+}
+
+fn token_with_alg(alg: &str, secret: &[u8], claims: Value) -> String {
+    token(json!({ "alg": alg }), claims, |msg| {
+        let key = hmac::Key::new(hmac::HMAC_SHA256, secret);
+        hmac::sign(&key, msg).as_ref().to_vec()
+    })
+}
+
+#[test]
+fn test_decode_claims() {
+    let keys = KeySet::new().key(Algorithm::HS256, DecodingKey::from_secret(b"secret"));
+    let now = now();
+
+    let token = hs256(b"secret", None, json!({"sub": "a", "exp": now - 10}));
+    match decode::<Claims>(&token, &keys, &Validation::new()) {
+        Err(JwtError::Expired) => (),
+        res => panic!("{:?}", res),
+    }
+    assert!(decode::<Claims>(&token, &keys, &Validation::new().leeway(30)).is_ok());
+
+    let token = hs256(
+        b"secret",
+        None,
+        json!({"sub": "a", "exp": now + 60, "nbf": now + 10}),
+    );
+    match decode::<Claims>(&token, &keys, &Validation::new()) {
+        Err(JwtError::NotYetValid) => (),
+        res => panic!("{:?}", res),
+    }
+    assert!(decode::<Claims>(&token, &keys, &Validation::new().leeway(30)).is_ok());
+
+    let validation = Validation::new().audience("api").issuer("auth");
+    let token = hs256(
+        b"secret",
+        None,
+        json!({"sub": "a", "exp": now + 60, "aud": ["web", "api"], "iss": "auth"}),
+    );
+    assert!(decode::<Claims>(&token, &keys, &validation).is_ok());
+
+    let token = hs256(
+        b"secret",
+        None,
+        json!({"sub": "a", "exp": now + 60, "aud": "web", "iss": "auth"}),
+    );
+    match decode::<Claims>(&token, &keys, &validation) {
+        Err(JwtError::InvalidAudience) => (),
+        res => panic!("{:?}", res),
+    }
+
+    let token = hs256(b"secret", None, json!({"exp": now + 60}));
+    match decode::<Claims>(&token, &keys, &Validation::new()) {
+        Err(JwtError::InvalidClaims(_)) => (),
+        res => panic!("{:?}", res),
+    }
+}
+
+fn jwks() -> Value {
+    let ec = ec_key();
+    let point = ec.public_key().as_ref();
+    json!({
+        "keys": [
+            {"kty": "oct", "kid": "hs", "k": b64(b"secret")},
+            {"kty": "oct", "kid": "enc", "use": "enc", "k": b64(b"encryption")},
+            {"kty": "OKP", "kid": "ed", "crv": "Ed25519", "x": "AAAA"},
+            {
+                "kty": "EC",
+                "kid": "ec",
+                "alg": "ES256",
+                "crv": "P-256",
+                "x": b64(&point[1..33]),
+                "y": b64(&point[33..]),
+            },
+        ]
+    })
+}
+
+#[test]
+fn test_jwks() {
+    let keys = KeySet::from_jwks(jwks().to_string().as_bytes()).unwrap();
+    assert_eq!(keys.len(), 2);
+
+    let exp = now() + 60;
+    let validation = Validation::new();
+    let token = hs256(b"secret", Some("hs"), json!({"sub": "hs", "exp": exp}));
+    assert!(decode::<Claims>(&token, &keys, &validation).is_ok());
+    let token = es256(&ec_key(), "ec", json!({"sub": "es", "exp": exp}));
+    assert!(decode::<Claims>(&token, &keys, &validation).is_ok());
+    let token = hs256(b"encryption", Some("enc"), json!({"sub": "hs", "exp": exp}));
+    match decode::<Claims>(&token, &keys, &validation) {
+        Err(JwtError::UnknownKey) => (),
+        res => panic!("{:?}", res),
+    }
+
+    let path = std::env::temp_dir().join(format!("kayrx-jwks-{}.json", std::process::id()));
+    std::fs::write(&path, jwks().to_string()).unwrap();
+    let keys = KeySet::from_jwks_file(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(keys.len(), 2);
+}
+
+#[kayrx::test]
+async fn test_fetch_jwks() {
+    let srv = test::start(|| {
+        App::new().route(
+            "/.well-known/jwks.json",
+            web::get().to(|| HttpResponse::Ok().json(jwks())),
+        )
+    });
+
+    let keys = KeySet::fetch_jwks(&Client::new(), &srv.url("/.well-known/jwks.json"))
+        .await
+        .unwrap();
+    assert_eq!(keys.len(), 2);
+
+    match KeySet::fetch_jwks(&Client::new(), &srv.url("/missing")).await {
+        Err(JwtError::Fetch(_)) => (),
+        res => panic!("{:?}", res),
+    }
+}
+
+#[kayrx::test]
+async fn test_middleware() {
+    let keys = KeySet::new().key(Algorithm::HS256, DecodingKey::from_secret(b"secret"));
+    let mut srv = init_service(
+        App::new()
+            .app_data(BearerConfig::default().realm("api"))
+            .service(
+                web::scope("/api")
+                    .wrap(
+                        JwtAuth::<Claims>::new(keys).validation(Validation::new().audience("api")),
+                    )
+                    .route(
+                        "",
+                        web::get()
+                            .to(|claims: ReqData<Claims>| async move { claims.into_inner().sub }),
+                    ),
+            ),
+    )
+    .await;
+
+    let token = hs256(
+        b"secret",
+        None,
+        json!({"sub": "alice", "aud": "api", "exp": now() + 60}),
+    );
+    let req = TestRequest::with_uri("/api")
+        .header(AUTHORIZATION, format!("Bearer {}", token))
+        .to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(read_body(resp).await, "alice");
+
+    let req = TestRequest::with_uri("/api").to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(
+        resp.headers().get(WWW_AUTHENTICATE).unwrap(),
+        "Bearer realm=\"api\""
+    );
+
+    let token = hs256(
+        b"secret",
+        None,
+        json!({"sub": "alice", "aud": "api", "exp": now() - 60}),
+    );
+    let req = TestRequest::with_uri("/api")
+        .header(AUTHORIZATION, format!("Bearer {}", token))
+        .to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(
+        resp.headers().get(WWW_AUTHENTICATE).unwrap(),
+        "Bearer realm=\"api\", error=\"invalid_token\", error_description=\"Token is expired\""
+    );
+}
@@ -1,6 +1,15 @@
+mod auth;
+mod compress;
 mod condition;
 mod cors;
+#[cfg(feature = "cookie")]
+mod csrf;
 mod defaultheaders;
 mod errhandlers;
+mod jwt;
 // mod logger;
-mod normalize;
\ No newline at end of file
+mod normalize;
+mod ratelimit;
+mod secureheaders;
+#[cfg(feature = "cookie")]
+mod session;
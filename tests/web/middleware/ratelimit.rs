@@ -0,0 +1,289 @@
+use std::thread;
+use std::time::Duration;
+
+use kayrx::fiber::System;
+use kayrx::http::header::RETRY_AFTER;
+use kayrx::http::StatusCode;
+use kayrx::service::{Service, Transform};
+use kayrx::timer::delay_for;
+use kayrx::web::middleware::ratelimit::{
+    self, MemoryRateLimitStore, Quota, RateLimitStore, RateLimiter,
+};
+use kayrx::web::test::{call_service, init_service, ok_service, TestRequest};
+use kayrx::web::{self, App, HttpResponse, TrustedProxies};
+
+#[kayrx::test]
+async fn test_token_bucket() {
+    let store = MemoryRateLimitStore::new();
+    let mut mw = RateLimiter::new(
+        store.clone(),
+        Quota::token_bucket(2, Duration::from_secs(60)),
+    )
+    .new_transform(ok_service())
+    .await
+    .unwrap();
+
+    let addr = "127.0.0.1:8080".parse().unwrap();
+    for remaining in &["1", "0"] {
+        let req = TestRequest::default().peer_addr(addr).to_srv_request();
+        let resp = mw.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers().get("ratelimit-limit").unwrap(), "2");
+        assert_eq!(
+            resp.headers().get("ratelimit-remaining").unwrap(),
+            remaining
+        );
+    }
+
+    let req = TestRequest::default().peer_addr(addr).to_srv_request();
+    let resp = mw.call(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(resp.headers().get(RETRY_AFTER).unwrap(), "30");
+    assert_eq!(resp.headers().get("ratelimit-remaining").unwrap(), "0");
+    assert_eq!(resp.headers().get("ratelimit-reset").unwrap(), "60");
+
+    // same ip, other port
+    let req = TestRequest::default()
+        .peer_addr("127.0.0.1:8081".parse().unwrap())
+        .to_srv_request();
+    let resp = mw.call(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+
+    // other client
+    let req = TestRequest::default()
+        .peer_addr("127.0.0.2:8080".parse().unwrap())
+        .to_srv_request();
+    let resp = mw.call(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(store.len(), 2);
+
+    // no key, not limited
+    let req = TestRequest::default().to_srv_request();
+    let resp = mw.call(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert!(resp.headers().get("ratelimit-limit").is_none());
+}
+
+#[kayrx::test]
+async fn test_sliding_window_header_key() {
+    let store = MemoryRateLimitStore::new();
+    let mut mw = RateLimiter::new(store, Quota::sliding_window(1, Duration::from_secs(10)))
+        .key(ratelimit::header("x-api-key"))
+        .new_transform(ok_service())
+        .await
+        .unwrap();
+
+    let req = TestRequest::with_header("x-api-key", "a").to_srv_request();
+    let resp = mw.call(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers().get("ratelimit-remaining").unwrap(), "0");
+
+    let req = TestRequest::with_header("x-api-key", "a").to_srv_request();
+    let resp = mw.call(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(resp.headers().get(RETRY_AFTER).unwrap(), "20");
+
+    let req = TestRequest::with_header("x-api-key", "b").to_srv_request();
+    let resp = mw.call(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+}
+
+#[kayrx::test]
+async fn test_scope_limiter() {
+    let store = MemoryRateLimitStore::new();
+    let mut srv = init_service(
+        App::new()
+            .wrap(RateLimiter::new(
+                store.clone(),
+                Quota::token_bucket(10, Duration::from_secs(60)),
+            ))
+            .service(
+                web::scope("/user/{id}")
+                    .wrap(
+                        RateLimiter::new(
+                            store.clone(),
+                            Quota::token_bucket(1, Duration::from_secs(60)),
+                        )
+                        .key(ratelimit::path_param("id")),
+                    )
+                    .route("", web::get().to(|| HttpResponse::Ok())),
+            )
+            .route("/", web::get().to(|| HttpResponse::Ok())),
+    )
+    .await;
+
+    let addr = "127.0.0.1:8080".parse().unwrap();
+    let req = TestRequest::with_uri("/").peer_addr(addr).to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers().get("ratelimit-limit").unwrap(), "10");
+    assert_eq!(resp.headers().get("ratelimit-remaining").unwrap(), "9");
+
+    // headers of the scope limiter, it has less remaining requests
+    let req = TestRequest::with_uri("/user/1")
+        .peer_addr(addr)
+        .to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers().get("ratelimit-limit").unwrap(), "1");
+    assert_eq!(resp.headers().get("ratelimit-remaining").unwrap(), "0");
+
+    let req = TestRequest::with_uri("/user/1")
+        .peer_addr(addr)
+        .to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+
+    let req = TestRequest::with_uri("/user/2")
+        .peer_addr(addr)
+        .to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers().get("ratelimit-remaining").unwrap(), "0");
+
+    let req = TestRequest::with_uri("/").peer_addr(addr).to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.headers().get("ratelimit-remaining").unwrap(), "5");
+}
+
+#[kayrx::test]
+async fn test_scope_override() {
+    let store = MemoryRateLimitStore::new();
+    let mut srv = init_service(
+        App::new()
+            .wrap(
+                RateLimiter::new(store, Quota::token_bucket(1, Duration::from_secs(60)))
+                    .scope("/api", Quota::token_bucket(3, Duration::from_secs(60)))
+                    .resource(
+                        "/api/{id}/slow",
+                        Quota::token_bucket(2, Duration::from_secs(60)),
+                    ),
+            )
+            .service(
+                web::scope("/api")
+                    .route("/{id}/slow", web::get().to(|| HttpResponse::Ok()))
+                    .route("/{id}", web::get().to(|| HttpResponse::Ok())),
+            )
+            .route("/", web::get().to(|| HttpResponse::Ok())),
+    )
+    .await;
+
+    let addr = "127.0.0.1:8080".parse().unwrap();
+    let req = TestRequest::with_uri("/").peer_addr(addr).to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let req = TestRequest::with_uri("/").peer_addr(addr).to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+
+    // the scope has a higher limit than the app, and its own counter
+    for remaining in &["2", "1", "0"] {
+        let req = TestRequest::with_uri("/api/1").peer_addr(addr).to_request();
+        let resp = call_service(&mut srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers().get("ratelimit-limit").unwrap(), "3");
+        assert_eq!(
+            resp.headers().get("ratelimit-remaining").unwrap(),
+            *remaining
+        );
+    }
+    let req = TestRequest::with_uri("/api/2").peer_addr(addr).to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+
+    // the first matching override is used
+    let req = TestRequest::with_uri("/api/1/slow")
+        .peer_addr(addr)
+        .to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(resp.headers().get("ratelimit-limit").unwrap(), "3");
+
+    // prefix matches whole segments only
+    let req = TestRequest::with_uri("/apis").peer_addr(addr).to_request();
+    let resp = call_service(&mut srv, req).await;
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(resp.headers().get("ratelimit-limit").unwrap(), "1");
+}
+
+#[kayrx::test]
+async fn test_client_ip_forwarded() {
+    let store = MemoryRateLimitStore::new();
+    let mut mw = RateLimiter::new(store, Quota::token_bucket(1, Duration::from_secs(60)))
+        .new_transform(ok_service())
+        .await
+        .unwrap();
+
+    // forwarded headers of an untrusted peer do not change the key
+    let addr = "192.0.2.1:8080".parse().unwrap();
+    for (ip, status) in &[
+        ("203.0.113.1", StatusCode::OK),
+        ("203.0.113.2", StatusCode::TOO_MANY_REQUESTS),
+    ] {
+        let req = TestRequest::with_header("x-forwarded-for", *ip)
+            .peer_addr(addr)
+            .to_srv_request();
+        let resp = mw.call(req).await.unwrap();
+        assert_eq!(resp.status(), *status);
+    }
+
+    // the client address is taken from the headers of a trusted proxy
+    let proxy = "10.0.0.1:8080".parse().unwrap();
+    for (ip, status) in &[
+        ("203.0.113.1", StatusCode::OK),
+        ("203.0.113.2", StatusCode::OK),
+        ("203.0.113.1", StatusCode::TOO_MANY_REQUESTS),
+    ] {
+        let req = TestRequest::with_header("x-forwarded-for", *ip)
+            .trusted_proxies(TrustedProxies::new().add("10.0.0.0/8"))
+            .peer_addr(proxy)
+            .to_srv_request();
+        let resp = mw.call(req).await.unwrap();
+        assert_eq!(resp.status(), *status);
+    }
+}
+
+#[kayrx::test]
+async fn test_memory_store_expiry() {
+    let store = MemoryRateLimitStore::new();
+    let mut mw = RateLimiter::new(
+        store.clone(),
+        Quota::token_bucket(1, Duration::from_millis(100)),
+    )
+    .new_transform(ok_service())
+    .await
+    .unwrap();
+
+    let req = TestRequest::default()
+        .peer_addr("127.0.0.1:8080".parse().unwrap())
+        .to_srv_request();
+    let resp = mw.call(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(store.len(), 1);
+
+    delay_for(Duration::from_millis(300)).await;
+    assert!(store.is_empty());
+}
+
+#[test]
+fn test_memory_store_reaper_restart() {
+    let store = MemoryRateLimitStore::new();
+    let quota = Quota::token_bucket(1, Duration::from_millis(50));
+
+    // the reaper stops along with the thread it was spawned on
+    let s = store.clone();
+    thread::spawn(move || {
+        System::new("test").block_on(async move {
+            s.acquire("first", &quota).await.unwrap();
+        })
+    })
+    .join()
+    .unwrap();
+    assert_eq!(store.len(), 1);
+
+    System::new("test").block_on(async move {
+        store.acquire("second", &quota).await.unwrap();
+        delay_for(Duration::from_millis(150)).await;
+        assert!(store.is_empty());
+    });
+}
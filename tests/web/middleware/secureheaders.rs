@@ -0,0 +1,223 @@
+use std::time::Duration;
+
+use kayrx::http::header::{
+    CONTENT_SECURITY_POLICY, CONTENT_SECURITY_POLICY_REPORT_ONLY, REFERRER_POLICY,
+    STRICT_TRANSPORT_SECURITY, X_CONTENT_TYPE_OPTIONS, X_FRAME_OPTIONS,
+};
+use kayrx::http::StatusCode;
+use kayrx::web::middleware::secureheaders::{
+    ContentSecurityPolicy, CspNonce, FrameOptions, Hsts, PermissionsPolicy, ReferrerPolicy,
+    SecureHeaders,
+};
+use kayrx::web::test::{call_service, init_service, read_body, TestRequest};
+use kayrx::web::{self, App, HttpResponse};
+
+#[kayrx::test]
+async fn test_default_headers() {
+    let mut app = init_service(
+        App::new()
+            .wrap(SecureHeaders::new())
+            .route("/", web::get().to(|| HttpResponse::Ok()))
+            .route(
+                "/embed",
+                web::get().to(|| {
+                    HttpResponse::Ok()
+                        .header(X_FRAME_OPTIONS, "SAMEORIGIN")
+                        .finish()
+                }),
+            ),
+    )
+    .await;
+
+    let res = call_service(&mut app, TestRequest::with_uri("/").to_request()).await;
+    assert!(res.headers().get(STRICT_TRANSPORT_SECURITY).is_none());
+    assert_eq!(res.headers().get(X_FRAME_OPTIONS).unwrap(), "DENY");
+    assert_eq!(
+        res.headers().get(X_CONTENT_TYPE_OPTIONS).unwrap(),
+        "nosniff"
+    );
+    assert_eq!(
+        res.headers().get(REFERRER_POLICY).unwrap(),
+        "strict-origin-when-cross-origin"
+    );
+    assert!(res.headers().get("permissions-policy").is_none());
+    assert!(res.headers().get(CONTENT_SECURITY_POLICY).is_none());
+
+    let req = TestRequest::with_uri("https://localhost/").to_request();
+    let res = call_service(&mut app, req).await;
+    assert_eq!(
+        res.headers().get(STRICT_TRANSPORT_SECURITY).unwrap(),
+        "max-age=31536000"
+    );
+
+    // the scheme of an untrusted peer is not taken from forwarded headers
+    let req = TestRequest::with_uri("/")
+        .header("x-forwarded-proto", "https")
+        .to_request();
+    let res = call_service(&mut app, req).await;
+    assert!(res.headers().get(STRICT_TRANSPORT_SECURITY).is_none());
+
+    // headers set by the handler are kept
+    let res = call_service(&mut app, TestRequest::with_uri("/embed").to_request()).await;
+    assert_eq!(res.headers().get(X_FRAME_OPTIONS).unwrap(), "SAMEORIGIN");
+}
+
+#[kayrx::test]
+async fn test_configured_headers() {
+    let mut app = init_service(
+        App::new()
+            .wrap(
+                SecureHeaders::new()
+                    .hsts(
+                        Hsts::new(Duration::from_secs(600))
+                            .include_subdomains()
+                            .preload(),
+                    )
+                    .frame_options(FrameOptions::SameOrigin)
+                    .content_type_options(false)
+                    .referrer_policy(ReferrerPolicy::NoReferrer)
+                    .permissions_policy(
+                        PermissionsPolicy::new()
+                            .deny("camera")
+                            .allow("fullscreen", &["*"])
+                            .allow("geolocation", &["self", "https://maps.example.com"]),
+                    )
+                    .content_security_policy(
+                        ContentSecurityPolicy::new()
+                            .directive("default-src", &["'self'"])
+                            .directive("upgrade-insecure-requests", &[])
+                            .report_only(true),
+                    ),
+            )
+            .route("/", web::get().to(|| HttpResponse::Ok())),
+    )
+    .await;
+
+    let req = TestRequest::with_uri("https://localhost/").to_request();
+    let res = call_service(&mut app, req).await;
+    assert_eq!(
+        res.headers().get(STRICT_TRANSPORT_SECURITY).unwrap(),
+        "max-age=600; includeSubDomains; preload"
+    );
+    assert_eq!(res.headers().get(X_FRAME_OPTIONS).unwrap(), "SAMEORIGIN");
+    assert!(res.headers().get(X_CONTENT_TYPE_OPTIONS).is_none());
+    assert_eq!(res.headers().get(REFERRER_POLICY).unwrap(), "no-referrer");
+    assert_eq!(
+        res.headers().get("permissions-policy").unwrap(),
+        "camera=(), fullscreen=*, geolocation=(self \"https://maps.example.com\")"
+    );
+    assert!(res.headers().get(CONTENT_SECURITY_POLICY).is_none());
+    assert_eq!(
+        res.headers()
+            .get(CONTENT_SECURITY_POLICY_REPORT_ONLY)
+            .unwrap(),
+        "default-src 'self'; upgrade-insecure-requests"
+    );
+
+    let mut app = init_service(
+        App::new()
+            .wrap(
+                SecureHeaders::new()
+                    .hsts(None)
+                    .frame_options(None)
+                    .referrer_policy(None),
+            )
+            .route("/", web::get().to(|| HttpResponse::Ok())),
+    )
+    .await;
+
+    let req = TestRequest::with_uri("https://localhost/").to_request();
+    let res = call_service(&mut app, req).await;
+    assert!(res.headers().get(STRICT_TRANSPORT_SECURITY).is_none());
+    assert!(res.headers().get(X_FRAME_OPTIONS).is_none());
+    assert!(res.headers().get(REFERRER_POLICY).is_none());
+    assert_eq!(
+        res.headers().get(X_CONTENT_TYPE_OPTIONS).unwrap(),
+        "nosniff"
+    );
+}
+
+#[kayrx::test]
+async fn test_csp_nonce() {
+    let mut app = init_service(
+        App::new()
+            .wrap(
+                SecureHeaders::new().content_security_policy(
+                    ContentSecurityPolicy::new()
+                        .directive("default-src", &["'self'"])
+                        .directive("script-src", &["'strict-dynamic'"])
+                        .nonce("script-src")
+                        .nonce("style-src"),
+                ),
+            )
+            .route(
+                "/",
+                web::get().to(|nonce: CspNonce| async move { nonce.nonce().to_owned() }),
+            ),
+    )
+    .await;
+
+    let res = call_service(&mut app, TestRequest::with_uri("/").to_request()).await;
+    let csp = res
+        .headers()
+        .get(CONTENT_SECURITY_POLICY)
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .to_owned();
+    let nonce = String::from_utf8(read_body(res).await.to_vec()).unwrap();
+    assert_eq!(nonce.len(), 24);
+    assert_eq!(
+        csp,
+        format!(
+            "default-src 'self'; script-src 'strict-dynamic' 'nonce-{0}'; style-src 'nonce-{0}'",
+            nonce
+        )
+    );
+
+    // every request gets a fresh nonce
+    let res = call_service(&mut app, TestRequest::with_uri("/").to_request()).await;
+    assert_ne!(read_body(res).await, nonce.as_bytes());
+
+    // the extractor fails without a nonce in the policy
+    let mut app = init_service(
+        App::new()
+            .wrap(SecureHeaders::new())
+            .route("/", web::get().to(|_: CspNonce| HttpResponse::Ok())),
+    )
+    .await;
+    let res = call_service(&mut app, TestRequest::with_uri("/").to_request()).await;
+    assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+}
+
+#[kayrx::test]
+async fn test_configure_clone() {
+    let headers = SecureHeaders::new();
+    let mut app = init_service(
+        App::new()
+            .wrap(headers.clone().frame_options(FrameOptions::SameOrigin))
+            .route("/", web::get().to(|| HttpResponse::Ok())),
+    )
+    .await;
+
+    let res = call_service(&mut app, TestRequest::with_uri("/").to_request()).await;
+    assert_eq!(res.headers().get(X_FRAME_OPTIONS).unwrap(), "SAMEORIGIN");
+
+    // the original is not changed
+    let mut app = init_service(
+        App::new()
+            .wrap(headers)
+            .route("/", web::get().to(|| HttpResponse::Ok())),
+    )
+    .await;
+    let res = call_service(&mut app, TestRequest::with_uri("/").to_request()).await;
+    assert_eq!(res.headers().get(X_FRAME_OPTIONS).unwrap(), "DENY");
+}
+
+#[test]
+#[should_panic(expected = "Invalid content security policy")]
+fn test_invalid_csp() {
+    SecureHeaders::new().content_security_policy(
+        ContentSecurityPolicy::new().directive("default-src", &["'self'\n"]),
+    );
+}
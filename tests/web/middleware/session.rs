@@ -0,0 +1,182 @@
+use std::time::Duration;
+
+use kayrx::http::error::coo_kie::Cookie;
+use kayrx::http::error::Error;
+use kayrx::http::header::SET_COOKIE;
+use kayrx::http::Response as HttpResponse;
+use kayrx::web::dev::ServiceResponse;
+use kayrx::web::middleware::session::{
+    CookieSessionStore, MemorySessionStore, Session, SessionMiddleware,
+};
+use kayrx::web::test::{call_service, init_service, read_body, TestRequest};
+use kayrx::web::{self, App};
+
+async fn counter(session: Session) -> Result<String, Error> {
+    let counter = session.get::<i32>("counter")?.unwrap_or(0) + 1;
+    session.set("counter", counter)?;
+    Ok(counter.to_string())
+}
+
+async fn login(session: Session) -> Result<HttpResponse, Error> {
+    session.renew();
+    session.set("user", "alice")?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+async fn logout(session: Session) -> HttpResponse {
+    session.purge();
+    HttpResponse::Ok().finish()
+}
+
+async fn user(session: Session) -> Result<String, Error> {
+    Ok(session.get::<String>("user")?.unwrap_or_default())
+}
+
+fn session_cookie<B>(res: &ServiceResponse<B>) -> Option<Cookie<'static>> {
+    res.headers()
+        .get_all(SET_COOKIE)
+        .map(|value| Cookie::parse_encoded(value.to_str().unwrap().to_owned()).unwrap())
+        .find(|cookie| cookie.name() == "kayrx-session")
+}
+
+macro_rules! app {
+    ($store:expr) => {
+        init_service(
+            App::new()
+                .wrap(SessionMiddleware::new($store).ttl(Duration::from_secs(60)))
+                .service(web::resource("/").to(counter))
+                .service(web::resource("/login").to(login))
+                .service(web::resource("/logout").to(logout))
+                .service(web::resource("/user").to(user)),
+        )
+        .await
+    };
+}
+
+#[kayrx::test]
+async fn test_cookie_session() {
+    let mut app = app!(CookieSessionStore::private(&[0; 32]));
+
+    let res = call_service(&mut app, TestRequest::with_uri("/").to_request()).await;
+    let cookie = session_cookie(&res).unwrap();
+    assert_eq!(cookie.http_only(), Some(true));
+    assert_eq!(cookie.path(), Some("/"));
+    assert_eq!(cookie.max_age(), Some(time::Duration::seconds(60)));
+    assert_eq!(read_body(res).await, "1");
+
+    let req = TestRequest::with_uri("/")
+        .cookie(cookie.clone())
+        .to_request();
+    let res = call_service(&mut app, req).await;
+    let cookie = session_cookie(&res).unwrap();
+    assert_eq!(read_body(res).await, "2");
+
+    // A tampered cookie starts a new session.
+    let mut tampered = cookie.clone();
+    tampered.set_value(format!("x{}", cookie.value()));
+    let req = TestRequest::with_uri("/").cookie(tampered).to_request();
+    let res = call_service(&mut app, req).await;
+    assert_eq!(read_body(res).await, "1");
+
+    let req = TestRequest::with_uri("/logout").cookie(cookie).to_request();
+    let res = call_service(&mut app, req).await;
+    let removal = session_cookie(&res).unwrap();
+    assert_eq!(removal.value(), "");
+    assert_eq!(removal.max_age(), Some(time::Duration::zero()));
+}
+
+#[kayrx::test]
+async fn test_memory_session() {
+    let store = MemorySessionStore::new();
+    let mut app = app!(store.clone());
+
+    let res = call_service(&mut app, TestRequest::with_uri("/").to_request()).await;
+    let cookie = session_cookie(&res).unwrap();
+    assert_eq!(cookie.value().len(), 32);
+    assert_eq!(store.len(), 1);
+
+    let req = TestRequest::with_uri("/")
+        .cookie(cookie.clone())
+        .to_request();
+    let res = call_service(&mut app, req).await;
+    // The key stays the same.
+    assert_eq!(session_cookie(&res).unwrap().value(), cookie.value());
+    assert_eq!(read_body(res).await, "2");
+
+    // Logging in keeps the state under a new key.
+    let req = TestRequest::with_uri("/login")
+        .cookie(cookie.clone())
+        .to_request();
+    let res = call_service(&mut app, req).await;
+    let renewed = session_cookie(&res).unwrap();
+    assert_ne!(renewed.value(), cookie.value());
+    assert_eq!(store.len(), 1);
+
+    // The old key is gone, its cookie gets removed.
+    let req = TestRequest::with_uri("/user")
+        .cookie(cookie.clone())
+        .to_request();
+    let res = call_service(&mut app, req).await;
+    assert_eq!(session_cookie(&res).unwrap().value(), "");
+    assert_eq!(read_body(res).await, "");
+
+    let req = TestRequest::with_uri("/")
+        .cookie(renewed.clone())
+        .to_request();
+    assert_eq!(read_body(call_service(&mut app, req).await).await, "3");
+
+    // Logging out deletes the session.
+    let req = TestRequest::with_uri("/logout")
+        .cookie(renewed.clone())
+        .to_request();
+    let res = call_service(&mut app, req).await;
+    assert_eq!(session_cookie(&res).unwrap().value(), "");
+    assert!(store.is_empty());
+
+    let req = TestRequest::with_uri("/user").cookie(renewed).to_request();
+    assert_eq!(read_body(call_service(&mut app, req).await).await, "");
+}
+
+#[kayrx::test]
+async fn test_session_ttl() {
+    let store = MemorySessionStore::new();
+    let mut app = init_service(
+        App::new()
+            .wrap(SessionMiddleware::new(store.clone()).ttl(Duration::from_millis(50)))
+            .service(web::resource("/").to(counter)),
+    )
+    .await;
+
+    let res = call_service(&mut app, TestRequest::with_uri("/").to_request()).await;
+    let cookie = session_cookie(&res).unwrap();
+
+    kayrx::timer::delay_for(Duration::from_millis(100)).await;
+
+    let req = TestRequest::with_uri("/").cookie(cookie).to_request();
+    assert_eq!(read_body(call_service(&mut app, req).await).await, "1");
+}
+
+#[kayrx::test]
+async fn test_refresh_on_access() {
+    let store = MemorySessionStore::new();
+    let mut app = init_service(
+        App::new()
+            .wrap(SessionMiddleware::new(store.clone()).refresh_on_access(true))
+            .service(web::resource("/").to(counter))
+            .service(web::resource("/user").to(user)),
+    )
+    .await;
+
+    let res = call_service(&mut app, TestRequest::with_uri("/").to_request()).await;
+    let cookie = session_cookie(&res).unwrap();
+
+    let req = TestRequest::with_uri("/user")
+        .cookie(cookie.clone())
+        .to_request();
+    let res = call_service(&mut app, req).await;
+    assert_eq!(session_cookie(&res).unwrap().value(), cookie.value());
+
+    // Without a session there is nothing to refresh.
+    let res = call_service(&mut app, TestRequest::with_uri("/user").to_request()).await;
+    assert!(session_cookie(&res).is_none());
+}
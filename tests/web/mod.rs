@@ -11,6 +11,7 @@ mod multipart;
 // mod resource;
 mod responder;
 mod route;
+mod server;
 mod service;
 mod scope;
 mod test;
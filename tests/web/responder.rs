@@ -127,6 +127,30 @@
         assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
     }
 
+    #[kayrx::test]
+    async fn test_vnode_responder() {
+        use kayrx::webui::vdom::VNode;
+
+        let mut srv = init_service(App::new().service(web::resource("/").to(|| async {
+            let mut node = VNode::element("h1");
+            node.as_velement_mut()
+                .unwrap()
+                .children
+                .push(VNode::text("Tom & Jerry"));
+            node
+        })))
+        .await;
+
+        let req = TestRequest::with_uri("/").to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(CONTENT_TYPE).unwrap(),
+            HeaderValue::from_static("text/html; charset=utf-8")
+        );
+        assert_eq!(resp.response().body().bin_ref(), b"<h1>Tom &amp; Jerry</h1>");
+    }
+
     #[kayrx::test]
     async fn test_result_responder() {
         let req = TestRequest::default().to_http_request();
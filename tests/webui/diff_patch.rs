@@ -195,3 +195,30 @@ fn replace_text_with_element() {
     }
     .test();
 }
+
+#[wasm_bindgen_test]
+fn reorder_keyed_children() {
+    DiffPatchTest {
+        desc: "Insert a keyed child at the top of a list",
+        old: html! { <ul> <li key="a">a</li> <li key="b">b</li> </ul> },
+        new: html! { <ul> <li key="c">c</li> <li key="a">a</li> <li key="b">b</li> </ul> },
+        override_expected: None,
+    }
+    .test();
+
+    DiffPatchTest {
+        desc: "Remove, move, insert and patch keyed children",
+        old: html! {
+         <ul>
+           <li key="a">a</li> <li key="b">b</li> <li key="c">c</li> <li key="d">d</li>
+         </ul>
+        },
+        new: html! {
+         <ul>
+           <li key="d">d</li> <li key="e">e</li> <li key="a">changed</li> <li key="c">c</li>
+         </ul>
+        },
+        override_expected: None,
+    }
+    .test();
+}
@@ -145,3 +145,76 @@ fn updates_active_closures_on_append() {
 
     assert_eq!(&*text.borrow(), "End Text");
 }
+
+// Hydrating server rendered HTML should adopt the existing DOM nodes and attach our closures
+// to them instead of creating new nodes.
+#[wasm_bindgen_test]
+fn hydrates_server_rendered_dom() {
+    console_error_panic_hook::set_once();
+
+    let document = web_sys::window().unwrap().document().unwrap();
+    let body = document.body().unwrap();
+
+    let clicked = Rc::new(RefCell::new(false));
+    let clicked_clone = Rc::clone(&clicked);
+
+    let id = "hydrates-server-rendered-dom";
+
+    let vdom = html! {
+     <div>
+       Hello <b>world</b>
+       <button id=id onclick=move |_: MouseEvent| { *clicked_clone.borrow_mut() = true; }></button>
+     </div>
+    };
+
+    let mount = document.create_element("div").unwrap();
+    mount.set_inner_html(&vdom.to_string());
+    body.append_child(&mount).unwrap();
+
+    let server_button = document.get_element_by_id(&id).unwrap();
+
+    let dom_updater = DomUpdater::new_hydrate(vdom, mount.first_child().unwrap());
+
+    // The server rendered nodes were adopted, not recreated.
+    let button = document.get_element_by_id(&id).unwrap();
+    assert!(server_button.is_same_node(Some(button.as_ref())));
+    assert!(mount.is_same_node(dom_updater.root_node().parent_node().as_ref()));
+
+    button.dyn_ref::<HtmlElement>().unwrap().click();
+
+    assert_eq!(*clicked.borrow(), true);
+}
+
+// Empty text renders to nothing on the server, the hydration must not adopt the next node
+// in its place.
+#[wasm_bindgen_test]
+fn hydrates_empty_text() {
+    console_error_panic_hook::set_once();
+
+    let document = web_sys::window().unwrap().document().unwrap();
+    let body = document.body().unwrap();
+
+    let id = "hydrates-empty-text";
+    let empty = "";
+
+    let vdom = html! { <div>{empty}<span id=id></span></div> };
+
+    let mount = document.create_element("div").unwrap();
+    mount.set_inner_html(&vdom.to_string());
+    body.append_child(&mount).unwrap();
+
+    let server_span = document.get_element_by_id(&id).unwrap();
+
+    let mut dom_updater = DomUpdater::new_hydrate(vdom, mount.first_child().unwrap());
+
+    let span = document.get_element_by_id(&id).unwrap();
+    assert!(server_span.is_same_node(Some(span.as_ref())));
+
+    let text = "text";
+    dom_updater.update(html! { <div>{text}<span id=id></span></div> });
+
+    assert_eq!(
+        dom_updater.root_node().dyn_ref::<Element>().unwrap().inner_html(),
+        r#"text<span id="hydrates-empty-text"></span>"#
+    );
+}
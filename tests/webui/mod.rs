@@ -0,0 +1,10 @@
+//! Browser tests for webui, they run under wasm-bindgen-test:
+//!
+//! wasm-pack test --chrome --headless
+#![cfg(target_arch = "wasm32")]
+
+mod closures;
+mod create_element;
+mod diff_patch;
+mod dom_updater;
+mod events;
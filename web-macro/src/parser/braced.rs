@@ -1,4 +1,4 @@
-use crate::parser::HtmlParser;
+use crate::parser::{parenthesize, HtmlParser};
 use crate::tag::{Tag, TagKind};
 use proc_macro2::Span;
 use quote::quote;
@@ -68,6 +68,7 @@ impl HtmlParser {
                 // Here we handle a block being the root node of an `html!` call
                 //
                 // html { { some_node }  }
+                let stmt = parenthesize(stmt);
                 let node = quote! {
                     let node_0: VirtualNode = #stmt.into();
                 };
@@ -219,9 +219,10 @@ impl HtmlParser {
         match nodes {
             NodesToPush::Stmt(stmt) => {
                 let node_ident = self.new_virtual_node_ident(stmt.span());
+                let nodes = parenthesize(stmt);
 
                 self.push_tokens(quote! {
-                    let mut #node_ident: IterableNodes = #stmt.into();
+                    let mut #node_ident: IterableNodes = #nodes.into();
                 });
             },
             NodesToPush::TokenStream(stmt, tokens) => {
@@ -261,3 +262,13 @@ struct RecentSpanLocations {
 fn is_self_closing(tag: &str) -> bool {
     crate::validation::is_self_closing(tag)
 }
+
+/// Wrap an expression in parentheses, so that `.into()` converts all of it.
+///
+/// html! { <div> { &component } </div> } -> (&component).into()
+pub(crate) fn parenthesize(stmt: &Stmt) -> proc_macro2::TokenStream {
+    match stmt {
+        Stmt::Expr(expr) => quote! { (#expr) },
+        _ => quote! { #stmt },
+    }
+}